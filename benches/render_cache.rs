@@ -0,0 +1,121 @@
+//! Compiled template cache benchmark
+//!
+//! Renders the same prompt repeatedly two ways:
+//!
+//! - **uncached:** `render_template`, which parses the template and builds a
+//!   new MiniJinja environment on every call (how every render worked before
+//!   the compiled cache)
+//! - **cached:** `PromptManager::render_prompt`, which compiles the prompt
+//!   once per version and reuses it
+//!
+//! ```bash
+//! cargo bench --bench render_cache
+//! RENDER_CACHE_ITERATIONS=20000 cargo bench --bench render_cache
+//! ```
+
+use kodegen_mcp_schema::prompt::TemplateParamValue;
+use kodegen_tools_prompt::template::render_template;
+use kodegen_tools_prompt::{FsStore, PromptManager, UsageStore};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const PROMPT_NAME: &str = "bench_review";
+
+/// A prompt shaped like the shipped ones: frontmatter, loops, conditionals
+/// and message blocks, about 7 KB of template source
+fn prompt_source() -> String {
+    let mut body = String::from(
+        "{% message \"system\" %}You are a careful reviewer for {{ language }} code.{% endmessage %}\n\
+         {% message \"user\" %}\n",
+    );
+    for section in 0..40 {
+        body.push_str(&format!(
+            "## Step {section}\n\
+             {{% if strict %}}Check {{{{ focus | upper }}}} rules strictly.{{% else %}}Skim for {{{{ focus }}}}.{{% endif %}}\n\
+             {{% for file in files %}}- {{{{ loop.index }}}}. {{{{ file }}}}\n{{% endfor %}}\n"
+        ));
+    }
+    body.push_str("{% endmessage %}\n");
+
+    format!(
+        "---\n\
+         title: \"Benchmark Review\"\n\
+         description: \"Review checklist used by the render cache benchmark\"\n\
+         categories: [\"bench\"]\n\
+         author: \"bench\"\n\
+         parameters:\n\
+         \x20 - name: \"language\"\n\
+         \x20   description: \"Language\"\n\
+         \x20   default: \"rust\"\n\
+         \x20 - name: \"focus\"\n\
+         \x20   description: \"Focus area\"\n\
+         \x20   default: \"safety\"\n\
+         \x20 - name: \"strict\"\n\
+         \x20   description: \"Strict mode\"\n\
+         \x20   param_type: \"boolean\"\n\
+         \x20   default: true\n\
+         \x20 - name: \"files\"\n\
+         \x20   description: \"Files to review\"\n\
+         \x20   param_type: \"array\"\n\
+         ---\n\
+         {body}"
+    )
+}
+
+fn parameters() -> HashMap<String, TemplateParamValue> {
+    HashMap::from([(
+        "files".to_string(),
+        TemplateParamValue::StringArray(vec![
+            "src/lib.rs".to_string(),
+            "src/manager.rs".to_string(),
+            "src/template.rs".to_string(),
+        ]),
+    )])
+}
+
+fn per_render(elapsed: Duration, iterations: u32) -> f64 {
+    elapsed.as_secs_f64() * 1_000_000.0 / f64::from(iterations)
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let iterations: u32 = std::env::var("RENDER_CACHE_ITERATIONS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(5_000);
+
+    let dir = std::env::temp_dir().join(format!("kodegen-render-cache-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    // Usage analytics would write the sidecar on every render; keep them in memory
+    let manager =
+        PromptManager::with_store(FsStore::new(&dir)).with_usage_store(UsageStore::in_memory());
+    manager.add_prompt(PROMPT_NAME, &prompt_source()).await?;
+    let template = manager.load_prompt(PROMPT_NAME).await?;
+    let params = parameters();
+
+    // Warm up both paths (and compile the cached prompt)
+    for _ in 0..100 {
+        render_template(&template, Some(&params)).await?;
+        manager.render_prompt(PROMPT_NAME, Some(params.clone())).await?;
+    }
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        render_template(&template, Some(&params)).await?;
+    }
+    let uncached = per_render(start.elapsed(), iterations);
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        manager.render_prompt(PROMPT_NAME, Some(params.clone())).await?;
+    }
+    let cached = per_render(start.elapsed(), iterations);
+
+    println!("render_cache: {iterations} renders of a {} byte template", template.content.len());
+    println!("  uncached (compile every render): {uncached:>9.1} µs/render");
+    println!("  cached (compiled once):          {cached:>9.1} µs/render");
+    println!("  speedup:                         {:>9.2}x", uncached / cached);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    Ok(())
+}
@@ -1,7 +1,10 @@
+use super::audit::AuditEntry;
+use super::diagnostics::{diagnostics_of, format_diagnostic_lines, validation_diagnostics, Diagnostic};
 use super::manager::PromptManager;
+use super::schema::{AddPromptArgs, PromptAddOutput};
 use super::template::parse_template;
 use kodegen_mcp_schema::{McpError, Tool, ToolExecutionContext, ToolResponse};
-use kodegen_mcp_schema::prompt::{AddPromptArgs, PromptAddOutput, PromptAddPrompts, PROMPT_ADD};
+use kodegen_mcp_schema::prompt::{PromptAddPrompts, PROMPT_ADD};
 
 #[derive(Clone)]
 pub struct AddPromptTool {
@@ -34,7 +37,10 @@ impl Tool for AddPromptTool {
         "Create a new prompt template. The content must include YAML frontmatter with metadata \
          (title, description, categories, author) followed by the template body. Template syntax \
          is validated before saving. Environment variables are accessible via {{ env.VAR }}. \
-         Parameters can be defined in frontmatter and used via {{ param_name }}."
+         Parameters can be defined in frontmatter and used via {{ param_name }}. \
+         Invalid content is rejected with success=false and a diagnostics list giving the \
+         line, column, key path (e.g. parameters[1].default), expected vs. actual type, and \
+         a suggested fix for every problem."
     }
 
     fn read_only() -> bool {
@@ -49,22 +55,40 @@ impl Tool for AddPromptTool {
         false // Will fail if prompt already exists
     }
 
-    async fn execute(&self, args: Self::Args, _ctx: ToolExecutionContext) -> Result<ToolResponse<<Self::Args as kodegen_mcp_schema::ToolArgs>::Output>, McpError> {
+    async fn execute(&self, args: Self::Args, ctx: ToolExecutionContext) -> Result<ToolResponse<<Self::Args as kodegen_mcp_schema::ToolArgs>::Output>, McpError> {
         // Parse template to extract metadata (for output formatting)
-        let template = parse_template(&args.name, &args.content)
-            .map_err(McpError::Other)?;
+        let template = match parse_template(&args.name, &args.content) {
+            Ok(template) => template,
+            Err(e) => return Ok(rejected(&args.name, diagnostics_of(&e))),
+        };
 
         // Extract statistics
         let param_count = template.metadata.parameters.len();
         let template_length = template.content.len();
 
         // Add prompt (validates syntax automatically, async)
-        self.manager
-            .add_prompt(&args.name, &args.content)
-            .await
-            .map_err(McpError::Other)?;
-
-        let path = format!("~/.kodegen/prompts/{}.j2.md", args.name);
+        let manager = self.manager.for_connection(ctx.connection_id());
+        let result = manager.add_prompt(&args.name, &args.content).await;
+        // A failed add leaves no new version to hash
+        let hash_after = match &result {
+            Ok(()) => manager.audit_hash(&args.name).await,
+            Err(_) => None,
+        };
+        manager
+            .audit(
+                AuditEntry::new(PROMPT_ADD, &args.name, ctx.connection_id())
+                    .with_hashes(None, hash_after)
+                    .with_result(&result),
+            )
+            .await;
+        if let Err(e) = result {
+            return match validation_diagnostics(&e) {
+                Some(diagnostics) => Ok(rejected(&args.name, diagnostics)),
+                None => Err(McpError::Other(e)),
+            };
+        }
+
+        let path = manager.prompt_path(&args.name).map(|path| path.display().to_string());
 
         // Terminal summary
         let summary = format!(
@@ -80,11 +104,35 @@ impl Tool for AddPromptTool {
             success: true,
             name: args.name.clone(),
             message: format!("Prompt '{}' created successfully", args.name),
-            path: Some(path),
+            path,
             template_length: Some(template_length),
             parameter_count: Some(param_count),
+            diagnostics: Vec::new(),
         };
 
         Ok(ToolResponse::new(summary, output))
     }
 }
+
+/// Response for content rejected by validation
+fn rejected(name: &str, diagnostics: Vec<Diagnostic>) -> ToolResponse<PromptAddOutput> {
+    let summary = format!(
+        "\x1b[31m Prompt Rejected: {}\x1b[0m\n{}",
+        name,
+        format_diagnostic_lines(&diagnostics)
+    );
+    let output = PromptAddOutput {
+        success: false,
+        name: name.to_string(),
+        message: format!(
+            "Prompt '{}' was not created: {} validation error(s)",
+            name,
+            diagnostics.len()
+        ),
+        path: None,
+        template_length: None,
+        parameter_count: None,
+        diagnostics,
+    };
+    ToolResponse::new(summary, output)
+}
@@ -0,0 +1,291 @@
+use super::config::PromptLimits;
+use anyhow::{Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// How an attached file is delivered in a rendered message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentKind {
+    /// Embedded resource (text or base64 blob)
+    Resource,
+    /// Image content (base64)
+    Image,
+}
+
+impl AttachmentKind {
+    /// Template helper name that produces this kind (`attach` / `image`)
+    pub fn helper_name(&self) -> &'static str {
+        match self {
+            Self::Resource => "attach",
+            Self::Image => "image",
+        }
+    }
+
+    pub(crate) fn parse_helper(name: &str) -> Option<Self> {
+        match name {
+            "attach" => Some(Self::Resource),
+            "image" => Some(Self::Image),
+            _ => None,
+        }
+    }
+}
+
+/// Attachment payload
+#[derive(Debug, Clone)]
+pub enum AttachmentData {
+    /// UTF-8 text content
+    Text(String),
+    /// Base64-encoded binary content
+    Base64(String),
+}
+
+/// A workspace file attached to a rendered message
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    pub kind: AttachmentKind,
+    /// Path as written in the template
+    pub path: String,
+    /// `file://` URI of the resolved file
+    pub uri: String,
+    pub mime_type: String,
+    pub data: AttachmentData,
+}
+
+/// What one render may still attach
+///
+/// Every file loaded is charged against the render's limits: the number of
+/// attachments, the size of each file and their combined size. Files are
+/// charged before they are read, so an over-budget file is never loaded.
+#[derive(Debug, Clone)]
+pub struct AttachmentBudget {
+    max_count: usize,
+    max_file_size: u64,
+    max_total_size: u64,
+    count: usize,
+    total_size: u64,
+    /// Set when the render timed out; stops loading between files
+    cancelled: Option<Arc<AtomicBool>>,
+}
+
+impl AttachmentBudget {
+    /// Full budget for one render under `limits`
+    pub fn new(limits: &PromptLimits) -> Self {
+        Self {
+            max_count: limits.max_attachments,
+            max_file_size: limits.max_attachment_size,
+            max_total_size: limits.max_total_attachment_size,
+            count: 0,
+            total_size: 0,
+            cancelled: None,
+        }
+    }
+
+    /// Stop loading once `cancelled` is set (the caller stopped waiting)
+    pub(crate) fn with_cancel_flag(mut self, cancelled: Arc<AtomicBool>) -> Self {
+        self.cancelled = Some(cancelled);
+        self
+    }
+
+    /// Reserve room for a `size`-byte file, or fail when it doesn't fit
+    fn charge(&mut self, path: &str, size: u64) -> Result<()> {
+        if self.cancelled.as_ref().is_some_and(|c| c.load(Ordering::Relaxed)) {
+            anyhow::bail!("Attachment loading cancelled: the render timed out");
+        }
+        if self.count >= self.max_count {
+            anyhow::bail!(
+                "Template attaches more than {} files\n\
+                 Consider attaching fewer files or setting KODEGEN_MAX_ATTACHMENTS",
+                self.max_count
+            );
+        }
+        if size > self.max_file_size {
+            anyhow::bail!(
+                "Attachment '{path}' is too large: {size} bytes (max {} bytes)\n\
+                 Consider setting KODEGEN_MAX_ATTACHMENT_SIZE if this is legitimate",
+                self.max_file_size
+            );
+        }
+        if self.total_size + size > self.max_total_size {
+            anyhow::bail!(
+                "Attachment '{path}' would bring this render's attachments to {} bytes \
+                 (max {} bytes in total)\n\
+                 Consider attaching fewer or smaller files, or setting \
+                 KODEGEN_MAX_TOTAL_ATTACHMENT_SIZE",
+                self.total_size + size,
+                self.max_total_size
+            );
+        }
+        self.count += 1;
+        self.total_size += size;
+        Ok(())
+    }
+}
+
+/// Sandbox that confines template attachments to the caller's workspace
+///
+/// Relative paths resolve against the caller's working directory. After
+/// resolving symlinks, the file must live inside the workspace root (the git
+/// root when known, otherwise the working directory), must be a regular file,
+/// and must fit the render's [`AttachmentBudget`].
+#[derive(Debug, Clone)]
+pub struct AttachmentSandbox {
+    root: PathBuf,
+    base: PathBuf,
+}
+
+impl AttachmentSandbox {
+    /// Create a sandbox rooted at `root`, resolving relative paths against `base`
+    pub fn new(root: impl Into<PathBuf>, base: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            base: base.into(),
+        }
+    }
+
+    /// Build a sandbox from the caller's pwd and git root (either may be unknown)
+    pub fn for_workspace(pwd: Option<&Path>, git_root: Option<&Path>) -> Option<Self> {
+        match (pwd, git_root) {
+            (Some(pwd), Some(root)) if pwd.starts_with(root) => Some(Self::new(root, pwd)),
+            (Some(pwd), _) => Some(Self::new(pwd, pwd)),
+            (None, Some(root)) => Some(Self::new(root, root)),
+            (None, None) => None,
+        }
+    }
+
+    /// Workspace root that attachments are confined to
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolve a template path to a canonical path inside the workspace
+    pub fn resolve(&self, path: &str) -> Result<PathBuf> {
+        if path.is_empty() {
+            anyhow::bail!("Attachment path cannot be empty");
+        }
+
+        let requested = Path::new(path);
+        let joined = if requested.is_absolute() {
+            requested.to_path_buf()
+        } else {
+            self.base.join(requested)
+        };
+
+        let root = self.root.canonicalize().with_context(|| {
+            format!("Failed to resolve workspace root: {}", self.root.display())
+        })?;
+        let resolved = joined
+            .canonicalize()
+            .with_context(|| format!("Attachment not found: {path}"))?;
+
+        // Canonical paths have symlinks resolved, so this also catches links
+        // pointing outside the workspace.
+        if !resolved.starts_with(&root) {
+            anyhow::bail!(
+                "Attachment '{path}' is outside the workspace ({}). \
+                 Only files inside the caller's workspace can be attached.",
+                root.display()
+            );
+        }
+
+        Ok(resolved)
+    }
+
+    /// Read a workspace file as an attachment, charging it to `budget`
+    pub fn load(
+        &self,
+        kind: AttachmentKind,
+        path: &str,
+        budget: &mut AttachmentBudget,
+    ) -> Result<Attachment> {
+        let resolved = self.resolve(path)?;
+
+        let metadata = std::fs::metadata(&resolved)
+            .with_context(|| format!("Failed to read attachment metadata: {path}"))?;
+        if !metadata.is_file() {
+            anyhow::bail!("Attachment '{path}' is not a regular file");
+        }
+
+        budget.charge(path, metadata.len())?;
+
+        // Read no more than was charged, even if the file grew meanwhile
+        let mut bytes = Vec::with_capacity(metadata.len() as usize);
+        std::fs::File::open(&resolved)
+            .and_then(|file| file.take(metadata.len()).read_to_end(&mut bytes))
+            .with_context(|| format!("Failed to read attachment: {path}"))?;
+        let uri = format!("file://{}", resolved.display());
+
+        let (mime_type, data) = match kind {
+            AttachmentKind::Image => {
+                let mime_type = image_mime_type(&resolved).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Attachment '{path}' is not a supported image \
+                         (png, jpg, jpeg, gif, webp, bmp, svg)"
+                    )
+                })?;
+                (mime_type.to_string(), AttachmentData::Base64(BASE64.encode(&bytes)))
+            }
+            AttachmentKind::Resource => match String::from_utf8(bytes) {
+                Ok(text) => (text_mime_type(&resolved).to_string(), AttachmentData::Text(text)),
+                Err(e) => (
+                    image_mime_type(&resolved)
+                        .unwrap_or("application/octet-stream")
+                        .to_string(),
+                    AttachmentData::Base64(BASE64.encode(e.into_bytes())),
+                ),
+            },
+        };
+
+        Ok(Attachment {
+            kind,
+            path: path.to_string(),
+            uri,
+            mime_type,
+            data,
+        })
+    }
+}
+
+/// File extension in lowercase (empty if none)
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+/// MIME type for supported image extensions
+fn image_mime_type(path: &Path) -> Option<&'static str> {
+    match extension(path).as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "bmp" => Some("image/bmp"),
+        "svg" => Some("image/svg+xml"),
+        _ => None,
+    }
+}
+
+/// MIME type for text attachments (falls back to text/plain)
+fn text_mime_type(path: &Path) -> &'static str {
+    match extension(path).as_str() {
+        "md" | "markdown" => "text/markdown",
+        "json" => "application/json",
+        "yaml" | "yml" => "application/yaml",
+        "toml" => "application/toml",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "xml" => "application/xml",
+        "js" | "mjs" => "text/javascript",
+        "svg" => "image/svg+xml",
+        _ => "text/plain",
+    }
+}
@@ -0,0 +1,368 @@
+//! Append-only audit log of prompt changes and renders
+//!
+//! Each line of the log is one JSON [`AuditEntry`]. Tools append an entry
+//! for every create, edit, delete, rename, copy, import, restore and render
+//! they perform; `PromptManager` appends one for every policy denial. The
+//! log is only ever appended to, and is queried by the `prompt_audit` tool.
+//!
+//! Rendered parameter values are left out (only their names are recorded)
+//! unless `KODEGEN_PROMPTS_AUDIT_VALUES` is set, since they often carry user
+//! data.
+
+use super::policy::{ClientIdentity, PolicyAction, PolicyDenied};
+use anyhow::{Context, Result};
+use kodegen_mcp_schema::prompt::TemplateParamValue;
+use log::warn;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+/// Path of the JSONL audit log (auditing is off when unset)
+pub const AUDIT_LOG_ENV: &str = "KODEGEN_PROMPTS_AUDIT_LOG";
+
+/// Also record rendered parameter values (`1`, `true`, `yes`, `on`)
+pub const AUDIT_VALUES_ENV: &str = "KODEGEN_PROMPTS_AUDIT_VALUES";
+
+/// Tool name recorded for denials made by the policy itself
+pub const POLICY_TOOL: &str = "policy";
+
+/// Bytes read at a time when scanning the log from its end
+const READ_CHUNK: u64 = 64 * 1024;
+
+/// How an audited operation ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+    /// Rejected by the client policy
+    Denied,
+}
+
+impl AuditOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Failure => "failure",
+            Self::Denied => "denied",
+        }
+    }
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AuditEntry {
+    /// RFC 3339 time the entry was recorded
+    pub timestamp: String,
+    /// Tool (or `prompts/get`, or `policy` for denials) that performed the operation
+    pub tool: String,
+    /// Tool action or policy action (`render`, `restore`, `delete`, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    /// Prompt the operation applied to
+    pub prompt: String,
+    /// Destination prompt of a rename or copy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// MCP connection that made the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_id: Option<String>,
+    pub outcome: AuditOutcome,
+    /// SHA-256 of the prompt file before a change (absent if it did not exist)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash_before: Option<String>,
+    /// SHA-256 of the prompt file after a change, or of the version rendered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash_after: Option<String>,
+    /// Names of the parameters passed to a render
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<String>,
+    /// Parameter values (only when value recording is enabled)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameter_values: Option<BTreeMap<String, serde_json::Value>>,
+    /// Error message of a failed or denied operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    /// Successful operation by `tool` on `prompt`, timestamped now
+    pub fn new(tool: &str, prompt: &str, connection_id: Option<&str>) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            tool: tool.to_string(),
+            action: None,
+            prompt: prompt.to_string(),
+            target: None,
+            connection_id: connection_id.map(str::to_string),
+            outcome: AuditOutcome::Success,
+            hash_before: None,
+            hash_after: None,
+            parameters: Vec::new(),
+            parameter_values: None,
+            error: None,
+        }
+    }
+
+    /// Denial of `action` on `prompt` for `client`
+    pub fn denied(client: &ClientIdentity, action: PolicyAction, prompt: &str, error: &str) -> Self {
+        let mut entry = Self::new(POLICY_TOOL, prompt, client.connection_id.as_deref())
+            .with_action(action.as_str());
+        entry.outcome = AuditOutcome::Denied;
+        entry.error = Some(error.to_string());
+        entry
+    }
+
+    #[must_use]
+    pub fn with_action(mut self, action: &str) -> Self {
+        self.action = Some(action.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn with_target(mut self, target: &str) -> Self {
+        self.target = Some(target.to_string());
+        self
+    }
+
+    /// Content hashes before and after the operation
+    #[must_use]
+    pub fn with_hashes(mut self, before: Option<String>, after: Option<String>) -> Self {
+        self.hash_before = before;
+        self.hash_after = after;
+        self
+    }
+
+    /// Parameter names of a render, and their values when `values` is set
+    #[must_use]
+    pub fn with_parameters(
+        mut self,
+        parameters: Option<&HashMap<String, TemplateParamValue>>,
+        values: bool,
+    ) -> Self {
+        let Some(parameters) = parameters else {
+            return self;
+        };
+        let mut names: Vec<String> = parameters.keys().cloned().collect();
+        names.sort();
+        self.parameters = names;
+        if values {
+            self.parameter_values = Some(
+                parameters
+                    .iter()
+                    .map(|(name, value)| {
+                        (
+                            name.clone(),
+                            serde_json::to_value(value).unwrap_or(serde_json::Value::Null),
+                        )
+                    })
+                    .collect(),
+            );
+        }
+        self
+    }
+
+    /// Outcome and error of `result` (policy denials are marked `denied`)
+    #[must_use]
+    pub fn with_result<T>(mut self, result: &Result<T>) -> Self {
+        if let Err(e) = result {
+            self.outcome = if is_denial(e) {
+                AuditOutcome::Denied
+            } else {
+                AuditOutcome::Failure
+            };
+            self.error = Some(format!("{e:#}"));
+        }
+        self
+    }
+}
+
+/// Whether `error` is a policy denial
+pub(crate) fn is_denial(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<PolicyDenied>().is_some()
+}
+
+/// Filter for [`AuditLog::query`]
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    /// Entries whose prompt or target is this name
+    pub prompt: Option<String>,
+    pub connection_id: Option<String>,
+    pub tool: Option<String>,
+    pub outcome: Option<AuditOutcome>,
+    /// Entries recorded at or after this time
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Maximum entries returned (newest first)
+    pub limit: usize,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        if let Some(prompt) = &self.prompt
+            && entry.prompt != *prompt
+            && entry.target.as_deref() != Some(prompt.as_str())
+        {
+            return false;
+        }
+        if self.connection_id.is_some() && entry.connection_id != self.connection_id {
+            return false;
+        }
+        if let Some(tool) = &self.tool
+            && entry.tool != *tool
+        {
+            return false;
+        }
+        if let Some(outcome) = self.outcome
+            && entry.outcome != outcome
+        {
+            return false;
+        }
+        if let Some(since) = self.since {
+            match chrono::DateTime::parse_from_rfc3339(&entry.timestamp) {
+                Ok(timestamp) if timestamp >= since => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Handle to a JSONL audit log file (clones share the write lock)
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+    record_values: bool,
+    lock: Arc<Mutex<()>>,
+}
+
+impl AuditLog {
+    /// Log at `path`, created (with its directory) on the first append
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            record_values: false,
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Record rendered parameter values as well as their names
+    #[must_use]
+    pub fn with_parameter_values(mut self, record_values: bool) -> Self {
+        self.record_values = record_values;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether rendered parameter values are recorded
+    pub fn records_parameter_values(&self) -> bool {
+        self.record_values
+    }
+
+    /// Append `entry` as one line
+    pub async fn append(&self, entry: &AuditEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry).context("Failed to serialize audit entry")?;
+        line.push('\n');
+
+        let _guard = self.lock.lock().await;
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            tokio::fs::create_dir_all(parent).await.with_context(|| {
+                format!("Failed to create audit log directory: {}", parent.display())
+            })?;
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .with_context(|| format!("Failed to open audit log: {}", self.path.display()))?;
+        file.write_all(line.as_bytes())
+            .await
+            .with_context(|| format!("Failed to write audit log: {}", self.path.display()))?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Entries matching `query`, newest first
+    ///
+    /// Lines that do not parse are skipped with a warning.
+    pub async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        self.query_where(query, |_| true).await
+    }
+
+    /// Entries matching `query` that `visible` also accepts, newest first
+    ///
+    /// The log is read backwards in chunks and reading stops once `limit`
+    /// entries are found, so recent entries come back quickly from a large log.
+    pub(crate) async fn query_where(
+        &self,
+        query: &AuditQuery,
+        visible: impl Fn(&AuditEntry) -> bool,
+    ) -> Result<Vec<AuditEntry>> {
+        let read_error = || format!("Failed to read audit log: {}", self.path.display());
+        let mut file = match tokio::fs::File::open(&self.path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(read_error),
+        };
+        let mut pos = file.metadata().await.with_context(read_error)?.len();
+
+        let mut entries = Vec::new();
+        // Start of a line whose beginning lies in the part not read yet
+        let mut partial = Vec::new();
+        while pos > 0 && entries.len() < query.limit {
+            let start = pos.saturating_sub(READ_CHUNK);
+            let mut chunk = vec![0; (pos - start) as usize];
+            file.seek(std::io::SeekFrom::Start(start))
+                .await
+                .with_context(read_error)?;
+            file.read_exact(&mut chunk).await.with_context(read_error)?;
+            chunk.append(&mut partial);
+            pos = start;
+
+            // Everything before the first newline may continue further back
+            let complete = if pos == 0 {
+                0
+            } else {
+                match chunk.iter().position(|&b| b == b'\n') {
+                    Some(newline) => newline + 1,
+                    None => {
+                        partial = chunk;
+                        continue;
+                    }
+                }
+            };
+
+            let mut end = chunk.len();
+            for line in chunk[complete..].rsplit(|&b| b == b'\n') {
+                let offset = pos + (end - line.len()) as u64;
+                end = end.saturating_sub(line.len() + 1);
+                if entries.len() >= query.limit {
+                    break;
+                }
+                if line.trim_ascii().is_empty() {
+                    continue;
+                }
+                match serde_json::from_slice::<AuditEntry>(line) {
+                    Ok(entry) if query.matches(&entry) && visible(&entry) => entries.push(entry),
+                    Ok(_) => {}
+                    Err(e) => warn!(
+                        "Skipping malformed audit log line at byte {offset} in {}: {e}",
+                        self.path.display()
+                    ),
+                }
+            }
+            chunk.truncate(complete);
+            partial = chunk;
+        }
+        Ok(entries)
+    }
+}
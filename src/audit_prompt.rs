@@ -0,0 +1,104 @@
+use super::audit::AuditQuery;
+use super::manager::PromptManager;
+use super::schema::{AuditPromptArgs, PromptAuditOutput, PromptAuditPrompts, PROMPT_AUDIT};
+use kodegen_mcp_schema::{McpError, Tool, ToolExecutionContext, ToolResponse};
+
+/// Entries returned when no limit is given
+const DEFAULT_LIMIT: usize = 50;
+
+#[derive(Clone)]
+pub struct AuditPromptTool {
+    manager: PromptManager,
+}
+
+impl AuditPromptTool {
+    /// Create with a pre-initialized PromptManager (for HTTP server)
+    pub fn with_manager(manager: PromptManager) -> Self {
+        Self { manager }
+    }
+
+    /// Create with default manager (for standalone use)
+    pub async fn new() -> Result<Self, McpError> {
+        let manager = PromptManager::new();
+        manager.init().await?;
+        Ok(Self { manager })
+    }
+}
+
+impl Tool for AuditPromptTool {
+    type Args = AuditPromptArgs;
+    type Prompts = PromptAuditPrompts;
+
+    fn name() -> &'static str {
+        PROMPT_AUDIT
+    }
+
+    fn description() -> &'static str {
+        "Query the audit log (requires KODEGEN_PROMPTS_AUDIT_LOG). Every prompt change \
+         (add, edit, delete, rename, copy, import, restore), every render and every policy \
+         denial is recorded with the tool, prompt name, connection ID, timestamp and SHA-256 \
+         content hashes before and after. Filter by name, connection_id, tool, outcome \
+         (success, failure, denied) and since (RFC 3339); newest entries first."
+    }
+
+    fn read_only() -> bool {
+        true
+    }
+
+    fn destructive() -> bool {
+        false
+    }
+
+    fn idempotent() -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Self::Args, _ctx: ToolExecutionContext) -> Result<ToolResponse<<Self::Args as kodegen_mcp_schema::ToolArgs>::Output>, McpError> {
+        let since = args
+            .since
+            .as_deref()
+            .map(|since| {
+                chrono::DateTime::parse_from_rfc3339(since)
+                    .map(|time| time.with_timezone(&chrono::Utc))
+                    .map_err(|e| {
+                        McpError::InvalidArguments(format!("since must be an RFC 3339 time: {e}"))
+                    })
+            })
+            .transpose()?;
+        let query = AuditQuery {
+            prompt: args.name.clone(),
+            connection_id: args.connection_id.clone(),
+            tool: args.tool.clone(),
+            outcome: args.outcome,
+            since,
+            limit: args.limit.unwrap_or(DEFAULT_LIMIT),
+        };
+
+        let entries = self
+            .manager
+            .audit_entries(&query)
+            .await
+            .map_err(McpError::Other)?;
+
+        let mut summary = format!("\x1b[36m Prompt Audit\x1b[0m\n  Entries: {}", entries.len());
+        for entry in &entries {
+            let action = entry.action.as_deref().map(|a| format!(" {a}")).unwrap_or_default();
+            let target = entry.target.as_deref().map(|t| format!(" → {t}")).unwrap_or_default();
+            summary.push_str(&format!(
+                "\n  • {} {}{action} {}{target} · {} · {}",
+                entry.timestamp,
+                entry.tool,
+                entry.prompt,
+                entry.outcome.as_str(),
+                entry.connection_id.as_deref().unwrap_or("-")
+            ));
+        }
+
+        let output = PromptAuditOutput {
+            success: true,
+            count: entries.len(),
+            entries,
+        };
+        Ok(ToolResponse::new(summary, output))
+    }
+}
@@ -0,0 +1,463 @@
+use super::diagnostics::{Diagnostic, ValidationErrors};
+use super::manager::{is_valid_prompt_name, PROMPT_NAME_RULE};
+use super::metadata::PromptTemplate;
+use super::validation::validate_prompt_file;
+use anyhow::{Context, Result};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::Path;
+
+/// Bundle layout version written to manifests
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Manifest path inside tar.gz archives
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Directory holding `<name>.j2.md` files inside tar.gz archives
+const PROMPTS_DIR: &str = "prompts";
+
+/// Maximum number of prompts in one bundle
+const MAX_BUNDLE_ENTRIES: usize = 10_000;
+
+/// Maximum bytes read from a single archive member (larger prompts fail validation anyway)
+const MAX_ENTRY_SIZE: u64 = 2_000_000;
+
+/// On-disk / on-wire encoding of a prompt bundle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum)]
+pub enum BundleFormat {
+    /// Gzipped tar with `manifest.json` and `prompts/<name>.j2.md`
+    #[serde(rename = "tar.gz")]
+    #[value(name = "tar.gz")]
+    TarGz,
+    /// Single JSON document (manifest + prompt contents)
+    #[serde(rename = "json")]
+    #[value(name = "json")]
+    Json,
+    /// Single YAML document (manifest + prompt contents)
+    #[serde(rename = "yaml")]
+    #[value(name = "yaml")]
+    Yaml,
+}
+
+impl BundleFormat {
+    /// Format implied by a file extension (`.tar.gz`, `.tgz`, `.json`, `.yaml`, `.yml`)
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".json") {
+            Some(Self::Json)
+        } else if name.ends_with(".yaml") || name.ends_with(".yml") {
+            Some(Self::Yaml)
+        } else {
+            None
+        }
+    }
+
+    /// Format implied by the content (gzip magic, JSON object, otherwise YAML)
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Self::TarGz
+        } else if bytes.trim_ascii_start().starts_with(b"{") {
+            Self::Json
+        } else {
+            Self::Yaml
+        }
+    }
+
+    /// Name used in schemas and on the command line
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TarGz => "tar.gz",
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+        }
+    }
+
+    /// Whether the encoded bundle is binary (base64 over text transports)
+    pub fn is_binary(&self) -> bool {
+        matches!(self, Self::TarGz)
+    }
+}
+
+/// What to do when an imported prompt name already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Keep the existing prompt and skip the imported one
+    #[default]
+    Skip,
+    /// Replace the existing prompt with the imported one
+    Overwrite,
+    /// Import under the first free `<name>_<n>` name
+    Rename,
+}
+
+/// Selects prompts for export
+///
+/// Each non-empty list must match (any entry within a list); empty lists
+/// match everything. Name patterns are globs (`review_*`, `code_00?`).
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    pub categories: Vec<String>,
+    pub tags: Vec<String>,
+    pub names: Vec<String>,
+}
+
+impl ExportFilter {
+    /// Keep the templates matching this filter
+    ///
+    /// # Errors
+    /// Returns an error if a name pattern is not a valid glob.
+    pub fn select(&self, templates: Vec<PromptTemplate>) -> Result<Vec<PromptTemplate>> {
+        let globs = self.name_globs()?;
+        Ok(templates
+            .into_iter()
+            .filter(|t| {
+                (self.categories.is_empty()
+                    || t.metadata.categories.iter().any(|c| self.categories.contains(c)))
+                    && (self.tags.is_empty()
+                        || t.metadata.tags.iter().any(|tag| self.tags.contains(tag)))
+                    && globs.as_ref().is_none_or(|globs| globs.is_match(&t.filename))
+            })
+            .collect())
+    }
+
+    fn name_globs(&self) -> Result<Option<GlobSet>> {
+        if self.names.is_empty() {
+            return Ok(None);
+        }
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.names {
+            builder.add(
+                Glob::new(pattern).with_context(|| format!("Invalid name pattern: {pattern}"))?,
+            );
+        }
+        Ok(Some(builder.build()?))
+    }
+}
+
+/// Bundle manifest: what the bundle contains and checksums for every entry
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BundleManifest {
+    /// Bundle layout version
+    pub format_version: u32,
+    /// Creation time (RFC 3339)
+    pub created_at: String,
+    /// Tool that wrote the bundle
+    pub generator: String,
+    /// One entry per prompt, in bundle order
+    pub prompts: Vec<ManifestEntry>,
+}
+
+/// Manifest entry for one prompt
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ManifestEntry {
+    /// Prompt name (file name without .j2.md)
+    pub name: String,
+    /// Prompt title
+    pub title: String,
+    /// Prompt version (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// SHA-256 of the file content (hex)
+    pub sha256: String,
+}
+
+/// A prompt file carried in a bundle
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BundleEntry {
+    /// Prompt name (file name without .j2.md)
+    pub name: String,
+    /// Full file content (frontmatter + template)
+    pub content: String,
+}
+
+/// A set of prompt files plus their manifest
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptBundle {
+    pub manifest: BundleManifest,
+    pub prompts: Vec<BundleEntry>,
+}
+
+impl PromptBundle {
+    /// Build a bundle (and manifest) from templates and their raw file contents
+    pub fn from_templates(templates: Vec<(PromptTemplate, String)>) -> Self {
+        let mut manifest_entries = Vec::with_capacity(templates.len());
+        let mut prompts = Vec::with_capacity(templates.len());
+
+        for (template, content) in templates {
+            manifest_entries.push(ManifestEntry {
+                name: template.filename.clone(),
+                title: template.metadata.title,
+                version: template.metadata.version,
+                sha256: sha256_hex(&content),
+            });
+            prompts.push(BundleEntry {
+                name: template.filename,
+                content,
+            });
+        }
+
+        Self {
+            manifest: BundleManifest {
+                format_version: BUNDLE_FORMAT_VERSION,
+                created_at: chrono::Utc::now().to_rfc3339(),
+                generator: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+                prompts: manifest_entries,
+            },
+            prompts,
+        }
+    }
+
+    /// Encode the bundle in the given format
+    pub fn encode(&self, format: BundleFormat) -> Result<Vec<u8>> {
+        match format {
+            BundleFormat::Json => {
+                serde_json::to_vec_pretty(self).context("Failed to encode JSON bundle")
+            }
+            BundleFormat::Yaml => Ok(serde_yaml::to_string(self)
+                .context("Failed to encode YAML bundle")?
+                .into_bytes()),
+            BundleFormat::TarGz => self.encode_tar_gz(),
+        }
+    }
+
+    /// Decode a bundle (format detected from content when `format` is `None`)
+    ///
+    /// Decoding only checks the container; call [`PromptBundle::validate`]
+    /// before using the entries.
+    pub fn decode(bytes: &[u8], format: Option<BundleFormat>) -> Result<Self> {
+        match format.unwrap_or_else(|| BundleFormat::detect(bytes)) {
+            BundleFormat::Json => {
+                serde_json::from_slice(bytes).context("Failed to decode JSON bundle")
+            }
+            BundleFormat::Yaml => {
+                serde_yaml::from_slice(bytes).context("Failed to decode YAML bundle")
+            }
+            BundleFormat::TarGz => Self::decode_tar_gz(bytes),
+        }
+    }
+
+    /// Validate every entry before anything is written
+    ///
+    /// Checks the manifest version, prompt names (valid and unique), that each
+    /// entry is listed in the manifest with a matching checksum, and runs full
+    /// prompt file validation. Diagnostics are prefixed with the entry name.
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut diagnostics = Vec::new();
+
+        if self.manifest.format_version > BUNDLE_FORMAT_VERSION {
+            diagnostics.push(
+                Diagnostic::new(format!(
+                    "Unsupported bundle format version {} (this build reads up to {})",
+                    self.manifest.format_version, BUNDLE_FORMAT_VERSION
+                ))
+                .with_path("manifest.format_version")
+                .with_suggestion("Upgrade kodegen-tools-prompt to import this bundle"),
+            );
+        }
+
+        if self.prompts.len() > MAX_BUNDLE_ENTRIES {
+            diagnostics.push(Diagnostic::new(format!(
+                "Bundle has {} prompts (max {MAX_BUNDLE_ENTRIES})",
+                self.prompts.len()
+            )));
+        }
+
+        let mut seen = HashSet::new();
+        for (index, entry) in self.prompts.iter().enumerate() {
+            let name = &entry.name;
+            if !is_valid_prompt_name(name) {
+                diagnostics.push(
+                    Diagnostic::new(format!("Invalid prompt name '{name}'"))
+                        .with_path(format!("prompts[{index}].name"))
+                        .with_suggestion(PROMPT_NAME_RULE),
+                );
+                continue;
+            }
+            if !seen.insert(name.as_str()) {
+                diagnostics.push(
+                    Diagnostic::new(format!("Prompt '{name}' appears more than once"))
+                        .with_path(format!("prompts[{index}].name")),
+                );
+                continue;
+            }
+
+            match self.manifest.prompts.iter().find(|m| &m.name == name) {
+                None => diagnostics.push(
+                    Diagnostic::new(format!("'{name}': not listed in the manifest"))
+                        .with_path(format!("prompts[{index}]")),
+                ),
+                Some(listed) if listed.sha256 != sha256_hex(&entry.content) => diagnostics.push(
+                    Diagnostic::new(format!("'{name}': checksum does not match the manifest"))
+                        .with_path(format!("prompts[{index}].content"))
+                        .with_suggestion("The bundle was modified or truncated; re-export it"),
+                ),
+                Some(_) => {}
+            }
+
+            if let Err(errors) = validate_prompt_file(&entry.content) {
+                diagnostics.extend(errors.diagnostics.into_iter().map(|mut d| {
+                    d.message = format!("'{name}': {}", d.message);
+                    d
+                }));
+            }
+        }
+
+        for listed in &self.manifest.prompts {
+            if !self.prompts.iter().any(|entry| entry.name == listed.name) {
+                diagnostics.push(
+                    Diagnostic::new(format!("'{}': listed in the manifest but missing", listed.name))
+                        .with_path("manifest.prompts"),
+                );
+            }
+        }
+
+        if diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors::new(diagnostics))
+        }
+    }
+
+    fn encode_tar_gz(&self) -> Result<Vec<u8>> {
+        let encoder = GzEncoder::new(Vec::new(), Compression::default());
+        let mut archive = tar::Builder::new(encoder);
+        let mtime = chrono::Utc::now().timestamp().max(0) as u64;
+
+        let manifest =
+            serde_json::to_vec_pretty(&self.manifest).context("Failed to encode manifest")?;
+        append_file(&mut archive, MANIFEST_FILE, &manifest, mtime)?;
+        for entry in &self.prompts {
+            let path = format!("{PROMPTS_DIR}/{}.j2.md", entry.name);
+            append_file(&mut archive, &path, entry.content.as_bytes(), mtime)?;
+        }
+
+        archive
+            .into_inner()
+            .and_then(|encoder| encoder.finish())
+            .context("Failed to finish tar.gz bundle")
+    }
+
+    fn decode_tar_gz(bytes: &[u8]) -> Result<Self> {
+        let mut archive = tar::Archive::new(GzDecoder::new(bytes));
+        let mut manifest = None;
+        let mut prompts = Vec::new();
+
+        for entry in archive.entries().context("Failed to read tar.gz bundle")? {
+            let entry = entry.context("Failed to read tar.gz bundle entry")?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry.path()?.to_string_lossy().into_owned();
+            let path = path.trim_start_matches("./").to_string();
+
+            if path == MANIFEST_FILE {
+                let content = read_limited(entry, &path)?;
+                manifest = Some(
+                    serde_json::from_str(&content).context("Failed to decode bundle manifest")?,
+                );
+            } else if let Some(name) = path
+                .strip_prefix(&format!("{PROMPTS_DIR}/"))
+                .and_then(|file| file.strip_suffix(".j2.md"))
+            {
+                if prompts.len() >= MAX_BUNDLE_ENTRIES {
+                    anyhow::bail!("Bundle has more than {MAX_BUNDLE_ENTRIES} prompts");
+                }
+                let name = name.to_string();
+                let content = read_limited(entry, &path)?;
+                prompts.push(BundleEntry { name, content });
+            } else {
+                log::warn!("Ignoring unexpected bundle entry: {path}");
+            }
+        }
+
+        let manifest = manifest
+            .ok_or_else(|| anyhow::anyhow!("Bundle is missing {MANIFEST_FILE}"))?;
+        Ok(Self { manifest, prompts })
+    }
+}
+
+/// Outcome for one imported prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportAction {
+    /// New prompt created
+    Created,
+    /// Existing prompt replaced
+    Overwritten,
+    /// Imported under a new name (name already taken)
+    Renamed,
+    /// Existing prompt kept, bundle entry ignored
+    Skipped,
+}
+
+/// What happened to one bundle entry
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ImportOutcome {
+    /// Name in the bundle
+    pub source: String,
+    /// Name written (differs from `source` when renamed)
+    pub name: String,
+    pub action: ImportAction,
+}
+
+/// Result of importing a bundle
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ImportReport {
+    /// True when nothing was written (planned outcomes only)
+    pub dry_run: bool,
+    pub outcomes: Vec<ImportOutcome>,
+}
+
+impl ImportReport {
+    /// Number of outcomes with the given action
+    pub fn count(&self, action: ImportAction) -> usize {
+        self.outcomes.iter().filter(|o| o.action == action).count()
+    }
+}
+
+/// Hex SHA-256 of prompt content
+pub(crate) fn sha256_hex(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn append_file<W: std::io::Write>(
+    archive: &mut tar::Builder<W>,
+    path: &str,
+    data: &[u8],
+    mtime: u64,
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(mtime);
+    header.set_entry_type(tar::EntryType::Regular);
+    archive
+        .append_data(&mut header, path, data)
+        .with_context(|| format!("Failed to add {path} to bundle"))
+}
+
+/// Read an archive member as UTF-8, refusing oversized members
+fn read_limited(entry: impl Read, path: &str) -> Result<String> {
+    let mut content = String::new();
+    entry
+        .take(MAX_ENTRY_SIZE + 1)
+        .read_to_string(&mut content)
+        .with_context(|| format!("Bundle entry {path} is not valid UTF-8"))?;
+    if content.len() as u64 > MAX_ENTRY_SIZE {
+        anyhow::bail!("Bundle entry {path} is too large (max {MAX_ENTRY_SIZE} bytes)");
+    }
+    Ok(content)
+}
@@ -0,0 +1,893 @@
+// Command-line interface for managing the local prompt library.
+//
+// Every subcommand runs against the same `PromptManager` the server uses.
+// The server's own flags (`--http`, `--tls-cert`, ...) are parsed by
+// `ServerBuilder::run`, so these subcommands are only dispatched when the
+// first argument names one of them; `serve` re-executes the binary with the
+// remaining arguments to start the server.
+
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand};
+use kodegen_mcp_schema::prompt::TemplateParamValue;
+use kodegen_tools_prompt::bundle::ImportAction;
+use kodegen_tools_prompt::template::parse_template;
+use kodegen_tools_prompt::validation::check_prompt_content;
+use kodegen_tools_prompt::{
+    diagnostics_of, AttachmentSandbox, BundleFormat, ConflictPolicy, Diagnostic, ExportFilter,
+    PromptBundle, PromptManager, PromptQuery, PromptServerConfig, PromptTemplate, ValidationErrors,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// `println!` that ignores a closed stdout (e.g. output piped into `head`),
+/// so the command still finishes with its real exit code
+macro_rules! outln {
+    ($($arg:tt)*) => {{
+        let _ = writeln!(std::io::stdout().lock(), $($arg)*);
+    }};
+}
+
+/// `print!` counterpart of [`outln!`]
+macro_rules! out {
+    ($($arg:tt)*) => {{
+        let _ = write!(std::io::stdout().lock(), $($arg)*);
+    }};
+}
+
+/// Success
+const EXIT_OK: i32 = 0;
+/// Operation failed (unknown prompt, name taken, I/O or store error)
+const EXIT_FAILURE: i32 = 1;
+/// Content or bundle rejected by validation
+const EXIT_INVALID: i32 = 2;
+/// `lint` found warnings but no errors
+const EXIT_WARNINGS: i32 = 3;
+
+#[derive(Parser)]
+#[command(name = "kodegen-prompt", about = "Prompt template library tools")]
+struct Cli {
+    /// Print machine-readable JSON on stdout
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List prompts, optionally filtered
+    List(ListCmd),
+    /// Print a prompt's source (frontmatter and template)
+    Show(ShowCmd),
+    /// Render a prompt with parameters
+    Render(RenderCmd),
+    /// Add a prompt from a file
+    Add(AddCmd),
+    /// Replace a prompt's content from a file, or edit it in $VISUAL / $EDITOR
+    Edit(EditCmd),
+    /// Delete prompts
+    Delete(DeleteCmd),
+    /// Check prompt files without saving them
+    Validate(ValidateCmd),
+    /// Check stored prompts for errors and lint warnings
+    Lint(LintCmd),
+    /// Export prompts to a bundle (tar.gz, json or yaml)
+    Export(ExportCmd),
+    /// Import prompts from a bundle
+    Import(ImportCmd),
+    /// Start the MCP server (remaining arguments are server flags)
+    Serve(ServeCmd),
+    /// Copy a prompts directory into a SQLite database
+    #[cfg(feature = "sqlite")]
+    Migrate(MigrateCmd),
+}
+
+#[derive(Args)]
+struct ListCmd {
+    /// Only prompts in this namespace (and below)
+    #[arg(long)]
+    namespace: Option<String>,
+    /// Only prompts in this category
+    #[arg(long)]
+    category: Option<String>,
+    /// Only prompts with this tag
+    #[arg(long)]
+    tag: Option<String>,
+    /// Only prompts by this author (case-insensitive)
+    #[arg(long)]
+    author: Option<String>,
+    /// Words that must appear in the name, title, description, tags or body
+    #[arg(short, long)]
+    query: Option<String>,
+    /// Maximum number of prompts
+    #[arg(long)]
+    limit: Option<usize>,
+}
+
+#[derive(Args)]
+struct ShowCmd {
+    /// Prompt name (or alias)
+    name: String,
+}
+
+#[derive(Args)]
+struct RenderCmd {
+    /// Prompt name (or alias)
+    name: String,
+    /// Parameter as `key=value` (string) or `key:=<json>` (number, boolean,
+    /// string array); repeatable
+    #[arg(short, long = "param", value_name = "KEY=VALUE", value_parser = parse_param)]
+    params: Vec<(String, TemplateParamValue)>,
+    /// Render role-tagged messages instead of one string
+    #[arg(long)]
+    messages: bool,
+}
+
+#[derive(Args)]
+struct AddCmd {
+    /// Prompt file (`-` reads stdin)
+    file: PathBuf,
+    /// Prompt name (default: the file name without `.j2.md`)
+    #[arg(long)]
+    name: Option<String>,
+}
+
+#[derive(Args)]
+struct EditCmd {
+    /// Prompt name
+    name: String,
+    /// File with the new content (`-` reads stdin); opens an editor when omitted
+    file: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct DeleteCmd {
+    /// Prompt names
+    #[arg(required = true)]
+    names: Vec<String>,
+}
+
+#[derive(Args)]
+struct ValidateCmd {
+    /// Prompt files (`-` reads stdin)
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+struct LintCmd {
+    /// Prompt names (default: every stored prompt)
+    names: Vec<String>,
+}
+
+#[derive(Args)]
+struct ExportCmd {
+    /// Output file (stdout when omitted; format inferred from the extension)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Bundle format (default: from --output extension, else json)
+    #[arg(long, value_enum)]
+    format: Option<BundleFormat>,
+    /// Only prompts in this category (repeatable)
+    #[arg(long = "category")]
+    categories: Vec<String>,
+    /// Only prompts with this tag (repeatable)
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+    /// Only prompts whose name matches this glob (repeatable)
+    #[arg(long = "name")]
+    names: Vec<String>,
+}
+
+#[derive(Args)]
+struct ImportCmd {
+    /// Bundle file (`-` reads stdin)
+    file: PathBuf,
+    /// Bundle format (default: from extension, else detected from content)
+    #[arg(long, value_enum)]
+    format: Option<BundleFormat>,
+    /// What to do when a prompt name already exists
+    #[arg(long, value_enum, default_value_t = ConflictPolicy::Skip)]
+    on_conflict: ConflictPolicy,
+    /// Show what would happen without writing anything
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Args)]
+#[command(disable_help_flag = true)]
+struct ServeCmd {
+    /// Speak MCP over stdin/stdout instead of HTTP
+    #[arg(long)]
+    stdio: bool,
+    /// Server flags: --http, --tls-cert, --tls-key, --shutdown-timeout-secs,
+    /// --keep-alive (`serve --help` lists them)
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<OsString>,
+}
+
+#[cfg(feature = "sqlite")]
+#[derive(Args)]
+struct MigrateCmd {
+    /// SQLite database to create or extend
+    #[arg(long)]
+    to: PathBuf,
+    /// Prompts directory to read (default: the configured prompts directory)
+    #[arg(long)]
+    from: Option<PathBuf>,
+}
+
+/// Subcommands handled here; anything else goes to the server
+const SUBCOMMANDS: &[&str] = &[
+    "list",
+    "show",
+    "render",
+    "add",
+    "edit",
+    "delete",
+    "validate",
+    "lint",
+    "export",
+    "import",
+    "serve",
+    #[cfg(feature = "sqlite")]
+    "migrate",
+    "help",
+];
+
+/// Whether argv names a CLI subcommand (rather than server flags)
+pub fn is_subcommand_invocation() -> bool {
+    std::env::args()
+        .skip(1)
+        .find(|arg| arg != "--json")
+        .is_some_and(|arg| SUBCOMMANDS.contains(&arg.as_str()))
+}
+
+/// Run the subcommand and return the process exit code
+///
+/// Errors are reported here (as JSON with `--json`): validation failures
+/// exit with [`EXIT_INVALID`], anything else with [`EXIT_FAILURE`].
+pub async fn run() -> i32 {
+    let cli = Cli::parse();
+    let json = cli.json;
+
+    dispatch(cli.command, json)
+        .await
+        .unwrap_or_else(|e| report_error(&e, json))
+}
+
+async fn dispatch(command: Command, json: bool) -> Result<i32> {
+    match command {
+        Command::List(cmd) => list(&manager().await?, cmd, json).await,
+        Command::Show(cmd) => show(&manager().await?, cmd, json).await,
+        Command::Render(cmd) => {
+            let manager = manager().await?;
+            let result = render(&manager, cmd, json).await;
+            // Render counts are batched; save this one before exiting
+            if let Err(e) = manager.flush_usage().await {
+                log::warn!("Failed to save prompt usage: {e:#}");
+            }
+            result
+        }
+        Command::Add(cmd) => add(&manager().await?, cmd, json).await,
+        Command::Edit(cmd) => edit(&manager().await?, cmd, json).await,
+        Command::Delete(cmd) => delete(&manager().await?, cmd, json).await,
+        Command::Validate(cmd) => validate(cmd, json),
+        Command::Lint(cmd) => lint(&manager().await?, cmd, json).await,
+        Command::Export(cmd) => export(&manager().await?, cmd, json).await,
+        Command::Import(cmd) => import(&manager().await?, cmd, json).await,
+        Command::Serve(cmd) => serve(cmd).await,
+        #[cfg(feature = "sqlite")]
+        Command::Migrate(cmd) => migrate(cmd, json).await,
+    }
+}
+
+/// Manager for the configured store (see `PromptManager::from_env`)
+async fn manager() -> Result<PromptManager> {
+    let manager = PromptManager::from_env()?;
+    manager.init().await?;
+    Ok(manager)
+}
+
+/// Print an error (with its diagnostics) and pick the exit code
+fn report_error(error: &anyhow::Error, json: bool) -> i32 {
+    let validation = error.downcast_ref::<ValidationErrors>();
+    if json {
+        print_json(&json!({
+            "success": false,
+            "error": format!("{error:#}"),
+            "diagnostics": diagnostics_of(error),
+        }));
+    } else if let Some(errors) = validation {
+        eprintln!("error: invalid prompt content");
+        for diagnostic in &errors.diagnostics {
+            eprintln!("  {diagnostic}");
+        }
+    } else {
+        eprintln!("error: {error:#}");
+    }
+
+    if validation.is_some() {
+        EXIT_INVALID
+    } else {
+        EXIT_FAILURE
+    }
+}
+
+fn print_json(value: &serde_json::Value) {
+    outln!("{}", serde_json::to_string_pretty(value).unwrap_or_default());
+}
+
+/// Parse `key=value` (string) or `key:=<json>` (typed) render parameters
+fn parse_param(arg: &str) -> Result<(String, TemplateParamValue), String> {
+    let (key, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE or KEY:=JSON, got '{arg}'"))?;
+
+    let (key, value) = match key.strip_suffix(':') {
+        Some(key) => {
+            let value: serde_json::Value =
+                serde_json::from_str(value).map_err(|e| format!("invalid JSON for '{key}': {e}"))?;
+            let value = serde_json::from_value(value).map_err(|_| {
+                format!("'{key}' must be a string, number, boolean, or array of strings")
+            })?;
+            (key, value)
+        }
+        None => (key, TemplateParamValue::String(value.to_string())),
+    };
+
+    if key.is_empty() {
+        return Err(format!("missing parameter name in '{arg}'"));
+    }
+    Ok((key.to_string(), value))
+}
+
+/// Read a file, or stdin for `-`
+fn read_input(path: &Path) -> Result<Vec<u8>> {
+    if path.as_os_str() == "-" {
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes)?;
+        Ok(bytes)
+    } else {
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+    }
+}
+
+fn read_text_input(path: &Path) -> Result<String> {
+    String::from_utf8(read_input(path)?)
+        .with_context(|| format!("{} is not valid UTF-8", path.display()))
+}
+
+/// Summary of a prompt for `list --json`
+fn summary_json(template: &PromptTemplate) -> serde_json::Value {
+    let meta = &template.metadata;
+    json!({
+        "name": template.filename,
+        "title": meta.title,
+        "description": meta.description,
+        "categories": meta.categories,
+        "tags": meta.tags,
+        "author": meta.author,
+        "version": meta.version,
+        "verified": meta.verified,
+        "deprecated": meta.deprecated,
+    })
+}
+
+async fn list(manager: &PromptManager, cmd: ListCmd, json: bool) -> Result<i32> {
+    let query = PromptQuery {
+        namespace: cmd.namespace,
+        category: cmd.category,
+        tag: cmd.tag,
+        author: cmd.author,
+        text: cmd.query,
+        limit: cmd.limit,
+    };
+    let templates = manager.search_prompts(&query).await?;
+
+    if json {
+        print_json(&json!({
+            "count": templates.len(),
+            "prompts": templates.iter().map(summary_json).collect::<Vec<_>>(),
+        }));
+        return Ok(EXIT_OK);
+    }
+
+    let width = templates.iter().map(|t| t.filename.len()).max().unwrap_or(0);
+    for template in &templates {
+        let meta = &template.metadata;
+        let mut line = format!("{:<width$}  {}", template.filename, meta.title);
+        if !meta.categories.is_empty() {
+            line.push_str(&format!("  [{}]", meta.categories.join(", ")));
+        }
+        if meta.deprecated {
+            line.push_str("  (deprecated)");
+        }
+        outln!("{line}");
+    }
+    Ok(EXIT_OK)
+}
+
+async fn show(manager: &PromptManager, cmd: ShowCmd, json: bool) -> Result<i32> {
+    let source = manager.read_prompt_source(&cmd.name).await?;
+
+    if json {
+        // Content that no longer parses is still shown, without metadata
+        let template = manager.load_prompt(&cmd.name).await.ok();
+        print_json(&json!({
+            "name": template.as_ref().map_or(cmd.name.as_str(), |t| t.filename.as_str()),
+            "metadata": template.as_ref().map(|t| &t.metadata),
+            "source": source,
+        }));
+    } else {
+        out!("{source}");
+        if !source.ends_with('\n') {
+            outln!();
+        }
+    }
+    Ok(EXIT_OK)
+}
+
+async fn render(manager: &PromptManager, cmd: RenderCmd, json: bool) -> Result<i32> {
+    let parameters: HashMap<String, TemplateParamValue> = cmd.params.into_iter().collect();
+    let parameters = (!parameters.is_empty()).then_some(parameters);
+
+    if !cmd.messages {
+        let rendered = manager.render_prompt(&cmd.name, parameters).await?;
+        let content = rendered.content;
+        if json {
+            print_json(&json!({ "name": cmd.name, "content": content, "tokens": rendered.tokens }));
+        } else {
+            out!("{content}");
+            if !content.ends_with('\n') {
+                outln!();
+            }
+        }
+        return Ok(EXIT_OK);
+    }
+
+    // Attachments resolve against (and stay inside) the working directory
+    let cwd = std::env::current_dir().context("Cannot determine working directory")?;
+    let sandbox = AttachmentSandbox::for_workspace(Some(&cwd), None);
+    let rendered = manager
+        .render_prompt_messages(&cmd.name, parameters, sandbox.as_ref())
+        .await?;
+
+    if json {
+        print_json(&json!({
+            "name": cmd.name,
+            "messages": rendered
+                .messages
+                .iter()
+                .map(|m| json!({
+                    "role": m.role,
+                    "content": m.content,
+                    "attachments": m.attachments.iter().map(|a| json!({
+                        "path": a.path,
+                        "uri": a.uri,
+                        "mime_type": a.mime_type,
+                    })).collect::<Vec<_>>(),
+                }))
+                .collect::<Vec<_>>(),
+            "tokens": rendered.tokens,
+        }));
+    } else {
+        for (index, message) in rendered.messages.iter().enumerate() {
+            if index > 0 {
+                outln!();
+            }
+            outln!("[{}]", message.role.as_str());
+            outln!("{}", message.content);
+            for attachment in &message.attachments {
+                outln!("  attached: {} ({})", attachment.path, attachment.mime_type);
+            }
+        }
+    }
+    Ok(EXIT_OK)
+}
+
+async fn add(manager: &PromptManager, cmd: AddCmd, json: bool) -> Result<i32> {
+    let name = match cmd.name {
+        Some(name) => name,
+        None => prompt_name_from_path(&cmd.file)
+            .context("Cannot derive a prompt name from the file; pass --name")?,
+    };
+    let content = read_text_input(&cmd.file)?;
+
+    manager.add_prompt(&name, &content).await?;
+
+    if json {
+        print_json(&json!({ "success": true, "name": name }));
+    } else {
+        eprintln!("Added prompt '{name}'");
+    }
+    Ok(EXIT_OK)
+}
+
+/// `review.j2.md` -> `review` (stdin has no name)
+fn prompt_name_from_path(path: &Path) -> Option<String> {
+    if path.as_os_str() == "-" {
+        return None;
+    }
+    let file_name = path.file_name()?.to_str()?;
+    let name = file_name
+        .strip_suffix(".j2.md")
+        .or_else(|| file_name.strip_suffix(".md"))
+        .unwrap_or(file_name);
+    Some(name.to_string())
+}
+
+async fn edit(manager: &PromptManager, cmd: EditCmd, json: bool) -> Result<i32> {
+    let content = match &cmd.file {
+        Some(file) => read_text_input(file)?,
+        None => {
+            let source = manager.read_prompt_source(&cmd.name).await?;
+            let edited = edit_in_editor(&cmd.name, &source).await?;
+            if edited == source {
+                if json {
+                    print_json(&json!({ "success": true, "name": cmd.name, "changed": false }));
+                } else {
+                    eprintln!("No changes to '{}'", cmd.name);
+                }
+                return Ok(EXIT_OK);
+            }
+            edited
+        }
+    };
+
+    manager.edit_prompt(&cmd.name, &content).await?;
+
+    if json {
+        print_json(&json!({ "success": true, "name": cmd.name, "changed": true }));
+    } else {
+        eprintln!("Updated prompt '{}'", cmd.name);
+    }
+    Ok(EXIT_OK)
+}
+
+/// Open `source` in `$VISUAL` / `$EDITOR` and return the saved text
+async fn edit_in_editor(name: &str, source: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .context("Set $VISUAL or $EDITOR, or pass a file with the new content")?;
+    let mut words = editor.split_whitespace();
+    let program = words.next().context("$VISUAL / $EDITOR is empty")?;
+
+    let leaf = name.rsplit('/').next().unwrap_or(name);
+    let path = std::env::temp_dir().join(format!("kodegen-prompt-{}-{leaf}.j2.md", std::process::id()));
+    std::fs::write(&path, source)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let status = tokio::process::Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status()
+        .await;
+    let edited = std::fs::read_to_string(&path);
+    if let Err(e) = std::fs::remove_file(&path) {
+        log::warn!("Failed to remove {}: {e}", path.display());
+    }
+
+    let status = status.with_context(|| format!("Failed to run editor '{program}'"))?;
+    if !status.success() {
+        anyhow::bail!("Editor exited with {status}; '{name}' was not changed");
+    }
+    edited.with_context(|| format!("Failed to read {}", path.display()))
+}
+
+async fn delete(manager: &PromptManager, cmd: DeleteCmd, json: bool) -> Result<i32> {
+    for name in &cmd.names {
+        manager.delete_prompt(name).await?;
+        if !json {
+            eprintln!("Deleted prompt '{name}'");
+        }
+    }
+    if json {
+        print_json(&json!({ "success": true, "deleted": cmd.names }));
+    }
+    Ok(EXIT_OK)
+}
+
+/// Check results for one file or prompt
+struct CheckReport {
+    label: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl CheckReport {
+    fn error_count(&self) -> usize {
+        self.diagnostics.iter().filter(|d| d.is_error()).count()
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let errors = self.error_count();
+        json!({
+            "name": self.label,
+            "valid": errors == 0,
+            "error_count": errors,
+            "warning_count": self.diagnostics.len() - errors,
+            "diagnostics": self.diagnostics,
+        })
+    }
+}
+
+/// Print check results and pick the exit code
+///
+/// Errors exit with [`EXIT_INVALID`]; `warnings_fail` makes warnings alone
+/// exit with [`EXIT_WARNINGS`].
+fn finish_checks(reports: &[CheckReport], json: bool, warnings_fail: bool) -> i32 {
+    let errors: usize = reports.iter().map(CheckReport::error_count).sum();
+    let total: usize = reports.iter().map(|r| r.diagnostics.len()).sum();
+    let warnings = total - errors;
+
+    if json {
+        print_json(&json!({
+            "success": errors == 0,
+            "error_count": errors,
+            "warning_count": warnings,
+            "results": reports.iter().map(CheckReport::to_json).collect::<Vec<_>>(),
+        }));
+    } else {
+        for report in reports {
+            for diagnostic in &report.diagnostics {
+                outln!("{}: {diagnostic}", report.label);
+            }
+        }
+        eprintln!(
+            "Checked {} prompt(s): {errors} error(s), {warnings} warning(s)",
+            reports.len()
+        );
+    }
+
+    if errors > 0 {
+        EXIT_INVALID
+    } else if warnings_fail && warnings > 0 {
+        EXIT_WARNINGS
+    } else {
+        EXIT_OK
+    }
+}
+
+fn validate(cmd: ValidateCmd, json: bool) -> Result<i32> {
+    let mut reports = Vec::with_capacity(cmd.files.len());
+    for file in &cmd.files {
+        let content = read_text_input(file)?;
+        let name = prompt_name_from_path(file).unwrap_or_else(|| "_validation".to_string());
+        let label = if file.as_os_str() == "-" {
+            "<stdin>".to_string()
+        } else {
+            file.display().to_string()
+        };
+        reports.push(CheckReport {
+            label,
+            diagnostics: check_prompt_content(&name, &content),
+        });
+    }
+    Ok(finish_checks(&reports, json, false))
+}
+
+async fn lint(manager: &PromptManager, cmd: LintCmd, json: bool) -> Result<i32> {
+    let names = if cmd.names.is_empty() {
+        manager.prompt_names().await?
+    } else {
+        cmd.names
+    };
+
+    let mut reports = Vec::with_capacity(names.len());
+    for name in names {
+        let source = manager.read_prompt_source(&name).await?;
+        let mut diagnostics = check_prompt_content(&name, &source);
+
+        // Stored prompts must also parse under their own name
+        if diagnostics.is_empty()
+            && let Err(e) = parse_template(&name, &source)
+        {
+            diagnostics = diagnostics_of(&e);
+        }
+        reports.push(CheckReport {
+            label: name,
+            diagnostics,
+        });
+    }
+    Ok(finish_checks(&reports, json, true))
+}
+
+async fn export(manager: &PromptManager, cmd: ExportCmd, json: bool) -> Result<i32> {
+    let format = cmd
+        .format
+        .or_else(|| cmd.output.as_deref().and_then(BundleFormat::from_path))
+        .unwrap_or(BundleFormat::Json);
+    let filter = ExportFilter {
+        categories: cmd.categories,
+        tags: cmd.tags,
+        names: cmd.names,
+    };
+
+    let bundle = manager.export_prompts(&filter).await?;
+    let bytes = bundle.encode(format)?;
+
+    match &cmd.output {
+        Some(path) => {
+            std::fs::write(path, &bytes)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            if json {
+                print_json(&json!({
+                    "success": true,
+                    "format": format.as_str(),
+                    "count": bundle.prompts.len(),
+                    "output": path,
+                    "manifest": bundle.manifest,
+                }));
+            } else {
+                eprintln!(
+                    "Exported {} prompt(s) to {} ({})",
+                    bundle.prompts.len(),
+                    path.display(),
+                    format.as_str()
+                );
+            }
+        }
+        // The bundle itself is the output
+        None => std::io::stdout().write_all(&bytes)?,
+    }
+    Ok(EXIT_OK)
+}
+
+async fn import(manager: &PromptManager, cmd: ImportCmd, json: bool) -> Result<i32> {
+    let bytes = read_input(&cmd.file)?;
+    let format = cmd.format.or_else(|| BundleFormat::from_path(&cmd.file));
+
+    let result = match PromptBundle::decode(&bytes, format) {
+        Ok(bundle) => manager.import_prompts(&bundle, cmd.on_conflict, cmd.dry_run).await,
+        Err(e) => Err(e),
+    };
+    let report = match result {
+        Ok(report) => report,
+        Err(e) => {
+            if json {
+                print_json(&json!({
+                    "success": false,
+                    "error": "Import rejected; nothing was written",
+                    "diagnostics": diagnostics_of(&e),
+                }));
+            } else {
+                eprintln!("Import rejected; nothing was written:");
+                for diagnostic in diagnostics_of(&e) {
+                    eprintln!("  {diagnostic}");
+                }
+            }
+            return Ok(EXIT_INVALID);
+        }
+    };
+
+    if json {
+        print_json(&json!({
+            "success": true,
+            "dry_run": report.dry_run,
+            "created": report.count(ImportAction::Created),
+            "overwritten": report.count(ImportAction::Overwritten),
+            "renamed": report.count(ImportAction::Renamed),
+            "skipped": report.count(ImportAction::Skipped),
+            "outcomes": report.outcomes,
+        }));
+        return Ok(EXIT_OK);
+    }
+
+    for outcome in &report.outcomes {
+        let action = match outcome.action {
+            ImportAction::Created => "create",
+            ImportAction::Overwritten => "overwrite",
+            ImportAction::Renamed => "rename",
+            ImportAction::Skipped => "skip",
+        };
+        if outcome.source == outcome.name {
+            outln!("{action:<9} {}", outcome.name);
+        } else {
+            outln!("{action:<9} {} -> {}", outcome.source, outcome.name);
+        }
+    }
+    eprintln!(
+        "{}: {} created, {} overwritten, {} renamed, {} skipped",
+        if report.dry_run { "Dry run" } else { "Imported" },
+        report.count(ImportAction::Created),
+        report.count(ImportAction::Overwritten),
+        report.count(ImportAction::Renamed),
+        report.count(ImportAction::Skipped),
+    );
+    Ok(EXIT_OK)
+}
+
+/// Start the server by re-executing this binary with the server flags
+///
+/// `ServerBuilder::run` parses the process arguments itself, so the
+/// `serve` word has to be dropped from argv rather than passed through.
+/// `--stdio` serves in this process instead.
+async fn serve(cmd: ServeCmd) -> Result<i32> {
+    if let Some(first) = cmd.args.first().and_then(|arg| arg.to_str())
+        && SUBCOMMANDS.contains(&first)
+    {
+        anyhow::bail!("Unexpected argument '{first}' for serve");
+    }
+
+    if cmd.stdio {
+        if let Some(arg) = cmd.args.first() {
+            anyhow::bail!(
+                "--stdio does not take server flags (got '{}')",
+                arg.to_string_lossy()
+            );
+        }
+        PromptServerConfig::from_env().stdio().run().await?;
+        return Ok(EXIT_OK);
+    }
+
+    let exe = std::env::current_exe().context("Cannot locate the kodegen-prompt binary")?;
+    let mut command = std::process::Command::new(exe);
+    command.args(&cmd.args);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Only returns on failure
+        let error = command.exec();
+        Err(error).context("Failed to start the server")
+    }
+
+    #[cfg(not(unix))]
+    {
+        let status = command.status().context("Failed to start the server")?;
+        Ok(status.code().unwrap_or(EXIT_FAILURE))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+async fn migrate(cmd: MigrateCmd, json: bool) -> Result<i32> {
+    use kodegen_tools_prompt::store::{migrate_prompts, FsStore, PromptStore, SqliteStore};
+
+    let from = match cmd.from {
+        Some(dir) => dir,
+        None => PromptManager::new()
+            .prompts_dir()
+            .map(PathBuf::from)
+            .context("Cannot determine prompts directory; pass --from")?,
+    };
+    if !from.is_dir() {
+        anyhow::bail!("Prompts directory not found: {}", from.display());
+    }
+
+    let source = FsStore::new(&from);
+    let target = SqliteStore::open(&cmd.to)?;
+    target.init().await?;
+
+    let report = migrate_prompts(&source, &target).await?;
+    if json {
+        print_json(&json!({
+            "success": true,
+            "from": from,
+            "to": cmd.to,
+            "copied": report.copied,
+            "skipped": report.skipped,
+        }));
+        return Ok(EXIT_OK);
+    }
+
+    for name in &report.skipped {
+        outln!("skip      {name} (already in database)");
+    }
+    eprintln!(
+        "Migrated {} prompt(s) from {} to {} ({} skipped)",
+        report.copied.len(),
+        from.display(),
+        cmd.to.display(),
+        report.skipped.len()
+    );
+    eprintln!(
+        "Set {}={} to serve from the database",
+        kodegen_tools_prompt::manager::PROMPTS_DB_ENV,
+        cmd.to.display()
+    );
+    Ok(EXIT_OK)
+}
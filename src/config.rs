@@ -0,0 +1,838 @@
+//! Server bootstrap shared by the binary and embedders (kodegend)
+//!
+//! [`PromptServerConfig`] decides where prompts are stored, which tools are
+//! registered, the rendering limits, and the transport. The binary builds it
+//! from the environment; embedders can start from the same defaults and
+//! override what they need:
+//!
+//! ```no_run
+//! # async fn example(listener: tokio::net::TcpListener) -> anyhow::Result<()> {
+//! use kodegen_tools_prompt::PromptServerConfig;
+//!
+//! let handle = PromptServerConfig::from_env()
+//!     .prompts_dir("/srv/prompts")
+//!     .enabled_tools(["prompt_get", "prompt_validate"])
+//!     .http_listener(listener)
+//!     .start()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::audit::{AuditLog, AUDIT_LOG_ENV, AUDIT_VALUES_ENV};
+use crate::env_policy::EnvPolicy;
+use crate::git_store::git_storage_enabled;
+use crate::manager::{
+    PromptManager, PROMPTS_DB_ENV, READ_ONLY_ENV, READ_ONLY_NAMESPACES_ENV, SEARCH_ENV,
+};
+use crate::metadata::RenderLimits;
+use crate::store::{FsStore, SearchMode};
+use crate::policy::{scope_routes, Policy, POLICY_ENV};
+use crate::prompt_routes::TemplatePrompts;
+use crate::stdio::StdioServer;
+use crate::tokens::TokenEstimator;
+use crate::usage::{UsageStore, USAGE_ENV};
+use anyhow::{Context, Result};
+use kodegen_mcp_schema::Tool;
+use kodegen_server_http::{
+    register_tool, Managers, RouterSet, ServerBuilder, ServerHandle, ShutdownHook,
+};
+use log::{debug, warn};
+use rmcp::handler::server::router::{prompt::PromptRouter, tool::ToolRouter};
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// How long an embedded HTTP server may take to finish in-flight requests
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Size and resource limits applied when rendering templates
+///
+/// Defaults come from `KODEGEN_MAX_PARAM_SIZE`, `KODEGEN_MAX_PARAM_COUNT`,
+/// `KODEGEN_MAX_TOTAL_PARAMS_SIZE`, `KODEGEN_MAX_ATTACHMENT_SIZE`,
+/// `KODEGEN_MAX_ATTACHMENTS`, `KODEGEN_MAX_TOTAL_ATTACHMENT_SIZE`,
+/// `KODEGEN_RENDER_TIMEOUT_MS`, `KODEGEN_RENDER_FUEL`,
+/// `KODEGEN_MAX_RECURSION_DEPTH` and `KODEGEN_MAX_OUTPUT_SIZE`. Each
+/// [`PromptManager`] carries its own limits
+/// ([`with_limits`](PromptManager::with_limits)), so servers in one process
+/// can differ. A prompt can tighten the render limits in its frontmatter
+/// ([`RenderLimits`](crate::RenderLimits)) but not raise them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptLimits {
+    /// Largest single parameter value, in bytes
+    pub max_param_size: usize,
+    /// Most parameters per render
+    pub max_param_count: usize,
+    /// Largest combined size of all parameter values, in bytes
+    pub max_total_params_size: usize,
+    /// Largest file `attach()` / `image()` may read, in bytes
+    pub max_attachment_size: u64,
+    /// Most `attach()` / `image()` calls per render
+    pub max_attachments: usize,
+    /// Largest combined size of the files one render attaches, in bytes
+    pub max_total_attachment_size: u64,
+    /// How long a caller waits for a render
+    pub render_timeout: Duration,
+    /// Template instructions a render may execute
+    ///
+    /// Unlike the timeout, running out of fuel stops the render thread, so a
+    /// runaway template cannot keep a CPU busy after the caller gave up.
+    pub render_fuel: u64,
+    /// Deepest nesting of macro calls and blocks
+    pub max_recursion_depth: usize,
+    /// Largest rendered output, in bytes
+    pub max_output_size: usize,
+}
+
+impl Default for PromptLimits {
+    fn default() -> Self {
+        Self {
+            max_param_size: 1_000_000,
+            max_param_count: 100,
+            max_total_params_size: 10_000_000,
+            max_attachment_size: 5_000_000,
+            max_attachments: 20,
+            max_total_attachment_size: 20_000_000,
+            render_timeout: Duration::from_secs(5),
+            render_fuel: 50_000_000,
+            max_recursion_depth: 500,
+            max_output_size: 10_000_000,
+        }
+    }
+}
+
+impl PromptLimits {
+    /// Defaults, overridden by the `KODEGEN_MAX_*` environment variables
+    pub fn from_env() -> Self {
+        fn env<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(default)
+        }
+
+        let defaults = Self::default();
+        Self {
+            max_param_size: env("KODEGEN_MAX_PARAM_SIZE", defaults.max_param_size),
+            max_param_count: env("KODEGEN_MAX_PARAM_COUNT", defaults.max_param_count),
+            max_total_params_size: env(
+                "KODEGEN_MAX_TOTAL_PARAMS_SIZE",
+                defaults.max_total_params_size,
+            ),
+            max_attachment_size: env("KODEGEN_MAX_ATTACHMENT_SIZE", defaults.max_attachment_size),
+            max_attachments: env("KODEGEN_MAX_ATTACHMENTS", defaults.max_attachments),
+            max_total_attachment_size: env(
+                "KODEGEN_MAX_TOTAL_ATTACHMENT_SIZE",
+                defaults.max_total_attachment_size,
+            ),
+            render_timeout: Duration::from_millis(env(
+                "KODEGEN_RENDER_TIMEOUT_MS",
+                defaults.render_timeout.as_millis() as u64,
+            )),
+            render_fuel: env("KODEGEN_RENDER_FUEL", defaults.render_fuel),
+            max_recursion_depth: env("KODEGEN_MAX_RECURSION_DEPTH", defaults.max_recursion_depth),
+            max_output_size: env("KODEGEN_MAX_OUTPUT_SIZE", defaults.max_output_size),
+        }
+    }
+
+    /// Render limits for one prompt: the prompt's own limits, capped at these
+    pub fn for_prompt(&self, prompt: Option<&RenderLimits>) -> Self {
+        let prompt = prompt.copied().unwrap_or_default();
+        Self {
+            render_timeout: prompt
+                .timeout_ms
+                .map_or(self.render_timeout, |ms| {
+                    self.render_timeout.min(Duration::from_millis(ms))
+                }),
+            render_fuel: prompt.fuel.map_or(self.render_fuel, |f| f.min(self.render_fuel)),
+            max_recursion_depth: prompt
+                .recursion_depth
+                .map_or(self.max_recursion_depth, |d| d.min(self.max_recursion_depth)),
+            max_output_size: prompt
+                .max_output_size
+                .map_or(self.max_output_size, |s| s.min(self.max_output_size)),
+            ..*self
+        }
+    }
+
+    /// Make these the process-wide default limits
+    ///
+    /// The defaults apply to managers created afterwards and to the free
+    /// `render_template*` functions. They are fixed once installed or first
+    /// used, so this must run before any manager is created.
+    ///
+    /// # Errors
+    /// Fails when different defaults are already in effect; give the manager
+    /// its own limits with [`PromptManager::with_limits`] instead.
+    pub fn install(self) -> Result<()> {
+        let installed = *LIMITS.get_or_init(|| self);
+        if installed != self {
+            anyhow::bail!(
+                "Default prompt limits are already in effect ({installed:?}) and cannot be \
+                 replaced; use PromptManager::with_limits to give a manager its own limits"
+            );
+        }
+        Ok(())
+    }
+}
+
+static LIMITS: OnceLock<PromptLimits> = OnceLock::new();
+
+/// Whether a boolean environment variable is set (`1`, `true`, `yes`, `on`)
+pub(crate) fn env_flag(name: &str) -> bool {
+    std::env::var(name)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
+}
+
+/// Process-wide default limits (from the environment unless installed earlier)
+pub(crate) fn limits() -> &'static PromptLimits {
+    LIMITS.get_or_init(PromptLimits::from_env)
+}
+
+/// Where the server talks to its clients
+#[derive(Debug, Default)]
+pub enum Transport {
+    /// HTTP/HTTPS configured by the process arguments (`--http`,
+    /// `--tls-cert`, ...), running until a shutdown signal
+    #[default]
+    HttpFromArgs,
+    /// HTTP/HTTPS on a pre-bound listener (TOCTOU-safe, used by kodegend)
+    HttpListener {
+        listener: tokio::net::TcpListener,
+        tls: Option<(PathBuf, PathBuf)>,
+    },
+    /// MCP over stdin/stdout, until the client disconnects
+    Stdio,
+}
+
+/// Configuration for a prompt MCP server
+///
+/// Built with chained setters, then started with [`run`](Self::run) or
+/// [`start`](Self::start). Routers for a custom server type can be built
+/// with [`register_tools`](Self::register_tools).
+#[derive(Default)]
+pub struct PromptServerConfig {
+    settings: Settings,
+    transport: Transport,
+}
+
+/// Everything except the transport (cloned into the registration closure)
+#[derive(Clone, Default)]
+struct Settings {
+    prompts_dir: Option<PathBuf>,
+    database: Option<PathBuf>,
+    git_storage: bool,
+    read_only: bool,
+    read_only_namespaces: Vec<String>,
+    search: Option<SearchMode>,
+    policy_file: Option<PathBuf>,
+    policy: Option<Arc<Policy>>,
+    audit_log: Option<PathBuf>,
+    audit_parameter_values: bool,
+    usage_file: Option<PathBuf>,
+    token_estimator: Option<Arc<dyn TokenEstimator>>,
+    manager: Option<PromptManager>,
+    enabled_tools: Option<BTreeSet<String>>,
+    disable_template_prompts: bool,
+    limits: Option<PromptLimits>,
+    env_policy: Option<EnvPolicy>,
+}
+
+impl PromptServerConfig {
+    /// Defaults: the kodegen prompts directory, every tool, HTTP from args
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defaults plus the storage settings from the environment
+    ///
+    /// `KODEGEN_PROMPTS_DB` selects a SQLite database instead of the prompts
+    /// directory (requires the `sqlite` feature); `KODEGEN_PROMPTS_GIT=1`
+    /// enables git storage and `KODEGEN_PROMPTS_READ_ONLY=1` read-only mode;
+    /// `KODEGEN_PROMPTS_READ_ONLY_NAMESPACES` lists read-only namespaces and
+    /// `KODEGEN_PROMPTS_SEARCH` picks the [`SearchMode`].
+    /// `KODEGEN_PROMPTS_POLICY` names a client policy file and
+    /// `KODEGEN_PROMPTS_AUDIT_LOG` an audit log (`KODEGEN_PROMPTS_AUDIT_VALUES=1`
+    /// also records rendered parameter values). `KODEGEN_PROMPTS_USAGE` moves
+    /// the usage analytics file.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let mut config = Self::new();
+        config.settings.database = std::env::var_os(PROMPTS_DB_ENV).map(PathBuf::from);
+        config.settings.git_storage = git_storage_enabled();
+        config.settings.read_only = env_flag(READ_ONLY_ENV);
+        config.settings.read_only_namespaces = std::env::var(READ_ONLY_NAMESPACES_ENV)
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|namespace| !namespace.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        config.settings.search = std::env::var(SEARCH_ENV).ok().and_then(|value| {
+            value
+                .parse()
+                .inspect_err(|e| warn!("Ignoring {SEARCH_ENV}: {e}"))
+                .ok()
+        });
+        config.settings.policy_file = std::env::var_os(POLICY_ENV).map(PathBuf::from);
+        config.settings.audit_log = std::env::var_os(AUDIT_LOG_ENV).map(PathBuf::from);
+        config.settings.audit_parameter_values = env_flag(AUDIT_VALUES_ENV);
+        config.settings.usage_file = std::env::var_os(USAGE_ENV).map(PathBuf::from);
+        config
+    }
+
+    /// Store prompts as files under `dir`
+    #[must_use]
+    pub fn prompts_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.settings.prompts_dir = Some(dir.into());
+        self.settings.database = None;
+        self
+    }
+
+    /// Store prompts in a SQLite database
+    #[cfg(feature = "sqlite")]
+    #[must_use]
+    pub fn database(mut self, path: impl Into<PathBuf>) -> Self {
+        self.settings.database = Some(path.into());
+        self
+    }
+
+    /// Commit every change to git (directory-backed storage only)
+    #[must_use]
+    pub fn git_storage(mut self, enabled: bool) -> Self {
+        self.settings.git_storage = enabled;
+        self
+    }
+
+    /// Serve the library read-only
+    ///
+    /// Tools that only change prompts (`prompt_add`, `prompt_edit`,
+    /// `prompt_delete`, `prompt_rename`, `prompt_copy`, `prompt_import`) are
+    /// not registered, and the manager rejects every write, including
+    /// `prompt_history` restores.
+    #[must_use]
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.settings.read_only = read_only;
+        self
+    }
+
+    /// Serve the prompts in `namespace` (and below) read-only
+    ///
+    /// Every tool stays registered; the manager rejects changes to prompts
+    /// in the namespace, so e.g. a `system` layer is immutable while the
+    /// rest of the library stays writable.
+    #[must_use]
+    pub fn read_only_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.settings.read_only_namespaces.push(namespace.into());
+        self
+    }
+
+    /// Search with the store's index, by scanning every prompt, or whichever fits
+    ///
+    /// The default, [`SearchMode::Auto`], uses SQLite's full-text index when
+    /// serving from a database and scans the prompts directory otherwise.
+    /// [`SearchMode::Index`] without a database fails at startup.
+    #[must_use]
+    pub fn search(mut self, mode: SearchMode) -> Self {
+        self.settings.search = Some(mode);
+        self
+    }
+
+    /// Authorize every client request against `policy`
+    #[must_use]
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.settings.policy = Some(Arc::new(policy));
+        self.settings.policy_file = None;
+        self
+    }
+
+    /// Authorize every client request against a YAML policy file
+    ///
+    /// The file is read when the server starts.
+    #[must_use]
+    pub fn policy_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.settings.policy_file = Some(path.into());
+        self.settings.policy = None;
+        self
+    }
+
+    /// Append every change, render and policy denial to a JSONL log at `path`
+    #[must_use]
+    pub fn audit_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.settings.audit_log = Some(path.into());
+        self
+    }
+
+    /// Record rendered parameter values in the audit log, not just their names
+    #[must_use]
+    pub fn audit_parameter_values(mut self, enabled: bool) -> Self {
+        self.settings.audit_parameter_values = enabled;
+        self
+    }
+
+    /// Keep usage analytics (render counts, last use, votes) in this JSON file
+    ///
+    /// Defaults to `.usage.json` in the prompts directory, or beside the
+    /// SQLite database.
+    #[must_use]
+    pub fn usage_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.settings.usage_file = Some(path.into());
+        self
+    }
+
+    /// Count rendered tokens with `estimator` instead of the built-in heuristic
+    ///
+    /// Applies to the manager this config creates; give a manager passed to
+    /// [`manager`](Self::manager) its own with `PromptManager::with_token_estimator`.
+    #[must_use]
+    pub fn token_estimator(mut self, estimator: impl TokenEstimator + 'static) -> Self {
+        self.settings.token_estimator = Some(Arc::new(estimator));
+        self
+    }
+
+    /// Serve an existing manager (any store), ignoring the storage settings
+    #[must_use]
+    pub fn manager(mut self, manager: PromptManager) -> Self {
+        self.settings.manager = Some(manager);
+        self
+    }
+
+    /// Register only these tools (by name, e.g. `prompt_get`)
+    ///
+    /// Unknown names are reported when the server starts.
+    #[must_use]
+    pub fn enabled_tools<I>(mut self, names: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.settings.enabled_tools = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Whether templates are served through `prompts/list` and `prompts/get`
+    #[must_use]
+    pub fn template_prompts(mut self, enabled: bool) -> Self {
+        self.settings.disable_template_prompts = !enabled;
+        self
+    }
+
+    /// Rendering limits for this server's manager
+    ///
+    /// Also applied to a manager passed to [`manager`](Self::manager).
+    /// Defaults to the process-wide limits ([`PromptLimits::install`], or
+    /// the environment).
+    #[must_use]
+    pub fn limits(mut self, limits: PromptLimits) -> Self {
+        self.settings.limits = Some(limits);
+        self
+    }
+
+    /// Which environment variables templates can read as `env`
+    ///
+    /// Installed process-wide when the server starts, replacing the policy
+    /// from `KODEGEN_ALLOWED_ENV_VARS` / `KODEGEN_BLOCKED_ENV_VARS`. Change it
+    /// later with [`EnvPolicy::install`].
+    #[must_use]
+    pub fn env_policy(mut self, policy: EnvPolicy) -> Self {
+        self.settings.env_policy = Some(policy);
+        self
+    }
+
+    /// Serve HTTP on a pre-bound listener
+    #[must_use]
+    pub fn http_listener(mut self, listener: tokio::net::TcpListener) -> Self {
+        self.transport = Transport::HttpListener { listener, tls: None };
+        self
+    }
+
+    /// Serve HTTPS with this certificate and key (with [`http_listener`](Self::http_listener))
+    #[must_use]
+    pub fn tls(mut self, cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        if let Transport::HttpListener { tls, .. } = &mut self.transport {
+            *tls = Some((cert.into(), key.into()));
+        } else {
+            warn!("TLS settings only apply to a pre-bound listener; ignoring");
+        }
+        self
+    }
+
+    /// Serve MCP over stdin/stdout
+    #[must_use]
+    pub fn stdio(mut self) -> Self {
+        self.transport = Transport::Stdio;
+        self
+    }
+
+    /// Set the transport directly
+    #[must_use]
+    pub fn transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Manager for the configured storage (not yet initialized)
+    pub fn create_manager(&self) -> Result<PromptManager> {
+        self.settings.create_manager()
+    }
+
+    /// Register the enabled tools and template prompts for `manager`
+    ///
+    /// Generic over the server type that owns the routers, so custom MCP
+    /// servers can serve the same tools.
+    pub async fn register_tools<S>(
+        &self,
+        manager: &PromptManager,
+        tool_router: ToolRouter<S>,
+        prompt_router: PromptRouter<S>,
+    ) -> Result<(ToolRouter<S>, PromptRouter<S>)>
+    where
+        S: Send + Sync + 'static,
+    {
+        self.settings.register_tools(manager, tool_router, prompt_router).await
+    }
+
+    /// Run the server until shutdown
+    ///
+    /// HTTP servers stop on a shutdown signal, stdio servers when the client
+    /// disconnects. Either way, pending usage counts are saved first.
+    pub async fn run(self) -> Result<()> {
+        let Self {
+            settings,
+            transport,
+        } = self;
+        settings.validate()?;
+
+        match transport {
+            Transport::HttpFromArgs => {
+                ServerBuilder::new()
+                    .category(kodegen_config::CATEGORY_PROMPT)
+                    .register_tools(move || async move { settings.router_set().await })
+                    .run()
+                    .await
+            }
+            transport @ Transport::HttpListener { .. } => {
+                let handle = Self {
+                    settings,
+                    transport,
+                }
+                .start()
+                .await?;
+                tokio::signal::ctrl_c()
+                    .await
+                    .context("Failed to listen for the shutdown signal")?;
+                handle.cancel();
+                handle
+                    .wait_for_completion(SHUTDOWN_TIMEOUT)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Prompt server shutdown failed: {e}"))
+            }
+            Transport::Stdio => {
+                // stdout carries the protocol; logs go to stderr
+                let _ = env_logger::Builder::from_env(
+                    env_logger::Env::default().default_filter_or("warn"),
+                )
+                .target(env_logger::Target::Stderr)
+                .try_init();
+
+                settings.install_globals();
+                let manager = settings.create_manager()?;
+                manager.init().await?;
+                // Templates are looked up per request rather than registered as
+                // routes, so prompts added or deleted later are listed correctly
+                let routes = Settings {
+                    disable_template_prompts: true,
+                    ..settings.clone()
+                };
+                let (tool_router, prompt_router, served) = routes
+                    .register_routes(&manager, ToolRouter::new(), PromptRouter::new())
+                    .await?;
+                let mut server = StdioServer::new(tool_router, prompt_router);
+                if !settings.disable_template_prompts {
+                    server = server.with_template_prompts(TemplatePrompts::new(served));
+                }
+                let result = server.serve().await;
+                if let Err(e) = manager.flush_usage().await {
+                    warn!("Failed to save prompt usage: {e:#}");
+                }
+                result
+            }
+        }
+    }
+
+    /// Start an HTTP server on the pre-bound listener and return its handle
+    ///
+    /// Non-blocking: the server runs in background tasks. Requires the
+    /// [`HttpListener`](Transport::HttpListener) transport. Cancelling the
+    /// handle saves pending usage counts before shutdown completes.
+    pub async fn start(self) -> Result<ServerHandle> {
+        let Transport::HttpListener { listener, tls } = self.transport else {
+            anyhow::bail!("start() requires a pre-bound HTTP listener; use run() instead");
+        };
+        let settings = self.settings;
+        settings.validate()?;
+
+        let mut builder = ServerBuilder::new()
+            .category(kodegen_config::CATEGORY_PROMPT)
+            .register_tools(move || async move { settings.router_set().await })
+            .with_listener(listener);
+        if let Some((cert, key)) = tls {
+            builder = builder.with_tls_config(cert, key);
+        }
+        builder.serve().await
+    }
+}
+
+/// Saves the last batch of render counts when an HTTP server shuts down
+struct FlushUsage(PromptManager);
+
+impl ShutdownHook for FlushUsage {
+    fn shutdown(&self) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+        Box::pin(self.0.flush_usage())
+    }
+}
+
+impl Settings {
+    fn create_manager(&self) -> Result<PromptManager> {
+        if let Some(manager) = &self.manager {
+            let mut manager = self.with_read_only(manager.clone());
+            if let Some(limits) = self.limits {
+                manager = manager.with_limits(limits);
+            }
+            if let Some(mode) = self.search {
+                manager = manager.with_search_mode(mode);
+            }
+            return Ok(self.with_audit(manager));
+        }
+        if self.search == Some(SearchMode::Index) && self.database.is_none() {
+            anyhow::bail!(
+                "Search mode 'index' needs a prompt database; the prompts directory has no search index"
+            );
+        }
+
+        let mut manager = match &self.database {
+            #[cfg(feature = "sqlite")]
+            Some(path) => PromptManager::with_store(crate::store::SqliteStore::open(path)?)
+                .with_usage_store(UsageStore::at(path.with_extension("usage.json"))),
+            #[cfg(not(feature = "sqlite"))]
+            Some(_) => anyhow::bail!(
+                "{PROMPTS_DB_ENV} is set, but this build does not include the `sqlite` feature"
+            ),
+            None => match &self.prompts_dir {
+                Some(dir) => PromptManager::with_store(FsStore::new(dir)),
+                None => PromptManager::new(),
+            },
+        };
+        if self.git_storage {
+            manager = manager.with_git_storage();
+        }
+        if let Some(path) = &self.usage_file {
+            manager = manager.with_usage_store(UsageStore::at(path));
+        }
+        if let Some(estimator) = &self.token_estimator {
+            manager = manager.with_token_estimator(estimator.clone());
+        }
+        if let Some(limits) = self.limits {
+            manager = manager.with_limits(limits);
+        }
+        if let Some(mode) = self.search {
+            manager = manager.with_search_mode(mode);
+        }
+        Ok(self.with_audit(self.with_read_only(manager)))
+    }
+
+    /// Apply the read-only settings on top of the manager's own
+    fn with_read_only(&self, manager: PromptManager) -> PromptManager {
+        let read_only = self.read_only || manager.is_read_only();
+        self.read_only_namespaces
+            .iter()
+            .fold(manager.with_read_only(read_only), |manager, namespace| {
+                manager.with_read_only_namespace(namespace.clone())
+            })
+    }
+
+    /// Attach the configured audit log unless the manager already has one
+    fn with_audit(&self, manager: PromptManager) -> PromptManager {
+        match &self.audit_log {
+            Some(path) if manager.audit_log().is_none() => manager.with_audit_log(
+                AuditLog::new(path).with_parameter_values(self.audit_parameter_values),
+            ),
+            _ => manager,
+        }
+    }
+
+    /// Policy to enforce, reading the policy file if one is configured
+    fn load_policy(&self) -> Result<Option<Arc<Policy>>> {
+        match (&self.policy, &self.policy_file) {
+            (Some(policy), _) => Ok(Some(policy.clone())),
+            (None, Some(path)) => Ok(Some(Arc::new(Policy::load(path)?))),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Reject tool names that no tool answers to
+    fn validate(&self) -> Result<()> {
+        if let Some(enabled) = &self.enabled_tools {
+            let known = tool_names();
+            let unknown: Vec<&str> = enabled
+                .iter()
+                .map(String::as_str)
+                .filter(|name| !known.contains(name))
+                .collect();
+            if !unknown.is_empty() {
+                anyhow::bail!(
+                    "Unknown tool name(s): {}. Available tools: {}",
+                    unknown.join(", "),
+                    known.join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Install the process-wide env policy, if set
+    fn install_globals(&self) {
+        if let Some(policy) = &self.env_policy {
+            policy.clone().install();
+        }
+    }
+
+    /// Routers for the HTTP server (`ServerBuilder::register_tools`)
+    async fn router_set<S>(self) -> Result<RouterSet<S>>
+    where
+        S: Send + Sync + 'static,
+    {
+        self.install_globals();
+        let manager = self.create_manager()?;
+        manager.init().await?;
+        let (tool_router, prompt_router) = self
+            .register_tools(&manager, ToolRouter::new(), PromptRouter::new())
+            .await?;
+        let managers = Managers::new();
+        managers.register(FlushUsage(manager)).await;
+        Ok(RouterSet::new(tool_router, prompt_router, managers))
+    }
+
+    async fn register_tools<S>(
+        &self,
+        manager: &PromptManager,
+        tool_router: ToolRouter<S>,
+        prompt_router: PromptRouter<S>,
+    ) -> Result<(ToolRouter<S>, PromptRouter<S>)>
+    where
+        S: Send + Sync + 'static,
+    {
+        let (tool_router, prompt_router, _) =
+            self.register_routes(manager, tool_router, prompt_router).await?;
+        Ok((tool_router, prompt_router))
+    }
+
+    /// Register the routes, also returning the manager the tools share
+    /// (read-only settings, audit log and policy applied)
+    async fn register_routes<S>(
+        &self,
+        manager: &PromptManager,
+        tool_router: ToolRouter<S>,
+        prompt_router: PromptRouter<S>,
+    ) -> Result<(ToolRouter<S>, PromptRouter<S>, PromptManager)>
+    where
+        S: Send + Sync + 'static,
+    {
+        use crate::*;
+
+        self.validate()?;
+        let policy = self.load_policy()?;
+        let mut manager = self.with_audit(self.with_read_only(manager.clone()));
+        if let Some(policy) = &policy {
+            manager = manager.with_policy(policy.clone());
+        }
+        let served = manager;
+        let manager = &served;
+        let mut routers = (tool_router, prompt_router);
+
+        // Register the enabled prompt management tools with the shared manager
+        routers = self.register(routers, AddPromptTool::with_manager(manager.clone()));
+        routers = self.register(routers, EditPromptTool::with_manager(manager.clone()));
+        routers = self.register(routers, DeletePromptTool::with_manager(manager.clone()));
+        routers = self.register(routers, GetPromptTool::with_manager(manager.clone()));
+        routers = self.register(routers, ValidatePromptTool::with_manager(manager.clone()));
+        routers = self.register(routers, RenamePromptTool::with_manager(manager.clone()));
+        routers = self.register(routers, CopyPromptTool::with_manager(manager.clone()));
+        routers = self.register(routers, ExportPromptsTool::with_manager(manager.clone()));
+        routers = self.register(routers, ImportPromptsTool::with_manager(manager.clone()));
+        routers = self.register(routers, HistoryPromptTool::with_manager(manager.clone()));
+        routers = self.register(routers, AuditPromptTool::with_manager(manager.clone()));
+        routers = self.register(routers, StatsPromptTool::with_manager(manager.clone()));
+        routers = self.register(routers, VotePromptTool::with_manager(manager.clone()));
+
+        let (tool_router, mut prompt_router) = routers;
+        let tool_router = crate::metrics::instrument_routes(tool_router);
+
+        // Serve the templates themselves through prompts/list and prompts/get
+        if !self.disable_template_prompts {
+            prompt_router = prompt_routes::register_template_prompts(prompt_router, manager).await;
+        }
+
+        // Requests run on behalf of their client, so the policy can see who asks
+        let (tool_router, prompt_router) = match policy {
+            Some(_) => scope_routes(tool_router, prompt_router),
+            None => (tool_router, prompt_router),
+        };
+        Ok((tool_router, prompt_router, served))
+    }
+
+    fn register<S, T>(
+        &self,
+        (tool_router, prompt_router): (ToolRouter<S>, PromptRouter<S>),
+        tool: T,
+    ) -> (ToolRouter<S>, PromptRouter<S>)
+    where
+        S: Send + Sync + 'static,
+        T: Tool,
+    {
+        if self
+            .enabled_tools
+            .as_ref()
+            .is_some_and(|enabled| !enabled.contains(T::name()))
+        {
+            debug!("Tool disabled by configuration: {}", T::name());
+            return (tool_router, prompt_router);
+        }
+        // prompt_history stays: its read actions work, and restores are
+        // rejected by the read-only manager
+        if self.read_only && !T::read_only() && T::name() != crate::HistoryPromptTool::name() {
+            debug!("Tool disabled in read-only mode: {}", T::name());
+            return (tool_router, prompt_router);
+        }
+        register_tool(tool_router, prompt_router, tool)
+    }
+}
+
+/// Names of every tool the server can register
+pub fn tool_names() -> [&'static str; 13] {
+    use crate::*;
+
+    [
+        AddPromptTool::name(),
+        EditPromptTool::name(),
+        DeletePromptTool::name(),
+        GetPromptTool::name(),
+        ValidatePromptTool::name(),
+        RenamePromptTool::name(),
+        CopyPromptTool::name(),
+        ExportPromptsTool::name(),
+        ImportPromptsTool::name(),
+        HistoryPromptTool::name(),
+        AuditPromptTool::name(),
+        StatsPromptTool::name(),
+        VotePromptTool::name(),
+    ]
+}
@@ -0,0 +1,100 @@
+use super::audit::AuditEntry;
+use super::manager::PromptManager;
+use super::schema::{CopyPromptArgs, PromptCopyOutput, PromptCopyPrompts, PROMPT_COPY};
+use kodegen_mcp_schema::{McpError, Tool, ToolExecutionContext, ToolResponse};
+
+#[derive(Clone)]
+pub struct CopyPromptTool {
+    manager: PromptManager,
+}
+
+impl CopyPromptTool {
+    /// Create with a pre-initialized PromptManager (for HTTP server)
+    pub fn with_manager(manager: PromptManager) -> Self {
+        Self { manager }
+    }
+
+    /// Create with default manager (for standalone use)
+    pub async fn new() -> Result<Self, McpError> {
+        let manager = PromptManager::new();
+        manager.init().await?;
+        Ok(Self { manager })
+    }
+}
+
+impl Tool for CopyPromptTool {
+    type Args = CopyPromptArgs;
+    type Prompts = PromptCopyPrompts;
+
+    fn name() -> &'static str {
+        PROMPT_COPY
+    }
+
+    fn description() -> &'static str {
+        "Copy (fork) a prompt template under a new name. The copy records the source prompt in \
+         forked_from, takes the given author (or keeps the source author), and starts \
+         unverified with zero votes. Aliases are not copied. Fails if a prompt with the new \
+         name already exists. The new name must contain only alphanumeric characters, \
+         hyphens, and underscores."
+    }
+
+    fn read_only() -> bool {
+        false
+    }
+
+    fn destructive() -> bool {
+        false // Creates new file, source is unchanged
+    }
+
+    fn idempotent() -> bool {
+        false // Will fail if the copy already exists
+    }
+
+    async fn execute(&self, args: Self::Args, ctx: ToolExecutionContext) -> Result<ToolResponse<<Self::Args as kodegen_mcp_schema::ToolArgs>::Output>, McpError> {
+        let manager = self.manager.for_connection(ctx.connection_id());
+        let result = manager
+            .copy_prompt(&args.name, &args.new_name, args.author.as_deref())
+            .await;
+        // The source may be named by an alias; hash the prompt actually copied
+        let (hash_before, hash_after) = match &result {
+            Ok(template) => (
+                manager
+                    .audit_hash(template.metadata.forked_from.as_deref().unwrap_or_default())
+                    .await,
+                manager.audit_hash(&args.new_name).await,
+            ),
+            Err(_) => (None, None),
+        };
+        manager
+            .audit(
+                AuditEntry::new(PROMPT_COPY, &args.name, ctx.connection_id())
+                    .with_target(&args.new_name)
+                    .with_hashes(hash_before, hash_after)
+                    .with_result(&result),
+            )
+            .await;
+        let template = result.map_err(McpError::Other)?;
+
+        let forked_from = template.metadata.forked_from.clone().unwrap_or_default();
+        let path = manager.prompt_path(&args.new_name).map(|path| path.display().to_string());
+
+        // Terminal summary
+        let summary = format!(
+            "\x1b[32m Prompt Copied: {} → {}\x1b[0m\n\
+              Author: {}",
+            forked_from, args.new_name, template.metadata.author
+        );
+
+        // Typed output
+        let output = PromptCopyOutput {
+            success: true,
+            name: args.new_name.clone(),
+            message: format!("Prompt '{}' copied to '{}'", forked_from, args.new_name),
+            forked_from,
+            author: template.metadata.author,
+            path,
+        };
+
+        Ok(ToolResponse::new(summary, output))
+    }
+}
@@ -1,9 +1,6 @@
+use super::store::{PromptStore, StoreError};
 use anyhow::{Context, Result};
 use log::debug;
-use std::io::ErrorKind;
-use std::path::Path;
-use tokio::fs::OpenOptions;
-use tokio::io::AsyncWriteExt;
 
 /// Default prompts embedded at compile time
 pub const DEFAULT_PROMPTS: &[(&str, &str)] = &[
@@ -25,53 +22,24 @@ pub const DEFAULT_PROMPTS: &[(&str, &str)] = &[
     ),
 ];
 
-/// Write default prompts to the prompts directory
+/// Write default prompts to the prompt store
 ///
 /// Only writes prompts that don't already exist (preserves user modifications)
-pub async fn write_default_prompts(prompts_dir: &Path) -> Result<()> {
+pub async fn write_default_prompts(store: &dyn PromptStore) -> Result<()> {
     for (name, content) in DEFAULT_PROMPTS {
-        let path = prompts_dir.join(format!("{name}.j2.md"));
-
-        // Atomic create-new: only write if file doesn't exist
-        match OpenOptions::new()
-            .write(true)
-            .create_new(true)  // Fails if file exists - preserves user changes
-            .open(&path)
-            .await
-        {
-            Ok(mut file) => {
-                // File created successfully, write default content
-                file.write_all(content.as_bytes())
-                    .await
-                    .with_context(|| format!("Failed to write default prompt: {name}"))?;
-                
-                file.flush()
-                    .await
-                    .with_context(|| format!("Failed to flush default prompt: {name}"))?;
-                
+        // Atomic create-new: only write if the prompt doesn't exist
+        match store.create(name, content).await {
+            Ok(()) => {
                 debug!("Wrote default prompt: {name}");
             }
-            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
-                // File exists - skip silently (user has customized it)
+            Err(StoreError::AlreadyExists) => {
+                // Prompt exists - skip silently (user has customized it)
                 debug!("Skipped default prompt '{name}' (already exists)");
             }
-            Err(e) if e.kind() == ErrorKind::PermissionDenied => {
-                // Permission error - propagate with helpful context
-                return Err(e).with_context(|| {
-                    format!(
-                        "Permission denied writing default prompt '{name}' to {}. \
-                         Check directory permissions.",
-                        path.display()
-                    )
-                });
-            }
             Err(e) => {
-                // Other IO error - propagate with context
+                // Permission or other storage error - propagate with context
                 return Err(e).with_context(|| {
-                    format!(
-                        "Failed to create default prompt '{name}' at {}",
-                        path.display()
-                    )
+                    format!("Failed to create default prompt '{name}'. Check storage permissions.")
                 });
             }
         }
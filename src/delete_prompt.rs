@@ -1,3 +1,5 @@
+use super::audit::AuditEntry;
+use super::bundle::sha256_hex;
 use super::manager::PromptManager;
 use kodegen_mcp_schema::{McpError, Tool, ToolExecutionContext, ToolResponse};
 use kodegen_mcp_schema::prompt::{DeletePromptArgs, PromptDeleteOutput, PromptDeletePrompts, PROMPT_DELETE};
@@ -46,17 +48,28 @@ impl Tool for DeletePromptTool {
         false // Second deletion will fail (file gone)
     }
 
-    async fn execute(&self, args: Self::Args, _ctx: ToolExecutionContext) -> Result<ToolResponse<<Self::Args as kodegen_mcp_schema::ToolArgs>::Output>, McpError> {
+    async fn execute(&self, args: Self::Args, ctx: ToolExecutionContext) -> Result<ToolResponse<<Self::Args as kodegen_mcp_schema::ToolArgs>::Output>, McpError> {
         if !args.confirm {
             return Err(McpError::InvalidArguments(
                 "Must set confirm=true to delete a prompt".into(),
             ));
         }
 
-        self.manager
-            .delete_prompt(&args.name)
-            .await
-            .map_err(McpError::Other)?;
+        let manager = self.manager.for_connection(ctx.connection_id());
+        // Hash what the delete removed, so a refused or failed delete records none
+        let result = manager.remove_prompt(&args.name).await;
+        let hash_before = match (&result, manager.audit_log()) {
+            (Ok(content), Some(_)) => Some(sha256_hex(content)),
+            _ => None,
+        };
+        manager
+            .audit(
+                AuditEntry::new(PROMPT_DELETE, &args.name, ctx.connection_id())
+                    .with_hashes(hash_before, None)
+                    .with_result(&result),
+            )
+            .await;
+        result.map_err(McpError::Other)?;
 
         // Terminal summary
         let summary = format!(
@@ -0,0 +1,560 @@
+use super::metadata::{ParameterDefinition, RenderLimits, TokenOverflow};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::LazyLock;
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::Marker;
+
+/// Frontmatter keys that must be present
+const REQUIRED_FIELDS: &[&str] = &["title", "description", "categories", "author"];
+
+/// Matches frontmatter-relative positions embedded in serde_yaml messages
+static LOCATION_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r" at line \d+ column \d+").expect("Failed to compile location pattern")
+});
+
+/// Matches serde type errors: `invalid type: string "x", expected a boolean`
+static INVALID_TYPE_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"invalid (?:type|value|length): (.+?), expected (.+?)$")
+        .expect("Failed to compile invalid type pattern")
+});
+
+/// Matches serde enum errors: ``unknown variant `int`, expected one of `string`, ...``
+static UNKNOWN_VARIANT_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"unknown variant `([^`]*)`, expected (.+?)$")
+        .expect("Failed to compile unknown variant pattern")
+});
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The prompt file is rejected
+    #[default]
+    Error,
+    /// Lint finding; the prompt file is still accepted
+    Warning,
+}
+
+impl Severity {
+    pub fn is_error(&self) -> bool {
+        *self == Self::Error
+    }
+}
+
+/// A single problem found while validating a prompt file
+///
+/// Locations are 1-based and relative to the whole file (frontmatter delimiters
+/// included), so they can be shown to the author as-is.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Diagnostic {
+    /// What is wrong
+    pub message: String,
+    /// Line in the prompt file (1-based)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// Column in the prompt file (1-based)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// Offending frontmatter key path (e.g. `parameters[1].default`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Expected type or value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+    /// Type or value actually found
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual: Option<String>,
+    /// Suggested fix
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+    /// Error (rejects the file) or warning (lint finding); omitted for errors
+    #[serde(default, skip_serializing_if = "Severity::is_error")]
+    pub severity: Severity,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn at(mut self, line: usize, column: usize) -> Self {
+        self.line = Some(line);
+        self.column = Some(column);
+        self
+    }
+
+    #[must_use]
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    #[must_use]
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_types(mut self, expected: impl Into<String>, actual: impl Into<String>) -> Self {
+        self.expected = Some(expected.into());
+        self.actual = Some(actual.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_suggestion(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+
+    #[must_use]
+    pub fn as_warning(mut self) -> Self {
+        self.severity = Severity::Warning;
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity.is_error()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.is_error() {
+            f.write_str("warning: ")?;
+        }
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "line {line}, column {column}: ")?,
+            (Some(line), None) => write!(f, "line {line}: ")?,
+            _ => {}
+        }
+        if let Some(path) = &self.path {
+            write!(f, "`{path}`: ")?;
+        }
+        f.write_str(&self.message)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " ({suggestion})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Validation failure carrying every diagnostic found in a prompt file
+///
+/// Returned (wrapped in `anyhow::Error`) by `parse_template` and
+/// `validate_prompt_file`; use [`diagnostics_of`] to recover the list.
+#[derive(Debug, Clone)]
+pub struct ValidationErrors {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationErrors {
+    pub fn new(diagnostics: Vec<Diagnostic>) -> Self {
+        Self { diagnostics }
+    }
+
+    pub fn single(diagnostic: Diagnostic) -> Self {
+        Self::new(vec![diagnostic])
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.diagnostics.as_slice() {
+            [] => f.write_str("Invalid prompt file"),
+            [diagnostic] => write!(f, "{diagnostic}"),
+            diagnostics => {
+                write!(f, "Invalid prompt file ({} errors):", diagnostics.len())?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n  - {diagnostic}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// Diagnostics carried by an error, or a single diagnostic built from its message
+pub fn diagnostics_of(error: &anyhow::Error) -> Vec<Diagnostic> {
+    match error.downcast_ref::<ValidationErrors>() {
+        Some(errors) => errors.diagnostics.clone(),
+        None => vec![Diagnostic::new(format!("{error:#}"))],
+    }
+}
+
+/// Validation diagnostics carried by an error, if it is a [`ValidationErrors`]
+pub(crate) fn validation_diagnostics(error: &anyhow::Error) -> Option<Vec<Diagnostic>> {
+    error
+        .downcast_ref::<ValidationErrors>()
+        .map(|errors| errors.diagnostics.clone())
+}
+
+/// One diagnostic per line for terminal summaries
+pub(crate) fn format_diagnostic_lines(diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|d| format!("  • {d}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Raw frontmatter text and the file line it starts on
+pub(crate) struct Frontmatter<'a> {
+    pub text: &'a str,
+    /// File line (1-based) of the first frontmatter line
+    pub first_line: usize,
+    /// Byte offset just past the closing `---` line
+    pub end: usize,
+}
+
+/// Locate the `---` delimited frontmatter block at the top of a prompt file
+pub(crate) fn split_frontmatter(file_content: &str) -> Option<Frontmatter<'_>> {
+    let mut offset = 0;
+    let mut start = None;
+
+    for (line, raw) in (1..).zip(file_content.split_inclusive('\n')) {
+        let trimmed = raw.trim_end();
+        match start {
+            None if trimmed.is_empty() => {}
+            None if trimmed == "---" => start = Some((offset + raw.len(), line + 1)),
+            None => return None,
+            Some((begin, first_line)) if trimmed == "---" => {
+                return Some(Frontmatter {
+                    text: &file_content[begin..offset],
+                    first_line,
+                    end: offset + raw.len(),
+                });
+            }
+            Some(_) => {}
+        }
+        offset += raw.len();
+    }
+
+    None
+}
+
+/// Number of lines before the template body starts in the file
+pub(crate) fn body_line_offset(file_content: &str, body: &str) -> usize {
+    let search_from = split_frontmatter(file_content).map_or(0, |fm| fm.end);
+    let start = if body.is_empty() {
+        None
+    } else {
+        file_content[search_from..]
+            .find(body)
+            .map(|i| search_from + i)
+    };
+    start.map_or(0, |start| file_content[..start].matches('\n').count())
+}
+
+/// Diagnose frontmatter that failed to deserialize
+///
+/// Reports YAML syntax errors, then missing required keys and wrongly-typed
+/// values for every known key (not just the first), each with its location.
+pub(crate) fn diagnose_frontmatter(file_content: &str) -> Vec<Diagnostic> {
+    let Some(frontmatter) = split_frontmatter(file_content) else {
+        return vec![
+            Diagnostic::new("No frontmatter found in template")
+                .at(1, 1)
+                .with_suggestion(
+                    "Start the file with a YAML block delimited by '---' lines containing \
+                     title, description, categories, and author",
+                ),
+        ];
+    };
+
+    let value: serde_yaml::Value = match serde_yaml::from_str(frontmatter.text) {
+        Ok(value) => value,
+        Err(e) => {
+            let mut diagnostic = Diagnostic::new(format!("Invalid YAML: {}", strip_location(&e)))
+                .with_suggestion(
+                    "Check indentation and quoting; quote values that contain ':' or '#'",
+                );
+            if let Some(location) = e.location() {
+                diagnostic = diagnostic.at(
+                    frontmatter.first_line + location.line() - 1,
+                    location.column(),
+                );
+            }
+            return vec![diagnostic];
+        }
+    };
+
+    let locator = YamlLocator::new(&frontmatter);
+    let serde_yaml::Value::Mapping(mapping) = value else {
+        return vec![
+            Diagnostic::new("Frontmatter must be a mapping of keys to values")
+                .at(frontmatter.first_line, 1)
+                .with_types("mapping", yaml_type_name(&value)),
+        ];
+    };
+
+    let mut diagnostics = Vec::new();
+    let keys: Vec<&str> = mapping.keys().filter_map(|k| k.as_str()).collect();
+
+    for field in REQUIRED_FIELDS {
+        if mapping.contains_key(*field) {
+            continue;
+        }
+        let mut diagnostic = Diagnostic::new(format!("Missing required field '{field}'"))
+            .with_path(*field)
+            .at(frontmatter.first_line, 1);
+        diagnostic = match keys.iter().find(|key| is_near_miss(key, field)) {
+            Some(typo) => diagnostic.with_suggestion(format!("Rename '{typo}' to '{field}'")),
+            None => diagnostic.with_suggestion(format!("Add '{field}:' to the frontmatter")),
+        };
+        diagnostics.push(diagnostic);
+    }
+
+    for (key, value) in &mapping {
+        let Some(key) = key.as_str() else { continue };
+        if let Err(e) = check_field(key, value.clone()) {
+            let inner = e.path().to_string();
+            let path = if inner == "." { key.to_string() } else { format!("{key}.{inner}") }
+                .replace(".[", "[");
+            diagnostics.push(locator.locate(type_diagnostic(&path, e.inner())));
+        }
+    }
+
+    diagnostics
+}
+
+/// Fill in file locations for diagnostics that carry a key path
+pub(crate) fn locate_diagnostics(file_content: &str, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    match split_frontmatter(file_content) {
+        Some(frontmatter) => {
+            let locator = YamlLocator::new(&frontmatter);
+            diagnostics.into_iter().map(|d| locator.locate(d)).collect()
+        }
+        None => diagnostics,
+    }
+}
+
+/// Type-check a single known frontmatter key (unknown keys are always valid)
+fn check_field(
+    key: &str,
+    value: serde_yaml::Value,
+) -> Result<(), serde_path_to_error::Error<serde_yaml::Error>> {
+    match key {
+        "title" | "description" | "author" => check::<String>(value),
+        "categories" | "tags" | "aliases" => check::<Vec<String>>(value),
+        "secondary_tag" | "version" | "model" | "replaced_by" | "forked_from" => check::<Option<String>>(value),
+        "verified" | "deprecated" => check::<bool>(value),
+        "votes" => check::<u32>(value),
+        "max_tokens" => check::<Option<u32>>(value),
+        "temperature" => check::<Option<f64>>(value),
+        "parameters" => check::<Vec<ParameterDefinition>>(value),
+        "limits" => check::<Option<RenderLimits>>(value),
+        "on_token_overflow" => check::<TokenOverflow>(value),
+        _ => Ok(()),
+    }
+}
+
+fn check<T: DeserializeOwned>(
+    value: serde_yaml::Value,
+) -> Result<(), serde_path_to_error::Error<serde_yaml::Error>> {
+    serde_path_to_error::deserialize::<_, T>(value).map(|_| ())
+}
+
+/// Build a diagnostic from a serde error at `path`
+fn type_diagnostic(path: &str, error: &serde_yaml::Error) -> Diagnostic {
+    let message = strip_location(error);
+    let diagnostic = Diagnostic::new(message.clone()).with_path(path);
+
+    if let Some(caps) = UNKNOWN_VARIANT_PATTERN.captures(&message) {
+        let expected = caps[2].replace('`', "");
+        return diagnostic
+            .with_types(expected.clone(), caps[1].to_string())
+            .with_suggestion(format!("Use {expected}"));
+    }
+    if let Some(caps) = INVALID_TYPE_PATTERN.captures(&message) {
+        let expected = caps[2].to_string();
+        return diagnostic
+            .with_types(expected.clone(), caps[1].to_string())
+            .with_suggestion(format!("Change '{path}' to {expected}"));
+    }
+    if let Some(field) = message
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.strip_suffix('`'))
+    {
+        return diagnostic.with_suggestion(format!("Add '{field}:' under '{path}'"));
+    }
+    diagnostic
+}
+
+/// serde_yaml error message without "at line X column Y" (frontmatter-relative)
+fn strip_location(error: &serde_yaml::Error) -> String {
+    LOCATION_PATTERN.replace_all(&error.to_string(), "").into_owned()
+}
+
+fn yaml_type_name(value: &serde_yaml::Value) -> &'static str {
+    match value {
+        serde_yaml::Value::Null => "null",
+        serde_yaml::Value::Bool(_) => "boolean",
+        serde_yaml::Value::Number(_) => "number",
+        serde_yaml::Value::String(_) => "string",
+        serde_yaml::Value::Sequence(_) => "sequence",
+        serde_yaml::Value::Mapping(_) => "mapping",
+        serde_yaml::Value::Tagged(_) => "tagged value",
+    }
+}
+
+/// Whether `key` looks like a misspelling of `field` (edit distance <= 2)
+fn is_near_miss(key: &str, field: &str) -> bool {
+    key != field && edit_distance(key, field) <= 2
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(row[j + 1])
+            };
+            prev = current;
+        }
+    }
+    row[b.len()]
+}
+
+/// Maps frontmatter key paths (`parameters[1].default`) to file positions
+struct YamlLocator {
+    positions: HashMap<String, (usize, usize)>,
+}
+
+impl YamlLocator {
+    fn new(frontmatter: &Frontmatter<'_>) -> Self {
+        let mut recorder = PathRecorder::default();
+        // Syntax errors are reported separately; keep whatever was recorded
+        let _ = Parser::new_from_str(frontmatter.text).load(&mut recorder, false);
+        let positions = recorder
+            .positions
+            .into_iter()
+            .map(|(path, mark)| (path, (frontmatter.first_line + mark.line() - 1, mark.col() + 1)))
+            .collect();
+        Self { positions }
+    }
+
+    /// Set the diagnostic location from its path (nearest recorded ancestor)
+    fn locate(&self, mut diagnostic: Diagnostic) -> Diagnostic {
+        if diagnostic.line.is_some() {
+            return diagnostic;
+        }
+        let Some(mut path) = diagnostic.path.clone() else {
+            return diagnostic;
+        };
+        loop {
+            if let Some(&(line, column)) = self.positions.get(&path) {
+                diagnostic.line = Some(line);
+                diagnostic.column = Some(column);
+                return diagnostic;
+            }
+            match path.rfind(['.', '[']) {
+                Some(i) => path.truncate(i),
+                None if !path.is_empty() => path.clear(),
+                None => return diagnostic,
+            }
+        }
+    }
+}
+
+/// Frame of the YAML node stack while walking parser events
+enum Frame {
+    Mapping { key: Option<String> },
+    Sequence { index: usize },
+}
+
+/// Event receiver recording the position of every value by key path
+#[derive(Default)]
+struct PathRecorder {
+    stack: Vec<Frame>,
+    positions: HashMap<String, Marker>,
+}
+
+impl PathRecorder {
+    fn current_path(&self) -> String {
+        let mut path = String::new();
+        for frame in &self.stack {
+            match frame {
+                Frame::Mapping { key: Some(key) } => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(key);
+                }
+                Frame::Mapping { key: None } => {}
+                Frame::Sequence { index } => path.push_str(&format!("[{index}]")),
+            }
+        }
+        path
+    }
+
+    /// Returns true if the event is a mapping key (and records it)
+    fn take_key(&mut self, event: &Event) -> bool {
+        if let Some(Frame::Mapping { key }) = self.stack.last_mut()
+            && key.is_none()
+        {
+            *key = Some(match event {
+                Event::Scalar(value, ..) => value.clone(),
+                _ => "?".to_string(),
+            });
+            return true;
+        }
+        false
+    }
+
+    /// Advance the parent frame after a complete value
+    fn value_done(&mut self) {
+        match self.stack.last_mut() {
+            Some(Frame::Mapping { key }) => *key = None,
+            Some(Frame::Sequence { index }) => *index += 1,
+            None => {}
+        }
+    }
+}
+
+impl MarkedEventReceiver for PathRecorder {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        match event {
+            Event::Scalar(..) | Event::Alias(..) => {
+                if self.take_key(&event) {
+                    return;
+                }
+                self.positions.insert(self.current_path(), mark);
+                self.value_done();
+            }
+            Event::MappingStart(..) | Event::SequenceStart(..) => {
+                // Complex (collection) keys are not addressable; only track nesting
+                if !self.take_key(&event) {
+                    self.positions.entry(self.current_path()).or_insert(mark);
+                }
+                self.stack.push(match event {
+                    Event::MappingStart(..) => Frame::Mapping { key: None },
+                    _ => Frame::Sequence { index: 0 },
+                });
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.stack.pop();
+                self.value_done();
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,7 +1,10 @@
+use super::audit::AuditEntry;
+use super::diagnostics::{diagnostics_of, format_diagnostic_lines, validation_diagnostics, Diagnostic};
 use super::manager::PromptManager;
+use super::schema::{EditPromptArgs, PromptEditOutput};
 use super::template::parse_template;
 use kodegen_mcp_schema::{McpError, Tool, ToolExecutionContext, ToolArgs, ToolResponse};
-use kodegen_mcp_schema::prompt::{EditPromptArgs, PromptEditOutput, PromptEditPrompts, PROMPT_EDIT};
+use kodegen_mcp_schema::prompt::{PromptEditPrompts, PROMPT_EDIT};
 
 #[derive(Clone)]
 pub struct EditPromptTool {
@@ -33,7 +36,9 @@ impl Tool for EditPromptTool {
     fn description() -> &'static str {
         "Edit an existing prompt template. Provide the prompt name and complete new content \
          (including YAML frontmatter). The content is validated before saving. Use get_prompt \
-         to retrieve current content before editing."
+         to retrieve current content before editing. Invalid content is rejected with \
+         success=false and a diagnostics list (line, column, key path, expected vs. actual, \
+         suggested fix)."
     }
 
     fn read_only() -> bool {
@@ -48,17 +53,35 @@ impl Tool for EditPromptTool {
         true // Same content produces same result
     }
 
-    async fn execute(&self, args: Self::Args, _ctx: ToolExecutionContext) -> Result<ToolResponse<<Self::Args as ToolArgs>::Output>, McpError> {
+    async fn execute(&self, args: Self::Args, ctx: ToolExecutionContext) -> Result<ToolResponse<<Self::Args as ToolArgs>::Output>, McpError> {
         // Edit prompt (validates syntax automatically, async)
-        self.manager
-            .edit_prompt(&args.name, &args.content)
-            .await
-            .map_err(McpError::Other)?;
+        let manager = self.manager.for_connection(ctx.connection_id());
+        let hash_before = manager.audit_hash(&args.name).await;
+        let result = manager.edit_prompt(&args.name, &args.content).await;
+        let hash_after = match &result {
+            Ok(()) => manager.audit_hash(&args.name).await,
+            Err(_) => None,
+        };
+        manager
+            .audit(
+                AuditEntry::new(PROMPT_EDIT, &args.name, ctx.connection_id())
+                    .with_hashes(hash_before, hash_after)
+                    .with_result(&result),
+            )
+            .await;
+        if let Err(e) = result {
+            return match validation_diagnostics(&e) {
+                Some(diagnostics) => Ok(rejected(&args.name, diagnostics)),
+                None => Err(McpError::Other(e)),
+            };
+        }
 
         // Parse the updated template to extract metadata
         let filename = format!("{}.j2.md", args.name);
-        let template = parse_template(&filename, &args.content)
-            .map_err(McpError::Other)?;
+        let template = match parse_template(&filename, &args.content) {
+            Ok(template) => template,
+            Err(e) => return Ok(rejected(&args.name, diagnostics_of(&e))),
+        };
 
         // Calculate metrics
         let template_length = args.content.len();
@@ -78,8 +101,30 @@ impl Tool for EditPromptTool {
             name: args.name.clone(),
             message: format!("Prompt '{}' updated successfully ({} bytes, {} parameters)", args.name, template_length, parameter_count),
             path: None,
+            diagnostics: Vec::new(),
         };
 
         Ok(ToolResponse::new(summary, output))
     }
 }
+
+/// Response for content rejected by validation
+fn rejected(name: &str, diagnostics: Vec<Diagnostic>) -> ToolResponse<PromptEditOutput> {
+    let summary = format!(
+        "\x1b[31m󰆐 Prompt Rejected: {}\x1b[0m\n{}",
+        name,
+        format_diagnostic_lines(&diagnostics)
+    );
+    let output = PromptEditOutput {
+        success: false,
+        name: name.to_string(),
+        message: format!(
+            "Prompt '{}' was not updated: {} validation error(s)",
+            name,
+            diagnostics.len()
+        ),
+        path: None,
+        diagnostics,
+    };
+    ToolResponse::new(summary, output)
+}
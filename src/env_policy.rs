@@ -0,0 +1,216 @@
+//! Which environment variables templates can read as `env`
+//!
+//! An [`EnvPolicy`] is an allowlist and a blocklist of glob patterns. The
+//! blocklist wins: a variable is exposed only if no blocked pattern matches
+//! it and an allowed one does. Patterns are compiled once into regex sets,
+//! and the filtered variables are snapshotted when the policy is installed,
+//! so renders don't rescan the process environment.
+//!
+//! The policy starts from `KODEGEN_ALLOWED_ENV_VARS` and
+//! `KODEGEN_BLOCKED_ENV_VARS`. Replace it at runtime with
+//! [`EnvPolicy::install`], and call [`reload_env`] after changing the process
+//! environment so templates see the new values.
+
+use super::messages::strip_marker_chars;
+use anyhow::{Context, Result};
+use log::warn;
+use regex::RegexSet;
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// Patterns exposed when `KODEGEN_ALLOWED_ENV_VARS` is unset or empty
+/// (Unix names plus their Windows equivalents)
+pub const DEFAULT_ALLOWED_ENV_VARS: &[&str] = &[
+    "USER", "HOME", "SHELL", "PWD", "EDITOR", "TERM",
+    "USERNAME", "USERPROFILE", "HOMEDRIVE", "HOMEPATH",
+];
+
+/// Patterns hidden when `KODEGEN_BLOCKED_ENV_VARS` is unset
+pub const DEFAULT_BLOCKED_ENV_VARS: &[&str] = &[
+    "*_SECRET", "*SECRET*",
+    "*_PASSWORD", "*PASSWORD*",
+    "*_TOKEN", "*TOKEN*",
+    "*_KEY", "*KEY*",
+    "*_CREDENTIAL", "*CREDENTIAL*",
+    "*_AUTH", "*AUTH*",
+    "AWS_SECRET_ACCESS_KEY",
+    "GITHUB_TOKEN",
+    "DATABASE_PASSWORD",
+];
+
+const ALLOWED_ENV: &str = "KODEGEN_ALLOWED_ENV_VARS";
+const BLOCKED_ENV: &str = "KODEGEN_BLOCKED_ENV_VARS";
+
+/// Allow and block patterns for the `env` template object
+///
+/// Patterns are `*` (everything), `PREFIX*`, `*SUFFIX`, `*MIDDLE*` or an
+/// exact name. Only a leading or trailing `*` is a wildcard; one inside a
+/// pattern matches a literal `*`.
+#[derive(Debug, Clone)]
+pub struct EnvPolicy {
+    allowed: Vec<String>,
+    blocked: Vec<String>,
+    allowed_set: RegexSet,
+    blocked_set: RegexSet,
+}
+
+impl Default for EnvPolicy {
+    /// The built-in allowlist and blocklist
+    fn default() -> Self {
+        Self::new(DEFAULT_ALLOWED_ENV_VARS, DEFAULT_BLOCKED_ENV_VARS)
+            .expect("default env patterns compile")
+    }
+}
+
+impl EnvPolicy {
+    /// Compile a policy from allow and block patterns
+    ///
+    /// Patterns are trimmed and blank ones ignored. An empty allowlist exposes
+    /// nothing; use [`DEFAULT_ALLOWED_ENV_VARS`] for the defaults.
+    pub fn new<A, B>(allowed: A, blocked: B) -> Result<Self>
+    where
+        A: IntoIterator,
+        A::Item: AsRef<str>,
+        B: IntoIterator,
+        B::Item: AsRef<str>,
+    {
+        let allowed = clean_patterns(allowed);
+        let blocked = clean_patterns(blocked);
+        Ok(Self {
+            allowed_set: compile(&allowed).context("Invalid allowed env var pattern")?,
+            blocked_set: compile(&blocked).context("Invalid blocked env var pattern")?,
+            allowed,
+            blocked,
+        })
+    }
+
+    /// Policy from `KODEGEN_ALLOWED_ENV_VARS` and `KODEGEN_BLOCKED_ENV_VARS`
+    ///
+    /// Both are colon-separated (semicolon-separated on Windows), and entries
+    /// are trimmed. An unset or empty allowlist means the defaults; one that
+    /// holds only blanks or separators exposes nothing. An empty blocklist
+    /// disables blocking; an unset one means the defaults.
+    pub fn from_env() -> Self {
+        let separator = if cfg!(windows) { ';' } else { ':' };
+        let split = |value: &str| clean_patterns(value.split(separator));
+        let defaults = |patterns: &[&str]| clean_patterns(patterns);
+
+        let allowed = match std::env::var(ALLOWED_ENV) {
+            Ok(custom) if !custom.is_empty() => split(&custom),
+            _ => defaults(DEFAULT_ALLOWED_ENV_VARS),
+        };
+        let blocked = match std::env::var(BLOCKED_ENV) {
+            Ok(custom) => split(&custom),
+            Err(_) => defaults(DEFAULT_BLOCKED_ENV_VARS),
+        };
+
+        Self::new(allowed, blocked).unwrap_or_else(|e| {
+            warn!("{e:#}; using the default env var policy");
+            Self::default()
+        })
+    }
+
+    /// Allow patterns, as given
+    pub fn allowed(&self) -> &[String] {
+        &self.allowed
+    }
+
+    /// Block patterns, as given
+    pub fn blocked(&self) -> &[String] {
+        &self.blocked
+    }
+
+    /// Whether templates may read `name` (blocklist first, then allowlist)
+    pub fn allows(&self, name: &str) -> bool {
+        !self.blocked_set.is_match(name) && self.allowed_set.is_match(name)
+    }
+
+    /// The process environment, filtered by this policy
+    pub fn filter_env(&self) -> BTreeMap<String, String> {
+        std::env::vars().filter(|(key, _)| self.allows(key)).collect()
+    }
+
+    /// Make this the process-wide policy and snapshot the environment with it
+    ///
+    /// Unlike the render limits, the policy can be replaced at any time;
+    /// renders that already started keep the snapshot they took.
+    pub fn install(self) {
+        let snapshot = EnvSnapshot::new(Arc::new(self));
+        *STATE.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(snapshot);
+    }
+}
+
+/// Trimmed, non-empty patterns
+fn clean_patterns<I>(patterns: I) -> Vec<String>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    patterns
+        .into_iter()
+        .map(|p| p.as_ref().trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// One anchored regex per pattern
+fn compile(patterns: &[String]) -> Result<RegexSet, regex::Error> {
+    RegexSet::new(patterns.iter().map(|pattern| pattern_regex(pattern)))
+}
+
+/// Regex for `PREFIX*`, `*SUFFIX`, `*MIDDLE*`, `*` or an exact name
+///
+/// Only the ends can be wildcards; any other `*` is literal.
+fn pattern_regex(pattern: &str) -> String {
+    let (any_prefix, rest) = match pattern.strip_prefix('*') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+    let (any_suffix, literal) = match rest.strip_suffix('*') {
+        Some(literal) => (true, literal),
+        None => (false, rest),
+    };
+    let wildcard = |any: bool| if any { ".*" } else { "" };
+    format!("^{}{}{}$", wildcard(any_prefix), regex::escape(literal), wildcard(any_suffix))
+}
+
+/// Installed policy and the variables it exposed when last loaded
+struct EnvSnapshot {
+    policy: Arc<EnvPolicy>,
+    vars: Arc<BTreeMap<String, String>>,
+}
+
+impl EnvSnapshot {
+    fn new(policy: Arc<EnvPolicy>) -> Self {
+        let mut vars = policy.filter_env();
+        // Values are rendered verbatim; keep them from forging message markers
+        vars.values_mut().for_each(strip_marker_chars);
+        Self { policy, vars: Arc::new(vars) }
+    }
+}
+
+static STATE: LazyLock<RwLock<Arc<EnvSnapshot>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(EnvSnapshot::new(Arc::new(EnvPolicy::from_env())))));
+
+fn current() -> Arc<EnvSnapshot> {
+    STATE.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// The policy in effect (from the environment unless installed earlier)
+pub fn env_policy() -> Arc<EnvPolicy> {
+    current().policy.clone()
+}
+
+/// Snapshot the process environment again with the current policy
+///
+/// Call after `std::env::set_var` (or similar) so templates see the change.
+pub fn reload_env() {
+    let policy = env_policy();
+    let snapshot = EnvSnapshot::new(policy);
+    *STATE.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(snapshot);
+}
+
+/// Variables exposed to templates as `env`
+pub(crate) fn env_snapshot() -> Arc<BTreeMap<String, String>> {
+    current().vars.clone()
+}
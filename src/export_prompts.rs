@@ -0,0 +1,98 @@
+use super::bundle::{BundleFormat, ExportFilter};
+use super::manager::PromptManager;
+use super::schema::{ExportPromptsArgs, PromptExportOutput, PromptExportPrompts, PROMPT_EXPORT};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use kodegen_mcp_schema::{McpError, Tool, ToolExecutionContext, ToolResponse};
+
+#[derive(Clone)]
+pub struct ExportPromptsTool {
+    manager: PromptManager,
+}
+
+impl ExportPromptsTool {
+    /// Create with a pre-initialized PromptManager (for HTTP server)
+    pub fn with_manager(manager: PromptManager) -> Self {
+        Self { manager }
+    }
+
+    /// Create with default manager (for standalone use)
+    pub async fn new() -> Result<Self, McpError> {
+        let manager = PromptManager::new();
+        manager.init().await?;
+        Ok(Self { manager })
+    }
+}
+
+impl Tool for ExportPromptsTool {
+    type Args = ExportPromptsArgs;
+    type Prompts = PromptExportPrompts;
+
+    fn name() -> &'static str {
+        PROMPT_EXPORT
+    }
+
+    fn description() -> &'static str {
+        "Export prompt templates as a single bundle. Select prompts by categories, tags, and \
+         name globs (each filter that is set must match; no filters exports everything). \
+         Formats: json (default), yaml, or tar.gz (returned base64-encoded). The bundle \
+         includes a manifest with names, titles, versions, and SHA-256 checksums. Pass the \
+         returned data to prompt_import to restore it elsewhere."
+    }
+
+    fn read_only() -> bool {
+        true
+    }
+
+    fn destructive() -> bool {
+        false
+    }
+
+    fn idempotent() -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Self::Args, _ctx: ToolExecutionContext) -> Result<ToolResponse<<Self::Args as kodegen_mcp_schema::ToolArgs>::Output>, McpError> {
+        let format = args.format.unwrap_or(BundleFormat::Json);
+        let filter = ExportFilter {
+            categories: args.categories,
+            tags: args.tags,
+            names: args.names,
+        };
+
+        let bundle = self
+            .manager
+            .export_prompts(&filter)
+            .await
+            .map_err(McpError::Other)?;
+        let bytes = bundle.encode(format).map_err(McpError::Other)?;
+
+        let (encoding, data) = if format.is_binary() {
+            ("base64", BASE64.encode(&bytes))
+        } else {
+            ("utf8", String::from_utf8_lossy(&bytes).into_owned())
+        };
+
+        let count = bundle.prompts.len();
+
+        // Terminal summary
+        let summary = format!(
+            "\x1b[32m Prompts Exported: {count}\x1b[0m\n\
+              Format: {} · Size: {} bytes",
+            format.as_str(),
+            bytes.len()
+        );
+
+        // Typed output
+        let output = PromptExportOutput {
+            success: true,
+            format,
+            count,
+            manifest: bundle.manifest,
+            encoding: encoding.to_string(),
+            data,
+        };
+
+        Ok(ToolResponse::new(summary, output))
+    }
+}
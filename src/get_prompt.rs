@@ -1,13 +1,21 @@
+use super::attachments::{Attachment, AttachmentData, AttachmentSandbox};
+use super::audit::AuditEntry;
 use super::manager::PromptManager;
 use super::metadata::PromptTemplate;
+use super::schema::{
+    GetPromptAction, GetPromptArgs, PromptAttachmentOutput, PromptContentResult, PromptGetOutput,
+    PromptListResult, PromptMessageOutput, PromptMessagesResult, PromptMetadataOutput,
+    PromptPreviewResult, PromptRenderedResult, PromptResult, PromptSort, PromptSummary,
+};
+use super::store::PromptQuery;
+use super::tokens::TokenEstimate;
+use super::usage::PromptUsage;
 use kodegen_mcp_schema::prompt::{
-    CategoryInfo, GetPromptAction, GetPromptArgs, PromptCategoriesResult,
-    PromptContentResult, PromptGetOutput, PromptGetPrompts, PromptListResult, PromptMetadataOutput,
-    PromptParameterDef, PromptParameterType, PromptRenderedResult, PromptResult, PromptSummary,
-    TemplateParamValue, PROMPT_GET,
+    CategoryInfo, PromptCategoriesResult, PromptGetPrompts, PromptParameterDef,
+    PromptParameterType, TemplateParamValue, PROMPT_GET,
 };
 use kodegen_mcp_schema::{McpError, Tool, ToolExecutionContext, ToolResponse};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Clone)]
 pub struct GetPromptTool {
@@ -40,14 +48,26 @@ impl Tool for GetPromptTool {
         "Browse and retrieve prompt templates. \n\n\
          Actions:\n\
          - list_categories: Show all prompt categories\n\
-         - list_prompts: List all prompts (optionally filtered by category)\n\
+         - list_prompts: List all prompts (optionally filtered by namespace prefix, \
+         category, tag, author, full-text query, or custom frontmatter keys via extra). \
+         Names may be namespaced with '/' (e.g. review/security)\n\
          - get: Get prompt metadata and raw template content\n\
-         - render: Render prompt with parameters\n\n\
+         - render: Render prompt with parameters\n\
+         - render_messages: Render prompt into role-tagged messages. Templates mark \
+         messages with {% message \"system\" %}...{% endmessage %} blocks (roles: system, \
+         user, assistant); text outside blocks becomes a user message. {{ attach(\"path\") }} \
+         and {{ image(\"path\") }} embed a file from the caller's workspace in the message.\n\
+         - preview: Render prompt and report which parameters the caller provided, which \
+         came from defaults, which env vars were read, which variables were undefined, and \
+         the render time\n\n\
          Examples:\n\
          - prompt_get({\"action\": \"list_categories\"})\n\
          - prompt_get({\"action\": \"list_prompts\", \"category\": \"onboarding\"})\n\
+         - prompt_get({\"action\": \"list_prompts\", \"extra\": {\"owner_team\": \"platform\"}})\n\
          - prompt_get({\"action\": \"get\", \"name\": \"getting_started\"})\n\
-         - prompt_get({\"action\": \"render\", \"name\": \"analyze_project\", \"parameters\": {\"project_path\": \"/path\"}})"
+         - prompt_get({\"action\": \"render\", \"name\": \"analyze_project\", \"parameters\": {\"project_path\": \"/path\"}})\n\
+         - prompt_get({\"action\": \"render_messages\", \"name\": \"code_review\", \"parameters\": {\"language\": \"rust\"}})\n\
+         - prompt_get({\"action\": \"preview\", \"name\": \"analyze_project\", \"parameters\": {\"project_path\": \"/path\"}})"
     }
 
     fn read_only() -> bool {
@@ -65,7 +85,7 @@ impl Tool for GetPromptTool {
     async fn execute(
         &self,
         args: Self::Args,
-        _ctx: ToolExecutionContext,
+        ctx: ToolExecutionContext,
     ) -> Result<ToolResponse<<Self::Args as kodegen_mcp_schema::ToolArgs>::Output>, McpError> {
         let start = std::time::Instant::now();
         let action = args.action.clone();
@@ -78,7 +98,16 @@ impl Tool for GetPromptTool {
                 PromptResult::ListCategories(res)
             }
             GetPromptAction::ListPrompts => {
-                let mut res = self.list_prompts(args.category.as_deref()).await?;
+                let query = PromptQuery {
+                    namespace: args.namespace,
+                    category: args.category,
+                    tag: args.tag,
+                    author: args.author,
+                    text: args.query,
+                    limit: None,
+                };
+                let sort = args.sort.unwrap_or_default();
+                let mut res = self.list_prompts(query, args.extra.as_ref(), sort).await?;
                 res.elapsed_ms = Some(start.elapsed().as_secs_f64() * 1000.0);
                 PromptResult::ListPrompts(res)
             }
@@ -88,16 +117,35 @@ impl Tool for GetPromptTool {
                 })?;
                 let mut res = self.get_prompt(name).await?;
                 res.elapsed_ms = Some(start.elapsed().as_secs_f64() * 1000.0);
-                PromptResult::Get(res)
+                PromptResult::Get(Box::new(res))
             }
             GetPromptAction::Render => {
                 let name = args.name.as_ref().ok_or_else(|| {
                     McpError::InvalidArguments("name required for render action".into())
                 })?;
-                let mut res = self.render_prompt(name, args.parameters).await?;
+                let mut res = self.render_prompt(name, args.parameters, ctx.connection_id()).await?;
                 res.elapsed_ms = Some(start.elapsed().as_secs_f64() * 1000.0);
                 PromptResult::Render(res)
             }
+            GetPromptAction::RenderMessages => {
+                let name = args.name.as_ref().ok_or_else(|| {
+                    McpError::InvalidArguments("name required for render_messages action".into())
+                })?;
+                let sandbox = AttachmentSandbox::for_workspace(ctx.pwd(), ctx.git_root());
+                let mut res = self
+                    .render_prompt_messages(name, args.parameters, sandbox.as_ref(), ctx.connection_id())
+                    .await?;
+                res.elapsed_ms = Some(start.elapsed().as_secs_f64() * 1000.0);
+                PromptResult::RenderMessages(res)
+            }
+            GetPromptAction::Preview => {
+                let name = args.name.as_ref().ok_or_else(|| {
+                    McpError::InvalidArguments("name required for preview action".into())
+                })?;
+                let mut res = self.preview_prompt(name, args.parameters, ctx.connection_id()).await?;
+                res.elapsed_ms = Some(start.elapsed().as_secs_f64() * 1000.0);
+                PromptResult::Preview(res)
+            }
         };
 
         let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
@@ -122,21 +170,60 @@ impl Tool for GetPromptTool {
                 )
             }
             PromptResult::Get(res) => {
+                let deprecated_suffix = if res.metadata.deprecated {
+                    res.metadata
+                        .replaced_by
+                        .as_ref()
+                        .map(|r| format!(" · Deprecated (use {})", r))
+                        .unwrap_or_else(|| " · Deprecated".to_string())
+                } else {
+                    String::new()
+                };
                 format!(
-                    "\x1b[36m󰗚 Prompt: {}\x1b[0m\n󰈙 Template Length: {} chars · Parameters: {}",
+                    "\x1b[36m󰗚 Prompt: {}\x1b[0m\n󰈙 Template Length: {} chars · Parameters: {}{}",
                     res.name,
                     res.content.len(),
-                    res.metadata.parameters.len()
+                    res.metadata.parameters.len(),
+                    deprecated_suffix
                 )
             }
             PromptResult::Render(res) => {
                 format!(
-                    "\x1b[36m󰗚 Prompt: {} (Rendered)\x1b[0m\n󰈙 Output Length: {} chars · Elapsed: {:.0}ms",
+                    "\x1b[36m󰗚 Prompt: {} (Rendered)\x1b[0m\n󰈙 Output Length: {} chars · {} · Elapsed: {:.0}ms",
                     res.name,
                     res.content.len(),
+                    token_summary(&res.tokens),
                     elapsed_ms
                 )
             }
+            PromptResult::RenderMessages(res) => {
+                format!(
+                    "\x1b[36m󰗚 Prompt: {} (Messages)\x1b[0m\n󰈙 Messages: {} · {} · Elapsed: {:.0}ms",
+                    res.name,
+                    res.messages.len(),
+                    token_summary(&res.tokens),
+                    elapsed_ms
+                )
+            }
+            PromptResult::Preview(res) => {
+                let mut lines = vec![
+                    format!("󰈙 Output Length: {} chars · Render: {:.1}ms", res.content.len(), res.render_ms),
+                    format!("󰈙 Provided: {}", name_list(&res.provided)),
+                    format!("󰈙 Defaults: {}", name_list(&res.defaulted)),
+                    format!("󰈙 Env: {}", name_list(&res.env_vars)),
+                ];
+                if let Some(tokens) = &res.tokens {
+                    lines.insert(1, format!("󰈙 Tokens: {}", token_summary(tokens)));
+                }
+                if !res.undefined.is_empty() {
+                    lines.push(format!("\x1b[33m Undefined: {}\x1b[0m", name_list(&res.undefined)));
+                }
+                format!(
+                    "\x1b[36m󰗚 Prompt: {} (Preview)\x1b[0m\n{}",
+                    res.name,
+                    lines.join("\n")
+                )
+            }
         };
 
         // Typed output
@@ -175,15 +262,33 @@ impl GetPromptTool {
         })
     }
 
-    async fn list_prompts(&self, category: Option<&str>) -> Result<PromptListResult, McpError> {
-        let mut prompts = self.manager.list_prompts().await.map_err(McpError::Other)?;
+    async fn list_prompts(
+        &self,
+        query: PromptQuery,
+        extra: Option<&BTreeMap<String, serde_json::Value>>,
+        sort: PromptSort,
+    ) -> Result<PromptListResult, McpError> {
+        // Namespace, category, tag, author and text filters (indexed when the store supports it)
+        let mut prompts = self
+            .manager
+            .search_prompts(&query)
+            .await
+            .map_err(McpError::Other)?;
 
-        // Filter by category if specified
-        if let Some(cat) = category {
-            prompts.retain(|p| p.metadata.categories.contains(&cat.to_string()));
+        // Filter by extra frontmatter keys if specified
+        if let Some(filter) = extra {
+            prompts.retain(|p| {
+                filter.iter().all(|(key, expected)| {
+                    p.metadata
+                        .extra
+                        .get(key)
+                        .is_some_and(|actual| extra_value_matches(actual, expected))
+                })
+            });
         }
 
-        let prompts_list: Vec<PromptSummary> = prompts
+        let mut usage = self.manager.all_usage().await.map_err(McpError::Other)?;
+        let mut prompts_list: Vec<PromptSummary> = prompts
             .iter()
             .map(|p| PromptSummary {
                 name: p.filename.clone(),
@@ -204,29 +309,49 @@ impl GetPromptTool {
                         default: param.default.clone(),
                     })
                     .collect(),
+                version: p.metadata.version.clone(),
+                tags: p.metadata.tags.clone(),
+                deprecated: p.metadata.deprecated,
+                replaced_by: p.metadata.replaced_by.clone(),
+                aliases: p.metadata.aliases.clone(),
+                extra: p.metadata.extra.clone(),
+                votes: usage
+                    .get(&p.filename)
+                    .map_or(p.metadata.votes, |u| u.total_votes(p.metadata.votes)),
+                usage: usage.remove(&p.filename),
             })
             .collect();
+        sort_summaries(&mut prompts_list, sort);
 
         let count = prompts_list.len();
         Ok(PromptListResult {
             prompts: prompts_list,
             count,
-            category: category.map(String::from),
+            namespace: query.namespace,
+            category: query.category,
+            tag: query.tag,
+            author: query.author,
+            query: query.text,
+            extra: extra.cloned(),
+            sort: (sort != PromptSort::Name).then_some(sort),
             elapsed_ms: None,
         })
     }
 
     async fn get_prompt(&self, name: &str) -> Result<PromptContentResult, McpError> {
-        let template = self
+        let (template, usage) = self
             .manager
-            .load_prompt(name)
+            .prompt_usage(name)
             .await
             .map_err(McpError::Other)?;
 
+        let mut metadata = convert_metadata(&template);
+        metadata.votes = usage.total_votes(template.metadata.votes);
         Ok(PromptContentResult {
-            name: name.to_string(),
-            metadata: convert_metadata(&template),
+            name: template.filename.clone(),
+            metadata,
             content: template.content,
+            usage: (usage != PromptUsage::default()).then_some(usage),
             rendered: false,
             elapsed_ms: None,
         })
@@ -236,20 +361,152 @@ impl GetPromptTool {
         &self,
         name: &str,
         parameters: Option<HashMap<String, TemplateParamValue>>,
+        connection_id: Option<&str>,
     ) -> Result<PromptRenderedResult, McpError> {
+        let entry = self.render_entry("render", name, parameters.as_ref(), connection_id);
         let rendered = self
             .manager
+            .auditing_render(entry)
             .render_prompt(name, parameters)
             .await
             .map_err(McpError::Other)?;
 
         Ok(PromptRenderedResult {
             name: name.to_string(),
-            content: rendered,
+            content: rendered.content,
+            tokens: rendered.tokens,
+            rendered: true,
+            elapsed_ms: None,
+        })
+    }
+
+    async fn preview_prompt(
+        &self,
+        name: &str,
+        parameters: Option<HashMap<String, TemplateParamValue>>,
+        connection_id: Option<&str>,
+    ) -> Result<PromptPreviewResult, McpError> {
+        let entry = self.render_entry("preview", name, parameters.as_ref(), connection_id);
+        let preview = self
+            .manager
+            .auditing_render(entry)
+            .preview_prompt(name, parameters)
+            .await
+            .map_err(McpError::Other)?;
+
+        Ok(PromptPreviewResult {
+            name: name.to_string(),
+            content: preview.rendered,
+            provided: preview.provided,
+            defaulted: preview.defaulted,
+            env_vars: preview.env_vars,
+            undefined: preview.undefined,
+            render_ms: preview.render_ms,
+            tokens: preview.tokens,
+            rendered: true,
+            elapsed_ms: None,
+        })
+    }
+
+    async fn render_prompt_messages(
+        &self,
+        name: &str,
+        parameters: Option<HashMap<String, TemplateParamValue>>,
+        sandbox: Option<&AttachmentSandbox>,
+        connection_id: Option<&str>,
+    ) -> Result<PromptMessagesResult, McpError> {
+        let entry = self.render_entry("render_messages", name, parameters.as_ref(), connection_id);
+        let rendered = self
+            .manager
+            .auditing_render(entry)
+            .render_prompt_messages(name, parameters, sandbox)
+            .await
+            .map_err(McpError::Other)?;
+
+        Ok(PromptMessagesResult {
+            name: name.to_string(),
+            messages: rendered
+                .messages
+                .into_iter()
+                .map(|m| PromptMessageOutput {
+                    role: m.role,
+                    content: m.content,
+                    attachments: m.attachments.into_iter().map(convert_attachment).collect(),
+                })
+                .collect(),
+            tokens: rendered.tokens,
             rendered: true,
             elapsed_ms: None,
         })
     }
+
+    /// Audit entry for a render of `name` (the manager adds the hash of the
+    /// version it renders)
+    fn render_entry(
+        &self,
+        action: &str,
+        name: &str,
+        parameters: Option<&HashMap<String, TemplateParamValue>>,
+        connection_id: Option<&str>,
+    ) -> AuditEntry {
+        let record_values = self
+            .manager
+            .audit_log()
+            .is_some_and(|log| log.records_parameter_values());
+        AuditEntry::new(PROMPT_GET, name, connection_id)
+            .with_action(action)
+            .with_parameters(parameters, record_values)
+    }
+}
+
+/// Token estimate for terminal summaries (e.g. `~1200 tokens / 1000 (truncated from 1500)`)
+fn token_summary(estimate: &TokenEstimate) -> String {
+    let mut summary = format!("~{} tokens", estimate.tokens);
+    if let Some(budget) = estimate.budget {
+        summary.push_str(&format!(" / {budget}"));
+    }
+    if let Some(original) = estimate.truncated_from {
+        summary.push_str(&format!(" (truncated from {original})"));
+    } else if estimate.over_budget {
+        summary.push_str(" (over budget)");
+    }
+    summary
+}
+
+/// Order list results the way `list_prompts` does (the search returns them by name)
+pub fn sort_summaries(prompts: &mut [PromptSummary], sort: PromptSort) {
+    let renders = |p: &PromptSummary| p.usage.as_ref().map_or(0, |u| u.renders);
+    match sort {
+        PromptSort::Name => {}
+        PromptSort::Popular => prompts.sort_by(|a, b| {
+            renders(b)
+                .cmp(&renders(a))
+                .then(b.votes.cmp(&a.votes))
+                .then_with(|| a.name.cmp(&b.name))
+        }),
+        PromptSort::Votes => prompts.sort_by(|a, b| {
+            b.votes
+                .cmp(&a.votes)
+                .then(renders(b).cmp(&renders(a)))
+                .then_with(|| a.name.cmp(&b.name))
+        }),
+        // Fixed-precision UTC RFC 3339 times sort chronologically as strings
+        PromptSort::Recent => prompts.sort_by(|a, b| {
+            let last_used = |p: &PromptSummary| p.usage.as_ref().and_then(|u| u.last_used.clone());
+            last_used(b)
+                .cmp(&last_used(a))
+                .then_with(|| a.name.cmp(&b.name))
+        }),
+    }
+}
+
+/// Comma-separated names for terminal summaries
+fn name_list(names: &[String]) -> String {
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join(", ")
+    }
 }
 
 /// Convert internal ParameterType to schema PromptParameterType
@@ -262,6 +519,22 @@ fn convert_param_type(pt: &super::metadata::ParameterType) -> PromptParameterTyp
     }
 }
 
+/// Convert a loaded attachment to schema PromptAttachmentOutput
+fn convert_attachment(attachment: Attachment) -> PromptAttachmentOutput {
+    let (text, data) = match attachment.data {
+        AttachmentData::Text(text) => (Some(text), None),
+        AttachmentData::Base64(data) => (None, Some(data)),
+    };
+    PromptAttachmentOutput {
+        kind: attachment.kind,
+        path: attachment.path,
+        uri: attachment.uri,
+        mime_type: attachment.mime_type,
+        text,
+        data,
+    }
+}
+
 /// Convert internal PromptTemplate metadata to schema PromptMetadataOutput
 fn convert_metadata(template: &PromptTemplate) -> PromptMetadataOutput {
     PromptMetadataOutput {
@@ -284,5 +557,41 @@ fn convert_metadata(template: &PromptTemplate) -> PromptMetadataOutput {
                 default: param.default.clone(),
             })
             .collect(),
+        version: template.metadata.version.clone(),
+        tags: template.metadata.tags.clone(),
+        model: template.metadata.model.clone(),
+        temperature: template.metadata.temperature,
+        max_tokens: template.metadata.max_tokens,
+        deprecated: template.metadata.deprecated,
+        replaced_by: template.metadata.replaced_by.clone(),
+        forked_from: template.metadata.forked_from.clone(),
+        aliases: template.metadata.aliases.clone(),
+        extra: template.metadata.extra.clone(),
+    }
+}
+
+/// Check an extra frontmatter value against a `list_prompts` filter value
+///
+/// Arrays match if any element matches; scalars compare by their string form,
+/// so `ticket: 1234` matches both `1234` and `"1234"`.
+fn extra_value_matches(actual: &serde_json::Value, expected: &serde_json::Value) -> bool {
+    if actual == expected {
+        return true;
+    }
+    match (actual, expected) {
+        (serde_json::Value::Array(items), _) => {
+            items.iter().any(|item| extra_value_matches(item, expected))
+        }
+        (serde_json::Value::Object(_), _) | (_, serde_json::Value::Object(_))
+        | (_, serde_json::Value::Array(_)) => false,
+        _ => scalar_string(actual) == scalar_string(expected),
+    }
+}
+
+/// String form of a scalar JSON value (strings without quotes)
+fn scalar_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
     }
 }
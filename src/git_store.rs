@@ -0,0 +1,319 @@
+//! Git-backed history for the prompts directory
+//!
+//! When enabled, the prompts directory is a git repository and every change
+//! made through `PromptManager` is committed. History, diffs and restores are
+//! served from git. All operations shell out to the `git` CLI, so no git
+//! library is linked and the repository stays usable with normal git tooling.
+
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+/// Identity commits are made as when no connection author is known,
+/// and the committer identity for every commit
+const DEFAULT_NAME: &str = "kodegen-prompt";
+const DEFAULT_EMAIL: &str = "kodegen-prompt@localhost";
+
+/// Field separator for `git log --format` (ASCII unit separator)
+const FIELD_SEP: char = '\x1f';
+
+/// Separator between commits in `git log` output (ASCII record separator)
+const RECORD_SEP: char = '\x1e';
+
+/// Enable git storage (`1`, `true`, `yes`, `on`)
+const GIT_STORAGE_ENV: &str = "KODEGEN_PROMPTS_GIT";
+
+/// Whether git storage is enabled via `KODEGEN_PROMPTS_GIT`
+pub fn git_storage_enabled() -> bool {
+    crate::config::env_flag(GIT_STORAGE_ENV)
+}
+
+/// Author recorded on commits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeAuthor {
+    pub name: String,
+    pub email: String,
+}
+
+impl ChangeAuthor {
+    pub fn new(name: impl Into<String>, email: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            email: email.into(),
+        }
+    }
+
+    /// Author for an MCP connection (kodegen stdio connection ID)
+    pub fn from_connection(connection_id: &str) -> Self {
+        let id: String = connection_id
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            .collect();
+        Self::new(format!("kodegen connection {id}"), format!("{id}@connections.kodegen"))
+    }
+}
+
+impl Default for ChangeAuthor {
+    fn default() -> Self {
+        Self::new(DEFAULT_NAME, DEFAULT_EMAIL)
+    }
+}
+
+/// One commit (or database revision) touching a prompt
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HistoryEntry {
+    /// Full commit hash, or the revision number in a prompt database
+    pub commit: String,
+    /// Author name (empty for database revisions)
+    pub author: String,
+    /// Author email (empty for database revisions)
+    pub email: String,
+    /// Commit date (RFC 3339)
+    pub date: String,
+    /// Commit subject, or the revision's action
+    pub message: String,
+}
+
+/// Parse a `git log` header line written with the history format
+fn parse_log_line(line: &str) -> Option<HistoryEntry> {
+    let mut fields = line.splitn(5, FIELD_SEP);
+    Some(HistoryEntry {
+        commit: fields.next()?.to_string(),
+        author: fields.next()?.to_string(),
+        email: fields.next()?.to_string(),
+        date: fields.next()?.to_string(),
+        message: fields.next()?.to_string(),
+    })
+}
+
+/// Prompts directory managed as a git repository
+#[derive(Clone)]
+pub struct GitStore {
+    dir: PathBuf,
+    /// Serializes index updates and commits (git's index.lock is not shared)
+    lock: Arc<Mutex<()>>,
+}
+
+impl GitStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Initialize the repository and commit any untracked prompts
+    pub async fn init(&self) -> Result<()> {
+        let _guard = self.lock.lock().await;
+
+        if !tokio::fs::try_exists(self.dir.join(".git")).await.unwrap_or(false) {
+            self.git(&["init", "--quiet"]).await?;
+        }
+
+        self.git(&["add", "--all", "--", "*.j2.md"]).await?;
+        if self.has_staged_changes(&[]).await? {
+            self.commit_staged("Import existing prompts", &ChangeAuthor::default(), &[])
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Commit the current state of the given prompt files
+    ///
+    /// Paths are relative to the prompts directory; deleted files are
+    /// recorded as deletions. Does nothing if the files are unchanged.
+    pub async fn commit(&self, files: &[&str], message: &str, author: &ChangeAuthor) -> Result<()> {
+        let _guard = self.lock.lock().await;
+
+        let mut add = vec!["add", "--all", "--"];
+        add.extend_from_slice(files);
+        self.git(&add).await?;
+
+        if self.has_staged_changes(files).await? {
+            self.commit_staged(message, author, files).await?;
+        }
+        Ok(())
+    }
+
+    /// Commits touching a prompt file, newest first (follows renames and deletions)
+    ///
+    /// Renames are followed by hand: `git log --follow` also follows copies,
+    /// so a new prompt resembling another would inherit that prompt's history.
+    pub async fn history(&self, file: &str, limit: usize) -> Result<Vec<HistoryEntry>> {
+        let format = format!(
+            "--format={RECORD_SEP}%H{FIELD_SEP}%an{FIELD_SEP}%ae{FIELD_SEP}%aI{FIELD_SEP}%s"
+        );
+        let mut entries = Vec::new();
+        let mut path = file.to_string();
+        let mut start: Option<String> = None;
+        while entries.len() < limit {
+            let max_count = format!("--max-count={}", limit - entries.len());
+            let mut args = vec!["log", "--name-status", "--no-renames", &format, &max_count];
+            if let Some(start) = &start {
+                args.push(start);
+            }
+            args.extend_from_slice(&["--", &path]);
+            let output = match self.git(&args).await {
+                Ok(output) => output,
+                // No commits yet
+                Err(_) if !self.has_head().await => return Ok(Vec::new()),
+                Err(e) => return Err(e),
+            };
+
+            let mut oldest_added = None;
+            for record in output.split(RECORD_SEP) {
+                let mut lines = record.lines();
+                let Some(entry) = lines.next().and_then(parse_log_line) else {
+                    continue;
+                };
+                let added = lines.any(|line| line.starts_with('A'));
+                oldest_added = added.then(|| entry.commit.clone());
+                entries.push(entry);
+            }
+
+            // Where the file appeared, continue with the file it was renamed from
+            let Some(commit) = oldest_added else {
+                break;
+            };
+            match self.renamed_from(&commit, &path).await? {
+                Some(source) => {
+                    path = source;
+                    start = Some(format!("{commit}^"));
+                }
+                None => break,
+            }
+        }
+        Ok(entries)
+    }
+
+    /// File that `commit` renamed to `file`, if any
+    async fn renamed_from(&self, commit: &str, file: &str) -> Result<Option<String>> {
+        let output = self
+            .git(&["show", "--find-renames", "--name-status", "--format=", commit])
+            .await?;
+        Ok(output.lines().find_map(|line| {
+            // `R<score><TAB>source<TAB>destination`
+            let mut fields = line.split('\t');
+            let status = fields.next()?;
+            let (source, destination) = (fields.next()?, fields.next()?);
+            (status.starts_with('R') && destination == file).then(|| source.to_string())
+        }))
+    }
+
+    /// Unified diff of a prompt file between two revisions
+    ///
+    /// `to` defaults to the working tree.
+    pub async fn diff(&self, file: &str, from: &str, to: Option<&str>) -> Result<String> {
+        let from = self.resolve(from).await?;
+        let mut args = vec!["diff", "--no-color", "--no-ext-diff", from.as_str()];
+        let to = match to {
+            Some(to) => Some(self.resolve(to).await?),
+            None => None,
+        };
+        if let Some(to) = &to {
+            args.push(to);
+        }
+        args.extend_from_slice(&["--", file]);
+        self.git(&args).await
+    }
+
+    /// Content of a prompt file at a revision
+    pub async fn show(&self, file: &str, revision: &str) -> Result<String> {
+        let commit = self.resolve(revision).await?;
+        self.git(&["show", &format!("{commit}:{file}")])
+            .await
+            .with_context(|| format!("'{file}' does not exist at revision {revision}"))
+    }
+
+    /// Resolve a revision to a full commit hash
+    ///
+    /// Rejects anything that could be read as an option, so user input can
+    /// be passed to git safely.
+    pub async fn resolve(&self, revision: &str) -> Result<String> {
+        let revision = revision.trim();
+        if revision.is_empty() || revision.starts_with('-') {
+            anyhow::bail!("Invalid revision '{revision}'");
+        }
+        let spec = format!("{revision}^{{commit}}");
+        let output = self
+            .git(&["rev-parse", "--verify", "--quiet", "--end-of-options", &spec])
+            .await
+            .with_context(|| format!("Unknown revision '{revision}'"))?;
+        Ok(output.trim().to_string())
+    }
+
+    async fn has_head(&self) -> bool {
+        self.git(&["rev-parse", "--verify", "--quiet", "HEAD"])
+            .await
+            .is_ok()
+    }
+
+    async fn has_staged_changes(&self, files: &[&str]) -> Result<bool> {
+        let mut args = vec!["diff", "--cached", "--quiet", "--"];
+        args.extend_from_slice(files);
+        let status = self
+            .command(&args)
+            .status()
+            .await
+            .context("Failed to run git")?;
+        match status.code() {
+            Some(0) => Ok(false),
+            Some(1) => Ok(true),
+            _ => anyhow::bail!("git diff --cached failed in {}", self.dir.display()),
+        }
+    }
+
+    async fn commit_staged(&self, message: &str, author: &ChangeAuthor, files: &[&str]) -> Result<()> {
+        let mut args = vec!["commit", "--quiet", "--no-verify", "-m", message, "--"];
+        args.extend_from_slice(files);
+        let output = self
+            .command(&args)
+            .env("GIT_AUTHOR_NAME", &author.name)
+            .env("GIT_AUTHOR_EMAIL", &author.email)
+            .env("GIT_COMMITTER_NAME", DEFAULT_NAME)
+            .env("GIT_COMMITTER_EMAIL", DEFAULT_EMAIL)
+            .output()
+            .await
+            .context("Failed to run git commit")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "git commit failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+
+    /// Run git in the prompts directory and return stdout
+    async fn git(&self, args: &[&str]) -> Result<String> {
+        let output = self
+            .command(args)
+            .output()
+            .await
+            .context("Failed to run git (is it installed?)")?;
+        if !output.status.success() {
+            let command = args.first().unwrap_or(&"");
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            match stderr.trim() {
+                "" => anyhow::bail!("git {command} failed ({})", output.status),
+                stderr => anyhow::bail!("git {command} failed: {stderr}"),
+            }
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new("git");
+        command
+            .arg("-C")
+            .arg(&self.dir)
+            .args(args)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .kill_on_drop(true);
+        command
+    }
+}
@@ -0,0 +1,173 @@
+use super::audit::AuditEntry;
+use super::manager::PromptManager;
+use super::schema::{
+    HistoryAction, HistoryPromptArgs, PromptHistoryOutput, PromptHistoryPrompts, PROMPT_HISTORY,
+};
+use kodegen_mcp_schema::{McpError, Tool, ToolExecutionContext, ToolResponse};
+
+/// Commits listed by the log action when no limit is given
+const DEFAULT_LOG_LIMIT: usize = 20;
+
+#[derive(Clone)]
+pub struct HistoryPromptTool {
+    manager: PromptManager,
+}
+
+impl HistoryPromptTool {
+    /// Create with a pre-initialized PromptManager (for HTTP server)
+    pub fn with_manager(manager: PromptManager) -> Self {
+        Self { manager }
+    }
+
+    /// Create with default manager (for standalone use)
+    pub async fn new() -> Result<Self, McpError> {
+        let manager = PromptManager::new();
+        manager.init().await?;
+        Ok(Self { manager })
+    }
+}
+
+impl Tool for HistoryPromptTool {
+    type Args = HistoryPromptArgs;
+    type Prompts = PromptHistoryPrompts;
+
+    fn name() -> &'static str {
+        PROMPT_HISTORY
+    }
+
+    fn description() -> &'static str {
+        "History of a prompt template (requires git storage, KODEGEN_PROMPTS_GIT=1, or a \
+         prompt database, KODEGEN_PROMPTS_DB, whose revisions are numbered). Actions: log \
+         (commits or revisions that changed the prompt, with date), diff (unified diff from a \
+         revision to another revision or the current file), restore (write the prompt back as \
+         it was at a revision; recreates deleted prompts and is itself recorded)."
+    }
+
+    fn read_only() -> bool {
+        false // restore writes the prompt
+    }
+
+    fn destructive() -> bool {
+        true // restore replaces the current content
+    }
+
+    fn idempotent() -> bool {
+        true // Restoring the same revision twice leaves the same content
+    }
+
+    async fn execute(&self, args: Self::Args, ctx: ToolExecutionContext) -> Result<ToolResponse<<Self::Args as kodegen_mcp_schema::ToolArgs>::Output>, McpError> {
+        match args.action {
+            HistoryAction::Log => {
+                let limit = args.limit.unwrap_or(DEFAULT_LOG_LIMIT);
+                let entries = self
+                    .manager
+                    .prompt_history(&args.name, limit)
+                    .await
+                    .map_err(McpError::Other)?;
+
+                let mut summary = format!(
+                    "\x1b[36m Prompt History: {}\x1b[0m\n  Commits: {}",
+                    args.name,
+                    entries.len()
+                );
+                for entry in &entries {
+                    summary.push_str(&format!(
+                        "\n  • {} {}",
+                        &entry.commit[..entry.commit.len().min(10)],
+                        entry.date
+                    ));
+                    // Database revisions have no author
+                    if !entry.author.is_empty() {
+                        summary.push_str(&format!(" · {}", entry.author));
+                    }
+                    summary.push_str(&format!(" · {}", entry.message));
+                }
+
+                let output = PromptHistoryOutput {
+                    success: true,
+                    action: args.action,
+                    name: args.name.clone(),
+                    message: format!("{} commit(s) changed '{}'", entries.len(), args.name),
+                    entries,
+                    diff: None,
+                    restored_from: None,
+                };
+                Ok(ToolResponse::new(summary, output))
+            }
+            HistoryAction::Diff => {
+                let from = args.from.as_deref().ok_or_else(|| {
+                    McpError::InvalidArguments("from is required for diff action".into())
+                })?;
+                let diff = self
+                    .manager
+                    .prompt_diff(&args.name, from, args.to.as_deref())
+                    .await
+                    .map_err(McpError::Other)?;
+
+                let to = args.to.as_deref().unwrap_or("current");
+                let message = if diff.is_empty() {
+                    format!("'{}' is unchanged between {from} and {to}", args.name)
+                } else {
+                    format!("Changes to '{}' from {from} to {to}", args.name)
+                };
+                let summary = format!(
+                    "\x1b[36m Prompt Diff: {} ({from} → {to})\x1b[0m\n{}",
+                    args.name,
+                    if diff.is_empty() { "  No changes" } else { diff.trim_end() }
+                );
+
+                let output = PromptHistoryOutput {
+                    success: true,
+                    action: args.action,
+                    name: args.name.clone(),
+                    message,
+                    entries: Vec::new(),
+                    diff: Some(diff),
+                    restored_from: None,
+                };
+                Ok(ToolResponse::new(summary, output))
+            }
+            HistoryAction::Restore => {
+                let revision = args.revision.as_deref().ok_or_else(|| {
+                    McpError::InvalidArguments("revision is required for restore action".into())
+                })?;
+                let manager = self.manager.for_connection(ctx.connection_id());
+                let hash_before = manager.audit_hash(&args.name).await;
+                let result = manager.restore_prompt(&args.name, revision).await;
+                let restored = result.as_ref().map_or(args.name.as_str(), |t| t.filename.as_str());
+                let hash_after = match &result {
+                    Ok(_) => manager.audit_hash(restored).await,
+                    Err(_) => None,
+                };
+                manager
+                    .audit(
+                        AuditEntry::new(PROMPT_HISTORY, restored, ctx.connection_id())
+                            .with_action("restore")
+                            .with_hashes(hash_before, hash_after)
+                            .with_result(&result),
+                    )
+                    .await;
+                let template = result.map_err(McpError::Other)?;
+
+                let summary = format!(
+                    "\x1b[32m Prompt Restored: {}\x1b[0m\n  Revision: {revision}",
+                    template.filename
+                );
+
+                let output = PromptHistoryOutput {
+                    success: true,
+                    action: args.action,
+                    name: template.filename.clone(),
+                    message: format!(
+                        "Prompt '{}' restored to revision {revision}",
+                        template.filename
+                    ),
+                    entries: Vec::new(),
+                    diff: None,
+                    restored_from: Some(revision.to_string()),
+                };
+                Ok(ToolResponse::new(summary, output))
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use super::bundle::{BundleFormat, ImportAction, ImportReport, PromptBundle};
+use super::audit::AuditEntry;
+use super::diagnostics::{diagnostics_of, format_diagnostic_lines, validation_diagnostics, Diagnostic};
+use super::manager::PromptManager;
+use super::schema::{ImportPromptsArgs, PromptImportOutput, PromptImportPrompts, PROMPT_IMPORT};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use kodegen_mcp_schema::{McpError, Tool, ToolExecutionContext, ToolResponse};
+
+/// Base64 prefix of a gzip stream (`1f 8b 08`)
+const GZIP_BASE64_PREFIX: &str = "H4sI";
+
+#[derive(Clone)]
+pub struct ImportPromptsTool {
+    manager: PromptManager,
+}
+
+impl ImportPromptsTool {
+    /// Create with a pre-initialized PromptManager (for HTTP server)
+    pub fn with_manager(manager: PromptManager) -> Self {
+        Self { manager }
+    }
+
+    /// Create with default manager (for standalone use)
+    pub async fn new() -> Result<Self, McpError> {
+        let manager = PromptManager::new();
+        manager.init().await?;
+        Ok(Self { manager })
+    }
+}
+
+impl Tool for ImportPromptsTool {
+    type Args = ImportPromptsArgs;
+    type Prompts = PromptImportPrompts;
+
+    fn name() -> &'static str {
+        PROMPT_IMPORT
+    }
+
+    fn description() -> &'static str {
+        "Import a prompt bundle produced by prompt_export (json, yaml, or base64 tar.gz; \
+         detected automatically). Every entry is validated before anything is written: an \
+         invalid bundle is rejected with success=false and a diagnostics list. on_conflict \
+         controls existing names: skip (default), overwrite, or rename (<name>_2, ...). \
+         Use dry_run to see the planned outcome for every entry without writing."
+    }
+
+    fn read_only() -> bool {
+        false
+    }
+
+    fn destructive() -> bool {
+        true // on_conflict=overwrite replaces existing prompts
+    }
+
+    fn idempotent() -> bool {
+        false // Rename policy creates new copies on every call
+    }
+
+    async fn execute(&self, args: Self::Args, ctx: ToolExecutionContext) -> Result<ToolResponse<<Self::Args as kodegen_mcp_schema::ToolArgs>::Output>, McpError> {
+        let bundle = match decode_bundle(&args.data, args.format) {
+            Ok(bundle) => bundle,
+            Err(e) => return Ok(rejected(args.dry_run, diagnostics_of(&e))),
+        };
+
+        let manager = self.manager.for_connection(ctx.connection_id());
+        let mut hashes_before = Vec::with_capacity(bundle.prompts.len());
+        if !args.dry_run {
+            for entry in &bundle.prompts {
+                hashes_before.push(manager.audit_hash(&entry.name).await);
+            }
+        }
+        let result = manager
+            .import_prompts(&bundle, args.on_conflict, args.dry_run)
+            .await;
+        if !args.dry_run {
+            audit_import(&manager, &bundle, hashes_before, &result, ctx.connection_id()).await;
+        }
+
+        let report = match result {
+            Ok(report) => report,
+            Err(e) => {
+                return match validation_diagnostics(&e) {
+                    Some(diagnostics) => Ok(rejected(args.dry_run, diagnostics)),
+                    None => Err(McpError::Other(e)),
+                };
+            }
+        };
+
+        let created = report.count(ImportAction::Created);
+        let overwritten = report.count(ImportAction::Overwritten);
+        let renamed = report.count(ImportAction::Renamed);
+        let skipped = report.count(ImportAction::Skipped);
+
+        let verb = if report.dry_run { "Would import" } else { "Imported" };
+        let message = format!(
+            "{verb} {} prompt(s): {created} created, {overwritten} overwritten, {renamed} renamed, {skipped} skipped",
+            report.outcomes.len()
+        );
+
+        // Terminal summary
+        let mut summary = format!(
+            "\x1b[32m Prompts {}: {}\x1b[0m\n\
+              Created: {created} · Overwritten: {overwritten} · Renamed: {renamed} · Skipped: {skipped}",
+            if report.dry_run { "Import (dry run)" } else { "Imported" },
+            report.outcomes.len()
+        );
+        for outcome in report
+            .outcomes
+            .iter()
+            .filter(|o| o.action == ImportAction::Renamed)
+        {
+            summary.push_str(&format!("\n  • {} → {}", outcome.source, outcome.name));
+        }
+
+        // Typed output
+        let output = PromptImportOutput {
+            success: true,
+            dry_run: report.dry_run,
+            message,
+            created,
+            overwritten,
+            renamed,
+            skipped,
+            outcomes: report.outcomes,
+            diagnostics: Vec::new(),
+        };
+
+        Ok(ToolResponse::new(summary, output))
+    }
+}
+
+/// One audit entry per prompt written (or per bundle entry when the import failed)
+async fn audit_import(
+    manager: &PromptManager,
+    bundle: &PromptBundle,
+    hashes_before: Vec<Option<String>>,
+    result: &anyhow::Result<ImportReport>,
+    connection_id: Option<&str>,
+) {
+    let report = match result {
+        Ok(report) => report,
+        Err(_) => {
+            for entry in &bundle.prompts {
+                manager
+                    .audit(
+                        AuditEntry::new(PROMPT_IMPORT, &entry.name, connection_id)
+                            .with_result(result),
+                    )
+                    .await;
+            }
+            return;
+        }
+    };
+    for (outcome, hash_before) in report.outcomes.iter().zip(hashes_before) {
+        let (action, hash_before) = match outcome.action {
+            ImportAction::Skipped => continue,
+            ImportAction::Created => ("created", None),
+            ImportAction::Overwritten => ("overwritten", hash_before),
+            ImportAction::Renamed => ("renamed", None),
+        };
+        let mut entry = AuditEntry::new(PROMPT_IMPORT, &outcome.source, connection_id)
+            .with_action(action)
+            .with_hashes(hash_before, manager.audit_hash(&outcome.name).await);
+        if outcome.source != outcome.name {
+            entry = entry.with_target(&outcome.name);
+        }
+        manager.audit(entry).await;
+    }
+}
+
+/// Decode tool input; tar.gz bundles arrive base64-encoded
+fn decode_bundle(data: &str, format: Option<BundleFormat>) -> anyhow::Result<PromptBundle> {
+    let binary = match format {
+        Some(format) => format.is_binary(),
+        None => data.trim_start().starts_with(GZIP_BASE64_PREFIX),
+    };
+    if binary {
+        let bytes = BASE64
+            .decode(data.trim())
+            .map_err(|e| anyhow::anyhow!("tar.gz bundle data is not valid base64: {e}"))?;
+        PromptBundle::decode(&bytes, Some(BundleFormat::TarGz))
+    } else {
+        PromptBundle::decode(data.as_bytes(), format)
+    }
+}
+
+/// Response for a bundle rejected by validation (nothing written)
+fn rejected(dry_run: bool, diagnostics: Vec<Diagnostic>) -> ToolResponse<PromptImportOutput> {
+    let summary = format!(
+        "\x1b[31m Import Rejected\x1b[0m\n{}",
+        format_diagnostic_lines(&diagnostics)
+    );
+    let output = PromptImportOutput {
+        success: false,
+        dry_run,
+        message: format!(
+            "Bundle was not imported: {} validation error(s)",
+            diagnostics.len()
+        ),
+        created: 0,
+        overwritten: 0,
+        renamed: 0,
+        skipped: 0,
+        outcomes: Vec::new(),
+        diagnostics,
+    };
+    ToolResponse::new(summary, output)
+}
@@ -1,7 +1,24 @@
+pub mod attachments;
+pub mod audit;
+pub mod bundle;
+pub mod config;
 mod defaults;
+pub mod diagnostics;
+pub mod env_policy;
+pub mod git_store;
+pub mod lint;
 pub mod manager;
+pub mod messages;
 pub mod metadata;
+pub mod metrics;
+pub mod policy;
+pub mod prompt_routes;
+pub mod schema;
+pub mod stdio;
+pub mod store;
 pub mod template;
+pub mod tokens;
+pub mod usage;
 pub mod validation;
 
 pub mod add_prompt;
@@ -16,9 +33,58 @@ pub use delete_prompt::*;
 pub mod get_prompt;
 pub use get_prompt::*;
 
+pub mod validate_prompt;
+pub use validate_prompt::*;
+
+pub mod rename_prompt;
+pub use rename_prompt::*;
+
+pub mod copy_prompt;
+pub use copy_prompt::*;
+
+pub mod export_prompts;
+pub use export_prompts::*;
+
+pub mod import_prompts;
+pub use import_prompts::*;
+
+pub mod history_prompt;
+pub use history_prompt::*;
+
+pub mod audit_prompt;
+pub use audit_prompt::*;
+
+pub mod stats_prompt;
+pub use stats_prompt::*;
+
+pub mod vote_prompt;
+pub use vote_prompt::*;
+
 // Re-export commonly used types
-pub use manager::PromptManager;
-pub use metadata::{ParameterDefinition, ParameterType, PromptMetadata, PromptTemplate};
+pub use attachments::{
+    Attachment, AttachmentBudget, AttachmentData, AttachmentKind, AttachmentSandbox,
+};
+pub use audit::{AuditEntry, AuditLog, AuditOutcome, AuditQuery};
+pub use config::{tool_names, PromptLimits, PromptServerConfig, Transport};
+pub use bundle::{BundleFormat, ConflictPolicy, ExportFilter, ImportReport, PromptBundle};
+pub use diagnostics::{diagnostics_of, Diagnostic, Severity, ValidationErrors};
+pub use env_policy::{env_policy, reload_env, EnvPolicy};
+pub use git_store::{ChangeAuthor, HistoryEntry};
+pub use manager::{in_namespace, prompt_namespace, PromptManager, NAMESPACE_SEPARATOR};
+pub use messages::{MessageRole, RenderedMessage};
+pub use policy::{ClientIdentity, Policy, PolicyAction, PolicyDenied};
+pub use metrics::{metrics, MetricsSnapshot};
+pub use metadata::{
+    ParameterDefinition, ParameterType, PromptMetadata, PromptTemplate, RenderLimits, TokenOverflow,
+};
+pub use tokens::{HeuristicEstimator, TokenEstimate, TokenEstimator};
+pub use usage::{PromptUsage, UsageStore};
+pub use store::{
+    FsStore, MemoryStore, PromptQuery, PromptRevision, PromptStore, SearchMode, StoreError,
+    StoredMetadata,
+};
+#[cfg(feature = "sqlite")]
+pub use store::SqliteStore;
 
 /// Start the prompt tools HTTP server programmatically
 ///
@@ -66,49 +132,9 @@ pub async fn start_server_with_listener(
     listener: tokio::net::TcpListener,
     tls_config: Option<(std::path::PathBuf, std::path::PathBuf)>,
 ) -> anyhow::Result<kodegen_server_http::ServerHandle> {
-    use kodegen_server_http::{ServerBuilder, Managers, RouterSet, register_tool};
-    use rmcp::handler::server::router::{prompt::PromptRouter, tool::ToolRouter};
-
-    let mut builder = ServerBuilder::new()
-        .category(kodegen_config::CATEGORY_PROMPT)
-        .register_tools(|| async {
-            let mut tool_router = ToolRouter::new();
-            let mut prompt_router = PromptRouter::new();
-            let managers = Managers::new();
-
-            // Initialize PromptManager (clean async initialization)
-            let manager = crate::PromptManager::new();
-            manager.init().await?;
-
-            // Register all 4 prompt management tools with shared manager
-            (tool_router, prompt_router) = register_tool(
-                tool_router,
-                prompt_router,
-                crate::AddPromptTool::with_manager(manager.clone()),
-            );
-            (tool_router, prompt_router) = register_tool(
-                tool_router,
-                prompt_router,
-                crate::EditPromptTool::with_manager(manager.clone()),
-            );
-            (tool_router, prompt_router) = register_tool(
-                tool_router,
-                prompt_router,
-                crate::DeletePromptTool::with_manager(manager.clone()),
-            );
-            (tool_router, prompt_router) = register_tool(
-                tool_router,
-                prompt_router,
-                crate::GetPromptTool::with_manager(manager.clone()),
-            );
-
-            Ok(RouterSet::new(tool_router, prompt_router, managers))
-        })
-        .with_listener(listener);
-
+    let mut config = PromptServerConfig::from_env().http_listener(listener);
     if let Some((cert, key)) = tls_config {
-        builder = builder.with_tls_config(cert, key);
+        config = config.tls(cert, key);
     }
-
-    builder.serve().await
+    config.start().await
 }
@@ -0,0 +1,123 @@
+use super::diagnostics::{body_line_offset, locate_diagnostics, Diagnostic};
+use super::messages::expand_message_blocks;
+use super::metadata::PromptTemplate;
+use minijinja::Environment;
+use regex::Regex;
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+/// Template helpers the renderer registers as functions
+const HELPER_NAMES: &[&str] = &["attach", "image"];
+
+/// Matches `{{ ... }}` expressions and `{% ... %}` statements
+static TAG_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)\{\{.*?\}\}|\{%.*?%\}").expect("Failed to compile tag pattern")
+});
+
+/// Lint a parsed prompt template
+///
+/// Returns warnings for problems that do not stop the template from being
+/// saved: variables used but not declared as parameters, parameters declared
+/// but never used, and deprecated prompts without a replacement. Locations are
+/// relative to `file_content`. Templates with syntax errors produce no lint
+/// findings (the syntax error is reported by validation instead).
+pub fn lint_template(template: &PromptTemplate, file_content: &str) -> Vec<Diagnostic> {
+    let Some(used) = referenced_variables(&template.content, false) else {
+        return Vec::new();
+    };
+
+    let declared: HashSet<&str> = template
+        .metadata
+        .parameters
+        .iter()
+        .map(|p| p.name.as_str())
+        .collect();
+
+    let mut diagnostics = Vec::new();
+
+    // Variables the template reads but the frontmatter never declares
+    let line_offset = body_line_offset(file_content, &template.content);
+    let mut undeclared: Vec<&String> = used
+        .iter()
+        .filter(|name| !declared.contains(name.as_str()))
+        .collect();
+    undeclared.sort();
+    for name in undeclared {
+        let mut diagnostic = Diagnostic::new(format!(
+            "Variable '{name}' is used but not declared as a parameter"
+        ))
+        .with_suggestion(format!(
+            "Declare '{name}' under `parameters`, or it renders as empty"
+        ))
+        .as_warning();
+        if let Some((line, column)) = first_use(&template.content, name) {
+            diagnostic = diagnostic.at(line + line_offset, column);
+        }
+        diagnostics.push(diagnostic);
+    }
+
+    // Parameters the frontmatter declares but the template never reads
+    let mut frontmatter = Vec::new();
+    for (index, param) in template.metadata.parameters.iter().enumerate() {
+        if !used.contains(&param.name) {
+            frontmatter.push(
+                Diagnostic::new(format!("Parameter '{}' is declared but never used", param.name))
+                    .with_path(format!("parameters[{index}].name"))
+                    .with_suggestion(format!(
+                        "Reference it as {{{{ {} }}}} or remove the declaration",
+                        param.name
+                    ))
+                    .as_warning(),
+            );
+        }
+    }
+
+    if template.metadata.deprecated && template.metadata.replaced_by.is_none() {
+        frontmatter.push(
+            Diagnostic::new("Deprecated prompt does not name a replacement")
+                .with_path("deprecated")
+                .with_suggestion("Set `replaced_by` to the prompt callers should use instead")
+                .as_warning(),
+        );
+    }
+
+    diagnostics.extend(locate_diagnostics(file_content, frontmatter));
+    diagnostics
+}
+
+/// Variables a template reads from its context
+///
+/// With `nested`, attribute paths are reported as well (`env.USER`). Excludes
+/// names the template assigns itself (loop targets, `set`), MiniJinja globals,
+/// and the `attach` / `image` helpers; `env` is excluded unless `nested`.
+/// Returns `None` if the template does not compile.
+pub(crate) fn referenced_variables(content: &str, nested: bool) -> Option<HashSet<String>> {
+    let source = expand_message_blocks(content).ok()?;
+    let mut env = Environment::new();
+    env.add_template("_lint", &source).ok()?;
+    let template = env.get_template("_lint").ok()?;
+
+    let globals: HashSet<&str> = env.globals().map(|(name, _)| name).collect();
+    Some(
+        template
+            .undeclared_variables(nested)
+            .into_iter()
+            .filter(|name| {
+                let root = name.split('.').next().unwrap_or(name);
+                !globals.contains(root) && !HELPER_NAMES.contains(&root) && (nested || root != "env")
+            })
+            .collect(),
+    )
+}
+
+/// Line and column (1-based, body-relative) of the first tag that mentions `name`
+fn first_use(content: &str, name: &str) -> Option<(usize, usize)> {
+    let word = Regex::new(&format!(r"\b{}\b", regex::escape(name))).ok()?;
+    let start = TAG_PATTERN
+        .find_iter(content)
+        .find_map(|tag| word.find(tag.as_str()).map(|m| tag.start() + m.start()))?;
+
+    let line = content[..start].matches('\n').count() + 1;
+    let column = start - content[..start].rfind('\n').map_or(0, |i| i + 1) + 1;
+    Some((line, column))
+}
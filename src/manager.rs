@@ -1,31 +1,95 @@
+use super::attachments::AttachmentSandbox;
+use super::audit::{AuditEntry, AuditLog, AuditOutcome, AuditQuery};
+use super::bundle::{
+    ConflictPolicy, ExportFilter, ImportAction, ImportOutcome, ImportReport, PromptBundle,
+};
+use super::config::PromptLimits;
 use super::defaults;
+use super::diagnostics::{Diagnostic, ValidationErrors};
+use super::git_store::{ChangeAuthor, GitStore, HistoryEntry};
+use super::metrics::{metrics, LibraryStats};
+use super::policy::{current_client, Policy, PolicyAction};
 use super::metadata::PromptTemplate;
-use super::template::{parse_template, render_template};
+use super::usage::{PromptUsage, UsageStore, USAGE_FILE};
+use super::store::{
+    FsStore, PromptQuery, PromptRevision, PromptStore, SearchMode, StoreError, StoredMetadata,
+};
+use super::template::{
+    parse_template, set_frontmatter_key, CompiledTemplate, RenderPreview, RenderedMessages,
+    RenderedPrompt,
+};
+use super::tokens::{fit_messages, fit_text, HeuristicEstimator, TokenEstimator};
 use anyhow::{Context, Result};
 use kodegen_config::KodegenConfig;
 use kodegen_mcp_schema::prompt::TemplateParamValue;
 use kodegen_mcp_schema::McpError;
 use log::{debug, info, warn};
-use std::collections::HashMap;
-use std::io::ErrorKind;
-use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::SystemTime;
-use tokio::fs;
-use tokio::fs::OpenOptions;
-use tokio::io::AsyncWriteExt;
-use tokio::sync::RwLock;
+use tokio::sync::{OnceCell, RwLock};
 
-/// Cached template with file modification time for validation
+/// Path of a SQLite prompt database to use instead of the prompts directory
+pub const PROMPTS_DB_ENV: &str = "KODEGEN_PROMPTS_DB";
+
+/// Serve the library read-only (`1`, `true`, `yes`, `on`)
+pub const READ_ONLY_ENV: &str = "KODEGEN_PROMPTS_READ_ONLY";
+
+/// Comma-separated namespaces served read-only (e.g. `system,vendor/acme`)
+pub const READ_ONLY_NAMESPACES_ENV: &str = "KODEGEN_PROMPTS_READ_ONLY_NAMESPACES";
+
+/// How searches run: `auto` (default), `scan` or `index`
+pub const SEARCH_ENV: &str = "KODEGEN_PROMPTS_SEARCH";
+
+/// Cached template with the stored size and modification time for validation
 struct CachedTemplate {
+    version: PromptVersion,
+    stamp: StoredMetadata,
+}
+
+/// One stored version of a prompt, as parsed from its file
+#[derive(Clone)]
+struct PromptVersion {
     template: PromptTemplate,
-    file_mtime: SystemTime,
+    /// Compiled on first render; dropped with the entry when the prompt changes
+    compiled: CompiledSlot,
+    /// SHA-256 of the file content, recorded in render audit entries
+    hash: Arc<str>,
 }
 
+/// Compiled form of one cached prompt version, shared by its renders
+type CompiledSlot = Arc<OnceCell<Arc<CompiledTemplate>>>;
+
 #[derive(Clone)]
 pub struct PromptManager {
-    prompts_dir: PathBuf,
+    store: Arc<dyn PromptStore>,
     cache: Arc<RwLock<HashMap<String, CachedTemplate>>>,
+    /// Alias -> prompt name, built on first alias lookup and kept current by writes
+    aliases: Arc<RwLock<Option<HashMap<String, String>>>>,
+    /// Commit every change when the prompts directory is git-backed
+    git: Option<GitStore>,
+    /// Author recorded on commits made through this handle
+    author: ChangeAuthor,
+    /// Reject every change (curated libraries on shared servers)
+    read_only: bool,
+    /// Namespaces whose prompts can't be changed (e.g. a curated `system` layer)
+    read_only_namespaces: Vec<String>,
+    /// Usage analytics live in the prompts directory (the default sidecar)
+    usage_in_library: bool,
+    /// Per-client authorization, checked inside client requests
+    policy: Option<Arc<Policy>>,
+    /// Append-only record of changes, renders and denials
+    audit: Option<AuditLog>,
+    /// Entry completed and appended for each render through this handle
+    render_audit: Option<AuditEntry>,
+    /// Render counts, last use and votes per prompt
+    usage: UsageStore,
+    /// Counts tokens in rendered output for `max_tokens` budgets
+    tokens: Arc<dyn TokenEstimator>,
+    /// Size and resource limits for renders through this manager
+    limits: PromptLimits,
+    /// Whether searches use the store's index or scan every prompt
+    search: SearchMode,
 }
 
 impl Default for PromptManager {
@@ -36,13 +100,308 @@ impl Default for PromptManager {
 
 impl PromptManager {
     /// Create new prompt manager (synchronous constructor)
+    ///
+    /// Prompts are stored as files in the kodegen config directory.
     #[must_use]
     pub fn new() -> Self {
         let prompts_dir =
             get_prompts_directory().unwrap_or_else(|_| PathBuf::from(".kodegen/prompts"));
+        Self::with_store(FsStore::new(prompts_dir))
+    }
+
+    /// Create a prompt manager configured from the environment
+    ///
+    /// `KODEGEN_PROMPTS_DB` selects a SQLite database instead of the prompts
+    /// directory (requires the `sqlite` feature); `KODEGEN_PROMPTS_GIT=1`
+    /// enables git storage. See [`PromptServerConfig::from_env`](crate::PromptServerConfig::from_env).
+    pub fn from_env() -> Result<Self> {
+        super::config::PromptServerConfig::from_env().create_manager()
+    }
+
+    /// Create a prompt manager over any storage backend
+    ///
+    /// Directory-backed stores keep usage analytics in `.usage.json` inside
+    /// the directory; other stores keep them in memory unless
+    /// [`with_usage_store`](Self::with_usage_store) says otherwise.
+    #[must_use]
+    pub fn with_store(store: impl PromptStore + 'static) -> Self {
+        let (usage, usage_in_library) = match store.root_dir() {
+            Some(dir) => (UsageStore::at(dir.join(USAGE_FILE)), true),
+            None => (UsageStore::in_memory(), false),
+        };
         Self {
-            prompts_dir,
+            store: Arc::new(store),
             cache: Arc::new(RwLock::new(HashMap::new())),
+            aliases: Arc::new(RwLock::new(None)),
+            git: None,
+            author: ChangeAuthor::default(),
+            read_only: false,
+            read_only_namespaces: Vec::new(),
+            usage_in_library,
+            policy: None,
+            audit: None,
+            render_audit: None,
+            usage,
+            tokens: Arc::new(HeuristicEstimator),
+            limits: *super::config::limits(),
+            search: SearchMode::Auto,
+        }
+    }
+
+    /// Keep the prompts directory in git, committing every change
+    ///
+    /// The repository is created (and existing prompts committed) by `init()`.
+    /// Only directory-backed stores can be git-backed; for other stores
+    /// `init()` fails.
+    #[must_use]
+    pub fn with_git_storage(mut self) -> Self {
+        self.git = Some(GitStore::new(
+            self.store.root_dir().map(PathBuf::from).unwrap_or_default(),
+        ));
+        self
+    }
+
+    /// Reject every change made through this manager (and its clones)
+    ///
+    /// Prompts can still be listed, read, rendered, exported and their
+    /// history inspected. `init()` does not seed default prompts or create
+    /// the git repository. Usage analytics kept in the prompts directory are
+    /// still read, but new renders and votes are only counted in memory.
+    #[must_use]
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        if read_only && !self.read_only && self.usage_in_library {
+            self.usage = self.usage.unsaved();
+        }
+        self.read_only = read_only;
+        self
+    }
+
+    /// Reject changes to prompts in `namespace` and its sub-namespaces
+    ///
+    /// Lets one layer of the library (say `system/...`) stay immutable while
+    /// the rest remains writable. Renaming a prompt into or out of the
+    /// namespace counts as a change to it. An empty namespace covers every
+    /// prompt, like [`with_read_only`](Self::with_read_only).
+    #[must_use]
+    pub fn with_read_only_namespace(mut self, namespace: impl Into<String>) -> Self {
+        let namespace = namespace.into().trim_matches(NAMESPACE_SEPARATOR).to_string();
+        if !self.read_only_namespaces.contains(&namespace) {
+            self.read_only_namespaces.push(namespace);
+        }
+        self
+    }
+
+    /// Check every operation made on behalf of a client against `policy`
+    ///
+    /// The client is the one recorded by [`policy::scope_routes`](super::policy::scope_routes)
+    /// for the current request; calls outside a request are not checked.
+    #[must_use]
+    pub fn with_policy(mut self, policy: Arc<Policy>) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Record changes, renders and policy denials in `log`
+    #[must_use]
+    pub fn with_audit_log(mut self, log: AuditLog) -> Self {
+        self.audit = Some(log);
+        self
+    }
+
+    /// Keep usage analytics (render counts, last use, votes) in `usage`
+    #[must_use]
+    pub fn with_usage_store(mut self, usage: UsageStore) -> Self {
+        self.usage = usage;
+        self.usage_in_library = false;
+        self
+    }
+
+    /// Estimate rendered tokens with `estimator` instead of the heuristic
+    #[must_use]
+    pub fn with_token_estimator(mut self, estimator: Arc<dyn TokenEstimator>) -> Self {
+        self.tokens = estimator;
+        self
+    }
+
+    /// Render with `limits` instead of the process-wide defaults
+    ///
+    /// The manager gets a template cache of its own, so templates compiled
+    /// under other limits are not reused.
+    #[must_use]
+    pub fn with_limits(mut self, limits: PromptLimits) -> Self {
+        if limits != self.limits {
+            self.limits = limits;
+            self.cache = Arc::new(RwLock::new(HashMap::new()));
+        }
+        self
+    }
+
+    /// Size and resource limits for renders through this manager
+    pub fn limits(&self) -> &PromptLimits {
+        &self.limits
+    }
+
+    /// Run searches as `mode` says instead of using the store's index when it has one
+    #[must_use]
+    pub fn with_search_mode(mut self, mode: SearchMode) -> Self {
+        self.search = mode;
+        self
+    }
+
+    /// How searches run
+    pub fn search_mode(&self) -> SearchMode {
+        self.search
+    }
+
+    /// Handle sharing this manager's cache that records `author` on commits
+    #[must_use]
+    pub fn as_author(&self, author: ChangeAuthor) -> Self {
+        Self {
+            author,
+            ..self.clone()
+        }
+    }
+
+    /// Handle that appends `entry` to the audit log for each render
+    ///
+    /// The entry gets the outcome of the render and the hash of the prompt
+    /// version that was rendered. Without an audit log this is a plain clone.
+    #[must_use]
+    pub fn auditing_render(&self, entry: AuditEntry) -> Self {
+        Self {
+            render_audit: self.audit.is_some().then_some(entry),
+            ..self.clone()
+        }
+    }
+
+    /// Handle recording the MCP connection as commit author (if known)
+    #[must_use]
+    pub fn for_connection(&self, connection_id: Option<&str>) -> Self {
+        match connection_id {
+            Some(id) if self.git.is_some() => self.as_author(ChangeAuthor::from_connection(id)),
+            _ => self.clone(),
+        }
+    }
+
+    /// Prompts directory, for directory-backed stores
+    pub fn prompts_dir(&self) -> Option<&std::path::Path> {
+        self.store.root_dir()
+    }
+
+    /// File holding a prompt, for directory-backed stores
+    pub fn prompt_path(&self, name: &str) -> Option<std::path::PathBuf> {
+        self.prompts_dir().map(|dir| dir.join(prompt_file_name(name)))
+    }
+
+    /// Whether changes are committed to git
+    pub fn is_git_backed(&self) -> bool {
+        self.git.is_some()
+    }
+
+    /// Whether changes are rejected
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Namespaces whose prompts can't be changed
+    pub fn read_only_namespaces(&self) -> &[String] {
+        &self.read_only_namespaces
+    }
+
+    fn read_only_namespace_of(&self, name: &str) -> Option<&str> {
+        self.read_only_namespaces
+            .iter()
+            .find(|namespace| in_namespace(name, namespace))
+            .map(String::as_str)
+    }
+
+    /// Audit log, when auditing is enabled
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.audit.as_ref()
+    }
+
+    /// Append `entry` to the audit log (no-op when auditing is off)
+    ///
+    /// Denied operations are skipped: the denial was already recorded when
+    /// the policy rejected it. Write failures are logged, not returned, so a
+    /// full disk does not fail the operation being audited.
+    pub async fn audit(&self, entry: AuditEntry) {
+        let Some(log) = &self.audit else {
+            return;
+        };
+        if entry.outcome == AuditOutcome::Denied {
+            return;
+        }
+        if let Err(e) = log.append(&entry).await {
+            warn!("Failed to write audit entry for '{}': {e:#}", entry.prompt);
+        }
+    }
+
+    /// SHA-256 of a prompt file's content, for audit entries
+    ///
+    /// Only the exact stored name is hashed; aliases are not resolved, so an
+    /// entry never carries the hash of a different prompt. `None` when
+    /// auditing is off (so callers skip the read) or no prompt has that name.
+    pub async fn audit_hash(&self, name: &str) -> Option<String> {
+        self.audit.as_ref()?;
+        let content = self.store.read(name).await.ok()?;
+        Some(super::bundle::sha256_hex(&content))
+    }
+
+    /// Audit entries matching `query` about prompts the current client may read
+    pub async fn audit_entries(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        let log = self.audit.as_ref().with_context(|| {
+            format!(
+                "Audit logging is disabled; set {} to enable it",
+                super::audit::AUDIT_LOG_ENV
+            )
+        })?;
+        log.query_where(query, |entry| self.readable(&entry.prompt)).await
+    }
+
+    /// Fail when the current client may not perform `action` on `name`
+    ///
+    /// Denials are appended to the audit log in the background.
+    fn authorize(&self, action: PolicyAction, name: &str) -> Result<()> {
+        let (Some(policy), Some(client)) = (&self.policy, current_client()) else {
+            return Ok(());
+        };
+        let result = policy.check(&client, action, name);
+        if let (Err(e), Some(log)) = (&result, &self.audit) {
+            let entry = AuditEntry::denied(&client, action, name, &e.to_string());
+            let log = log.clone();
+            tokio::spawn(async move {
+                if let Err(e) = log.append(&entry).await {
+                    warn!("Failed to write audit entry for '{}': {e:#}", entry.prompt);
+                }
+            });
+        }
+        result
+    }
+
+    /// Whether the current client may read `name` (for filtering listings)
+    fn readable(&self, name: &str) -> bool {
+        match (&self.policy, current_client()) {
+            (Some(policy), Some(client)) => policy.allows(&client, PolicyAction::Read, name),
+            _ => true,
+        }
+    }
+
+    /// Fail when the library is read-only
+    fn ensure_writable(&self, action: &str) -> Result<()> {
+        if self.read_only {
+            anyhow::bail!("Cannot {action}: the prompt library is read-only");
+        }
+        Ok(())
+    }
+
+    /// Fail when the library or the namespace of `name` is read-only
+    fn ensure_prompt_writable(&self, name: &str, action: &str) -> Result<()> {
+        self.ensure_writable(action)?;
+        match self.read_only_namespace_of(name) {
+            Some("") => anyhow::bail!("Cannot {action}: every namespace is read-only"),
+            Some(namespace) => anyhow::bail!("Cannot {action}: namespace '{namespace}' is read-only"),
+            None => Ok(()),
         }
     }
 
@@ -50,261 +409,1013 @@ impl PromptManager {
     ///
     /// Call this after `new()` to perform async setup operations.
     pub async fn init(&self) -> Result<(), McpError> {
-        // Ensure directory exists (async)
-        fs::create_dir_all(&self.prompts_dir)
-            .await
-            .with_context(|| {
-                format!(
-                    "Failed to create prompts directory: {}",
-                    self.prompts_dir.display()
-                )
-            })
-            .map_err(McpError::Other)?;
+        // Ensure storage exists (async)
+        self.store.init().await.map_err(McpError::Other)?;
 
-        // Initialize default prompts if directory is empty (async)
-        if let Err(e) = initialize_default_prompts(&self.prompts_dir).await {
+        // A read-only library is served as it is
+        if self.read_only {
+            return Ok(());
+        }
+
+        // Initialize default prompts if storage is empty (async)
+        if let Err(e) = initialize_default_prompts(self.store.as_ref()).await {
             warn!("Failed to initialize default prompts: {e}");
             // Don't fail - user can add prompts manually
         }
 
+        if let Some(git) = &self.git {
+            let Some(dir) = self.store.root_dir() else {
+                return Err(McpError::Other(anyhow::anyhow!(
+                    "Git storage requires a directory-backed prompt store"
+                )));
+            };
+            git.init()
+                .await
+                .with_context(|| {
+                    format!("Failed to initialize git storage in {}", dir.display())
+                })
+                .map_err(McpError::Other)?;
+        }
+
         Ok(())
     }
 
     /// List all available prompts (async)
+    ///
+    /// Stores with a metadata index answer directly; otherwise every prompt
+    /// is loaded through the cache. Prompts the current client may not read
+    /// are left out.
     pub async fn list_prompts(&self) -> Result<Vec<PromptTemplate>> {
-        let mut prompts = Vec::new();
+        let mut prompts = self.list_all_prompts().await?;
+        prompts.retain(|t| self.readable(&t.filename));
+        Ok(prompts)
+    }
 
-        let mut entries = fs::read_dir(&self.prompts_dir).await.with_context(|| {
-            format!(
-                "Failed to read prompts directory: {}",
-                self.prompts_dir.display()
-            )
-        })?;
+    /// Every parseable prompt, regardless of policy
+    async fn list_all_prompts(&self) -> Result<Vec<PromptTemplate>> {
+        if let Some(templates) = self
+            .store
+            .query_templates(&PromptQuery::default())
+            .await
+            .context("Failed to list prompts")?
+        {
+            return Ok(templates);
+        }
 
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
+        let names = self
+            .store
+            .list()
+            .await
+            .context("Failed to list prompts")?;
 
-            // CHANGE 1: Check file type first (reject symlinks and directories)
-            let file_type = match entry.file_type().await {
-                Ok(ft) => ft,
+        let mut prompts = Vec::with_capacity(names.len());
+        for name in names {
+            match self.load_prompt_file(&name).await {
+                Ok(template) => prompts.push(template),
                 Err(e) => {
-                    warn!("Failed to get file type for {}: {e}", path.display());
-                    continue;
+                    warn!("Failed to load prompt '{name}': {e}");
                 }
-            };
+            }
+        }
+
+        Ok(prompts)
+    }
 
-            // CHANGE 2: Skip non-regular files (directories, symlinks, etc.)
-            if !file_type.is_file() {
-                debug!("Skipping non-file entry: {}", path.display());
-                continue;
+    /// Names of every stored prompt, sorted, including prompts that fail to parse
+    pub async fn prompt_names(&self) -> Result<Vec<String>> {
+        let mut names = self
+            .store
+            .list()
+            .await
+            .context("Failed to list prompts")?;
+        names.retain(|name| self.readable(name));
+        names.sort();
+        Ok(names)
+    }
+
+    /// List prompts matching category, tag, author and text filters (async)
+    ///
+    /// Indexed stores (SQLite) run the query in the database, ranking text
+    /// matches by relevance; other stores filter the full list in memory.
+    /// [`with_search_mode`](Self::with_search_mode) forces one or the other.
+    pub async fn search_prompts(&self, query: &PromptQuery) -> Result<Vec<PromptTemplate>> {
+        // The policy filter runs after the store's query, so the limit must too
+        let unlimited;
+        let store_query = if self.policy.is_some() && current_client().is_some() {
+            unlimited = PromptQuery { limit: None, ..query.clone() };
+            &unlimited
+        } else {
+            query
+        };
+        let indexed = match self.search {
+            SearchMode::Scan => None,
+            SearchMode::Auto | SearchMode::Index => self
+                .store
+                .query_templates(store_query)
+                .await
+                .context("Failed to search prompts")?,
+        };
+        if let Some(templates) = indexed {
+            let mut templates = templates;
+            templates.retain(|t| self.readable(&t.filename));
+            if let Some(limit) = query.limit {
+                templates.truncate(limit);
             }
+            return Ok(templates);
+        }
+        if self.search == SearchMode::Index {
+            anyhow::bail!("Search mode is 'index', but this prompt store has no search index");
+        }
 
-            // CHANGE 3: Check for .j2.md extension (not just .md)
-            let filename_str = match path.file_name().and_then(|s| s.to_str()) {
-                Some(name) if name.ends_with(".j2.md") => name,
-                _ => continue, // Skip files that don't match pattern
+        let mut templates: Vec<PromptTemplate> = self
+            .list_prompts()
+            .await?
+            .into_iter()
+            .filter(|t| query.matches(t))
+            .collect();
+        templates.sort_by(|a, b| a.filename.cmp(&b.filename));
+        if let Some(limit) = query.limit {
+            templates.truncate(limit);
+        }
+        Ok(templates)
+    }
+
+    /// Load a specific prompt by filename or alias (async)
+    ///
+    /// If no prompt file named `name` exists, prompts declaring `name` in their
+    /// `aliases` metadata are searched, so renamed prompts keep resolving under
+    /// their old names. The returned template carries its canonical filename.
+    pub async fn load_prompt(&self, name: &str) -> Result<PromptTemplate> {
+        let template = self.resolve_prompt(name).await?;
+        self.authorize(PolicyAction::Read, &template.filename)?;
+        Ok(template)
+    }
+
+    /// Load a prompt that is about to be rendered (checked as a render)
+    ///
+    /// For callers that need the parameter definitions before rendering, so
+    /// clients allowed to render but not read can still use the prompt.
+    pub async fn load_prompt_for_render(&self, name: &str) -> Result<PromptTemplate> {
+        let template = self.resolve_prompt(name).await?;
+        self.authorize(PolicyAction::Render, &template.filename)?;
+        Ok(template)
+    }
+
+    /// Load a prompt by filename or alias without a policy check
+    async fn resolve_prompt(&self, name: &str) -> Result<PromptTemplate> {
+        self.resolve_cached(name).await.map(|version| version.template)
+    }
+
+    /// Load a prompt by filename or alias with the slot for its compiled form
+    async fn resolve_cached(&self, name: &str) -> Result<PromptVersion> {
+        // Validate name to prevent path traversal
+        validate_prompt_name(name)?;
+
+        if !self.prompt_exists(name).await
+            && let Some(template) = self.resolve_alias(name).await?
+        {
+            debug!("Resolved prompt alias '{name}' to '{}'", template.filename);
+            return self.load_cached(&template.filename).await;
+        }
+
+        self.load_cached(name).await
+    }
+
+    /// Render a prompt with `render`, counting and auditing the attempt
+    ///
+    /// The prompt is compiled once per cached version; a prompt that changes
+    /// is recompiled on its next render. The audit entry of an
+    /// [`auditing_render`](Self::auditing_render) handle records the hash of
+    /// the version rendered.
+    async fn render_with<T, F, Fut>(&self, name: &str, render: F) -> Result<T>
+    where
+        F: FnOnce(Arc<CompiledTemplate>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut hash = None;
+        let result = async {
+            let version = self.resolve_cached(name).await?;
+            hash = Some(version.hash.to_string());
+            let name = &version.template.filename;
+            self.authorize(PolicyAction::Render, name)?;
+            let result = match version
+                .compiled
+                .get_or_try_init(|| CompiledTemplate::compile(&version.template, &self.limits))
+                .await
+            {
+                Ok(compiled) => render(compiled.clone()).await,
+                Err(e) => Err(e),
             };
+            self.record_render(name, result.is_ok()).await;
+            result
+        }
+        .await;
 
-            // CHANGE 4: Extract stem by removing ".j2.md" suffix (6 chars)
-            let stem = &filename_str[..filename_str.len() - 6];
+        if let Some(entry) = &self.render_audit {
+            let entry = entry.clone().with_hashes(None, hash).with_result(&result);
+            self.audit(entry).await;
+        }
+        result
+    }
+
+    /// Raw file content of a prompt (frontmatter and body), resolving aliases
+    ///
+    /// Unlike `load_prompt`, content that no longer parses is still returned
+    /// so it can be inspected and fixed.
+    pub async fn read_prompt_source(&self, name: &str) -> Result<String> {
+        validate_prompt_name(name)?;
 
-            // Validate prompt name before attempting load (reuses existing validation)
-            if !is_valid_prompt_name(stem) {
-                warn!("Invalid prompt filename (skipping): {stem}");
-                continue;
+        let name = if self.prompt_exists(name).await {
+            name.to_string()
+        } else {
+            match self.resolve_alias(name).await? {
+                Some(template) => template.filename,
+                None => name.to_string(),
             }
+        };
+        self.authorize(PolicyAction::Read, &name)?;
 
-            // Load prompt (now guaranteed to be safe, regular file)
-            match self.load_prompt(stem).await {
-                Ok(template) => prompts.push(template),
-                Err(e) => {
-                    warn!("Failed to load prompt '{stem}': {e}");
+        match self.store.read(&name).await {
+            Ok(content) => Ok(content),
+            Err(StoreError::NotFound) => anyhow::bail!("Prompt '{name}' not found"),
+            Err(e) => Err(e).with_context(|| format!("Failed to read prompt: {name}")),
+        }
+    }
+
+    /// Find the prompt that declares `alias` in its metadata
+    ///
+    /// Looked up in the alias index. An entry that no longer matches the
+    /// prompt (changed outside this manager) rebuilds the index once.
+    async fn resolve_alias(&self, alias: &str) -> Result<Option<PromptTemplate>> {
+        for rebuilt in [false, true] {
+            if rebuilt {
+                *self.aliases.write().await = None;
+            }
+            let Some(name) = self.alias_owner(alias).await? else {
+                return Ok(None);
+            };
+            if let Ok(template) = self.load_prompt_file(&name).await
+                && template.metadata.aliases.iter().any(|a| a == alias)
+            {
+                return Ok(Some(template));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Prompt that declares `alias`, building the alias index if needed
+    async fn alias_owner(&self, alias: &str) -> Result<Option<String>> {
+        if let Some(index) = self.aliases.read().await.as_ref() {
+            return Ok(index.get(alias).cloned());
+        }
+
+        let mut templates = self.list_all_prompts().await?;
+        templates.sort_by(|a, b| a.filename.cmp(&b.filename));
+        let mut index: HashMap<String, String> = HashMap::new();
+        for template in templates {
+            for alias in &template.metadata.aliases {
+                match index.get(alias) {
+                    // Collisions are rejected on write; files edited by hand
+                    // can still have them, and the first by name wins
+                    Some(owner) => warn!(
+                        "Alias '{alias}' is claimed by '{owner}' and '{}'; using '{owner}'",
+                        template.filename
+                    ),
+                    None => {
+                        index.insert(alias.clone(), template.filename.clone());
+                    }
                 }
             }
         }
+        let owner = index.get(alias).cloned();
+        *self.aliases.write().await = Some(index);
+        Ok(owner)
+    }
 
-        Ok(prompts)
+    /// Reject a prompt whose name or aliases are taken by other prompts
+    ///
+    /// `renamed_from` is the prompt's previous name, whose file and aliases
+    /// the prompt replaces.
+    async fn check_alias_collisions(
+        &self,
+        template: &PromptTemplate,
+        renamed_from: Option<&str>,
+    ) -> Result<()> {
+        let name = template.filename.as_str();
+        let other = |owner: &str| owner != name && Some(owner) != renamed_from;
+        let mut diagnostics = Vec::new();
+
+        if let Some(owner) = self.alias_owner(name).await?.filter(|owner| other(owner)) {
+            diagnostics.push(
+                Diagnostic::new(format!("'{name}' is already an alias of prompt '{owner}'"))
+                    .with_suggestion("Choose another name, or remove the alias from that prompt"),
+            );
+        }
+        for (index, alias) in template.metadata.aliases.iter().enumerate() {
+            let path = format!("aliases[{index}]");
+            if alias == name {
+                diagnostics.push(
+                    Diagnostic::new(format!("Alias '{alias}' is the prompt's own name"))
+                        .with_path(path),
+                );
+            } else if other(alias) && self.prompt_exists(alias).await {
+                diagnostics.push(
+                    Diagnostic::new(format!("Alias '{alias}' is the name of another prompt"))
+                        .with_path(path),
+                );
+            } else if let Some(owner) = self.alias_owner(alias).await?.filter(|owner| other(owner)) {
+                diagnostics.push(
+                    Diagnostic::new(format!("Alias '{alias}' is already used by prompt '{owner}'"))
+                        .with_path(path),
+                );
+            }
+        }
+
+        if diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors::new(diagnostics).into())
+        }
     }
 
-    /// Load a specific prompt by filename (async)
-    pub async fn load_prompt(&self, name: &str) -> Result<PromptTemplate> {
-        // Validate name to prevent path traversal
-        validate_prompt_name(name)?;
+    /// Load a prompt by its exact name, using the mtime-validated cache
+    async fn load_prompt_file(&self, name: &str) -> Result<PromptTemplate> {
+        self.load_cached(name).await.map(|version| version.template)
+    }
 
-        let path = self.prompts_dir.join(format!("{name}.j2.md"));
+    /// Load a prompt by its exact name with the cache slot for its compiled form
+    ///
+    /// Stores without modification times are not cached, so their slot is
+    /// fresh on every call.
+    async fn load_cached(&self, name: &str) -> Result<PromptVersion> {
+        let stamp = self.store.metadata(name).await?;
 
         // Step 1: Check cache with read lock (allows concurrent reads)
         {
             let cache = self.cache.read().await;
-            if let Some(cached) = cache.get(name) {
-                // Verify file hasn't been modified since caching
-                if let Ok(current_meta) = fs::metadata(&path).await
-                    && let Ok(current_mtime) = current_meta.modified()
-                        && current_mtime == cached.file_mtime {
-                            // Cache hit: file unchanged, return cached template
-                            return Ok(cached.template.clone());
-                        }
-                // Cache stale: file modified, fall through to reload
+            if let Some(cached) = cache.get(name)
+                && stamp == Some(cached.stamp)
+            {
+                // Cache hit: prompt unchanged, return cached template
+                metrics().record_cache(true);
+                return Ok(cached.version.clone());
             }
-            // Cache miss: template not cached, fall through to load
+            // Cache miss or stale: fall through to load
         } // Read lock dropped here
 
-        // Step 2: Cache miss or stale - load from disk
-        let content = fs::read_to_string(&path)
-            .await
-            .with_context(|| format!("Failed to read prompt: {name}"))?;
-
-        let metadata = fs::metadata(&path).await?;
-        let file_mtime = metadata.modified()?;
-        let template = parse_template(name, &content)?;
+        // Step 2: Cache miss or stale - load from the store
+        metrics().record_cache(false);
+        let content = match self.store.read(name).await {
+            Ok(content) => content,
+            Err(StoreError::NotFound) => anyhow::bail!("Prompt '{name}' not found"),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read prompt: {name}")),
+        };
+        let version = PromptVersion {
+            template: parse_template(name, &content)?,
+            compiled: CompiledSlot::default(),
+            hash: super::bundle::sha256_hex(&content).into(),
+        };
 
         // Step 3: Update cache with write lock
-        {
+        if let Some(stamp) = stamp {
             let mut cache = self.cache.write().await;
             cache.insert(
                 name.to_string(),
                 CachedTemplate {
-                    template: template.clone(),
-                    file_mtime,
+                    version: version.clone(),
+                    stamp,
                 },
             );
         } // Write lock dropped here
 
-        Ok(template)
+        Ok(version)
+    }
+
+    /// Number and combined size of stored prompts, and cached templates
+    pub async fn library_stats(&self) -> Result<LibraryStats> {
+        let names = self.store.list().await.context("Failed to list prompts")?;
+        let mut bytes = 0;
+        for name in &names {
+            if let Ok(Some(stamp)) = self.store.metadata(name).await {
+                bytes += stamp.size;
+            }
+        }
+        let cache = self.cache.read().await;
+        Ok(LibraryStats {
+            prompts: names.len(),
+            bytes,
+            cached: cache.len(),
+            compiled: cache.values().filter(|c| c.version.compiled.initialized()).count(),
+        })
+    }
+
+    /// Check whether a prompt named `name` exists (aliases are not resolved)
+    pub async fn prompt_exists(&self, name: &str) -> bool {
+        if !is_valid_prompt_name(name) {
+            return false;
+        }
+        matches!(self.store.metadata(name).await, Ok(Some(_)))
     }
 
     /// Save a new prompt (async)
     pub async fn add_prompt(&self, name: &str, content: &str) -> Result<()> {
+        self.create_prompt_file(name, content).await?;
+        self.record(&[name], format!("Add prompt '{name}'")).await;
+        Ok(())
+    }
+
+    /// Create a new prompt without recording the change
+    async fn create_prompt_file(&self, name: &str, content: &str) -> Result<()> {
+        self.ensure_prompt_writable(name, &format!("create prompt '{name}'"))?;
+        self.authorize(PolicyAction::Create, name)?;
+
         // Validate name (prevent path traversal)
         validate_prompt_name(name)?;
-        
+
         // Validate content syntax
         super::validation::validate_prompt_file(content)?;
+        self.check_alias_collisions(&parse_template(name, content)?, None)
+            .await?;
 
-        let path = self.prompts_dir.join(format!("{name}.j2.md"));
-
-        // Atomic create-new operation - fails if file already exists
-        match OpenOptions::new()
-            .write(true)
-            .create_new(true)  // Atomic: fails if file exists
-            .open(&path)
-            .await
-        {
-            Ok(mut file) => {
-                // File created successfully, write content
-                file.write_all(content.as_bytes())
-                    .await
-                    .with_context(|| format!("Failed to write prompt: {name}"))?;
-                
-                file.flush()
-                    .await
-                    .with_context(|| format!("Failed to flush prompt: {name}"))?;
-                
-                // Sync to disk for durability (survive power loss)
-                file.sync_all()
-                    .await
-                    .with_context(|| format!("Failed to sync prompt to disk: {name}"))?;
-                
+        // Atomic create-new operation - fails if the prompt already exists
+        match self.store.create(name, content).await {
+            Ok(()) => {
                 // Invalidate cache after successful write
                 self.invalidate_cache(name).await;
                 Ok(())
             }
-            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
-                // File already exists - provide friendly error message
+            Err(StoreError::AlreadyExists) => {
                 anyhow::bail!("Prompt '{name}' already exists. Use edit_prompt to modify.")
             }
-            Err(e) => {
-                // Other IO error (permissions, disk full, etc.)
-                Err(e).with_context(|| format!("Failed to create prompt: {name}"))?
-            }
+            Err(e) => Err(e).with_context(|| format!("Failed to create prompt: {name}")),
         }
     }
 
     /// Update an existing prompt (async)
     pub async fn edit_prompt(&self, name: &str, content: &str) -> Result<()> {
+        self.update_prompt_file(name, content).await?;
+        self.record(&[name], format!("Edit prompt '{name}'")).await;
+        Ok(())
+    }
+
+    /// Overwrite an existing prompt without recording the change
+    async fn update_prompt_file(&self, name: &str, content: &str) -> Result<()> {
+        self.ensure_prompt_writable(name, &format!("update prompt '{name}'"))?;
+        self.authorize(PolicyAction::Update, name)?;
         validate_prompt_name(name)?;
         super::validation::validate_prompt_file(content)?;
+        self.check_alias_collisions(&parse_template(name, content)?, None)
+            .await?;
 
-        let path = self.prompts_dir.join(format!("{name}.j2.md"));
-
-        // Atomic update-only operation - fails if file doesn't exist
-        match OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .create(false)  // CRITICAL: Fail if file doesn't exist (edit-only semantics)
-            .open(&path)
-            .await
-        {
-            Ok(mut file) => {
-                // Write new content to existing file
-                file.write_all(content.as_bytes())
-                    .await
-                    .with_context(|| format!("Failed to write prompt: {name}"))?;
-                
-                // Ensure data is flushed to disk
-                file.flush()
-                    .await
-                    .with_context(|| format!("Failed to flush prompt: {name}"))?;
-                
-                // Sync to disk for durability (survive power loss)
-                file.sync_all()
-                    .await
-                    .with_context(|| format!("Failed to sync prompt to disk: {name}"))?;
-                
+        // Atomic update-only operation - fails if the prompt doesn't exist
+        match self.store.update(name, content).await {
+            Ok(()) => {
                 // Invalidate cache after successful write
                 self.invalidate_cache(name).await;
                 Ok(())
             }
-            Err(e) if e.kind() == ErrorKind::NotFound => {
-                // File doesn't exist - provide helpful error message
+            Err(StoreError::NotFound) => {
                 anyhow::bail!("Prompt '{name}' not found. Use add_prompt to create.")
             }
-            Err(e) => {
-                // Other IO error (permissions, disk full, etc.)
-                Err(e).with_context(|| format!("Failed to update prompt: {name}"))?
-            }
+            Err(e) => Err(e).with_context(|| format!("Failed to update prompt: {name}")),
         }
     }
 
     /// Delete a prompt (async)
     pub async fn delete_prompt(&self, name: &str) -> Result<()> {
-        validate_prompt_name(name)?;
+        self.remove_prompt(name).await.map(drop)
+    }
 
-        let path = self.prompts_dir.join(format!("{name}.j2.md"));
+    /// Delete a prompt and return the content it had (for audit hashes)
+    ///
+    /// `name` must be the stored name; aliases are not resolved.
+    pub async fn remove_prompt(&self, name: &str) -> Result<String> {
+        self.ensure_prompt_writable(name, &format!("delete prompt '{name}'"))?;
+        self.authorize(PolicyAction::Delete, name)?;
+        validate_prompt_name(name)?;
 
-        // Attempt delete directly, handle errors appropriately
-        match fs::remove_file(&path).await {
-            Ok(()) => {
+        let removed = self.store.read(name).await;
+        let deleted = match removed {
+            Ok(content) => self.store.delete(name).await.map(|()| content),
+            Err(e) => Err(e),
+        };
+        match deleted {
+            Ok(content) => {
                 self.invalidate_cache(name).await;
-                Ok(())
+                self.record(&[name], format!("Delete prompt '{name}'")).await;
+                if let Err(e) = self.usage.remove(name).await {
+                    warn!("Failed to remove usage of '{name}': {e:#}");
+                }
+                Ok(content)
             }
-            Err(e) if e.kind() == ErrorKind::NotFound => {
-                anyhow::bail!("Prompt '{name}' not found")
+            Err(StoreError::NotFound) => anyhow::bail!("Prompt '{name}' not found"),
+            Err(e) => Err(e).with_context(|| format!("Failed to delete prompt: {name}")),
+        }
+    }
+
+    /// Rename a prompt, keeping the old name as an alias (async)
+    ///
+    /// The store claims `new_name` atomically and never overwrites an
+    /// existing prompt; the filesystem store stages the renamed file and
+    /// hard-links it into place, removing the old file last, so the prompt is
+    /// reachable under one of its names throughout.
+    pub async fn rename_prompt(&self, old_name: &str, new_name: &str) -> Result<PromptTemplate> {
+        let action = format!("rename prompt '{old_name}' to '{new_name}'");
+        self.ensure_prompt_writable(old_name, &action)?;
+        self.ensure_prompt_writable(new_name, &action)?;
+        self.authorize(PolicyAction::Delete, old_name)?;
+        self.authorize(PolicyAction::Create, new_name)?;
+        validate_prompt_name(old_name)?;
+        validate_prompt_name(new_name)?;
+        if old_name == new_name {
+            anyhow::bail!("Prompt '{old_name}' already has that name");
+        }
+
+        if !self.prompt_exists(old_name).await {
+            anyhow::bail!("Prompt '{old_name}' not found");
+        }
+        let mut template = self.load_prompt_file(old_name).await?;
+        let source = self
+            .store
+            .read(old_name)
+            .await
+            .with_context(|| format!("Failed to read prompt: {old_name}"))?;
+
+        // Only `aliases` changes; the rest of the file is kept byte for byte
+        template.filename = new_name.to_string();
+        template.metadata.aliases.retain(|alias| alias != new_name);
+        if !template.metadata.aliases.iter().any(|alias| alias == old_name) {
+            template.metadata.aliases.push(old_name.to_string());
+        }
+        let content = set_frontmatter_key(
+            &source,
+            "aliases",
+            Some(&serde_json::json!(template.metadata.aliases)),
+        )?;
+        super::validation::validate_prompt_file(&content)?;
+        self.check_alias_collisions(&template, Some(old_name)).await?;
+
+        match self.store.rename(old_name, new_name, &content).await {
+            Ok(()) => {}
+            Err(StoreError::NotFound) => anyhow::bail!("Prompt '{old_name}' not found"),
+            Err(StoreError::AlreadyExists) => {
+                anyhow::bail!("Prompt '{new_name}' already exists. Choose another name.")
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to rename prompt: {old_name}"));
+            }
+        }
+
+        self.invalidate_cache(old_name).await;
+        self.invalidate_cache(new_name).await;
+        self.record(
+            &[old_name, new_name],
+            format!("Rename prompt '{old_name}' to '{new_name}'"),
+        )
+        .await;
+        if let Err(e) = self.usage.rename(old_name, new_name).await {
+            warn!("Failed to move usage of '{old_name}' to '{new_name}': {e:#}");
+        }
+        Ok(template)
+    }
+
+    /// Copy (fork) a prompt under a new name (async)
+    ///
+    /// The copy records the source in `forked_from`, takes `author` if given,
+    /// and starts unverified with no votes. Aliases are not copied, since they
+    /// still belong to the source prompt.
+    pub async fn copy_prompt(
+        &self,
+        source: &str,
+        new_name: &str,
+        author: Option<&str>,
+    ) -> Result<PromptTemplate> {
+        self.ensure_prompt_writable(new_name, &format!("copy prompt '{source}' to '{new_name}'"))?;
+        validate_prompt_name(new_name)?;
+        let mut template = self.load_prompt(source).await?;
+        let mut content = self
+            .store
+            .read(&template.filename)
+            .await
+            .with_context(|| format!("Failed to read prompt: {}", template.filename))?;
+
+        // Edit only the keys that change; comments, key order and the body
+        // are copied unchanged
+        let forked_from = template.filename.clone();
+        content = set_frontmatter_key(&content, "forked_from", Some(&forked_from.clone().into()))?;
+        template.metadata.forked_from = Some(forked_from);
+        template.filename = new_name.to_string();
+        if let Some(author) = author {
+            content = set_frontmatter_key(&content, "author", Some(&author.into()))?;
+            template.metadata.author = author.to_string();
+        }
+        if template.metadata.verified {
+            content = set_frontmatter_key(&content, "verified", Some(&false.into()))?;
+            template.metadata.verified = false;
+        }
+        if template.metadata.votes != 0 {
+            content = set_frontmatter_key(&content, "votes", Some(&0.into()))?;
+            template.metadata.votes = 0;
+        }
+        if !template.metadata.aliases.is_empty() {
+            content = set_frontmatter_key(&content, "aliases", None)?;
+            template.metadata.aliases.clear();
+        }
+
+        self.create_prompt_file(new_name, &content).await?;
+        self.record(
+            &[new_name],
+            format!("Copy prompt '{source}' to '{new_name}'"),
+        )
+        .await;
+        Ok(template)
+    }
+
+    /// Pack the prompts selected by `filter` into a bundle (async)
+    ///
+    /// Files are exported byte-for-byte, sorted by name.
+    pub async fn export_prompts(&self, filter: &ExportFilter) -> Result<PromptBundle> {
+        let mut templates = filter.select(self.list_prompts().await?)?;
+        templates.sort_by(|a, b| a.filename.cmp(&b.filename));
+
+        let mut entries = Vec::with_capacity(templates.len());
+        for template in templates {
+            let content = self
+                .store
+                .read(&template.filename)
+                .await
+                .with_context(|| format!("Failed to read prompt: {}", template.filename))?;
+            entries.push((template, content));
+        }
+
+        Ok(PromptBundle::from_templates(entries))
+    }
+
+    /// Unpack a bundle into the prompts directory (async)
+    ///
+    /// Every entry is validated before anything is written; a single invalid
+    /// entry rejects the whole bundle with a [`ValidationErrors`](super::diagnostics::ValidationErrors)
+    /// listing all problems. Existing names are handled by `policy`. With
+    /// `dry_run`, the planned outcomes are returned and nothing is written.
+    pub async fn import_prompts(
+        &self,
+        bundle: &PromptBundle,
+        policy: ConflictPolicy,
+        dry_run: bool,
+    ) -> Result<ImportReport> {
+        if !dry_run {
+            self.ensure_writable("import prompts")?;
+        }
+        bundle.validate()?;
+
+        // Plan every outcome first; renamed entries avoid existing prompts,
+        // other bundle entries, and each other
+        let mut taken: HashSet<String> = bundle.prompts.iter().map(|e| e.name.clone()).collect();
+        let mut planned = Vec::with_capacity(bundle.prompts.len());
+        for entry in &bundle.prompts {
+            let exists = self.prompt_exists(&entry.name).await;
+            let (name, action) = match (exists, policy) {
+                (false, _) => (entry.name.clone(), ImportAction::Created),
+                (true, ConflictPolicy::Skip) => (entry.name.clone(), ImportAction::Skipped),
+                (true, ConflictPolicy::Overwrite) => {
+                    (entry.name.clone(), ImportAction::Overwritten)
+                }
+                (true, ConflictPolicy::Rename) => {
+                    let mut suffix = 2;
+                    loop {
+                        let candidate = format!("{}_{suffix}", entry.name);
+                        if !taken.contains(&candidate) && !self.prompt_exists(&candidate).await {
+                            taken.insert(candidate.clone());
+                            break (candidate, ImportAction::Renamed);
+                        }
+                        suffix += 1;
+                    }
+                }
+            };
+            planned.push((entry, name, action));
+        }
+
+        // Authorize every write before making any, dry runs included
+        let denied: Vec<String> = planned
+            .iter()
+            .filter_map(|(_, name, action)| {
+                let action = match action {
+                    ImportAction::Created | ImportAction::Renamed => PolicyAction::Create,
+                    ImportAction::Overwritten => PolicyAction::Update,
+                    ImportAction::Skipped => return None,
+                };
+                self.ensure_prompt_writable(name, &format!("import prompt '{name}'"))
+                    .and_then(|()| self.authorize(action, name))
+                    .err()
+                    .map(|e| format!("{e:#}"))
+            })
+            .collect();
+        if !denied.is_empty() {
+            anyhow::bail!(
+                "Import rejected, nothing was written:\n  {}",
+                denied.join("\n  ")
+            );
+        }
+
+        let mut report = ImportReport {
+            dry_run,
+            outcomes: Vec::with_capacity(planned.len()),
+        };
+        // Prompts written so far, with their content before the import
+        let mut written: Vec<(String, Option<String>)> = Vec::new();
+        for (entry, name, action) in planned {
+            if !dry_run && action != ImportAction::Skipped {
+                let before = match action {
+                    ImportAction::Overwritten => self.store.read(&name).await.ok(),
+                    _ => None,
+                };
+                let result = match action {
+                    ImportAction::Overwritten => {
+                        self.update_prompt_file(&name, &entry.content).await
+                    }
+                    _ => self.create_prompt_file(&name, &entry.content).await,
+                };
+                if let Err(e) = result {
+                    let rolled_back = written.len();
+                    self.roll_back_import(written).await;
+                    return Err(e.context(format!(
+                        "Import failed at '{name}'; {rolled_back} prompt(s) already imported \
+                         were rolled back"
+                    )));
+                }
+                written.push((name.clone(), before));
             }
-            Err(e) if e.kind() == ErrorKind::IsADirectory => {
-                anyhow::bail!("'{name}' is a directory, not a prompt file")
+            report.outcomes.push(ImportOutcome {
+                source: entry.name.clone(),
+                name,
+                action,
+            });
+        }
+
+        if !written.is_empty() {
+            let names: Vec<&str> = written.iter().map(|(name, _)| name.as_str()).collect();
+            self.record(&names, format!("Import {} prompt(s)", names.len()))
+                .await;
+        }
+
+        Ok(report)
+    }
+
+    /// Undo the writes of an import that failed part-way, newest first
+    ///
+    /// Created prompts are deleted and overwritten ones restored. Prompts that
+    /// cannot be restored are committed as they are, so git storage still
+    /// records what is on disk.
+    async fn roll_back_import(&self, written: Vec<(String, Option<String>)>) {
+        let mut left = Vec::new();
+        for (name, before) in written.into_iter().rev() {
+            let result = match &before {
+                Some(content) => self.store.update(&name, content).await,
+                None => self.store.delete(&name).await,
+            };
+            self.invalidate_cache(&name).await;
+            if let Err(e) = result {
+                warn!("Failed to roll back import of '{name}': {e}");
+                left.push(name);
             }
-            Err(e) if e.kind() == ErrorKind::PermissionDenied => {
-                anyhow::bail!("Permission denied to delete prompt '{name}'")
+        }
+        if !left.is_empty() {
+            let names: Vec<&str> = left.iter().map(String::as_str).collect();
+            self.record(&names, format!("Partially import {} prompt(s)", names.len()))
+                .await;
+        }
+    }
+
+    /// Changes to a prompt, newest first
+    ///
+    /// Git storage lists commits; a prompt database lists its revisions.
+    /// Works for deleted prompts too; aliases resolve to the current file.
+    pub async fn prompt_history(&self, name: &str, limit: usize) -> Result<Vec<HistoryEntry>> {
+        let name = self.history_name(name).await?;
+        self.authorize(PolicyAction::Read, &name)?;
+        if let Some(git) = &self.git {
+            return git.history(&prompt_file_name(&name), limit).await;
+        }
+        let revisions = self.stored_revisions(&name).await?;
+        Ok(revisions.iter().take(limit).map(revision_entry).collect())
+    }
+
+    /// Unified diff of a prompt between two revisions
+    ///
+    /// Revisions are git revisions, or revision numbers with a prompt
+    /// database. `to` defaults to the current file.
+    pub async fn prompt_diff(&self, name: &str, from: &str, to: Option<&str>) -> Result<String> {
+        let name = self.history_name(name).await?;
+        self.authorize(PolicyAction::Read, &name)?;
+        if let Some(git) = &self.git {
+            return git.diff(&prompt_file_name(&name), from, to).await;
+        }
+        let revisions = self.stored_revisions(&name).await?;
+        // Deleted prompts diff as empty files
+        let old = revision_content(&name, &revisions, from)?.unwrap_or_default();
+        let new = match to {
+            Some(to) => revision_content(&name, &revisions, to)?.unwrap_or_default(),
+            None => match self.store.read(&name).await {
+                Ok(content) => content,
+                Err(StoreError::NotFound) => String::new(),
+                Err(e) => return Err(e).with_context(|| format!("Failed to read prompt: {name}")),
+            },
+        };
+        let file = prompt_file_name(&name);
+        Ok(similar::TextDiff::from_lines(&old, &new)
+            .unified_diff()
+            .header(&format!("a/{file}"), &format!("b/{file}"))
+            .to_string())
+    }
+
+    /// Restore a prompt to its content at `revision`
+    ///
+    /// Deleted prompts are recreated. The restore is itself committed (or
+    /// stored as a new revision), so it can be undone the same way.
+    pub async fn restore_prompt(&self, name: &str, revision: &str) -> Result<PromptTemplate> {
+        self.ensure_writable(&format!("restore prompt '{name}'"))?;
+        let name = self.history_name(name).await?;
+        let content = match &self.git {
+            Some(git) => git.show(&prompt_file_name(&name), revision).await?,
+            None => {
+                let revisions = self.stored_revisions(&name).await?;
+                revision_content(&name, &revisions, revision)?.ok_or_else(|| {
+                    anyhow::anyhow!("Revision {revision} of '{name}' deleted the prompt; nothing to restore")
+                })?
             }
-            Err(e) => Err(e).with_context(|| format!("Failed to delete prompt: {name}"))?
+        };
+        let template = parse_template(&name, &content)?;
+
+        if self.prompt_exists(&name).await {
+            self.update_prompt_file(&name, &content).await?;
+        } else {
+            self.create_prompt_file(&name, &content).await?;
+        }
+        if let Some(git) = &self.git {
+            let commit = git.resolve(revision).await?;
+            self.record(
+                &[&name],
+                format!("Restore prompt '{name}' to {}", &commit[..commit.len().min(12)]),
+            )
+            .await;
+        }
+        Ok(template)
+    }
+
+    /// Revisions kept by the store, for history without git
+    async fn stored_revisions(&self, name: &str) -> Result<Vec<PromptRevision>> {
+        self.store
+            .revisions(name)
+            .await
+            .with_context(|| format!("Failed to read revisions of prompt: {name}"))?
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Prompt history requires git storage (set KODEGEN_PROMPTS_GIT=1) \
+                     or a prompt database (KODEGEN_PROMPTS_DB)"
+                )
+            })
+    }
+
+    /// File name for history lookups: the prompt itself, or the prompt an alias points to
+    async fn history_name(&self, name: &str) -> Result<String> {
+        validate_prompt_name(name)?;
+        if self.prompt_exists(name).await {
+            return Ok(name.to_string());
+        }
+        match self.resolve_prompt(name).await {
+            Ok(template) => Ok(template.filename),
+            // Deleted prompts only live in history
+            Err(_) => Ok(name.to_string()),
+        }
+    }
+
+    /// Commit a change when git-backed; failures are logged, since the change
+    /// itself has already been written
+    async fn record(&self, names: &[&str], message: String) {
+        let Some(git) = &self.git else {
+            return;
+        };
+        let files: Vec<String> = names.iter().map(|name| prompt_file_name(name)).collect();
+        let files: Vec<&str> = files.iter().map(String::as_str).collect();
+        if let Err(e) = git.commit(&files, &message, &self.author).await {
+            warn!("Failed to commit prompt change ({message}): {e:#}");
         }
     }
 
     /// Render a prompt with parameters (async)
+    ///
+    /// The output is counted with the token estimator and checked against the
+    /// prompt's `max_tokens` budget, which may fail or truncate the render
+    /// (see [`tokens`](super::tokens)).
     pub async fn render_prompt(
         &self,
         name: &str,
         parameters: Option<HashMap<String, TemplateParamValue>>,
-    ) -> Result<String> {
+    ) -> Result<RenderedPrompt> {
+        self.render_with(name, |compiled| async move {
+            let content = compiled.render(parameters.as_ref()).await?;
+            let (content, tokens) =
+                fit_text(self.tokens.as_ref(), &compiled.template().metadata, content)?;
+            Ok(RenderedPrompt { content, tokens })
+        })
+        .await
+    }
+
+    /// Render a prompt and report where each value came from (async)
+    ///
+    /// Same token budget as [`render_prompt`](Self::render_prompt).
+    pub async fn preview_prompt(
+        &self,
+        name: &str,
+        parameters: Option<HashMap<String, TemplateParamValue>>,
+    ) -> Result<RenderPreview> {
+        self.render_with(name, |compiled| async move {
+            let mut preview = compiled.render_preview(parameters.as_ref()).await?;
+            let (rendered, tokens) =
+                fit_text(self.tokens.as_ref(), &compiled.template().metadata, preview.rendered)?;
+            preview.rendered = rendered;
+            preview.tokens = Some(tokens);
+            Ok(preview)
+        })
+        .await
+    }
+
+    /// Render a prompt into role-tagged messages (async)
+    ///
+    /// `sandbox` confines `attach()` / `image()` helpers to the caller's
+    /// workspace; without it, templates that attach files fail to render.
+    /// The budget applies to the text of all messages together.
+    pub async fn render_prompt_messages(
+        &self,
+        name: &str,
+        parameters: Option<HashMap<String, TemplateParamValue>>,
+        sandbox: Option<&AttachmentSandbox>,
+    ) -> Result<RenderedMessages> {
+        self.render_with(name, |compiled| async move {
+            let messages = compiled.render_messages(parameters.as_ref(), sandbox).await?;
+            let (messages, tokens) =
+                fit_messages(self.tokens.as_ref(), &compiled.template().metadata, messages)?;
+            Ok(RenderedMessages { messages, tokens })
+        })
+        .await
+    }
+
+    /// Count a render in the usage analytics (failures to save are logged)
+    async fn record_render(&self, name: &str, success: bool) {
+        if let Err(e) = self.usage.record_render(name, success).await {
+            warn!("Failed to record usage of '{name}': {e:#}");
+        }
+    }
+
+    /// Usage analytics of a prompt (resolving aliases)
+    pub async fn prompt_usage(&self, name: &str) -> Result<(PromptTemplate, PromptUsage)> {
+        let template = self.load_prompt(name).await?;
+        let usage = self.usage.get(&template.filename).await?;
+        Ok((template, usage))
+    }
+
+    /// Save render counts still waiting for their batch (call before exiting)
+    pub async fn flush_usage(&self) -> Result<()> {
+        self.usage.flush().await
+    }
+
+    /// Usage analytics of every prompt that has been rendered or voted on
+    pub async fn all_usage(&self) -> Result<BTreeMap<String, PromptUsage>> {
+        self.usage.all().await
+    }
+
+    /// Cast a vote for (`up`) or against a prompt, returning its new usage
+    ///
+    /// Votes are kept with the usage analytics, not in the prompt file; the
+    /// prompt's effective vote count is its frontmatter `votes` plus the net
+    /// votes cast. Any client that may read the prompt may vote on it.
+    pub async fn vote_prompt(&self, name: &str, up: bool) -> Result<(PromptTemplate, PromptUsage)> {
         let template = self.load_prompt(name).await?;
-        render_template(&template, parameters.as_ref()).await
+        let usage = self
+            .usage
+            .vote(&template.filename, if up { 1 } else { -1 })
+            .await?;
+        Ok((template, usage))
     }
 
-    /// Invalidate cached entry for a specific prompt
+    /// Invalidate cached entry for a prompt that was written or deleted,
+    /// and re-index its aliases
     async fn invalidate_cache(&self, name: &str) {
-        let mut cache = self.cache.write().await;
-        cache.remove(name);
+        self.cache.write().await.remove(name);
+        if self.aliases.read().await.is_none() {
+            return;
+        }
+
+        let aliases = match self.load_prompt_file(name).await {
+            Ok(template) => template.metadata.aliases,
+            Err(_) => Vec::new(),
+        };
+        if let Some(index) = self.aliases.write().await.as_mut() {
+            index.retain(|_, owner| owner != name);
+            for alias in aliases {
+                index.entry(alias).or_insert_with(|| name.to_string());
+            }
+        }
     }
 }
 
@@ -322,21 +1433,71 @@ fn get_prompts_directory() -> Result<PathBuf> {
         .ok_or_else(|| anyhow::anyhow!("Cannot determine prompts directory"))
 }
 
+/// History entry for a stored revision (numbered instead of a commit)
+fn revision_entry(revision: &PromptRevision) -> HistoryEntry {
+    HistoryEntry {
+        commit: revision.revision.to_string(),
+        author: String::new(),
+        email: String::new(),
+        date: chrono::DateTime::<chrono::Utc>::from(revision.modified).to_rfc3339(),
+        message: revision.action.clone(),
+    }
+}
+
+/// Content of a prompt at a numbered revision (`None` if it deleted the prompt)
+fn revision_content(name: &str, revisions: &[PromptRevision], revision: &str) -> Result<Option<String>> {
+    let number: u64 = revision
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid revision '{revision}': expected a revision number"))?;
+    revisions
+        .iter()
+        .find(|r| r.revision == number)
+        .map(|r| r.content.clone())
+        .ok_or_else(|| anyhow::anyhow!("Prompt '{name}' has no revision {number}"))
+}
+
+/// File name of a prompt relative to the prompts directory
+fn prompt_file_name(name: &str) -> String {
+    format!("{name}.j2.md")
+}
+
+/// Separator between namespace segments in prompt names (`review/security`)
+pub const NAMESPACE_SEPARATOR: char = '/';
+
+/// Deepest namespace nesting accepted in prompt names
+const MAX_NAME_SEGMENTS: usize = 8;
+
+/// What a valid prompt name looks like, for error messages and suggestions
+pub(crate) const PROMPT_NAME_RULE: &str = "Use alphanumeric characters, hyphens, and underscores, \
+     with '/' between namespace segments (e.g. 'review/security')";
+
 /// Validate prompt name to prevent path traversal
+///
+/// Names are one or more `/`-separated segments of alphanumerics, hyphens
+/// and underscores. Empty segments rule out absolute paths, trailing
+/// slashes and `//`; dots and backslashes are never allowed, so no segment
+/// can be `..`.
 fn validate_prompt_name(name: &str) -> Result<()> {
-    // Only alphanumeric, hyphen, underscore
-    if !name
-        .chars()
-        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
-    {
-        anyhow::bail!(
-            "Invalid prompt name: '{name}'. Only alphanumeric characters, hyphens, and underscores allowed."
-        );
+    if name.is_empty() {
+        anyhow::bail!("Invalid prompt name: name is empty");
     }
 
     // No path traversal
-    if name.contains('/') || name.contains('\\') || name.contains("..") {
-        anyhow::bail!("Invalid prompt name: '{name}'. Path separators and '..' not allowed.");
+    if name.contains('\\') || name.contains("..") {
+        anyhow::bail!("Invalid prompt name: '{name}'. Backslashes and '..' not allowed.");
+    }
+    if name.starts_with(NAMESPACE_SEPARATOR) || name.ends_with(NAMESPACE_SEPARATOR) {
+        anyhow::bail!("Invalid prompt name: '{name}'. Names cannot start or end with '/'.");
+    }
+
+    if !is_valid_prompt_name(name) {
+        if name.split(NAMESPACE_SEPARATOR).count() > MAX_NAME_SEGMENTS {
+            anyhow::bail!(
+                "Invalid prompt name: '{name}'. At most {MAX_NAME_SEGMENTS} namespace levels allowed."
+            );
+        }
+        anyhow::bail!("Invalid prompt name: '{name}'. {PROMPT_NAME_RULE}.");
     }
 
     Ok(())
@@ -344,48 +1505,60 @@ fn validate_prompt_name(name: &str) -> Result<()> {
 
 /// Quick validation check for prompt names (inline version for list_prompts)
 /// Mirrors the logic in validate_prompt_name() for early filtering
-fn is_valid_prompt_name(name: &str) -> bool {
-    !name.is_empty()
-        && name
+pub(crate) fn is_valid_prompt_name(name: &str) -> bool {
+    let mut segments = 0;
+    name.split(NAMESPACE_SEPARATOR).all(|segment| {
+        segments += 1;
+        is_valid_name_segment(segment)
+    }) && segments <= MAX_NAME_SEGMENTS
+}
+
+/// One namespace segment or leaf name: non-empty `[A-Za-z0-9_-]`
+pub(crate) fn is_valid_name_segment(segment: &str) -> bool {
+    !segment.is_empty()
+        && segment
             .chars()
             .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
-        && !name.contains("..")
+}
+
+/// Namespace of a prompt name (`review` for `review/security`), if any
+pub fn prompt_namespace(name: &str) -> Option<&str> {
+    name.rsplit_once(NAMESPACE_SEPARATOR).map(|(namespace, _)| namespace)
+}
+
+/// Whether `name` lies in `namespace` or one of its sub-namespaces
+///
+/// Segment-aware: `review` contains `review/security` but not `reviewer/x`.
+pub fn in_namespace(name: &str, namespace: &str) -> bool {
+    let namespace = namespace.trim_matches(NAMESPACE_SEPARATOR);
+    namespace.is_empty()
+        || name
+            .strip_prefix(namespace)
+            .is_some_and(|rest| rest.starts_with(NAMESPACE_SEPARATOR))
 }
 
 /// Initialize default prompts on first run (async)
-async fn initialize_default_prompts(prompts_dir: &Path) -> Result<()> {
+async fn initialize_default_prompts(store: &dyn PromptStore) -> Result<()> {
     // Fast check: does the first default prompt exist?
     // If it exists, assume initialization already happened
     let first_default = defaults::DEFAULT_PROMPTS[0].0;
-    let path = prompts_dir.join(format!("{first_default}.j2.md"));
-    
+
     // Check existence, propagating errors instead of masking them
-    match fs::try_exists(&path).await {
-        Ok(true) => {
-            // Initialization already complete - return immediately
-            return Ok(());
-        }
-        Ok(false) => {
-            // First run: write all default prompts
-            defaults::write_default_prompts(prompts_dir).await?;
-            
-            info!(
-                "Initialized {} default prompts in {}",
-                defaults::DEFAULT_PROMPTS.len(),
-                prompts_dir.display()
-            );
-        }
-        Err(e) => {
-            // Permission or IO error checking existence - propagate with context
-            return Err(e).with_context(|| {
-                format!(
-                    "Failed to check if default prompts exist in {}. \
-                     Check directory permissions.",
-                    prompts_dir.display()
-                )
-            });
-        }
+    let exists = store.metadata(first_default).await.with_context(|| {
+        "Failed to check if default prompts exist. Check storage permissions."
+    })?;
+    if exists.is_some() {
+        // Initialization already complete - return immediately
+        return Ok(());
     }
-    
+
+    // First run: write all default prompts
+    defaults::write_default_prompts(store).await?;
+
+    info!(
+        "Initialized {} default prompts",
+        defaults::DEFAULT_PROMPTS.len()
+    );
+
     Ok(())
 }
@@ -0,0 +1,292 @@
+use super::attachments::{Attachment, AttachmentBudget, AttachmentKind, AttachmentSandbox};
+use anyhow::Result;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+/// Marker that opens a rendered message block (followed by the role and `MARKER_CLOSE`)
+const MARKER_OPEN: char = '\u{1E}';
+
+/// Marker that terminates the role name of an opening marker
+const MARKER_CLOSE: char = '\u{1F}';
+
+/// Rendered marker emitted for `{% endmessage %}`
+const END_MARKER: &str = "\u{1E}/\u{1F}";
+
+/// Prefix of attachment markers emitted by the `attach()` / `image()` helpers
+const ATTACHMENT_PREFIX: char = '@';
+
+/// Matches `{% message "role" %}` and `{% endmessage %}` tags (with whitespace
+/// control), and the comments and raw blocks whose text must be left alone
+static MESSAGE_TAG_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"(?s)(?P<verbatim>\{#.*?#\}",
+        r"|\{%[-+]?\s*raw\s*[-+]?%\}.*?\{%[-+]?\s*endraw\s*[-+]?%\})",
+        r#"|\{%(?P<pre>[-+]?)\s*(?:message\s+(?:"(?P<dq>[^"]*)"|'(?P<sq>[^']*)')|(?P<end>endmessage))"#,
+        r"\s*(?P<post>[-+]?)%\}",
+    ))
+    .expect("Failed to compile message tag pattern")
+});
+
+/// Matches any rendered message marker
+static MARKER_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new("\u{1E}[^\u{1F}]*\u{1F}").expect("Failed to compile message marker pattern")
+});
+
+/// Role of a rendered prompt message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageRole {
+    System,
+    User,
+    Assistant,
+}
+
+impl MessageRole {
+    /// Parse a role name as written in a `{% message %}` tag
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "system" => Some(Self::System),
+            "user" => Some(Self::User),
+            "assistant" => Some(Self::Assistant),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::System => "system",
+            Self::User => "user",
+            Self::Assistant => "assistant",
+        }
+    }
+}
+
+/// A single role-tagged message produced by rendering a template
+#[derive(Debug, Clone)]
+pub struct RenderedMessage {
+    pub role: MessageRole,
+    pub content: String,
+    /// Files attached with the `attach()` / `image()` template helpers
+    pub attachments: Vec<Attachment>,
+}
+
+/// Message being accumulated while splitting rendered output
+#[derive(Default)]
+struct PendingMessage {
+    role: Option<MessageRole>,
+    text: String,
+    attachments: Vec<Attachment>,
+}
+
+impl PendingMessage {
+    /// Push the message unless it has neither text nor attachments
+    fn flush_into(self, messages: &mut Vec<RenderedMessage>) {
+        let text = self.text.trim();
+        if text.is_empty() && self.attachments.is_empty() {
+            return;
+        }
+        messages.push(RenderedMessage {
+            role: self.role.unwrap_or(MessageRole::User),
+            content: text.to_string(),
+            attachments: self.attachments,
+        });
+    }
+}
+
+/// Render the marker for an `attach(path)` / `image(path)` template call
+///
+/// The file is not read here; it is loaded through the caller's
+/// [`AttachmentSandbox`] when the output is split into messages.
+pub fn attachment_marker(kind: AttachmentKind, path: &str) -> Result<String, minijinja::Error> {
+    if path.is_empty() || path.contains(MARKER_OPEN) || path.contains(MARKER_CLOSE) {
+        return Err(minijinja::Error::new(
+            minijinja::ErrorKind::InvalidOperation,
+            format!("{}() requires a non-empty file path", kind.helper_name()),
+        ));
+    }
+    Ok(format!(
+        "{MARKER_OPEN}{ATTACHMENT_PREFIX}{}:{path}{MARKER_CLOSE}",
+        kind.helper_name()
+    ))
+}
+
+/// Remove message marker characters from text the template did not write
+///
+/// Parameters and environment values are rendered verbatim, so a caller
+/// passing the raw control characters could otherwise open a `system`
+/// message or forge an `attach()` marker for a file the template never
+/// attached. Stripped values render as literal text.
+pub(crate) fn strip_marker_chars(text: &mut String) {
+    if text.contains([MARKER_OPEN, MARKER_CLOSE]) {
+        text.retain(|c| c != MARKER_OPEN && c != MARKER_CLOSE);
+    }
+}
+
+/// Body of a rendered marker (text between the open and close characters)
+fn marker_body(marker: &str) -> &str {
+    &marker[MARKER_OPEN.len_utf8()..marker.len() - MARKER_CLOSE.len_utf8()]
+}
+
+/// Parse an attachment marker body (`@attach:path` / `@image:path`)
+fn parse_attachment_marker(body: &str) -> Option<(AttachmentKind, &str)> {
+    let (helper, path) = body.strip_prefix(ATTACHMENT_PREFIX)?.split_once(':')?;
+    Some((AttachmentKind::parse_helper(helper)?, path))
+}
+
+/// Rewrite `{% message %}` blocks into plain-text markers before compilation
+///
+/// MiniJinja has no custom block tags, so message blocks are replaced with
+/// control-character markers that pass through rendering untouched. Blocks may
+/// sit inside `{% if %}` or `{% for %}` bodies; the markers are resolved after
+/// rendering by [`split_messages`] or removed by [`strip_message_markers`].
+/// Tags inside comments and `{% raw %}` blocks are left as written, and tags
+/// with whitespace control (`{%- message "user" -%}`) keep trimming around
+/// their marker.
+///
+/// # Errors
+/// Returns an error for unknown roles, nested blocks, stray `endmessage` tags,
+/// and unclosed blocks.
+pub fn expand_message_blocks(source: &str) -> Result<String> {
+    if !source.contains("message") {
+        return Ok(source.to_string());
+    }
+
+    let mut expanded = String::with_capacity(source.len());
+    let mut last = 0;
+    let mut open_role: Option<MessageRole> = None;
+
+    for caps in MESSAGE_TAG_PATTERN.captures_iter(source) {
+        let tag = caps.get(0).expect("capture group 0 always matches");
+        if caps.name("verbatim").is_some() {
+            continue;
+        }
+        expanded.push_str(&source[last..tag.start()]);
+        last = tag.end();
+        let trim = (&caps["pre"], &caps["post"]);
+
+        if caps.name("end").is_some() {
+            if open_role.take().is_none() {
+                anyhow::bail!(
+                    "Invalid message block: '{{% endmessage %}}' without a matching '{{% message %}}'"
+                );
+            }
+            push_marker(&mut expanded, END_MARKER, trim);
+            continue;
+        }
+
+        let role_name = caps
+            .name("dq")
+            .or_else(|| caps.name("sq"))
+            .map(|m| m.as_str())
+            .unwrap_or_default();
+        let role = MessageRole::parse(role_name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Invalid message block: unknown role '{role_name}'. \
+                 Expected one of: system, user, assistant"
+            )
+        })?;
+
+        if let Some(open) = open_role {
+            anyhow::bail!(
+                "Invalid message block: '{{% message \"{}\" %}}' opened inside an unclosed \
+                 '{}' message. Message blocks cannot be nested.",
+                role.as_str(),
+                open.as_str()
+            );
+        }
+
+        open_role = Some(role);
+        let marker = format!("{MARKER_OPEN}{}{MARKER_CLOSE}", role.as_str());
+        push_marker(&mut expanded, &marker, trim);
+    }
+
+    if let Some(open) = open_role {
+        anyhow::bail!(
+            "Invalid message block: '{}' message is missing '{{% endmessage %}}'",
+            open.as_str()
+        );
+    }
+
+    expanded.push_str(&source[last..]);
+    Ok(expanded)
+}
+
+/// Append a marker in place of a tag, keeping the tag's whitespace control
+///
+/// A trimmed tag becomes a raw block carrying the same `-`/`+` modifiers, so
+/// MiniJinja still trims around the marker.
+fn push_marker(expanded: &mut String, marker: &str, (pre, post): (&str, &str)) {
+    if pre.is_empty() && post.is_empty() {
+        expanded.push_str(marker);
+    } else {
+        expanded.push_str(&format!("{{%{pre} raw %}}{marker}{{% endraw {post}%}}"));
+    }
+}
+
+/// Split rendered output into role-tagged messages
+///
+/// Text outside any message block becomes a `user` message, so templates that
+/// never use `{% message %}` render to a single user message. Messages with
+/// neither text nor attachments are dropped. Attachment markers are loaded
+/// through `sandbox`, charged to `budget`, and attached to the message they
+/// appear in.
+///
+/// # Errors
+/// Returns an error if the output contains attachments but no sandbox is
+/// available, if an attachment violates the sandbox rules, or if the
+/// attachments exceed the budget.
+pub fn split_messages(
+    rendered: &str,
+    sandbox: Option<&AttachmentSandbox>,
+    budget: &mut AttachmentBudget,
+) -> Result<Vec<RenderedMessage>> {
+    let mut messages = Vec::new();
+    let mut current = PendingMessage::default();
+    let mut last = 0;
+
+    for marker in MARKER_PATTERN.find_iter(rendered) {
+        current.text.push_str(&rendered[last..marker.start()]);
+        last = marker.end();
+
+        let body = marker_body(marker.as_str());
+        if let Some((kind, path)) = parse_attachment_marker(body) {
+            let sandbox = sandbox.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Template attaches '{path}' but the caller's workspace is unknown. \
+                     Attachments require a working directory to resolve files against."
+                )
+            })?;
+            current.attachments.push(sandbox.load(kind, path, budget)?);
+            continue;
+        }
+
+        // A new opening marker implicitly closes the current block; `endmessage`
+        // returns to top-level text.
+        std::mem::take(&mut current).flush_into(&mut messages);
+        current.role = MessageRole::parse(body);
+    }
+    current.text.push_str(&rendered[last..]);
+    current.flush_into(&mut messages);
+
+    Ok(messages)
+}
+
+/// Remove message markers from rendered output for flat (single string) rendering
+///
+/// Attachments cannot be represented in a single string and are replaced by a
+/// `[attach: path]` / `[image: path]` placeholder.
+pub fn strip_message_markers(rendered: &str) -> String {
+    if !rendered.contains(MARKER_OPEN) {
+        return rendered.to_string();
+    }
+    MARKER_PATTERN
+        .replace_all(rendered, |caps: &regex::Captures<'_>| {
+            match parse_attachment_marker(marker_body(&caps[0])) {
+                Some((kind, path)) => format!("[{}: {path}]", kind.helper_name()),
+                None => String::new(),
+            }
+        })
+        .into_owned()
+}
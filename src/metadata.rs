@@ -1,5 +1,6 @@
 use kodegen_mcp_schema::prompt::{PromptParameterType, TemplateParamValue};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Prompt metadata from YAML frontmatter
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -7,7 +8,7 @@ pub struct PromptMetadata {
     pub title: String,
     pub description: String,
     pub categories: Vec<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub secondary_tag: Option<String>,
     pub author: String,
     #[serde(default)]
@@ -16,6 +17,82 @@ pub struct PromptMetadata {
     pub votes: u32,
     #[serde(default)]
     pub parameters: Vec<ParameterDefinition>,
+    /// Semantic version of the prompt (e.g. "1.2.0")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Free-form tags for search and grouping
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Preferred model hint (e.g. "claude-sonnet-4")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Sampling temperature hint (0.0 - 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    /// Maximum output tokens hint, and the token budget for rendered output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// What a render over the `max_tokens` budget does
+    #[serde(default, skip_serializing_if = "TokenOverflow::is_allow")]
+    pub on_token_overflow: TokenOverflow,
+    /// Whether the prompt is deprecated
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+    /// Name of the prompt that replaces this one (requires `deprecated: true`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaced_by: Option<String>,
+    /// Alternative names that resolve to this prompt (e.g. names it was renamed from)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Name of the prompt this one was copied from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_from: Option<String>,
+    /// Render limits for this prompt (each can only tighten the server's limits)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<RenderLimits>,
+    /// Frontmatter keys not recognized above (e.g. `owner_team`, `ticket`), kept verbatim
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// What a render estimated above a prompt's `max_tokens` does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenOverflow {
+    /// Return the output and flag it as over budget
+    #[default]
+    Allow,
+    /// Fail the render
+    Fail,
+    /// Cut the output to fit the budget
+    Truncate,
+}
+
+impl TokenOverflow {
+    fn is_allow(&self) -> bool {
+        *self == Self::Allow
+    }
+}
+
+/// Render limits declared in a prompt's frontmatter
+///
+/// Unset fields use the server's [`PromptLimits`](crate::PromptLimits). A
+/// value above the server's limit is capped at it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RenderLimits {
+    /// Render timeout in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Template instructions a render may execute
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fuel: Option<u64>,
+    /// Deepest nesting of macro calls and blocks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recursion_depth: Option<usize>,
+    /// Largest rendered output, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_size: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,7 +103,7 @@ pub struct ParameterDefinition {
     pub param_type: PromptParameterType,
     #[serde(default)]
     pub required: bool,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub default: Option<TemplateParamValue>,
 }
 
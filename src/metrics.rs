@@ -0,0 +1,417 @@
+//! Process-wide counters and histograms for the prompt server
+//!
+//! Recorded where the work happens (tool routes, `render_with_markers`,
+//! `validate_prompt_file`, the template cache) and read by the `prompt_stats`
+//! tool as JSON or in the Prometheus text exposition format.
+
+use rmcp::handler::server::router::tool::ToolRouter;
+use rmcp::handler::server::tool::ToolCallContext;
+use rmcp::model::CallToolResult;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+/// Upper bounds (seconds) of the render latency histogram buckets
+const RENDER_BUCKETS: [f64; 11] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+/// The process-wide metrics registry
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+/// Counters and histograms shared by every manager in the process
+#[derive(Default)]
+pub struct Metrics {
+    tool_calls: Mutex<BTreeMap<ToolCallKey, ToolCallTotals>>,
+    render: Histogram,
+    render_timeouts: AtomicU64,
+    render_threads: AtomicU64,
+    compiles: AtomicU64,
+    validation_failures: Mutex<BTreeMap<&'static str, u64>>,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+}
+
+/// Running render thread, counted by [`Metrics::render_thread`]
+pub(crate) struct RenderThreadGuard {
+    metrics: &'static Metrics,
+}
+
+impl Drop for RenderThreadGuard {
+    fn drop(&mut self) {
+        self.metrics.render_threads.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct ToolCallKey {
+    tool: String,
+    action: String,
+    outcome: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ToolCallTotals {
+    count: u64,
+    seconds: f64,
+}
+
+/// Cumulative-bucket histogram (sum kept in microseconds)
+struct Histogram {
+    buckets: [AtomicU64; RENDER_BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            count: AtomicU64::new(0),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Histogram {
+    fn observe(&self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        for (bound, bucket) in RENDER_BUCKETS.iter().zip(&self.buckets) {
+            if seconds <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Metrics {
+    /// Count a tool call (`action` is the `action` argument, empty if none)
+    ///
+    /// Calls through [`instrument_routes`] pass only known actions, with
+    /// anything else as `other`.
+    pub fn record_tool_call(&self, tool: &str, action: &str, success: bool, elapsed: Duration) {
+        let key = ToolCallKey {
+            tool: tool.to_string(),
+            action: action.to_string(),
+            outcome: if success { "success" } else { "error" },
+        };
+        let mut calls = self.tool_calls.lock().unwrap_or_else(|e| e.into_inner());
+        let totals = calls.entry(key).or_default();
+        totals.count += 1;
+        totals.seconds += elapsed.as_secs_f64();
+    }
+
+    /// Time spent rendering one template (timeouts are counted separately)
+    pub fn record_render(&self, elapsed: Duration) {
+        self.render.observe(elapsed);
+    }
+
+    pub fn record_render_timeout(&self) {
+        self.render_timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A template compiled for rendering
+    pub fn record_compile(&self) {
+        self.compiles.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a render thread as running until the guard is dropped
+    pub(crate) fn render_thread(&'static self) -> RenderThreadGuard {
+        self.render_threads.fetch_add(1, Ordering::Relaxed);
+        RenderThreadGuard { metrics: self }
+    }
+
+    /// Content rejected by the validation rule `rule`
+    pub fn record_validation_failure(&self, rule: &'static str) {
+        let mut failures = self
+            .validation_failures
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        *failures.entry(rule).or_default() += 1;
+    }
+
+    /// Template cache lookup in `load_prompt`
+    pub fn record_cache(&self, hit: bool) {
+        let counter = if hit { &self.cache_hits } else { &self.cache_misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Current values, with the library size supplied by the caller
+    pub fn snapshot(&self, library: LibraryStats) -> MetricsSnapshot {
+        let tool_calls = self
+            .tool_calls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(key, totals)| ToolCallStats {
+                tool: key.tool.clone(),
+                action: (!key.action.is_empty()).then(|| key.action.clone()),
+                outcome: key.outcome.to_string(),
+                count: totals.count,
+                seconds: totals.seconds,
+            })
+            .collect();
+
+        let render = RenderStats {
+            count: self.render.count.load(Ordering::Relaxed),
+            sum_seconds: self.render.sum_micros.load(Ordering::Relaxed) as f64 / 1e6,
+            buckets: RENDER_BUCKETS
+                .iter()
+                .zip(&self.render.buckets)
+                .map(|(le, count)| HistogramBucket {
+                    le: *le,
+                    count: count.load(Ordering::Relaxed),
+                })
+                .collect(),
+            timeouts: self.render_timeouts.load(Ordering::Relaxed),
+            running: self.render_threads.load(Ordering::Relaxed),
+            compiles: self.compiles.load(Ordering::Relaxed),
+        };
+
+        let validation_failures = self
+            .validation_failures
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(rule, count)| (rule.to_string(), *count))
+            .collect();
+
+        let hits = self.cache_hits.load(Ordering::Relaxed);
+        let misses = self.cache_misses.load(Ordering::Relaxed);
+        let cache = CacheStats {
+            hits,
+            misses,
+            hit_rate: if hits + misses == 0 {
+                0.0
+            } else {
+                hits as f64 / (hits + misses) as f64
+            },
+        };
+
+        MetricsSnapshot {
+            tool_calls,
+            render,
+            validation_failures,
+            cache,
+            library,
+        }
+    }
+}
+
+/// Calls of one tool and action with one outcome
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ToolCallStats {
+    pub tool: String,
+    /// `action` argument of the call (for tools that take one)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    /// `success` or `error`
+    pub outcome: String,
+    pub count: u64,
+    /// Total time spent in these calls
+    pub seconds: f64,
+}
+
+/// Render latency histogram plus timeouts
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RenderStats {
+    /// Renders that finished (successfully or with a template error)
+    pub count: u64,
+    pub sum_seconds: f64,
+    /// Cumulative buckets: renders that took at most `le` seconds
+    pub buckets: Vec<HistogramBucket>,
+    /// Renders stopped by the render timeout
+    pub timeouts: u64,
+    /// Render threads still running, including renders whose caller timed
+    /// out (fuel or the output limit stops those)
+    #[serde(default)]
+    pub running: u64,
+    /// Templates compiled; the cache compiles each prompt version once
+    #[serde(default)]
+    pub compiles: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HistogramBucket {
+    pub le: f64,
+    pub count: u64,
+}
+
+/// Template cache lookups in `load_prompt`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// hits / (hits + misses), 0 before the first lookup
+    pub hit_rate: f64,
+}
+
+/// Size of the prompt library
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct LibraryStats {
+    /// Stored prompts
+    pub prompts: usize,
+    /// Combined size of the prompt files in bytes
+    pub bytes: u64,
+    /// Parsed templates held in the cache
+    pub cached: usize,
+    /// Cached templates already compiled for rendering
+    pub compiled: usize,
+}
+
+/// Every metric at one point in time
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MetricsSnapshot {
+    pub tool_calls: Vec<ToolCallStats>,
+    pub render: RenderStats,
+    /// Rejected prompt content by validation rule
+    pub validation_failures: BTreeMap<String, u64>,
+    pub cache: CacheStats,
+    pub library: LibraryStats,
+}
+
+impl MetricsSnapshot {
+    /// Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+
+        header(&mut out, "kodegen_prompt_tool_calls_total", "counter", "Tool calls by tool, action and outcome");
+        for call in &self.tool_calls {
+            let _ = writeln!(
+                out,
+                "kodegen_prompt_tool_calls_total{{tool=\"{}\",action=\"{}\",outcome=\"{}\"}} {}",
+                escape(&call.tool),
+                escape(call.action.as_deref().unwrap_or("")),
+                call.outcome,
+                call.count
+            );
+        }
+        header(&mut out, "kodegen_prompt_tool_call_seconds_total", "counter", "Time spent in tool calls");
+        for call in &self.tool_calls {
+            let _ = writeln!(
+                out,
+                "kodegen_prompt_tool_call_seconds_total{{tool=\"{}\",action=\"{}\",outcome=\"{}\"}} {}",
+                escape(&call.tool),
+                escape(call.action.as_deref().unwrap_or("")),
+                call.outcome,
+                call.seconds
+            );
+        }
+
+        header(&mut out, "kodegen_prompt_render_seconds", "histogram", "Template render latency");
+        for bucket in &self.render.buckets {
+            let _ = writeln!(
+                out,
+                "kodegen_prompt_render_seconds_bucket{{le=\"{}\"}} {}",
+                bucket.le, bucket.count
+            );
+        }
+        let _ = writeln!(out, "kodegen_prompt_render_seconds_bucket{{le=\"+Inf\"}} {}", self.render.count);
+        let _ = writeln!(out, "kodegen_prompt_render_seconds_sum {}", self.render.sum_seconds);
+        let _ = writeln!(out, "kodegen_prompt_render_seconds_count {}", self.render.count);
+
+        header(&mut out, "kodegen_prompt_render_timeouts_total", "counter", "Renders stopped by the timeout");
+        let _ = writeln!(out, "kodegen_prompt_render_timeouts_total {}", self.render.timeouts);
+        header(&mut out, "kodegen_prompt_render_threads", "gauge", "Render threads still running");
+        let _ = writeln!(out, "kodegen_prompt_render_threads {}", self.render.running);
+        header(&mut out, "kodegen_prompt_template_compiles_total", "counter", "Templates compiled for rendering");
+        let _ = writeln!(out, "kodegen_prompt_template_compiles_total {}", self.render.compiles);
+
+        header(&mut out, "kodegen_prompt_validation_failures_total", "counter", "Rejected prompt content by rule");
+        for (rule, count) in &self.validation_failures {
+            let _ = writeln!(out, "kodegen_prompt_validation_failures_total{{rule=\"{rule}\"}} {count}");
+        }
+
+        header(&mut out, "kodegen_prompt_cache_lookups_total", "counter", "Template cache lookups");
+        let _ = writeln!(out, "kodegen_prompt_cache_lookups_total{{result=\"hit\"}} {}", self.cache.hits);
+        let _ = writeln!(out, "kodegen_prompt_cache_lookups_total{{result=\"miss\"}} {}", self.cache.misses);
+
+        header(&mut out, "kodegen_prompt_library_prompts", "gauge", "Stored prompts");
+        let _ = writeln!(out, "kodegen_prompt_library_prompts {}", self.library.prompts);
+        header(&mut out, "kodegen_prompt_library_bytes", "gauge", "Combined size of the prompt files");
+        let _ = writeln!(out, "kodegen_prompt_library_bytes {}", self.library.bytes);
+        header(&mut out, "kodegen_prompt_cached_templates", "gauge", "Parsed templates held in the cache");
+        let _ = writeln!(out, "kodegen_prompt_cached_templates {}", self.library.cached);
+        header(&mut out, "kodegen_prompt_compiled_templates", "gauge", "Cached templates compiled for rendering");
+        let _ = writeln!(out, "kodegen_prompt_compiled_templates {}", self.library.compiled);
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Escape a Prometheus label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Label for the `action` argument of a `tool` call
+///
+/// The argument comes from the client, so only the tool's own actions are
+/// recorded as given; anything else is counted as `other`, keeping the
+/// number of label values bounded.
+fn action_label<'a>(tool: &str, action: &'a str) -> &'a str {
+    use crate::schema::{prompt_get::GetPromptAction, prompt_history::HistoryAction};
+    use kodegen_mcp_schema::Tool;
+
+    fn is<T: serde::de::DeserializeOwned>(action: &str) -> bool {
+        serde_json::from_value::<T>(serde_json::Value::from(action)).is_ok()
+    }
+    let known = match tool {
+        t if t == crate::GetPromptTool::name() => is::<GetPromptAction>(action),
+        t if t == crate::HistoryPromptTool::name() => is::<HistoryAction>(action),
+        _ => false,
+    };
+    if known { action } else { "other" }
+}
+
+type ToolFuture<'a> = Pin<Box<dyn Future<Output = Result<CallToolResult, rmcp::ErrorData>> + Send + 'a>>;
+
+/// Count and time every tool call made through `tool_router`
+pub fn instrument_routes<S>(mut tool_router: ToolRouter<S>) -> ToolRouter<S>
+where
+    S: Send + Sync + 'static,
+{
+    for route in tool_router.map.values_mut() {
+        let call = route.call.clone();
+        route.call = Arc::new(move |ctx: ToolCallContext<'_, S>| -> ToolFuture<'_> {
+            let tool = ctx.name.to_string();
+            let action = ctx
+                .arguments
+                .as_ref()
+                .and_then(|args| args.get("action"))
+                .and_then(|action| action.as_str())
+                .map_or("", |action| action_label(&tool, action))
+                .to_string();
+            let call = call.clone();
+            Box::pin(async move {
+                let start = Instant::now();
+                let result = call(ctx).await;
+                let success = matches!(&result, Ok(res) if res.is_error != Some(true));
+                metrics().record_tool_call(&tool, &action, success, start.elapsed());
+                result
+            })
+        });
+    }
+    tool_router
+}
@@ -0,0 +1,400 @@
+//! Per-client authorization for prompt operations
+//!
+//! A policy file maps client identities (connection ID patterns or bearer
+//! tokens) to the actions they may perform on which prompt names:
+//!
+//! ```yaml
+//! clients:
+//!   - name: ci
+//!     tokens: ["change-me"]
+//!     allow:
+//!       - actions: [all]
+//!         prompts: ["*"]
+//!   - name: agents
+//!     connection_ids: ["*"]
+//!     allow:
+//!       - actions: [read, render]
+//!         prompts: ["*"]
+//!       - actions: [write, delete]
+//!         prompts: ["scratch/*"]
+//! # Clients matching no entry (omit to deny them everything)
+//! default:
+//!   - actions: [read, render]
+//!     prompts: ["*"]
+//! ```
+//!
+//! The server records the identity of every request (see [`scope_routes`]);
+//! `PromptManager` checks it before each read, render and write. Calls made
+//! outside a client request (startup, the CLI) are not checked.
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use log::{debug, warn};
+use rmcp::handler::server::prompt::PromptContext;
+use rmcp::handler::server::router::{prompt::PromptRouter, tool::ToolRouter};
+use rmcp::handler::server::tool::ToolCallContext;
+use rmcp::model::{CallToolResult, GetPromptResult};
+use rmcp::service::RequestContext;
+use rmcp::RoleServer;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fmt;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+
+/// Path of the policy file the server enforces
+pub const POLICY_ENV: &str = "KODEGEN_PROMPTS_POLICY";
+
+/// Log target for authorization decisions worth auditing
+pub const AUDIT_TARGET: &str = "kodegen_prompt::audit";
+
+/// Operation on a prompt that a policy can allow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PolicyAction {
+    /// List, read, export, inspect history
+    Read,
+    /// Render into text or messages
+    Render,
+    /// Create a prompt (add, copy, import, rename target, restore)
+    Create,
+    /// Overwrite a prompt (edit, import overwrite, restore)
+    Update,
+    /// Remove a prompt (delete, rename source)
+    Delete,
+}
+
+impl PolicyAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Render => "render",
+            Self::Create => "create",
+            Self::Update => "update",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+impl fmt::Display for PolicyAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A client was not allowed to perform an action on a prompt
+///
+/// Returned (wrapped in `anyhow::Error`) by [`Policy::check`] and every
+/// `PromptManager` operation it guards.
+#[derive(Debug, Clone)]
+pub struct PolicyDenied {
+    /// Policy entry that decided (`unlisted client` for the default rules)
+    pub client: String,
+    pub action: PolicyAction,
+    pub prompt: String,
+}
+
+impl fmt::Display for PolicyDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Permission denied: {} may not {} prompt '{}'",
+            self.client, self.action, self.prompt
+        )
+    }
+}
+
+impl std::error::Error for PolicyDenied {}
+
+/// Action names accepted in the policy file (`write` and `all` are shorthands)
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ActionSpec {
+    Read,
+    Render,
+    Create,
+    Update,
+    Delete,
+    /// `create` + `update`
+    Write,
+    /// Every action
+    All,
+}
+
+impl ActionSpec {
+    fn actions(self) -> &'static [PolicyAction] {
+        use PolicyAction::*;
+        match self {
+            Self::Read => &[Read],
+            Self::Render => &[Render],
+            Self::Create => &[Create],
+            Self::Update => &[Update],
+            Self::Delete => &[Delete],
+            Self::Write => &[Create, Update],
+            Self::All => &[Read, Render, Create, Update, Delete],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    #[serde(default)]
+    clients: Vec<ClientFile>,
+    #[serde(default)]
+    default: Vec<RuleFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClientFile {
+    name: String,
+    #[serde(default)]
+    connection_ids: Vec<String>,
+    #[serde(default)]
+    tokens: Vec<String>,
+    #[serde(default)]
+    allow: Vec<RuleFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleFile {
+    actions: Vec<ActionSpec>,
+    prompts: Vec<String>,
+}
+
+/// Compiled authorization policy
+#[derive(Debug)]
+pub struct Policy {
+    clients: Vec<ClientPolicy>,
+    default: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct ClientPolicy {
+    name: String,
+    connection_ids: GlobSet,
+    /// SHA-256 of each token, so comparisons don't depend on token length
+    token_digests: Vec<[u8; 32]>,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    actions: HashSet<PolicyAction>,
+    prompts: GlobSet,
+}
+
+impl Rule {
+    fn allows(&self, action: PolicyAction, name: &str) -> bool {
+        self.actions.contains(&action) && self.prompts.is_match(name)
+    }
+}
+
+/// Who is making the current request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientIdentity {
+    /// `X-Kodegen-Connection-Id` header
+    pub connection_id: Option<String>,
+    /// `Authorization: Bearer` token
+    pub token: Option<String>,
+}
+
+impl ClientIdentity {
+    /// Identity from the HTTP request headers (empty for other transports)
+    pub fn from_request(context: &RequestContext<RoleServer>) -> Self {
+        let Some(parts) = context.extensions.get::<http::request::Parts>() else {
+            return Self::default();
+        };
+        let header = |name: &str| parts.headers.get(name).and_then(|v| v.to_str().ok());
+        Self {
+            connection_id: header(kodegen_config::X_KODEGEN_CONNECTION_ID).map(str::to_string),
+            token: header(http::header::AUTHORIZATION.as_str())
+                .and_then(|v| v.strip_prefix("Bearer "))
+                .map(|token| token.trim().to_string()),
+        }
+    }
+}
+
+tokio::task_local! {
+    static CLIENT: ClientIdentity;
+}
+
+/// Identity of the client request being served, if any
+pub fn current_client() -> Option<ClientIdentity> {
+    CLIENT.try_with(Clone::clone).ok()
+}
+
+/// Run `future` on behalf of `client`
+pub async fn with_client<F: Future>(client: ClientIdentity, future: F) -> F::Output {
+    CLIENT.scope(client, future).await
+}
+
+impl Policy {
+    /// Parse a YAML policy
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        let file: PolicyFile = serde_yaml::from_str(yaml).context("Invalid policy file")?;
+
+        let mut clients = Vec::with_capacity(file.clients.len());
+        for client in file.clients {
+            if client.connection_ids.is_empty() && client.tokens.is_empty() {
+                anyhow::bail!(
+                    "Policy client '{}' needs connection_ids or tokens",
+                    client.name
+                );
+            }
+            clients.push(ClientPolicy {
+                connection_ids: glob_set(&client.connection_ids)
+                    .with_context(|| format!("Policy client '{}'", client.name))?,
+                token_digests: client.tokens.iter().map(|token| token_digest(token)).collect(),
+                rules: compile_rules(client.allow)
+                    .with_context(|| format!("Policy client '{}'", client.name))?,
+                name: client.name,
+            });
+        }
+
+        Ok(Self {
+            clients,
+            default: compile_rules(file.default).context("Policy default rules")?,
+        })
+    }
+
+    /// Read a YAML policy file
+    pub fn load(path: &Path) -> Result<Self> {
+        let yaml = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read policy file: {}", path.display()))?;
+        Self::from_yaml(&yaml).with_context(|| format!("Policy file {}", path.display()))
+    }
+
+    /// Allow or deny `action` on prompt `name` for `client`
+    ///
+    /// Tokens are checked before connection IDs; the first matching client
+    /// entry decides. Denials are logged to [`AUDIT_TARGET`] and returned as
+    /// [`PolicyDenied`].
+    pub fn check(&self, client: &ClientIdentity, action: PolicyAction, name: &str) -> Result<()> {
+        let entry = self.client_for(client);
+        let rules = entry.map_or(&self.default, |entry| &entry.rules);
+        let label = entry.map_or("unlisted client", |entry| entry.name.as_str());
+
+        if rules.iter().any(|rule| rule.allows(action, name)) {
+            debug!("Policy allows {label} to {action} '{name}'");
+            return Ok(());
+        }
+
+        warn!(
+            target: AUDIT_TARGET,
+            "Policy denied {action} on '{name}' for {label} (connection {})",
+            client.connection_id.as_deref().unwrap_or("-")
+        );
+        Err(PolicyDenied {
+            client: label.to_string(),
+            action,
+            prompt: name.to_string(),
+        }
+        .into())
+    }
+
+    /// Whether `client` may perform `action` on `name` (no logging)
+    pub fn allows(&self, client: &ClientIdentity, action: PolicyAction, name: &str) -> bool {
+        let rules = self
+            .client_for(client)
+            .map_or(&self.default, |entry| &entry.rules);
+        rules.iter().any(|rule| rule.allows(action, name))
+    }
+
+    fn client_for(&self, client: &ClientIdentity) -> Option<&ClientPolicy> {
+        if let Some(token) = &client.token
+            && let Some(entry) = self.client_with_token(token)
+        {
+            return Some(entry);
+        }
+        let connection_id = client.connection_id.as_deref()?;
+        self.clients
+            .iter()
+            .find(|c| c.connection_ids.is_match(connection_id))
+    }
+
+    /// Client holding `token`
+    ///
+    /// Every configured token is compared in constant time, without stopping
+    /// at a match, so response times reveal neither how much of a token
+    /// matched nor which client holds it.
+    fn client_with_token(&self, token: &str) -> Option<&ClientPolicy> {
+        let digest = token_digest(token);
+        let mut found = None;
+        for entry in &self.clients {
+            for candidate in &entry.token_digests {
+                if bool::from(candidate.ct_eq(&digest)) && found.is_none() {
+                    found = Some(entry);
+                }
+            }
+        }
+        found
+    }
+}
+
+fn token_digest(token: &str) -> [u8; 32] {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+fn compile_rules(rules: Vec<RuleFile>) -> Result<Vec<Rule>> {
+    rules
+        .into_iter()
+        .map(|rule| {
+            Ok(Rule {
+                actions: rule
+                    .actions
+                    .iter()
+                    .flat_map(|spec| spec.actions().iter().copied())
+                    .collect(),
+                prompts: glob_set(&rule.prompts)?,
+            })
+        })
+        .collect()
+}
+
+/// `*` matches across namespace separators, so `scratch/*` covers the
+/// whole namespace
+fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).with_context(|| format!("Invalid pattern '{pattern}'"))?);
+    }
+    Ok(builder.build()?)
+}
+
+type ToolFuture<'a> = Pin<Box<dyn Future<Output = Result<CallToolResult, rmcp::ErrorData>> + Send + 'a>>;
+type PromptFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<GetPromptResult, rmcp::ErrorData>> + Send + 'a>>;
+
+/// Serve every route on behalf of the requesting client
+///
+/// Wraps each tool and prompt route so the request's [`ClientIdentity`] is
+/// available to `PromptManager` while it runs.
+pub fn scope_routes<S>(
+    mut tool_router: ToolRouter<S>,
+    mut prompt_router: PromptRouter<S>,
+) -> (ToolRouter<S>, PromptRouter<S>)
+where
+    S: Send + Sync + 'static,
+{
+    for route in tool_router.map.values_mut() {
+        let call = route.call.clone();
+        route.call = Arc::new(move |ctx: ToolCallContext<'_, S>| -> ToolFuture<'_> {
+            let client = ClientIdentity::from_request(&ctx.request_context);
+            Box::pin(with_client(client, call(ctx)))
+        });
+    }
+    for route in prompt_router.map.values_mut() {
+        let get = route.get.clone();
+        route.get = Arc::new(move |ctx: PromptContext<'_, S>| -> PromptFuture<'_> {
+            let client = ClientIdentity::from_request(&ctx.context);
+            Box::pin(with_client(client, get(ctx)))
+        });
+    }
+    (tool_router, prompt_router)
+}
@@ -0,0 +1,360 @@
+use super::attachments::{Attachment, AttachmentData, AttachmentKind, AttachmentSandbox};
+use super::audit::AuditEntry;
+use super::manager::PromptManager;
+use super::messages::{MessageRole, RenderedMessage};
+use super::metadata::{ParameterType, PromptTemplate};
+use super::policy::{with_client, ClientIdentity};
+use kodegen_config::{X_KODEGEN_GITROOT, X_KODEGEN_PWD};
+use kodegen_mcp_schema::prompt::TemplateParamValue;
+use log::{info, warn};
+use rmcp::handler::server::prompt::PromptContext;
+use rmcp::handler::server::router::prompt::{PromptRoute, PromptRouter};
+use rmcp::model::{
+    AnnotateAble, GetPromptResult, Prompt, PromptArgument, PromptMessage, PromptMessageContent,
+    PromptMessageRole, RawEmbeddedResource, RawImageContent, ResourceContents,
+};
+use rmcp::service::RequestContext;
+use rmcp::RoleServer;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+type PromptFuture = Pin<Box<dyn Future<Output = Result<GetPromptResult, rmcp::ErrorData>> + Send>>;
+
+/// Register every prompt template as an MCP prompt (`prompts/list`, `prompts/get`)
+///
+/// For servers whose prompt handlers are fixed routers (kodegend's HTTP
+/// server). Templates are rendered into role-tagged messages on each
+/// `prompts/get` call, so edits to an existing template are picked up
+/// immediately, but the set of registered names is a snapshot taken at
+/// startup: prompts added later are served after a restart, and deleted ones
+/// fail with "not found". Servers that answer `prompts/list` and
+/// `prompts/get` themselves should use [`TemplatePrompts`] instead. Templates
+/// whose name collides with an existing route (e.g. a tool's `*_help` prompt)
+/// are skipped.
+///
+/// `prompts/list` is answered by the server from this snapshot and is not
+/// filtered per client, so `prompts/get` checks the client policy for both
+/// `read` and `render`: a client that may not read a prompt cannot fetch it.
+pub async fn register_template_prompts<S>(
+    mut prompt_router: PromptRouter<S>,
+    manager: &PromptManager,
+) -> PromptRouter<S>
+where
+    S: Send + Sync + 'static,
+{
+    let templates = match manager.list_prompts().await {
+        Ok(templates) => templates,
+        Err(e) => {
+            warn!("Failed to list prompt templates for prompts/get: {e}");
+            return prompt_router;
+        }
+    };
+
+    let mut registered = 0;
+    for template in &templates {
+        if prompt_router.has_route(&template.filename) {
+            warn!(
+                "Prompt template '{}' collides with an existing prompt route (skipping)",
+                template.filename
+            );
+            continue;
+        }
+        prompt_router.add_route(template_prompt_route(manager.clone(), template));
+        registered += 1;
+    }
+
+    info!("Registered {registered} prompt templates for prompts/get");
+    prompt_router
+}
+
+/// Template prompts looked up in the library on every request
+///
+/// For `ServerHandler`s that answer `prompts/list` and `prompts/get`
+/// themselves, such as [`StdioServer`](crate::stdio::StdioServer): prompts
+/// added, renamed, copied or deleted after startup are listed and served at
+/// once, and the listing only shows prompts the client may read. Routes
+/// already in the server's router (tool `*_help` prompts) take precedence.
+#[derive(Clone)]
+pub struct TemplatePrompts {
+    manager: PromptManager,
+}
+
+impl TemplatePrompts {
+    /// Serve the templates of `manager` (with its policy and audit log)
+    pub fn new(manager: PromptManager) -> Self {
+        Self { manager }
+    }
+
+    /// Descriptors for `prompts/list`, except names `router` already serves
+    pub async fn list<S>(
+        &self,
+        router: &PromptRouter<S>,
+        context: &RequestContext<RoleServer>,
+    ) -> Vec<Prompt>
+    where
+        S: Send + Sync + 'static,
+    {
+        let client = ClientIdentity::from_request(context);
+        match with_client(client, self.manager.list_prompts()).await {
+            Ok(templates) => templates
+                .iter()
+                .filter(|template| !router.has_route(&template.filename))
+                .map(prompt_attr)
+                .collect(),
+            Err(e) => {
+                warn!("Failed to list prompt templates for prompts/list: {e}");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Render template `name` for `prompts/get`
+    pub async fn get(
+        &self,
+        name: &str,
+        arguments: Option<serde_json::Map<String, serde_json::Value>>,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, rmcp::ErrorData> {
+        let sandbox = workspace_sandbox(context);
+        let client = ClientIdentity::from_request(context);
+        let connection_id = client.connection_id.clone();
+        with_client(
+            client,
+            get_template_prompt(&self.manager, name, arguments, sandbox, connection_id),
+        )
+        .await
+    }
+}
+
+/// Tool name recorded in audit entries for `prompts/get` renders
+const PROMPTS_GET: &str = "prompts/get";
+
+/// Build the MCP prompt route for a single template
+fn template_prompt_route<S>(manager: PromptManager, template: &PromptTemplate) -> PromptRoute<S>
+where
+    S: Send + Sync + 'static,
+{
+    let name = template.filename.clone();
+    PromptRoute::new_dyn(prompt_attr(template), move |ctx: PromptContext<'_, S>| {
+        let manager = manager.clone();
+        let name = name.clone();
+        let sandbox = workspace_sandbox(&ctx.context);
+        let connection_id = ClientIdentity::from_request(&ctx.context).connection_id;
+        let future: PromptFuture = Box::pin(async move {
+            get_template_prompt(&manager, &name, ctx.arguments, sandbox, connection_id).await
+        });
+        future
+    })
+}
+
+/// Attachment sandbox from the kodegen workspace headers (HTTP transport only)
+fn workspace_sandbox(context: &RequestContext<RoleServer>) -> Option<AttachmentSandbox> {
+    let parts = context.extensions.get::<http::request::Parts>()?;
+    let header = |name: &str| {
+        parts
+            .headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(PathBuf::from)
+    };
+    let pwd = header(X_KODEGEN_PWD);
+    let git_root = header(X_KODEGEN_GITROOT);
+    AttachmentSandbox::for_workspace(pwd.as_deref(), git_root.as_deref())
+}
+
+/// MCP prompt descriptor built from template metadata
+fn prompt_attr(template: &PromptTemplate) -> Prompt {
+    let arguments = template
+        .metadata
+        .parameters
+        .iter()
+        .map(|param| PromptArgument {
+            name: param.name.clone(),
+            title: None,
+            description: Some(param.description.clone()),
+            required: Some(param.required),
+        })
+        .collect();
+
+    Prompt {
+        name: template.filename.clone(),
+        title: Some(template.metadata.title.clone()),
+        description: Some(template.metadata.description.clone()),
+        arguments: Some(arguments),
+        icons: None,
+        meta: None,
+    }
+}
+
+/// Handle `prompts/get` for a template
+async fn get_template_prompt(
+    manager: &PromptManager,
+    name: &str,
+    arguments: Option<serde_json::Map<String, serde_json::Value>>,
+    sandbox: Option<AttachmentSandbox>,
+    connection_id: Option<String>,
+) -> Result<GetPromptResult, rmcp::ErrorData> {
+    let record_values = manager
+        .audit_log()
+        .is_some_and(|log| log.records_parameter_values());
+    let entry = AuditEntry::new(PROMPTS_GET, name, connection_id.as_deref());
+
+    // Checked as a read (the listing exposes the prompt) and then as a render
+    let template = match manager.load_prompt(name).await {
+        Ok(template) => template,
+        Err(e) => {
+            let message = format!("Failed to load prompt '{name}': {e}");
+            manager.audit(entry.with_result(&Err::<(), _>(e))).await;
+            return Err(rmcp::ErrorData::internal_error(message, None));
+        }
+    };
+
+    let parameters = match convert_arguments(&template, arguments.unwrap_or_default()) {
+        Ok(parameters) => parameters,
+        Err(e) => {
+            let message = e.to_string();
+            manager.audit(entry.with_result(&Err::<(), _>(e))).await;
+            return Err(rmcp::ErrorData::invalid_params(message, None));
+        }
+    };
+    let entry = entry.with_parameters(Some(&parameters), record_values);
+
+    // Audited with the hash of the version actually rendered
+    let rendered = manager
+        .auditing_render(entry)
+        .render_prompt_messages(name, Some(parameters), sandbox.as_ref())
+        .await
+        .map_err(|e| {
+            rmcp::ErrorData::invalid_params(format!("Failed to render prompt '{name}': {e}"), None)
+        })?;
+
+    Ok(GetPromptResult {
+        description: Some(template.metadata.description),
+        messages: rendered.messages.into_iter().flat_map(to_prompt_messages).collect(),
+    })
+}
+
+/// Convert a rendered message to MCP prompt messages
+///
+/// MCP prompt messages only carry `user` and `assistant` roles, so system
+/// messages are delivered as user messages. Each MCP message holds a single
+/// content part, so attachments follow the text as separate messages with the
+/// same role.
+fn to_prompt_messages(message: RenderedMessage) -> Vec<PromptMessage> {
+    let role = match message.role {
+        MessageRole::System | MessageRole::User => PromptMessageRole::User,
+        MessageRole::Assistant => PromptMessageRole::Assistant,
+    };
+
+    let mut messages = Vec::with_capacity(1 + message.attachments.len());
+    if !message.content.is_empty() {
+        messages.push(PromptMessage::new_text(role.clone(), message.content));
+    }
+    for attachment in message.attachments {
+        messages.push(PromptMessage {
+            role: role.clone(),
+            content: attachment_content(attachment),
+        });
+    }
+    messages
+}
+
+/// Convert an attachment to an MCP image or embedded resource content part
+fn attachment_content(attachment: Attachment) -> PromptMessageContent {
+    match (attachment.kind, attachment.data) {
+        (AttachmentKind::Image, AttachmentData::Base64(data)) => PromptMessageContent::Image {
+            image: RawImageContent {
+                data,
+                mime_type: attachment.mime_type,
+                meta: None,
+            }
+            .no_annotation(),
+        },
+        (_, data) => {
+            let resource = match data {
+                AttachmentData::Text(text) => ResourceContents::TextResourceContents {
+                    uri: attachment.uri,
+                    mime_type: Some(attachment.mime_type),
+                    text,
+                    meta: None,
+                },
+                AttachmentData::Base64(blob) => ResourceContents::BlobResourceContents {
+                    uri: attachment.uri,
+                    mime_type: Some(attachment.mime_type),
+                    blob,
+                    meta: None,
+                },
+            };
+            PromptMessageContent::Resource {
+                resource: RawEmbeddedResource {
+                    meta: None,
+                    resource,
+                }
+                .no_annotation(),
+            }
+        }
+    }
+}
+
+/// Convert MCP prompt arguments (strings) to typed template parameters
+///
+/// String values are parsed according to the declared parameter type. Arrays
+/// accept a JSON array (`["a", "b"]`) or a comma-separated list. Already-typed
+/// JSON values are accepted as-is; undeclared arguments are passed through.
+fn convert_arguments(
+    template: &PromptTemplate,
+    arguments: serde_json::Map<String, serde_json::Value>,
+) -> anyhow::Result<HashMap<String, TemplateParamValue>> {
+    let mut parameters = HashMap::with_capacity(arguments.len());
+
+    for (key, value) in arguments {
+        let param_type = template
+            .metadata
+            .parameters
+            .iter()
+            .find(|p| p.name == key)
+            .map(|p| &p.param_type);
+
+        let converted = match (value, param_type) {
+            (serde_json::Value::String(s), Some(ParameterType::Number)) => {
+                let n = s.trim().parse::<f64>().map_err(|_| {
+                    anyhow::anyhow!("Argument '{key}' must be a number, got '{s}'")
+                })?;
+                TemplateParamValue::Number(n)
+            }
+            (serde_json::Value::String(s), Some(ParameterType::Boolean)) => {
+                match s.trim().to_ascii_lowercase().as_str() {
+                    "true" | "yes" | "1" => TemplateParamValue::Bool(true),
+                    "false" | "no" | "0" => TemplateParamValue::Bool(false),
+                    _ => anyhow::bail!("Argument '{key}' must be a boolean, got '{s}'"),
+                }
+            }
+            (serde_json::Value::String(s), Some(ParameterType::Array)) => {
+                let trimmed = s.trim();
+                if trimmed.starts_with('[') {
+                    let items: Vec<String> = serde_json::from_str(trimmed).map_err(|e| {
+                        anyhow::anyhow!("Argument '{key}' is not a JSON string array: {e}")
+                    })?;
+                    TemplateParamValue::StringArray(items)
+                } else {
+                    TemplateParamValue::StringArray(
+                        trimmed
+                            .split(',')
+                            .map(|item| item.trim().to_string())
+                            .filter(|item| !item.is_empty())
+                            .collect(),
+                    )
+                }
+            }
+            (serde_json::Value::String(s), _) => TemplateParamValue::String(s),
+            (other, _) => serde_json::from_value(other)
+                .map_err(|e| anyhow::anyhow!("Unsupported value for argument '{key}': {e}"))?,
+        };
+
+        parameters.insert(key, converted);
+    }
+
+    Ok(parameters)
+}
@@ -0,0 +1,96 @@
+use super::audit::AuditEntry;
+use super::manager::PromptManager;
+use super::schema::{PromptRenameOutput, PromptRenamePrompts, RenamePromptArgs, PROMPT_RENAME};
+use kodegen_mcp_schema::{McpError, Tool, ToolExecutionContext, ToolResponse};
+
+#[derive(Clone)]
+pub struct RenamePromptTool {
+    manager: PromptManager,
+}
+
+impl RenamePromptTool {
+    /// Create with a pre-initialized PromptManager (for HTTP server)
+    pub fn with_manager(manager: PromptManager) -> Self {
+        Self { manager }
+    }
+
+    /// Create with default manager (for standalone use)
+    pub async fn new() -> Result<Self, McpError> {
+        let manager = PromptManager::new();
+        manager.init().await?;
+        Ok(Self { manager })
+    }
+}
+
+impl Tool for RenamePromptTool {
+    type Args = RenamePromptArgs;
+    type Prompts = PromptRenamePrompts;
+
+    fn name() -> &'static str {
+        PROMPT_RENAME
+    }
+
+    fn description() -> &'static str {
+        "Rename a prompt template. The rename is atomic and fails if a prompt with the new name \
+         already exists. The old name is kept as an alias, so callers using it keep resolving \
+         to the prompt. Metadata and custom frontmatter keys are preserved. Both names must \
+         contain only alphanumeric characters, hyphens, and underscores."
+    }
+
+    fn read_only() -> bool {
+        false
+    }
+
+    fn destructive() -> bool {
+        false // Old name keeps resolving via the alias
+    }
+
+    fn idempotent() -> bool {
+        false // Second rename fails (old file gone)
+    }
+
+    async fn execute(&self, args: Self::Args, ctx: ToolExecutionContext) -> Result<ToolResponse<<Self::Args as kodegen_mcp_schema::ToolArgs>::Output>, McpError> {
+        let manager = self.manager.for_connection(ctx.connection_id());
+        let hash_before = manager.audit_hash(&args.name).await;
+        let result = manager.rename_prompt(&args.name, &args.new_name).await;
+        let hash_after = match &result {
+            Ok(_) => manager.audit_hash(&args.new_name).await,
+            Err(_) => None,
+        };
+        manager
+            .audit(
+                AuditEntry::new(PROMPT_RENAME, &args.name, ctx.connection_id())
+                    .with_target(&args.new_name)
+                    .with_hashes(hash_before, hash_after)
+                    .with_result(&result),
+            )
+            .await;
+        let template = result.map_err(McpError::Other)?;
+
+        let path = manager.prompt_path(&args.new_name).map(|path| path.display().to_string());
+
+        // Terminal summary
+        let summary = format!(
+            "\x1b[32m Prompt Renamed: {} → {}\x1b[0m\n\
+              Aliases: {}",
+            args.name,
+            args.new_name,
+            template.metadata.aliases.join(", ")
+        );
+
+        // Typed output
+        let output = PromptRenameOutput {
+            success: true,
+            old_name: args.name.clone(),
+            new_name: args.new_name.clone(),
+            message: format!(
+                "Prompt '{}' renamed to '{}' ('{}' remains as an alias)",
+                args.name, args.new_name, args.name
+            ),
+            path,
+            aliases: template.metadata.aliases,
+        };
+
+        Ok(ToolResponse::new(summary, output))
+    }
+}
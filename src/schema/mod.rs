@@ -0,0 +1,32 @@
+//! Tool schemas owned by this crate
+//!
+//! Types that extend the shared `kodegen_mcp_schema::prompt` definitions.
+//! Shared building blocks (parameter values, parameter definitions, category
+//! info) are re-used from `kodegen_mcp_schema` so the wire format stays a
+//! superset of the upstream schema.
+
+pub mod prompt_add;
+pub mod prompt_audit;
+pub mod prompt_copy;
+pub mod prompt_edit;
+pub mod prompt_export;
+pub mod prompt_get;
+pub mod prompt_history;
+pub mod prompt_import;
+pub mod prompt_rename;
+pub mod prompt_stats;
+pub mod prompt_validate;
+pub mod prompt_vote;
+
+pub use prompt_add::*;
+pub use prompt_audit::*;
+pub use prompt_copy::*;
+pub use prompt_edit::*;
+pub use prompt_export::*;
+pub use prompt_get::*;
+pub use prompt_history::*;
+pub use prompt_import::*;
+pub use prompt_rename::*;
+pub use prompt_stats::*;
+pub use prompt_validate::*;
+pub use prompt_vote::*;
@@ -0,0 +1,65 @@
+//! Schema types for prompt_add tool
+
+use crate::diagnostics::Diagnostic;
+use kodegen_config::{CATEGORY_PROMPT, PROMPT_ADD};
+use kodegen_mcp_schema::ToolArgs;
+use kodegen_mcp_schema::prompt::PromptAddPrompts;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// PROMPT ADD TOOL
+// ============================================================================
+
+/// Arguments for `prompt_add` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AddPromptArgs {
+    /// Filename for the prompt (without .j2.md extension)
+    /// Must contain only alphanumeric characters, hyphens, and underscores;
+    /// use '/' to place it in a namespace (e.g. "review/security")
+    pub name: String,
+
+    /// Full prompt content including YAML frontmatter
+    pub content: String,
+}
+
+// ============================================================================
+// OUTPUT TYPES
+// ============================================================================
+
+/// Output from `prompt_add` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptAddOutput {
+    /// Whether the operation succeeded
+    pub success: bool,
+    /// Name of the prompt created
+    pub name: String,
+    /// Human-readable message
+    pub message: String,
+    /// Path to the created file (absent for stores without a directory)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Template content length in characters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_length: Option<usize>,
+    /// Number of parameters defined
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameter_count: Option<usize>,
+    /// Validation errors (line, column, key path, expected/actual, suggestion)
+    /// when the content was rejected
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+// ============================================================================
+// TOOL ARGS TRAIT IMPLEMENTATION
+// ============================================================================
+
+impl ToolArgs for AddPromptArgs {
+    type Output = PromptAddOutput;
+    type Prompts = PromptAddPrompts;
+
+    const NAME: &'static str = PROMPT_ADD;
+    const CATEGORY: &'static kodegen_config::Category = CATEGORY_PROMPT;
+    const DESCRIPTION: &'static str = "Create new reusable Jinja2 prompt template with variables, metadata, and categorization";
+}
@@ -0,0 +1,133 @@
+//! Schema types for prompt_audit tool
+
+use crate::audit::{AuditEntry, AuditOutcome};
+use kodegen_config::CATEGORY_PROMPT;
+use kodegen_mcp_schema::ToolArgs;
+use kodegen_mcp_schema::tool::{PromptProvider, SealedPromptProvider};
+use rmcp::model::{PromptArgument, PromptMessage, PromptMessageContent, PromptMessageRole};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Tool name for prompt_audit
+pub const PROMPT_AUDIT: &str = "prompt_audit";
+
+// ============================================================================
+// PROMPT AUDIT TOOL
+// ============================================================================
+
+/// Arguments for `prompt_audit` tool
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct AuditPromptArgs {
+    /// Only entries for this prompt (as source or rename/copy target)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Only entries made by this MCP connection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_id: Option<String>,
+
+    /// Only entries recorded by this tool (e.g. `prompt_edit`, `prompts/get`, `policy`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+
+    /// Only entries with this outcome
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<AuditOutcome>,
+
+    /// Only entries recorded at or after this RFC 3339 time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+
+    /// Maximum entries to return, newest first (default 50)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+// ============================================================================
+// OUTPUT TYPES
+// ============================================================================
+
+/// Output from `prompt_audit` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptAuditOutput {
+    /// Whether the query succeeded
+    pub success: bool,
+    /// Number of entries returned
+    pub count: usize,
+    /// Matching entries, newest first
+    pub entries: Vec<AuditEntry>,
+}
+
+// ============================================================================
+// PROMPTS
+// ============================================================================
+
+/// Prompt arguments for `prompt_audit` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AuditPromptPromptArgs {
+    /// Scenario to show examples for
+    /// - "basic": Finding who changed a prompt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<String>,
+}
+
+/// Prompt provider for `prompt_audit` tool
+pub struct PromptAuditPrompts;
+
+impl SealedPromptProvider for PromptAuditPrompts {}
+
+impl PromptProvider for PromptAuditPrompts {
+    type PromptArgs = AuditPromptPromptArgs;
+
+    fn generate_prompts(_args: &Self::PromptArgs) -> Vec<PromptMessage> {
+        prompt_basic()
+    }
+
+    fn prompt_arguments() -> Vec<PromptArgument> {
+        vec![PromptArgument {
+            name: "scenario".to_string(),
+            title: None,
+            description: Some("Scenario to show (basic)".to_string()),
+            required: Some(false),
+        }]
+    }
+}
+
+/// Finding who changed a prompt
+fn prompt_basic() -> Vec<PromptMessage> {
+    vec![
+        PromptMessage {
+            role: PromptMessageRole::User,
+            content: PromptMessageContent::text(
+                "The release-notes prompt changed yesterday. Who changed it, and who has rendered it since?",
+            ),
+        },
+        PromptMessage {
+            role: PromptMessageRole::Assistant,
+            content: PromptMessageContent::text(
+                "With an audit log configured (KODEGEN_PROMPTS_AUDIT_LOG) every change and \
+                 render is recorded:\n\n\
+                 prompt_audit({ \"name\": \"release-notes\", \"since\": \"2025-01-14T00:00:00Z\" })\n\
+                 prompt_audit({ \"name\": \"release-notes\", \"tool\": \"prompt_edit\" })\n\
+                 prompt_audit({ \"outcome\": \"denied\" })\n\n\
+                 Each entry has the tool, connection ID and time. Changes carry the SHA-256 of \
+                 the prompt before and after (hash_before / hash_after); renders carry the hash \
+                 of the version rendered and the parameter names used. Matching hashes tell you \
+                 which renders used which edit.",
+            ),
+        },
+    ]
+}
+
+// ============================================================================
+// TOOL ARGS TRAIT IMPLEMENTATION
+// ============================================================================
+
+impl ToolArgs for AuditPromptArgs {
+    type Output = PromptAuditOutput;
+    type Prompts = PromptAuditPrompts;
+
+    const NAME: &'static str = PROMPT_AUDIT;
+    const CATEGORY: &'static kodegen_config::Category = CATEGORY_PROMPT;
+    const DESCRIPTION: &'static str = "Query the audit log of prompt changes, renders and policy denials";
+}
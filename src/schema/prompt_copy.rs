@@ -0,0 +1,127 @@
+//! Schema types for prompt_copy tool
+
+use kodegen_config::CATEGORY_PROMPT;
+use kodegen_mcp_schema::ToolArgs;
+use kodegen_mcp_schema::tool::{PromptProvider, SealedPromptProvider};
+use rmcp::model::{PromptArgument, PromptMessage, PromptMessageContent, PromptMessageRole};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Tool name for prompt_copy
+pub const PROMPT_COPY: &str = "prompt_copy";
+
+// ============================================================================
+// PROMPT COPY TOOL
+// ============================================================================
+
+/// Arguments for `prompt_copy` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CopyPromptArgs {
+    /// Prompt to copy (name or alias)
+    pub name: String,
+
+    /// Name for the copy (without .j2.md extension)
+    /// Must contain only alphanumeric characters, hyphens, and underscores
+    pub new_name: String,
+
+    /// Author of the copy (defaults to the source prompt's author)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+}
+
+// ============================================================================
+// OUTPUT TYPES
+// ============================================================================
+
+/// Output from `prompt_copy` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptCopyOutput {
+    /// Whether the operation succeeded
+    pub success: bool,
+    /// Name of the new prompt
+    pub name: String,
+    /// Prompt the copy was made from (canonical name)
+    pub forked_from: String,
+    /// Author of the copy
+    pub author: String,
+    /// Human-readable message
+    pub message: String,
+    /// Path to the created file (absent for stores without a directory)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+// ============================================================================
+// PROMPTS
+// ============================================================================
+
+/// Prompt arguments for `prompt_copy` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CopyPromptPromptArgs {
+    /// Scenario to show examples for
+    /// - "basic": Forking a prompt to customize it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<String>,
+}
+
+/// Prompt provider for `prompt_copy` tool
+pub struct PromptCopyPrompts;
+
+impl SealedPromptProvider for PromptCopyPrompts {}
+
+impl PromptProvider for PromptCopyPrompts {
+    type PromptArgs = CopyPromptPromptArgs;
+
+    fn generate_prompts(_args: &Self::PromptArgs) -> Vec<PromptMessage> {
+        prompt_basic()
+    }
+
+    fn prompt_arguments() -> Vec<PromptArgument> {
+        vec![PromptArgument {
+            name: "scenario".to_string(),
+            title: None,
+            description: Some("Scenario to show (basic)".to_string()),
+            required: Some(false),
+        }]
+    }
+}
+
+/// Forking a prompt to customize it
+fn prompt_basic() -> Vec<PromptMessage> {
+    vec![
+        PromptMessage {
+            role: PromptMessageRole::User,
+            content: PromptMessageContent::text(
+                "How do I make my own version of an existing prompt?",
+            ),
+        },
+        PromptMessage {
+            role: PromptMessageRole::Assistant,
+            content: PromptMessageContent::text(
+                "Fork it with prompt_copy, then edit the copy:\n\n\
+                 prompt_copy({\n\
+                     \"name\": \"code-review\",\n\
+                     \"new_name\": \"code-review-strict\",\n\
+                     \"author\": \"your-name\"\n\
+                 })\n\n\
+                 The copy records forked_from: code-review in its frontmatter, takes the \
+                 given author, and starts unverified with zero votes. Aliases stay with the \
+                 original. The source prompt is not changed. Use prompt_edit on \
+                 \"code-review-strict\" to customize it.",
+            ),
+        },
+    ]
+}
+
+// ============================================================================
+// TOOL ARGS TRAIT IMPLEMENTATION
+// ============================================================================
+
+impl ToolArgs for CopyPromptArgs {
+    type Output = PromptCopyOutput;
+    type Prompts = PromptCopyPrompts;
+
+    const NAME: &'static str = PROMPT_COPY;
+    const CATEGORY: &'static kodegen_config::Category = CATEGORY_PROMPT;
+    const DESCRIPTION: &'static str = "Copy (fork) prompt template under a new name, recording forked_from";
+}
@@ -0,0 +1,133 @@
+//! Schema types for prompt_export tool
+
+use crate::bundle::{BundleFormat, BundleManifest};
+use kodegen_config::CATEGORY_PROMPT;
+use kodegen_mcp_schema::ToolArgs;
+use kodegen_mcp_schema::tool::{PromptProvider, SealedPromptProvider};
+use rmcp::model::{PromptArgument, PromptMessage, PromptMessageContent, PromptMessageRole};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Tool name for prompt_export
+pub const PROMPT_EXPORT: &str = "prompt_export";
+
+// ============================================================================
+// PROMPT EXPORT TOOL
+// ============================================================================
+
+/// Arguments for `prompt_export` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ExportPromptsArgs {
+    /// Bundle format: "json" (default), "yaml", or "tar.gz" (returned base64-encoded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<BundleFormat>,
+
+    /// Only export prompts in any of these categories
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+
+    /// Only export prompts with any of these tags
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// Only export prompts whose name matches any of these globs (e.g. "review_*")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub names: Vec<String>,
+}
+
+// ============================================================================
+// OUTPUT TYPES
+// ============================================================================
+
+/// Output from `prompt_export` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptExportOutput {
+    /// Whether the operation succeeded
+    pub success: bool,
+    /// Bundle format
+    pub format: BundleFormat,
+    /// Number of prompts exported
+    pub count: usize,
+    /// Bundle manifest (names, titles, versions, checksums)
+    pub manifest: BundleManifest,
+    /// Encoding of `data`: "utf8" (json, yaml) or "base64" (tar.gz)
+    pub encoding: String,
+    /// Encoded bundle, ready to pass to prompt_import
+    pub data: String,
+}
+
+// ============================================================================
+// PROMPTS
+// ============================================================================
+
+/// Prompt arguments for `prompt_export` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ExportPromptsPromptArgs {
+    /// Scenario to show examples for
+    /// - "basic": Exporting part of the library
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<String>,
+}
+
+/// Prompt provider for `prompt_export` tool
+pub struct PromptExportPrompts;
+
+impl SealedPromptProvider for PromptExportPrompts {}
+
+impl PromptProvider for PromptExportPrompts {
+    type PromptArgs = ExportPromptsPromptArgs;
+
+    fn generate_prompts(_args: &Self::PromptArgs) -> Vec<PromptMessage> {
+        prompt_basic()
+    }
+
+    fn prompt_arguments() -> Vec<PromptArgument> {
+        vec![PromptArgument {
+            name: "scenario".to_string(),
+            title: None,
+            description: Some("Scenario to show (basic)".to_string()),
+            required: Some(false),
+        }]
+    }
+}
+
+/// Exporting part of the library
+fn prompt_basic() -> Vec<PromptMessage> {
+    vec![
+        PromptMessage {
+            role: PromptMessageRole::User,
+            content: PromptMessageContent::text(
+                "How do I share a set of prompts with another machine or team?",
+            ),
+        },
+        PromptMessage {
+            role: PromptMessageRole::Assistant,
+            content: PromptMessageContent::text(
+                "Export them as one bundle with prompt_export:\n\n\
+                 prompt_export({\n\
+                     \"categories\": [\"review\"],\n\
+                     \"names\": [\"code_*\"],\n\
+                     \"format\": \"json\"\n\
+                 })\n\n\
+                 Filters combine: a prompt must match every filter you set (any value within \
+                 a filter). With no filters the whole library is exported. The response \
+                 carries the manifest (names, titles, versions, SHA-256 checksums) and the \
+                 encoded bundle in `data`. Pass `data` unchanged to prompt_import on the \
+                 other side. The tar.gz format is returned base64-encoded.",
+            ),
+        },
+    ]
+}
+
+// ============================================================================
+// TOOL ARGS TRAIT IMPLEMENTATION
+// ============================================================================
+
+impl ToolArgs for ExportPromptsArgs {
+    type Output = PromptExportOutput;
+    type Prompts = PromptExportPrompts;
+
+    const NAME: &'static str = PROMPT_EXPORT;
+    const CATEGORY: &'static kodegen_config::Category = CATEGORY_PROMPT;
+    const DESCRIPTION: &'static str = "Export prompt templates filtered by category, tag, or name glob as a single bundle with manifest";
+}
@@ -0,0 +1,388 @@
+//! Schema types for prompt_get tool
+
+use crate::attachments::AttachmentKind;
+use crate::messages::MessageRole;
+use crate::tokens::TokenEstimate;
+use crate::usage::PromptUsage;
+use kodegen_config::{CATEGORY_PROMPT, PROMPT_GET};
+use kodegen_mcp_schema::ToolArgs;
+use kodegen_mcp_schema::prompt::{
+    PromptCategoriesResult, PromptGetPrompts, PromptParameterDef, TemplateParamValue,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+// ============================================================================
+// RESULT TYPES
+// ============================================================================
+
+/// Prompt summary for list_prompts action
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptSummary {
+    /// Prompt filename (without extension)
+    pub name: String,
+    /// Human-readable title
+    pub title: String,
+    /// Description of what the prompt does
+    pub description: String,
+    /// Categories this prompt belongs to
+    pub categories: Vec<String>,
+    /// Author of the prompt
+    pub author: String,
+    /// Whether this prompt is verified
+    pub verified: bool,
+    /// Parameter definitions
+    pub parameters: Vec<PromptParameterDef>,
+    /// Semantic version (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Free-form tags
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Whether this prompt is deprecated
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+    /// Replacement prompt name (if deprecated)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaced_by: Option<String>,
+    /// Alternative names that resolve to this prompt
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Unrecognized frontmatter keys, preserved verbatim
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, serde_json::Value>,
+    /// Frontmatter votes plus votes cast with `prompt_vote`
+    #[serde(default)]
+    pub votes: u32,
+    /// Render counts and last use (absent for prompts never rendered or voted on)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<PromptUsage>,
+}
+
+/// Order of the list_prompts result
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptSort {
+    /// Alphabetical by name
+    #[default]
+    Name,
+    /// Most successful renders first (ties: most votes)
+    Popular,
+    /// Most votes first (ties: most renders)
+    Votes,
+    /// Most recently rendered first; never-rendered prompts last
+    Recent,
+}
+
+/// Result from list_prompts action
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptListResult {
+    /// List of prompt summaries
+    pub prompts: Vec<PromptSummary>,
+    /// Number of prompts returned
+    pub count: usize,
+    /// Namespace filter applied (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Category filter applied (if any)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Tag filter applied (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Author filter applied (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Full-text query applied (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// Extra frontmatter filter applied (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra: Option<BTreeMap<String, serde_json::Value>>,
+    /// Order applied (if not by name)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<PromptSort>,
+    /// Elapsed time in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<f64>,
+}
+
+/// Full prompt metadata
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptMetadataOutput {
+    /// Human-readable title
+    pub title: String,
+    /// Description of what the prompt does
+    pub description: String,
+    /// Categories this prompt belongs to
+    pub categories: Vec<String>,
+    /// Secondary tag (if any)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secondary_tag: Option<String>,
+    /// Author of the prompt
+    pub author: String,
+    /// Whether this prompt is verified
+    pub verified: bool,
+    /// Vote count
+    pub votes: u32,
+    /// Parameter definitions
+    pub parameters: Vec<PromptParameterDef>,
+    /// Semantic version (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Free-form tags
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Preferred model hint
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Sampling temperature hint
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    /// Maximum output tokens hint
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Whether this prompt is deprecated
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+    /// Replacement prompt name (if deprecated)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaced_by: Option<String>,
+    /// Alternative names that resolve to this prompt
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Prompt this one was copied from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_from: Option<String>,
+    /// Unrecognized frontmatter keys, preserved verbatim
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// Result from get action (raw template)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptContentResult {
+    /// Prompt name
+    pub name: String,
+    /// Prompt metadata
+    pub metadata: PromptMetadataOutput,
+    /// Raw template content (Jinja2)
+    pub content: String,
+    /// Render counts and last use (absent for prompts never rendered or voted on)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<PromptUsage>,
+    /// Always false for get action
+    pub rendered: bool,
+    /// Elapsed time in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<f64>,
+}
+
+/// A workspace file attached to a message via `attach()` or `image()`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptAttachmentOutput {
+    /// Attachment kind (resource, image)
+    pub kind: AttachmentKind,
+    /// Path as written in the template
+    pub path: String,
+    /// `file://` URI of the resolved file
+    pub uri: String,
+    /// MIME type of the content
+    pub mime_type: String,
+    /// Text content (text resources)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Base64-encoded content (images and binary resources)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+/// A single role-tagged message
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptMessageOutput {
+    /// Message role (system, user, assistant)
+    pub role: MessageRole,
+    /// Rendered message text
+    pub content: String,
+    /// Embedded resources and images attached to this message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<PromptAttachmentOutput>,
+}
+
+/// Result from render action
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptRenderedResult {
+    /// Prompt name
+    pub name: String,
+    /// Rendered content (parameters applied)
+    pub content: String,
+    /// Estimated tokens, checked against the prompt's max_tokens budget
+    pub tokens: TokenEstimate,
+    /// Always true for render action
+    pub rendered: bool,
+    /// Elapsed time in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<f64>,
+}
+
+/// Result from render_messages action
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptMessagesResult {
+    /// Prompt name
+    pub name: String,
+    /// Rendered messages in template order
+    pub messages: Vec<PromptMessageOutput>,
+    /// Estimated tokens in the message text, checked against max_tokens
+    pub tokens: TokenEstimate,
+    /// Always true for render_messages action
+    pub rendered: bool,
+    /// Elapsed time in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<f64>,
+}
+
+/// Result from preview action
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptPreviewResult {
+    /// Prompt name
+    pub name: String,
+    /// Rendered content (same as the render action)
+    pub content: String,
+    /// Parameters supplied by the caller
+    pub provided: Vec<String>,
+    /// Parameters filled in from their declared defaults
+    pub defaulted: Vec<String>,
+    /// Environment variables the template reads (`env.NAME`) that are exposed
+    pub env_vars: Vec<String>,
+    /// Variables the template reads that are not defined (render as empty),
+    /// including environment variables that are not exposed (`env.NAME`)
+    pub undefined: Vec<String>,
+    /// Time spent rendering in milliseconds
+    pub render_ms: f64,
+    /// Estimated tokens, checked against the prompt's max_tokens budget
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<TokenEstimate>,
+    /// Always true for preview action
+    pub rendered: bool,
+    /// Elapsed time in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<f64>,
+}
+
+/// Typed result enum for prompt_get output
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PromptResult {
+    /// Result from list_categories action
+    ListCategories(PromptCategoriesResult),
+    /// Result from list_prompts action
+    ListPrompts(PromptListResult),
+    /// Result from get action
+    Get(Box<PromptContentResult>),
+    /// Result from render action
+    Render(PromptRenderedResult),
+    /// Result from render_messages action
+    RenderMessages(PromptMessagesResult),
+    /// Result from preview action
+    Preview(PromptPreviewResult),
+}
+
+// ============================================================================
+// PROMPT GET TOOL
+// ============================================================================
+
+/// Action enum for get_prompt tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GetPromptAction {
+    /// List all categories with counts
+    ListCategories,
+    /// List all prompts (optionally filtered by category)
+    ListPrompts,
+    /// Get prompt metadata and raw template
+    Get,
+    /// Render prompt with parameters
+    Render,
+    /// Render prompt into role-tagged messages (system/user/assistant)
+    RenderMessages,
+    /// Render prompt and report parameter provenance, env vars read, and
+    /// undefined variables
+    Preview,
+}
+
+/// Arguments for `prompt_get` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GetPromptArgs {
+    /// Action to perform
+    pub action: GetPromptAction,
+
+    /// Prompt name (for get/render/render_messages/preview actions)
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Namespace filter (for `list_prompts` action): `review` lists
+    /// `review/security`, `review/web/xss`, and so on
+    #[serde(default)]
+    pub namespace: Option<String>,
+
+    /// Category filter (for `list_prompts` action)
+    #[serde(default)]
+    pub category: Option<String>,
+
+    /// Tag filter (for `list_prompts` action)
+    #[serde(default)]
+    pub tag: Option<String>,
+
+    /// Author filter, case-insensitive (for `list_prompts` action)
+    #[serde(default)]
+    pub author: Option<String>,
+
+    /// Full-text search (for `list_prompts` action): every word must appear
+    /// in the name, title, description, tags, or template body
+    #[serde(default)]
+    pub query: Option<String>,
+
+    /// Extra frontmatter filter (for `list_prompts` action). Every key must
+    /// match: scalars compare by value, and array values match if they contain
+    /// the filter value.
+    #[serde(default)]
+    pub extra: Option<BTreeMap<String, serde_json::Value>>,
+
+    /// Result order (for `list_prompts` action): name (default), popular
+    /// (render count), votes, or recent (last rendered)
+    #[serde(default)]
+    pub sort: Option<PromptSort>,
+
+    /// Parameters for rendering (for render/render_messages/preview actions)
+    #[serde(default)]
+    pub parameters: Option<HashMap<String, TemplateParamValue>>,
+}
+
+// ============================================================================
+// OUTPUT TYPES
+// ============================================================================
+
+/// Output from `prompt_get` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptGetOutput {
+    /// Whether the operation succeeded
+    pub success: bool,
+    /// Action that was performed
+    pub action: GetPromptAction,
+    /// Typed result based on action performed
+    pub result: PromptResult,
+}
+
+// ============================================================================
+// TOOL ARGS TRAIT IMPLEMENTATION
+// ============================================================================
+
+impl ToolArgs for GetPromptArgs {
+    type Output = PromptGetOutput;
+    type Prompts = PromptGetPrompts;
+
+    const NAME: &'static str = PROMPT_GET;
+    const CATEGORY: &'static kodegen_config::Category = CATEGORY_PROMPT;
+    const DESCRIPTION: &'static str = "Retrieve prompt template by name, render with variables or into role-tagged messages, preview a render with parameter provenance, or list available templates by namespace, category, tag, author, or full-text query";
+}
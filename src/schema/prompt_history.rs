@@ -0,0 +1,154 @@
+//! Schema types for prompt_history tool
+
+use crate::git_store::HistoryEntry;
+use kodegen_config::CATEGORY_PROMPT;
+use kodegen_mcp_schema::ToolArgs;
+use kodegen_mcp_schema::tool::{PromptProvider, SealedPromptProvider};
+use rmcp::model::{PromptArgument, PromptMessage, PromptMessageContent, PromptMessageRole};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Tool name for prompt_history
+pub const PROMPT_HISTORY: &str = "prompt_history";
+
+// ============================================================================
+// PROMPT HISTORY TOOL
+// ============================================================================
+
+/// Action enum for prompt_history tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryAction {
+    /// List commits (or database revisions) that changed the prompt, newest first
+    Log,
+    /// Unified diff between two revisions (`to` defaults to the current file)
+    Diff,
+    /// Restore the prompt to its content at `revision`
+    Restore,
+}
+
+/// Arguments for `prompt_history` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HistoryPromptArgs {
+    /// Action to perform
+    pub action: HistoryAction,
+
+    /// Prompt name (deleted prompts are found too)
+    pub name: String,
+
+    /// Older revision (for diff action): commit hash or git revision like `HEAD~1`,
+    /// or a revision number with a prompt database
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+
+    /// Newer revision (for diff action); defaults to the current file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+
+    /// Revision to restore (for restore action)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+
+    /// Maximum commits or revisions to list (for log action, default 20)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+// ============================================================================
+// OUTPUT TYPES
+// ============================================================================
+
+/// Output from `prompt_history` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptHistoryOutput {
+    /// Whether the operation succeeded
+    pub success: bool,
+    /// Action performed
+    pub action: HistoryAction,
+    /// Prompt name
+    pub name: String,
+    /// Human-readable message
+    pub message: String,
+    /// Commits or revisions that changed the prompt (log action)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entries: Vec<HistoryEntry>,
+    /// Unified diff (diff action; empty when unchanged)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+    /// Revision the prompt was restored to (restore action)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restored_from: Option<String>,
+}
+
+// ============================================================================
+// PROMPTS
+// ============================================================================
+
+/// Prompt arguments for `prompt_history` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HistoryPromptPromptArgs {
+    /// Scenario to show examples for
+    /// - "basic": Reviewing and rolling back a change
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<String>,
+}
+
+/// Prompt provider for `prompt_history` tool
+pub struct PromptHistoryPrompts;
+
+impl SealedPromptProvider for PromptHistoryPrompts {}
+
+impl PromptProvider for PromptHistoryPrompts {
+    type PromptArgs = HistoryPromptPromptArgs;
+
+    fn generate_prompts(_args: &Self::PromptArgs) -> Vec<PromptMessage> {
+        prompt_basic()
+    }
+
+    fn prompt_arguments() -> Vec<PromptArgument> {
+        vec![PromptArgument {
+            name: "scenario".to_string(),
+            title: None,
+            description: Some("Scenario to show (basic)".to_string()),
+            required: Some(false),
+        }]
+    }
+}
+
+/// Reviewing and rolling back a change
+fn prompt_basic() -> Vec<PromptMessage> {
+    vec![
+        PromptMessage {
+            role: PromptMessageRole::User,
+            content: PromptMessageContent::text(
+                "Someone changed the code-review prompt and it got worse. How do I roll it back?",
+            ),
+        },
+        PromptMessage {
+            role: PromptMessageRole::Assistant,
+            content: PromptMessageContent::text(
+                "With git storage enabled (KODEGEN_PROMPTS_GIT=1) every change is a commit:\n\n\
+                 prompt_history({ \"action\": \"log\", \"name\": \"code-review\" })\n\
+                 prompt_history({ \"action\": \"diff\", \"name\": \"code-review\", \"from\": \"<commit>\" })\n\
+                 prompt_history({ \"action\": \"restore\", \"name\": \"code-review\", \"revision\": \"<commit>\" })\n\n\
+                 log shows who changed the prompt and when. diff compares a revision with the \
+                 current file (or with `to`). restore writes the old content back as a new \
+                 commit, so the restore can be undone the same way. Deleted prompts can be \
+                 restored from any commit before the deletion.",
+            ),
+        },
+    ]
+}
+
+// ============================================================================
+// TOOL ARGS TRAIT IMPLEMENTATION
+// ============================================================================
+
+impl ToolArgs for HistoryPromptArgs {
+    type Output = PromptHistoryOutput;
+    type Prompts = PromptHistoryPrompts;
+
+    const NAME: &'static str = PROMPT_HISTORY;
+    const CATEGORY: &'static kodegen_config::Category = CATEGORY_PROMPT;
+    const DESCRIPTION: &'static str = "Show git history and diffs of a prompt template, or restore an earlier revision";
+}
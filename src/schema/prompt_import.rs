@@ -0,0 +1,138 @@
+//! Schema types for prompt_import tool
+
+use crate::bundle::{BundleFormat, ConflictPolicy, ImportOutcome};
+use crate::diagnostics::Diagnostic;
+use kodegen_config::CATEGORY_PROMPT;
+use kodegen_mcp_schema::ToolArgs;
+use kodegen_mcp_schema::tool::{PromptProvider, SealedPromptProvider};
+use rmcp::model::{PromptArgument, PromptMessage, PromptMessageContent, PromptMessageRole};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Tool name for prompt_import
+pub const PROMPT_IMPORT: &str = "prompt_import";
+
+// ============================================================================
+// PROMPT IMPORT TOOL
+// ============================================================================
+
+/// Arguments for `prompt_import` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ImportPromptsArgs {
+    /// Encoded bundle as returned by prompt_export (`data`)
+    pub data: String,
+
+    /// Bundle format (detected from the data if omitted)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<BundleFormat>,
+
+    /// What to do when a prompt name already exists: "skip" (default),
+    /// "overwrite", or "rename" (import as `<name>_2`, `<name>_3`, ...)
+    #[serde(default)]
+    pub on_conflict: ConflictPolicy,
+
+    /// Report what would happen without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+// ============================================================================
+// OUTPUT TYPES
+// ============================================================================
+
+/// Output from `prompt_import` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptImportOutput {
+    /// Whether the bundle was accepted
+    pub success: bool,
+    /// True when nothing was written
+    pub dry_run: bool,
+    /// Human-readable message
+    pub message: String,
+    /// Prompts created
+    pub created: usize,
+    /// Existing prompts replaced
+    pub overwritten: usize,
+    /// Prompts imported under a new name
+    pub renamed: usize,
+    /// Bundle entries skipped because the name exists
+    pub skipped: usize,
+    /// Outcome for every bundle entry
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outcomes: Vec<ImportOutcome>,
+    /// Validation errors when the bundle was rejected (nothing is written)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+// ============================================================================
+// PROMPTS
+// ============================================================================
+
+/// Prompt arguments for `prompt_import` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ImportPromptsPromptArgs {
+    /// Scenario to show examples for
+    /// - "basic": Importing a bundle safely
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<String>,
+}
+
+/// Prompt provider for `prompt_import` tool
+pub struct PromptImportPrompts;
+
+impl SealedPromptProvider for PromptImportPrompts {}
+
+impl PromptProvider for PromptImportPrompts {
+    type PromptArgs = ImportPromptsPromptArgs;
+
+    fn generate_prompts(_args: &Self::PromptArgs) -> Vec<PromptMessage> {
+        prompt_basic()
+    }
+
+    fn prompt_arguments() -> Vec<PromptArgument> {
+        vec![PromptArgument {
+            name: "scenario".to_string(),
+            title: None,
+            description: Some("Scenario to show (basic)".to_string()),
+            required: Some(false),
+        }]
+    }
+}
+
+/// Importing a bundle safely
+fn prompt_basic() -> Vec<PromptMessage> {
+    vec![
+        PromptMessage {
+            role: PromptMessageRole::User,
+            content: PromptMessageContent::text("How do I import a prompt bundle?"),
+        },
+        PromptMessage {
+            role: PromptMessageRole::Assistant,
+            content: PromptMessageContent::text(
+                "Preview first, then import:\n\n\
+                 prompt_import({ \"data\": \"<bundle>\", \"dry_run\": true })\n\
+                 prompt_import({ \"data\": \"<bundle>\", \"on_conflict\": \"rename\" })\n\n\
+                 Every entry is validated (names, manifest checksums, frontmatter, template \
+                 syntax) before anything is written. If any entry is invalid the whole \
+                 bundle is rejected with success=false and a diagnostics list. on_conflict \
+                 decides what happens to names that already exist: skip (default) keeps \
+                 yours, overwrite replaces it, rename imports as <name>_2. The outcomes list \
+                 shows what happened to every entry.",
+            ),
+        },
+    ]
+}
+
+// ============================================================================
+// TOOL ARGS TRAIT IMPLEMENTATION
+// ============================================================================
+
+impl ToolArgs for ImportPromptsArgs {
+    type Output = PromptImportOutput;
+    type Prompts = PromptImportPrompts;
+
+    const NAME: &'static str = PROMPT_IMPORT;
+    const CATEGORY: &'static kodegen_config::Category = CATEGORY_PROMPT;
+    const DESCRIPTION: &'static str = "Import a prompt bundle with conflict policy (skip, overwrite, rename), validating every entry first";
+}
@@ -0,0 +1,121 @@
+//! Schema types for prompt_rename tool
+
+use kodegen_config::CATEGORY_PROMPT;
+use kodegen_mcp_schema::ToolArgs;
+use kodegen_mcp_schema::tool::{PromptProvider, SealedPromptProvider};
+use rmcp::model::{PromptArgument, PromptMessage, PromptMessageContent, PromptMessageRole};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Tool name for prompt_rename
+pub const PROMPT_RENAME: &str = "prompt_rename";
+
+// ============================================================================
+// PROMPT RENAME TOOL
+// ============================================================================
+
+/// Arguments for `prompt_rename` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RenamePromptArgs {
+    /// Current prompt name (without .j2.md extension)
+    pub name: String,
+
+    /// New prompt name (without .j2.md extension)
+    /// Must contain only alphanumeric characters, hyphens, and underscores
+    pub new_name: String,
+}
+
+// ============================================================================
+// OUTPUT TYPES
+// ============================================================================
+
+/// Output from `prompt_rename` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptRenameOutput {
+    /// Whether the operation succeeded
+    pub success: bool,
+    /// Previous prompt name (now an alias)
+    pub old_name: String,
+    /// New prompt name
+    pub new_name: String,
+    /// Human-readable message
+    pub message: String,
+    /// Path to the renamed file (absent for stores without a directory)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Aliases of the renamed prompt (includes the old name)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+// ============================================================================
+// PROMPTS
+// ============================================================================
+
+/// Prompt arguments for `prompt_rename` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RenamePromptPromptArgs {
+    /// Scenario to show examples for
+    /// - "basic": Renaming a prompt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<String>,
+}
+
+/// Prompt provider for `prompt_rename` tool
+pub struct PromptRenamePrompts;
+
+impl SealedPromptProvider for PromptRenamePrompts {}
+
+impl PromptProvider for PromptRenamePrompts {
+    type PromptArgs = RenamePromptPromptArgs;
+
+    fn generate_prompts(_args: &Self::PromptArgs) -> Vec<PromptMessage> {
+        prompt_basic()
+    }
+
+    fn prompt_arguments() -> Vec<PromptArgument> {
+        vec![PromptArgument {
+            name: "scenario".to_string(),
+            title: None,
+            description: Some("Scenario to show (basic)".to_string()),
+            required: Some(false),
+        }]
+    }
+}
+
+/// Renaming a prompt
+fn prompt_basic() -> Vec<PromptMessage> {
+    vec![
+        PromptMessage {
+            role: PromptMessageRole::User,
+            content: PromptMessageContent::text("How do I rename a prompt template?"),
+        },
+        PromptMessage {
+            role: PromptMessageRole::Assistant,
+            content: PromptMessageContent::text(
+                "Use prompt_rename instead of get + add + delete:\n\n\
+                 prompt_rename({\n\
+                     \"name\": \"code-review\",\n\
+                     \"new_name\": \"rust-code-review\"\n\
+                 })\n\n\
+                 The rename is atomic and never overwrites an existing prompt. The old name \
+                 is added to the prompt's aliases, so prompt_get and prompts/get calls that \
+                 still use \"code-review\" keep working. Metadata, votes and custom \
+                 frontmatter keys are kept.",
+            ),
+        },
+    ]
+}
+
+// ============================================================================
+// TOOL ARGS TRAIT IMPLEMENTATION
+// ============================================================================
+
+impl ToolArgs for RenamePromptArgs {
+    type Output = PromptRenameOutput;
+    type Prompts = PromptRenamePrompts;
+
+    const NAME: &'static str = PROMPT_RENAME;
+    const CATEGORY: &'static kodegen_config::Category = CATEGORY_PROMPT;
+    const DESCRIPTION: &'static str = "Atomically rename prompt template, keeping the old name as an alias";
+}
@@ -0,0 +1,124 @@
+//! Schema types for prompt_stats tool
+
+use crate::metrics::MetricsSnapshot;
+use kodegen_config::CATEGORY_PROMPT;
+use kodegen_mcp_schema::ToolArgs;
+use kodegen_mcp_schema::tool::{PromptProvider, SealedPromptProvider};
+use rmcp::model::{PromptArgument, PromptMessage, PromptMessageContent, PromptMessageRole};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Tool name for prompt_stats
+pub const PROMPT_STATS: &str = "prompt_stats";
+
+// ============================================================================
+// PROMPT STATS TOOL
+// ============================================================================
+
+/// Output format for `prompt_stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsFormat {
+    /// Structured metrics only
+    #[default]
+    Json,
+    /// Structured metrics plus the Prometheus text exposition format
+    Prometheus,
+}
+
+/// Arguments for `prompt_stats` tool
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct StatsPromptArgs {
+    /// Output format (default json)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<StatsFormat>,
+}
+
+// ============================================================================
+// OUTPUT TYPES
+// ============================================================================
+
+/// Output from `prompt_stats` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptStatsOutput {
+    /// Whether the metrics were collected
+    pub success: bool,
+    /// Metrics since the server started
+    pub metrics: MetricsSnapshot,
+    /// Same metrics in the Prometheus text format (prometheus format only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prometheus: Option<String>,
+}
+
+// ============================================================================
+// PROMPTS
+// ============================================================================
+
+/// Prompt arguments for `prompt_stats` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct StatsPromptPromptArgs {
+    /// Scenario to show examples for
+    /// - "basic": Checking server health
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<String>,
+}
+
+/// Prompt provider for `prompt_stats` tool
+pub struct PromptStatsPrompts;
+
+impl SealedPromptProvider for PromptStatsPrompts {}
+
+impl PromptProvider for PromptStatsPrompts {
+    type PromptArgs = StatsPromptPromptArgs;
+
+    fn generate_prompts(_args: &Self::PromptArgs) -> Vec<PromptMessage> {
+        prompt_basic()
+    }
+
+    fn prompt_arguments() -> Vec<PromptArgument> {
+        vec![PromptArgument {
+            name: "scenario".to_string(),
+            title: None,
+            description: Some("Scenario to show (basic)".to_string()),
+            required: Some(false),
+        }]
+    }
+}
+
+/// Checking server health
+fn prompt_basic() -> Vec<PromptMessage> {
+    vec![
+        PromptMessage {
+            role: PromptMessageRole::User,
+            content: PromptMessageContent::text(
+                "Renders feel slow on the shared prompt server. How can I check what's going on?",
+            ),
+        },
+        PromptMessage {
+            role: PromptMessageRole::Assistant,
+            content: PromptMessageContent::text(
+                "prompt_stats({})\n\n\
+                 render shows the latency histogram and how many renders hit the 5 second \
+                 timeout. cache shows how often load_prompt found a parsed template (a low \
+                 hit rate means prompts are being edited or the cache is cold). tool_calls \
+                 counts calls per tool and action with their total time, and \
+                 validation_failures counts rejected content by rule (syntax, include, ...).\n\n\
+                 prompt_stats({ \"format\": \"prometheus\" }) adds the same numbers in the \
+                 Prometheus text format for a scraper.",
+            ),
+        },
+    ]
+}
+
+// ============================================================================
+// TOOL ARGS TRAIT IMPLEMENTATION
+// ============================================================================
+
+impl ToolArgs for StatsPromptArgs {
+    type Output = PromptStatsOutput;
+    type Prompts = PromptStatsPrompts;
+
+    const NAME: &'static str = PROMPT_STATS;
+    const CATEGORY: &'static kodegen_config::Category = CATEGORY_PROMPT;
+    const DESCRIPTION: &'static str = "Server metrics: tool calls, render latency and timeouts, validation failures, cache hit rate, library size";
+}
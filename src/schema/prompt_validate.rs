@@ -0,0 +1,171 @@
+//! Schema types for prompt_validate tool
+
+use crate::diagnostics::Diagnostic;
+use kodegen_config::CATEGORY_PROMPT;
+use kodegen_mcp_schema::prompt::TemplateParamValue;
+use kodegen_mcp_schema::ToolArgs;
+use kodegen_mcp_schema::tool::{PromptProvider, SealedPromptProvider};
+use rmcp::model::{PromptArgument, PromptMessage, PromptMessageContent, PromptMessageRole};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Tool name for prompt_validate
+pub const PROMPT_VALIDATE: &str = "prompt_validate";
+
+// ============================================================================
+// PROMPT VALIDATE TOOL
+// ============================================================================
+
+/// Arguments for `prompt_validate` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ValidatePromptArgs {
+    /// Full prompt content including YAML frontmatter
+    pub content: String,
+
+    /// Prompt name the content is intended for (optional)
+    /// Checks the name is valid and reports whether it is already taken
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Sample parameters for a trial render (optional)
+    /// Supplying parameters implies `render: true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<HashMap<String, TemplateParamValue>>,
+
+    /// Trial-render the template (with `parameters` and defaults)
+    #[serde(default)]
+    pub render: bool,
+}
+
+// ============================================================================
+// OUTPUT TYPES
+// ============================================================================
+
+/// Output from `prompt_validate` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptValidateOutput {
+    /// Whether the content would be accepted by `prompt_add` / `prompt_edit`
+    pub valid: bool,
+    /// Prompt name checked (if provided)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Human-readable message
+    pub message: String,
+    /// Number of error diagnostics
+    pub error_count: usize,
+    /// Number of warning diagnostics
+    pub warning_count: usize,
+    /// Errors and lint warnings (line, column, key path, expected/actual, suggestion)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<Diagnostic>,
+    /// Trial render output (when requested and successful)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rendered: Option<String>,
+}
+
+// ============================================================================
+// PROMPTS
+// ============================================================================
+
+/// Prompt arguments for `prompt_validate` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ValidatePromptPromptArgs {
+    /// Scenario to show examples for
+    /// - "basic": Checking content before saving
+    /// - "render": Trial rendering with sample parameters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<String>,
+}
+
+/// Prompt provider for `prompt_validate` tool
+pub struct PromptValidatePrompts;
+
+impl SealedPromptProvider for PromptValidatePrompts {}
+
+impl PromptProvider for PromptValidatePrompts {
+    type PromptArgs = ValidatePromptPromptArgs;
+
+    fn generate_prompts(args: &Self::PromptArgs) -> Vec<PromptMessage> {
+        match args.scenario.as_deref() {
+            Some("render") => prompt_render(),
+            _ => prompt_basic(),
+        }
+    }
+
+    fn prompt_arguments() -> Vec<PromptArgument> {
+        vec![PromptArgument {
+            name: "scenario".to_string(),
+            title: None,
+            description: Some("Scenario to show (basic, render)".to_string()),
+            required: Some(false),
+        }]
+    }
+}
+
+/// Checking content before saving
+fn prompt_basic() -> Vec<PromptMessage> {
+    vec![
+        PromptMessage {
+            role: PromptMessageRole::User,
+            content: PromptMessageContent::text(
+                "How do I check a prompt template before saving it?",
+            ),
+        },
+        PromptMessage {
+            role: PromptMessageRole::Assistant,
+            content: PromptMessageContent::text(
+                "Call prompt_validate with the full file content. Nothing is written.\n\n\
+                 prompt_validate({\n\
+                     \"name\": \"code-review\",\n\
+                     \"content\": \"---\\ntitle: Code Review\\n...\\n---\\n\\nReview {{ code }}\"\n\
+                 })\n\n\
+                 The response lists every diagnostic with line, column, key path and a \
+                 suggested fix. Errors (severity omitted) make valid=false and would make \
+                 prompt_add reject the content. Warnings (severity: \"warning\") are lint \
+                 findings such as undeclared variables or unused parameters; the content is \
+                 still valid. Fix the diagnostics and validate again until valid=true with no \
+                 warnings, then save with prompt_add or prompt_edit.",
+            ),
+        },
+    ]
+}
+
+/// Trial rendering with sample parameters
+fn prompt_render() -> Vec<PromptMessage> {
+    vec![
+        PromptMessage {
+            role: PromptMessageRole::User,
+            content: PromptMessageContent::text(
+                "How do I preview what a template renders to before saving it?",
+            ),
+        },
+        PromptMessage {
+            role: PromptMessageRole::Assistant,
+            content: PromptMessageContent::text(
+                "Pass sample parameters to prompt_validate:\n\n\
+                 prompt_validate({\n\
+                     \"content\": \"---\\n...\\n---\\n\\nReview this {{ language }} code\",\n\
+                     \"parameters\": { \"language\": \"rust\" }\n\
+                 })\n\n\
+                 The rendered text is returned in `rendered`. Use \"render\": true to render \
+                 with defaults only. Render failures (missing required parameters, type \
+                 mismatches, runtime template errors) are reported as error diagnostics. \
+                 Attachments render as [attach: path] placeholders; no files are read.",
+            ),
+        },
+    ]
+}
+
+// ============================================================================
+// TOOL ARGS TRAIT IMPLEMENTATION
+// ============================================================================
+
+impl ToolArgs for ValidatePromptArgs {
+    type Output = PromptValidateOutput;
+    type Prompts = PromptValidatePrompts;
+
+    const NAME: &'static str = PROMPT_VALIDATE;
+    const CATEGORY: &'static kodegen_config::Category = CATEGORY_PROMPT;
+    const DESCRIPTION: &'static str = "Dry-run validation and lint of prompt template content with optional trial render";
+}
@@ -0,0 +1,128 @@
+//! Schema types for prompt_vote tool
+
+use crate::usage::PromptUsage;
+use kodegen_config::CATEGORY_PROMPT;
+use kodegen_mcp_schema::ToolArgs;
+use kodegen_mcp_schema::tool::{PromptProvider, SealedPromptProvider};
+use rmcp::model::{PromptArgument, PromptMessage, PromptMessageContent, PromptMessageRole};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Tool name for prompt_vote
+pub const PROMPT_VOTE: &str = "prompt_vote";
+
+// ============================================================================
+// PROMPT VOTE TOOL
+// ============================================================================
+
+/// Vote direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VoteDirection {
+    /// The prompt was useful
+    Up,
+    /// The prompt was not useful
+    Down,
+}
+
+/// Arguments for `prompt_vote` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct VotePromptArgs {
+    /// Prompt name (aliases resolve to the prompt)
+    pub name: String,
+
+    /// Vote for (`up`) or against (`down`) the prompt
+    pub direction: VoteDirection,
+}
+
+// ============================================================================
+// OUTPUT TYPES
+// ============================================================================
+
+/// Output from `prompt_vote` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptVoteOutput {
+    /// Whether the vote was recorded
+    pub success: bool,
+    /// Canonical prompt name
+    pub name: String,
+    /// Vote cast
+    pub direction: VoteDirection,
+    /// Votes after this one (frontmatter votes plus votes cast)
+    pub votes: u32,
+    /// Render counts, last use and net votes cast
+    pub usage: PromptUsage,
+    /// Human-readable message
+    pub message: String,
+}
+
+// ============================================================================
+// PROMPTS
+// ============================================================================
+
+/// Prompt arguments for `prompt_vote` tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct VotePromptPromptArgs {
+    /// Scenario to show examples for
+    /// - "basic": Voting after using a prompt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<String>,
+}
+
+/// Prompt provider for `prompt_vote` tool
+pub struct PromptVotePrompts;
+
+impl SealedPromptProvider for PromptVotePrompts {}
+
+impl PromptProvider for PromptVotePrompts {
+    type PromptArgs = VotePromptPromptArgs;
+
+    fn generate_prompts(_args: &Self::PromptArgs) -> Vec<PromptMessage> {
+        prompt_basic()
+    }
+
+    fn prompt_arguments() -> Vec<PromptArgument> {
+        vec![PromptArgument {
+            name: "scenario".to_string(),
+            title: None,
+            description: Some("Scenario to show (basic)".to_string()),
+            required: Some(false),
+        }]
+    }
+}
+
+/// Voting after using a prompt
+fn prompt_basic() -> Vec<PromptMessage> {
+    vec![
+        PromptMessage {
+            role: PromptMessageRole::User,
+            content: PromptMessageContent::text(
+                "That code-review prompt worked really well. How do I help others find it?",
+            ),
+        },
+        PromptMessage {
+            role: PromptMessageRole::Assistant,
+            content: PromptMessageContent::text(
+                "prompt_vote({ \"name\": \"code-review\", \"direction\": \"up\" })\n\n\
+                 Votes are added to the prompt's frontmatter `votes` without editing the file. \
+                 List the most useful prompts first with:\n\n\
+                 prompt_get({ \"action\": \"list_prompts\", \"sort\": \"votes\" })\n\n\
+                 `popular` sorts by how often prompts are rendered, `recent` by when they were \
+                 last rendered.",
+            ),
+        },
+    ]
+}
+
+// ============================================================================
+// TOOL ARGS TRAIT IMPLEMENTATION
+// ============================================================================
+
+impl ToolArgs for VotePromptArgs {
+    type Output = PromptVoteOutput;
+    type Prompts = PromptVotePrompts;
+
+    const NAME: &'static str = PROMPT_VOTE;
+    const CATEGORY: &'static kodegen_config::Category = CATEGORY_PROMPT;
+    const DESCRIPTION: &'static str = "Vote a prompt template up or down";
+}
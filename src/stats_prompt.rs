@@ -0,0 +1,89 @@
+use super::manager::PromptManager;
+use super::metrics::metrics;
+use super::schema::{PromptStatsOutput, PromptStatsPrompts, StatsFormat, StatsPromptArgs, PROMPT_STATS};
+use kodegen_mcp_schema::{McpError, Tool, ToolExecutionContext, ToolResponse};
+
+#[derive(Clone)]
+pub struct StatsPromptTool {
+    manager: PromptManager,
+}
+
+impl StatsPromptTool {
+    /// Create with a pre-initialized PromptManager (for HTTP server)
+    pub fn with_manager(manager: PromptManager) -> Self {
+        Self { manager }
+    }
+
+    /// Create with default manager (for standalone use)
+    pub async fn new() -> Result<Self, McpError> {
+        let manager = PromptManager::new();
+        manager.init().await?;
+        Ok(Self { manager })
+    }
+}
+
+impl Tool for StatsPromptTool {
+    type Args = StatsPromptArgs;
+    type Prompts = PromptStatsPrompts;
+
+    fn name() -> &'static str {
+        PROMPT_STATS
+    }
+
+    fn description() -> &'static str {
+        "Metrics for this prompt server since it started: tool calls by tool, action and \
+         outcome; render latency histogram and render timeouts; validation failures by rule \
+         (size, frontmatter, syntax, include, extends, import); template cache hits and \
+         misses; library size. format=prometheus also returns the Prometheus text \
+         exposition format."
+    }
+
+    fn read_only() -> bool {
+        true
+    }
+
+    fn destructive() -> bool {
+        false
+    }
+
+    fn idempotent() -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Self::Args, _ctx: ToolExecutionContext) -> Result<ToolResponse<<Self::Args as kodegen_mcp_schema::ToolArgs>::Output>, McpError> {
+        let library = self
+            .manager
+            .library_stats()
+            .await
+            .map_err(McpError::Other)?;
+        let snapshot = metrics().snapshot(library);
+
+        let calls: u64 = snapshot.tool_calls.iter().map(|c| c.count).sum();
+        let failures: u64 = snapshot.validation_failures.values().sum();
+        let mean_ms = if snapshot.render.count == 0 {
+            0.0
+        } else {
+            snapshot.render.sum_seconds * 1000.0 / snapshot.render.count as f64
+        };
+        let summary = format!(
+            "\x1b[36m Prompt Stats\x1b[0m\n\
+              Tool calls: {calls} · Renders: {} (mean {mean_ms:.1}ms, {} timed out)\n\
+              Cache hit rate: {:.0}% · Validation failures: {failures}\n\
+              Library: {} prompts · {} bytes",
+            snapshot.render.count,
+            snapshot.render.timeouts,
+            snapshot.cache.hit_rate * 100.0,
+            snapshot.library.prompts,
+            snapshot.library.bytes
+        );
+
+        let prometheus = (args.format.unwrap_or_default() == StatsFormat::Prometheus)
+            .then(|| snapshot.to_prometheus());
+        let output = PromptStatsOutput {
+            success: true,
+            metrics: snapshot,
+            prometheus,
+        };
+        Ok(ToolResponse::new(summary, output))
+    }
+}
@@ -0,0 +1,160 @@
+//! MCP over stdin/stdout
+//!
+//! For clients that launch servers as child processes instead of talking to
+//! kodegend over HTTP; started by `PromptServerConfig::stdio`, which
+//! registers the same tools and prompts as the HTTP server. stdout carries
+//! the protocol, so nothing else may be printed there; logging goes to stderr.
+
+use crate::prompt_routes::TemplatePrompts;
+use anyhow::{Context, Result};
+use kodegen_mcp_schema::Tool;
+use rmcp::handler::server::router::{prompt::PromptRouter, tool::ToolRouter};
+use rmcp::model::{
+    CallToolRequestParam, CallToolResult, GetPromptRequestParam, GetPromptResult,
+    Implementation, ListPromptsResult, ListToolsResult, PaginatedRequestParam, ProtocolVersion,
+    ServerCapabilities, ServerInfo,
+};
+use rmcp::service::{RequestContext, RoleServer};
+use rmcp::{ErrorData as McpError, ServerHandler, ServiceExt};
+
+/// MCP server handler for the stdio transport
+pub struct StdioServer {
+    tool_router: ToolRouter<Self>,
+    prompt_router: PromptRouter<Self>,
+    /// Templates resolved per request, beside the router's own prompts
+    templates: Option<TemplatePrompts>,
+}
+
+impl StdioServer {
+    /// Handler serving these routers (see [`PromptServerConfig::register_tools`])
+    ///
+    /// [`PromptServerConfig::register_tools`]: crate::PromptServerConfig::register_tools
+    pub fn new(tool_router: ToolRouter<Self>, prompt_router: PromptRouter<Self>) -> Self {
+        Self {
+            tool_router,
+            prompt_router,
+            templates: None,
+        }
+    }
+
+    /// Also serve the library's templates through `prompts/list` and `prompts/get`
+    ///
+    /// The list is read on every request, and clients are sent
+    /// `notifications/prompts/list_changed` after a tool call changes it.
+    #[must_use]
+    pub fn with_template_prompts(mut self, templates: TemplatePrompts) -> Self {
+        self.templates = Some(templates);
+        self
+    }
+
+    /// Serve over stdin/stdout until the client disconnects
+    pub async fn serve(self) -> Result<()> {
+        let service = ServiceExt::serve(self, rmcp::transport::stdio())
+            .await
+            .context("Failed to start the stdio MCP server")?;
+        service.waiting().await.context("stdio MCP server failed")?;
+        Ok(())
+    }
+}
+
+impl ServerHandler for StdioServer {
+    fn get_info(&self) -> ServerInfo {
+        let capabilities = ServerCapabilities::builder().enable_tools().enable_prompts();
+        let capabilities = if self.templates.is_some() {
+            capabilities.enable_prompts_list_changed().build()
+        } else {
+            capabilities.build()
+        };
+        ServerInfo {
+            protocol_version: ProtocolVersion::V_2024_11_05,
+            capabilities,
+            server_info: Implementation {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                ..Implementation::from_build_env()
+            },
+            instructions: Some("KODEGEN prompt template library".to_string()),
+        }
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let changes_prompts = self.templates.is_some() && changes_prompt_list(&request);
+        let peer = context.peer.clone();
+        let tcc = rmcp::handler::server::tool::ToolCallContext::new(self, request, context);
+        let result = self.tool_router.call(tcc).await;
+        if changes_prompts
+            && result.as_ref().is_ok_and(|r| r.is_error != Some(true))
+            && let Err(e) = peer.notify_prompt_list_changed().await
+        {
+            log::debug!("Failed to send prompts/list_changed: {e}");
+        }
+        result
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult::with_all_items(self.tool_router.list_all()))
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        if let Some(templates) = &self.templates
+            && !self.prompt_router.has_route(&request.name)
+        {
+            return templates.get(&request.name, request.arguments, &context).await;
+        }
+        let pcc = rmcp::handler::server::prompt::PromptContext::new(
+            self,
+            request.name,
+            request.arguments,
+            context,
+        );
+        self.prompt_router.get_prompt(pcc).await
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        let mut prompts = self.prompt_router.list_all();
+        if let Some(templates) = &self.templates {
+            prompts.extend(templates.list(&self.prompt_router, &context).await);
+        }
+        Ok(ListPromptsResult::with_all_items(prompts))
+    }
+}
+
+/// Whether a successful call to this tool can add, remove or change prompts
+fn changes_prompt_list(request: &CallToolRequestParam) -> bool {
+    use crate::*;
+
+    let name = request.name.as_ref();
+    if name == HistoryPromptTool::name() {
+        return request
+            .arguments
+            .as_ref()
+            .and_then(|arguments| arguments.get("action"))
+            .and_then(|action| action.as_str())
+            == Some("restore");
+    }
+    [
+        AddPromptTool::name(),
+        EditPromptTool::name(),
+        DeletePromptTool::name(),
+        RenamePromptTool::name(),
+        CopyPromptTool::name(),
+        ImportPromptsTool::name(),
+    ]
+    .contains(&name)
+}
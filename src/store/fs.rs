@@ -0,0 +1,298 @@
+use super::{PromptStore, StoreError, StoreResult, StoredMetadata};
+use crate::manager::{is_valid_name_segment, is_valid_prompt_name, NAMESPACE_SEPARATOR};
+use anyhow::Context;
+use async_trait::async_trait;
+use log::{debug, warn};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+
+/// Prompts as `<name>.j2.md` files under one directory (the default store)
+///
+/// Namespaced names map to subdirectories: `review/security` is stored as
+/// `review/security.j2.md`. Every path is checked to resolve inside the
+/// prompts directory before it is touched, so symlinked files or namespace
+/// directories cannot point the store elsewhere.
+#[derive(Debug, Clone)]
+pub struct FsStore {
+    dir: PathBuf,
+}
+
+impl FsStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        let mut path = self.dir.clone();
+        let (namespace, leaf) = name
+            .rsplit_once(NAMESPACE_SEPARATOR)
+            .map_or((None, name), |(namespace, leaf)| (Some(namespace), leaf));
+        if let Some(namespace) = namespace {
+            path.extend(namespace.split(NAMESPACE_SEPARATOR));
+        }
+        path.push(format!("{leaf}.j2.md"));
+        path
+    }
+
+    /// Path of a prompt, checked to resolve inside the prompts directory
+    async fn checked_path(&self, name: &str) -> StoreResult<PathBuf> {
+        let path = self.path(name);
+        self.ensure_contained(&path).await?;
+        Ok(path)
+    }
+
+    /// Refuse paths that resolve outside the prompts directory
+    ///
+    /// Canonicalizes the deepest existing ancestor of `path` (the path itself
+    /// when it exists), so a symlinked prompt file or namespace directory
+    /// that points elsewhere is rejected.
+    async fn ensure_contained(&self, path: &Path) -> StoreResult<()> {
+        let root = fs::canonicalize(&self.dir).await.with_context(|| {
+            format!("Failed to resolve prompts directory: {}", self.dir.display())
+        })?;
+
+        let mut current = path;
+        let resolved = loop {
+            match fs::canonicalize(current).await {
+                Ok(resolved) => break resolved,
+                Err(e) if e.kind() == ErrorKind::NotFound => match current.parent() {
+                    Some(parent) if parent.starts_with(&self.dir) => current = parent,
+                    _ => return Err(e.into()),
+                },
+                Err(e) => return Err(e.into()),
+            }
+        };
+
+        if !resolved.starts_with(&root) {
+            return Err(StoreError::Other(anyhow::anyhow!(
+                "'{}' resolves outside the prompts directory",
+                path.display()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Create the namespace directories for `path`
+    async fn create_parent(&self, path: &Path) -> StoreResult<()> {
+        if let Some(parent) = path.parent()
+            && parent != self.dir
+        {
+            fs::create_dir_all(parent).await?;
+            self.ensure_contained(parent).await?;
+        }
+        Ok(())
+    }
+
+    /// Remove namespace directories left empty under the prompts directory
+    async fn prune_empty_dirs(&self, path: &Path) {
+        let mut current = path.parent();
+        while let Some(dir) = current {
+            if dir == self.dir || !dir.starts_with(&self.dir) {
+                break;
+            }
+            // Fails (and stops) as soon as a directory still has entries
+            if fs::remove_dir(dir).await.is_err() {
+                break;
+            }
+            current = dir.parent();
+        }
+    }
+}
+
+#[async_trait]
+impl PromptStore for FsStore {
+    async fn init(&self) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.dir).await.with_context(|| {
+            format!("Failed to create prompts directory: {}", self.dir.display())
+        })
+    }
+
+    async fn list(&self) -> StoreResult<Vec<String>> {
+        let mut names = Vec::new();
+
+        // Depth-first walk; each entry carries its namespace prefix
+        let mut pending = vec![(self.dir.clone(), String::new())];
+        while let Some((dir, namespace)) = pending.pop() {
+            let mut entries = fs::read_dir(&dir).await.with_context(|| {
+                format!("Failed to read prompts directory: {}", dir.display())
+            })?;
+
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+
+                // Check file type first (does not follow symlinks)
+                let file_type = match entry.file_type().await {
+                    Ok(ft) => ft,
+                    Err(e) => {
+                        warn!("Failed to get file type for {}: {e}", path.display());
+                        continue;
+                    }
+                };
+
+                let Some(filename_str) = path.file_name().and_then(|s| s.to_str()) else {
+                    continue;
+                };
+
+                // Namespace directories; skips hidden ones such as .git
+                if file_type.is_dir() {
+                    if is_valid_name_segment(filename_str) {
+                        pending.push((path.clone(), format!("{namespace}{filename_str}/")));
+                    }
+                    continue;
+                }
+
+                // Skip non-regular files (symlinks, sockets, etc.)
+                if !file_type.is_file() {
+                    debug!("Skipping non-file entry: {}", path.display());
+                    continue;
+                }
+
+                // Check for .j2.md extension (not just .md)
+                let Some(stem) = filename_str.strip_suffix(".j2.md") else {
+                    continue; // Skip files that don't match pattern
+                };
+                let name = format!("{namespace}{stem}");
+
+                // Validate prompt name before listing it (reuses existing validation)
+                if !is_valid_prompt_name(&name) {
+                    warn!("Invalid prompt filename (skipping): {name}");
+                    continue;
+                }
+
+                names.push(name);
+            }
+        }
+
+        Ok(names)
+    }
+
+    async fn read(&self, name: &str) -> StoreResult<String> {
+        let path = self.checked_path(name).await?;
+        Ok(fs::read_to_string(path).await?)
+    }
+
+    async fn metadata(&self, name: &str) -> StoreResult<Option<StoredMetadata>> {
+        let path = self.checked_path(name).await?;
+        match fs::metadata(path).await {
+            Ok(meta) => Ok(Some(StoredMetadata {
+                size: meta.len(),
+                modified: meta.modified()?,
+            })),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn create(&self, name: &str, content: &str) -> StoreResult<()> {
+        let path = self.checked_path(name).await?;
+        self.create_parent(&path).await?;
+
+        // Atomic create-new operation - fails if file already exists
+        write_synced(&path, content).await?;
+        Ok(())
+    }
+
+    async fn update(&self, name: &str, content: &str) -> StoreResult<()> {
+        let path = self.checked_path(name).await?;
+        // Read before opening: truncating already moves the modification time
+        let previous = fs::metadata(&path).await?.modified()?;
+
+        // Atomic update-only operation - fails if file doesn't exist
+        let mut file = OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .create(false) // CRITICAL: Fail if file doesn't exist (edit-only semantics)
+            .open(path)
+            .await?;
+
+        file.write_all(content.as_bytes()).await?;
+        file.flush().await?;
+
+        // The cache compares stamps, so a write within the filesystem's
+        // timestamp resolution must still move the modification time
+        if file.metadata().await?.modified()? <= previous {
+            let file = file.try_clone().await?.into_std().await;
+            let bumped = previous + Duration::from_millis(1);
+            tokio::task::spawn_blocking(move || file.set_modified(bumped))
+                .await
+                .map_err(|e| StoreError::Other(e.into()))??;
+        }
+
+        // Sync to disk for durability (survive power loss)
+        file.sync_all().await?;
+        Ok(())
+    }
+
+    async fn delete(&self, name: &str) -> StoreResult<()> {
+        let path = self.checked_path(name).await?;
+        match fs::remove_file(&path).await {
+            Ok(()) => {
+                self.prune_empty_dirs(&path).await;
+                Ok(())
+            }
+            Err(e) if e.kind() == ErrorKind::IsADirectory => Err(StoreError::Other(
+                anyhow::anyhow!("'{name}' is a directory, not a prompt file"),
+            )),
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => Err(StoreError::Other(
+                anyhow::anyhow!("Permission denied to delete prompt '{name}'"),
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The new file is staged next to the prompts and hard-linked into place,
+    /// which atomically claims `new_name` and never overwrites an existing
+    /// prompt. The old file is removed last.
+    async fn rename(&self, old_name: &str, new_name: &str, content: &str) -> StoreResult<()> {
+        let old_path = self.checked_path(old_name).await?;
+        if !fs::try_exists(&old_path).await.unwrap_or(false) {
+            return Err(StoreError::NotFound);
+        }
+
+        let new_path = self.checked_path(new_name).await?;
+        self.create_parent(&new_path).await?;
+
+        // Staged in the target directory so the hard link stays on one filesystem
+        let leaf = new_path.file_name().and_then(|s| s.to_str()).unwrap_or_default();
+        let staging_path = new_path.with_file_name(format!(".{leaf}.rename"));
+        write_synced(&staging_path, content)
+            .await
+            .with_context(|| format!("Failed to stage renamed prompt: {new_name}"))?;
+
+        // Hard link creation fails if the target exists (no-clobber rename)
+        let linked = fs::hard_link(&staging_path, &new_path).await;
+        if let Err(e) = fs::remove_file(&staging_path).await {
+            warn!("Failed to remove staging file {}: {e}", staging_path.display());
+        }
+        if let Err(e) = linked {
+            self.prune_empty_dirs(&new_path).await;
+            return Err(e.into());
+        }
+
+        fs::remove_file(&old_path)
+            .await
+            .with_context(|| format!("Renamed to '{new_name}' but failed to remove '{old_name}'"))?;
+        self.prune_empty_dirs(&old_path).await;
+        Ok(())
+    }
+
+    fn root_dir(&self) -> Option<&Path> {
+        Some(&self.dir)
+    }
+}
+
+/// Create a new file with `content`, flushed and synced to disk
+async fn write_synced(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .await?;
+    file.write_all(content.as_bytes()).await?;
+    file.flush().await?;
+    file.sync_all().await
+}
@@ -0,0 +1,138 @@
+use super::{PromptStore, StoreError, StoreResult, StoredMetadata};
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+
+struct StoredPrompt {
+    content: String,
+    modified: SystemTime,
+}
+
+/// Prompts held in memory (for tests and embedding)
+///
+/// Clones share the same prompts. Nothing is persisted.
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    prompts: Arc<RwLock<BTreeMap<String, StoredPrompt>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store pre-populated with `(name, content)` pairs
+    pub fn with_prompts<I, N, C>(prompts: I) -> Self
+    where
+        I: IntoIterator<Item = (N, C)>,
+        N: Into<String>,
+        C: Into<String>,
+    {
+        let now = SystemTime::now();
+        let prompts = prompts
+            .into_iter()
+            .map(|(name, content)| {
+                (
+                    name.into(),
+                    StoredPrompt {
+                        content: content.into(),
+                        modified: now,
+                    },
+                )
+            })
+            .collect();
+        Self {
+            prompts: Arc::new(RwLock::new(prompts)),
+        }
+    }
+}
+
+/// Modification stamp for a write, strictly after the previous one so the
+/// cache notices back-to-back writes within the clock's resolution
+fn next_modified(previous: Option<SystemTime>) -> SystemTime {
+    let now = SystemTime::now();
+    match previous {
+        Some(previous) if now <= previous => previous + Duration::from_nanos(1),
+        _ => now,
+    }
+}
+
+#[async_trait]
+impl PromptStore for MemoryStore {
+    async fn init(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn list(&self) -> StoreResult<Vec<String>> {
+        Ok(self.prompts.read().await.keys().cloned().collect())
+    }
+
+    async fn read(&self, name: &str) -> StoreResult<String> {
+        self.prompts
+            .read()
+            .await
+            .get(name)
+            .map(|p| p.content.clone())
+            .ok_or(StoreError::NotFound)
+    }
+
+    async fn metadata(&self, name: &str) -> StoreResult<Option<StoredMetadata>> {
+        Ok(self.prompts.read().await.get(name).map(|p| StoredMetadata {
+            size: p.content.len() as u64,
+            modified: p.modified,
+        }))
+    }
+
+    async fn create(&self, name: &str, content: &str) -> StoreResult<()> {
+        let mut prompts = self.prompts.write().await;
+        if prompts.contains_key(name) {
+            return Err(StoreError::AlreadyExists);
+        }
+        prompts.insert(
+            name.to_string(),
+            StoredPrompt {
+                content: content.to_string(),
+                modified: next_modified(None),
+            },
+        );
+        Ok(())
+    }
+
+    async fn update(&self, name: &str, content: &str) -> StoreResult<()> {
+        let mut prompts = self.prompts.write().await;
+        let prompt = prompts.get_mut(name).ok_or(StoreError::NotFound)?;
+        prompt.content = content.to_string();
+        prompt.modified = next_modified(Some(prompt.modified));
+        Ok(())
+    }
+
+    async fn delete(&self, name: &str) -> StoreResult<()> {
+        self.prompts
+            .write()
+            .await
+            .remove(name)
+            .map(|_| ())
+            .ok_or(StoreError::NotFound)
+    }
+
+    async fn rename(&self, old_name: &str, new_name: &str, content: &str) -> StoreResult<()> {
+        let mut prompts = self.prompts.write().await;
+        if !prompts.contains_key(old_name) {
+            return Err(StoreError::NotFound);
+        }
+        if prompts.contains_key(new_name) {
+            return Err(StoreError::AlreadyExists);
+        }
+        let previous = prompts.remove(old_name).map(|p| p.modified);
+        prompts.insert(
+            new_name.to_string(),
+            StoredPrompt {
+                content: content.to_string(),
+                modified: next_modified(previous),
+            },
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+//! Storage backends for prompt files
+//!
+//! `PromptManager` parses, validates, caches and renders prompts; a
+//! [`PromptStore`] only keeps the raw `.j2.md` content by name. The
+//! filesystem store is the default. The in-memory store suits tests and
+//! embedding, and the SQLite store (feature `sqlite`) keeps a whole library in
+//! one database file with indexed metadata and full-text search.
+//!
+//! Stores receive names that the manager has already validated.
+
+mod fs;
+mod memory;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use fs::FsStore;
+pub use memory::MemoryStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+use crate::manager::in_namespace;
+use crate::metadata::PromptTemplate;
+use anyhow::Context;
+use async_trait::async_trait;
+use std::fmt;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Size and modification stamp of a stored prompt
+///
+/// Used to validate the template cache: any write changes `modified`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoredMetadata {
+    /// Content length in bytes
+    pub size: u64,
+    /// Time of the last write
+    pub modified: SystemTime,
+}
+
+/// One stored revision of a prompt
+#[derive(Debug, Clone)]
+pub struct PromptRevision {
+    /// Revision number, starting at 1 per prompt
+    pub revision: u64,
+    /// `create`, `update`, `rename` or `delete`
+    pub action: String,
+    /// Time of the change
+    pub modified: SystemTime,
+    /// Content after the change (`None` for deletions)
+    pub content: Option<String>,
+}
+
+/// Failure of a store operation
+#[derive(Debug)]
+pub enum StoreError {
+    /// No prompt with that name
+    NotFound,
+    /// A prompt with that name already exists
+    AlreadyExists,
+    /// Backend failure (I/O, database, ...)
+    Other(anyhow::Error),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => f.write_str("prompt not found"),
+            Self::AlreadyExists => f.write_str("prompt already exists"),
+            Self::Other(e) => write!(f, "{e:#}"),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<anyhow::Error> for StoreError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::Other(error)
+    }
+}
+
+impl From<std::io::Error> for StoreError {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::NotFound => Self::NotFound,
+            std::io::ErrorKind::AlreadyExists => Self::AlreadyExists,
+            _ => Self::Other(error.into()),
+        }
+    }
+}
+
+pub type StoreResult<T> = Result<T, StoreError>;
+
+/// Metadata filters for listing prompts
+///
+/// Every filter that is set must match. Results are sorted by name, or by
+/// relevance when `text` is set and the store has a full-text index.
+#[derive(Debug, Clone, Default)]
+pub struct PromptQuery {
+    /// Prompt name lies in this namespace or below (`review` matches
+    /// `review/security` and `review/web/xss`)
+    pub namespace: Option<String>,
+    /// Prompt has this category (exact)
+    pub category: Option<String>,
+    /// Prompt has this tag (exact)
+    pub tag: Option<String>,
+    /// Prompt author (case-insensitive)
+    pub author: Option<String>,
+    /// Words that must all appear in the name, title, description, tags or body
+    pub text: Option<String>,
+    /// Maximum number of results
+    pub limit: Option<usize>,
+}
+
+impl PromptQuery {
+    /// Whether no filter is set
+    pub fn is_empty(&self) -> bool {
+        self.namespace.is_none()
+            && self.category.is_none()
+            && self.tag.is_none() && self.author.is_none() && self.text.is_none()
+    }
+
+    /// Words of the text filter, lowercased
+    pub fn words(&self) -> Vec<String> {
+        self.text
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect()
+    }
+
+    /// In-memory evaluation, for stores without an index
+    ///
+    /// Words match as case-insensitive substrings.
+    pub fn matches(&self, template: &PromptTemplate) -> bool {
+        let meta = &template.metadata;
+        if let Some(namespace) = &self.namespace
+            && !in_namespace(&template.filename, namespace)
+        {
+            return false;
+        }
+        if let Some(category) = &self.category
+            && !meta.categories.contains(category)
+        {
+            return false;
+        }
+        if let Some(tag) = &self.tag
+            && !meta.tags.contains(tag)
+        {
+            return false;
+        }
+        if let Some(author) = &self.author
+            && !meta.author.eq_ignore_ascii_case(author)
+        {
+            return false;
+        }
+        let words = self.words();
+        if words.is_empty() {
+            return true;
+        }
+        let haystack = format!(
+            "{} {} {} {} {}",
+            template.filename,
+            meta.title,
+            meta.description,
+            meta.tags.join(" "),
+            template.content
+        )
+        .to_lowercase();
+        words.iter().all(|word| haystack.contains(word.as_str()))
+    }
+}
+
+/// How [`PromptManager::search_prompts`](crate::PromptManager::search_prompts) runs queries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchMode {
+    /// The store's index when it has one (SQLite FTS5), otherwise a scan
+    #[default]
+    Auto,
+    /// Parse every prompt and filter in memory; results sorted by name
+    Scan,
+    /// The store's index only; searching a store without one fails
+    Index,
+}
+
+impl std::str::FromStr for SearchMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "scan" | "fs" => Ok(Self::Scan),
+            "index" | "fts" => Ok(Self::Index),
+            other => anyhow::bail!("Unknown search mode '{other}' (expected auto, scan or index)"),
+        }
+    }
+}
+
+/// Raw prompt storage keyed by prompt name
+#[async_trait]
+pub trait PromptStore: Send + Sync {
+    /// Prepare the backend (create directories, tables)
+    async fn init(&self) -> anyhow::Result<()>;
+
+    /// Names of all stored prompts, in no particular order
+    async fn list(&self) -> StoreResult<Vec<String>>;
+
+    /// Content of a prompt
+    async fn read(&self, name: &str) -> StoreResult<String>;
+
+    /// Size and modification stamp, or `None` if the prompt does not exist
+    async fn metadata(&self, name: &str) -> StoreResult<Option<StoredMetadata>>;
+
+    /// Store a new prompt; fails with `AlreadyExists` without overwriting
+    async fn create(&self, name: &str, content: &str) -> StoreResult<()>;
+
+    /// Replace an existing prompt; fails with `NotFound` without creating
+    async fn update(&self, name: &str, content: &str) -> StoreResult<()>;
+
+    /// Remove a prompt; fails with `NotFound` if it does not exist
+    async fn delete(&self, name: &str) -> StoreResult<()>;
+
+    /// Move `old_name` to `new_name` with new content
+    ///
+    /// Fails with `AlreadyExists` if `new_name` is taken. The default
+    /// implementation creates the new prompt before deleting the old one, so
+    /// the prompt is reachable under one of its names throughout.
+    async fn rename(&self, old_name: &str, new_name: &str, content: &str) -> StoreResult<()> {
+        if self.metadata(old_name).await?.is_none() {
+            return Err(StoreError::NotFound);
+        }
+        self.create(new_name, content).await?;
+        self.delete(old_name).await
+    }
+
+    /// Parsed prompts matching `query`, for stores that index metadata
+    ///
+    /// `None` means the store has no index; the manager then parses every
+    /// prompt and filters in memory. Unparseable prompts are left out.
+    async fn query_templates(&self, _query: &PromptQuery) -> StoreResult<Option<Vec<PromptTemplate>>> {
+        Ok(None)
+    }
+
+    /// Revisions of a prompt, newest first, for stores that keep them
+    ///
+    /// `None` means the store keeps no history.
+    async fn revisions(&self, _name: &str) -> StoreResult<Option<Vec<PromptRevision>>> {
+        Ok(None)
+    }
+
+    /// Directory holding one `<name>.j2.md` file per prompt, for stores
+    /// backed by one (required by git storage)
+    fn root_dir(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// Result of copying prompts between stores
+#[derive(Debug, Clone, Default)]
+pub struct MigrationReport {
+    /// Prompts copied
+    pub copied: Vec<String>,
+    /// Prompts left alone because the target already has them
+    pub skipped: Vec<String>,
+}
+
+/// Copy every prompt from `source` into `target` (e.g. a prompts directory
+/// into a SQLite database)
+///
+/// Content is copied byte-for-byte. Prompts that already exist in `target`
+/// are skipped, so an interrupted migration can be re-run.
+pub async fn migrate_prompts(
+    source: &dyn PromptStore,
+    target: &dyn PromptStore,
+) -> anyhow::Result<MigrationReport> {
+    let mut names = source.list().await.context("Failed to list source prompts")?;
+    names.sort();
+
+    let mut report = MigrationReport::default();
+    for name in names {
+        let content = source
+            .read(&name)
+            .await
+            .with_context(|| format!("Failed to read prompt: {name}"))?;
+        match target.create(&name, &content).await {
+            Ok(()) => report.copied.push(name),
+            Err(StoreError::AlreadyExists) => report.skipped.push(name),
+            Err(e) => return Err(e).with_context(|| format!("Failed to write prompt: {name}")),
+        }
+    }
+    Ok(report)
+}
@@ -0,0 +1,557 @@
+use super::{PromptQuery, PromptRevision, PromptStore, StoreError, StoreResult, StoredMetadata};
+use crate::manager::NAMESPACE_SEPARATOR;
+use crate::metadata::{PromptMetadata, PromptTemplate};
+use crate::template::parse_template;
+use anyhow::Context;
+use async_trait::async_trait;
+use log::{debug, info};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, ErrorCode, OptionalExtension, Transaction};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Schema version stored in `PRAGMA user_version`
+const SCHEMA_VERSION: i64 = 2;
+
+/// Version 1: raw prompt content
+const SCHEMA_V1: &str = "
+    CREATE TABLE IF NOT EXISTS prompts (
+        name        TEXT PRIMARY KEY NOT NULL,
+        content     TEXT NOT NULL,
+        modified_ns INTEGER NOT NULL
+    );
+";
+
+/// Version 2: indexed metadata, parameters, revisions and full-text search
+///
+/// Metadata columns are NULL for prompts whose frontmatter does not parse;
+/// such prompts are stored and searchable by body but not listed.
+const SCHEMA_V2: &str = "
+    ALTER TABLE prompts ADD COLUMN title TEXT;
+    ALTER TABLE prompts ADD COLUMN description TEXT;
+    ALTER TABLE prompts ADD COLUMN author TEXT;
+    ALTER TABLE prompts ADD COLUMN version TEXT;
+    ALTER TABLE prompts ADD COLUMN votes INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE prompts ADD COLUMN verified INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE prompts ADD COLUMN deprecated INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE prompts ADD COLUMN metadata_json TEXT;
+    ALTER TABLE prompts ADD COLUMN body TEXT;
+    CREATE INDEX IF NOT EXISTS prompts_author ON prompts (author COLLATE NOCASE);
+
+    CREATE TABLE IF NOT EXISTS prompt_categories (
+        prompt   TEXT NOT NULL,
+        category TEXT NOT NULL,
+        PRIMARY KEY (prompt, category)
+    );
+    CREATE INDEX IF NOT EXISTS prompt_categories_category ON prompt_categories (category);
+
+    CREATE TABLE IF NOT EXISTS prompt_tags (
+        prompt TEXT NOT NULL,
+        tag    TEXT NOT NULL,
+        PRIMARY KEY (prompt, tag)
+    );
+    CREATE INDEX IF NOT EXISTS prompt_tags_tag ON prompt_tags (tag);
+
+    CREATE TABLE IF NOT EXISTS prompt_parameters (
+        prompt       TEXT NOT NULL,
+        position     INTEGER NOT NULL,
+        name         TEXT NOT NULL,
+        param_type   TEXT NOT NULL,
+        required     INTEGER NOT NULL,
+        description  TEXT NOT NULL,
+        default_json TEXT,
+        PRIMARY KEY (prompt, name)
+    );
+
+    CREATE TABLE IF NOT EXISTS prompt_revisions (
+        prompt      TEXT NOT NULL,
+        revision    INTEGER NOT NULL,
+        action      TEXT NOT NULL,
+        content     TEXT,
+        modified_ns INTEGER NOT NULL,
+        PRIMARY KEY (prompt, revision)
+    );
+
+    CREATE VIRTUAL TABLE IF NOT EXISTS prompts_fts USING fts5 (
+        name, title, description, tags, body,
+        tokenize = 'unicode61 remove_diacritics 2'
+    );
+";
+
+/// Prompts in a single SQLite database
+///
+/// Besides the raw content, every write indexes the parsed metadata
+/// (categories, tags, author, parameters) and the text into an FTS5 table,
+/// and appends a revision. Listing and searching read the index instead of
+/// reparsing prompts. Clones share one connection; queries run on the
+/// blocking thread pool.
+#[derive(Clone)]
+pub struct SqliteStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStore {
+    /// Open (or create) a database file
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open prompt database: {}", path.display()))?;
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Ok(Self::from_connection(conn))
+    }
+
+    /// Private in-memory database
+    pub fn open_in_memory() -> anyhow::Result<Self> {
+        Ok(Self::from_connection(Connection::open_in_memory()?))
+    }
+
+    fn from_connection(conn: Connection) -> Self {
+        Self {
+            conn: Arc::new(Mutex::new(conn)),
+        }
+    }
+
+    /// Revisions of a prompt, newest first (kept after deletion)
+    pub async fn revisions(&self, name: &str) -> anyhow::Result<Vec<PromptRevision>> {
+        let name = name.to_string();
+        let revisions = self
+            .with_conn(move |conn| {
+                let mut stmt = conn.prepare_cached(
+                    "SELECT revision, action, modified_ns, content FROM prompt_revisions
+                     WHERE prompt = ?1 ORDER BY revision DESC",
+                )?;
+                let revisions = stmt
+                    .query_map(params![name], |row| {
+                        Ok(PromptRevision {
+                            revision: row.get::<_, i64>(0)?.max(0) as u64,
+                            action: row.get(1)?,
+                            modified: from_nanos(row.get(2)?),
+                            content: row.get(3)?,
+                        })
+                    })?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(revisions)
+            })
+            .await?;
+        Ok(revisions)
+    }
+
+    /// Run `f` with the connection on the blocking thread pool
+    async fn with_conn<T, F>(&self, f: F) -> StoreResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> StoreResult<T> + Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn
+                .lock()
+                .map_err(|_| StoreError::Other(anyhow::anyhow!("Prompt database lock poisoned")))?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(|e| StoreError::Other(e.into()))?
+    }
+}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(error: rusqlite::Error) -> Self {
+        match error.sqlite_error_code() {
+            Some(ErrorCode::ConstraintViolation) => Self::AlreadyExists,
+            _ => Self::Other(error.into()),
+        }
+    }
+}
+
+fn nanos(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| i64::try_from(d.as_nanos()).unwrap_or(i64::MAX))
+        .unwrap_or(0)
+}
+
+fn from_nanos(ns: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_nanos(ns.max(0) as u64)
+}
+
+/// Modification stamp for a write, strictly after `previous_ns`
+fn next_modified_ns(previous_ns: Option<i64>) -> i64 {
+    let now = nanos(SystemTime::now());
+    match previous_ns {
+        Some(previous) if now <= previous => previous + 1,
+        _ => now,
+    }
+}
+
+/// Bring the schema up to `SCHEMA_VERSION`, reindexing existing prompts
+fn migrate_schema(conn: &mut Connection) -> StoreResult<()> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version >= SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    let tx = conn.transaction()?;
+    if version < 1 {
+        tx.execute_batch(SCHEMA_V1)?;
+    }
+    if version < 2 {
+        tx.execute_batch(SCHEMA_V2)?;
+        let rows = {
+            let mut stmt = tx.prepare("SELECT name, content, modified_ns FROM prompts")?;
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                .collect::<Result<Vec<(String, String, i64)>, _>>()?
+        };
+        for (name, content, modified_ns) in &rows {
+            index_prompt(&tx, name, content)?;
+            append_revision(&tx, name, "create", Some(content), *modified_ns)?;
+        }
+        if !rows.is_empty() {
+            info!("Indexed {} prompts for schema version 2", rows.len());
+        }
+    }
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Replace the index rows of a prompt with its parsed metadata
+fn index_prompt(tx: &Transaction<'_>, name: &str, content: &str) -> StoreResult<()> {
+    unindex_prompt(tx, name)?;
+
+    let template = match parse_template(name, content) {
+        Ok(template) => template,
+        Err(e) => {
+            // Keep the body searchable; metadata columns stay NULL
+            debug!("Prompt '{name}' not indexed: {e}");
+            tx.execute(
+                "INSERT INTO prompts_fts (name, title, description, tags, body)
+                 VALUES (?1, '', '', '', ?2)",
+                params![name, content],
+            )?;
+            return Ok(());
+        }
+    };
+    let meta = &template.metadata;
+    let metadata_json = serde_json::to_string(meta).map_err(|e| StoreError::Other(e.into()))?;
+
+    tx.execute(
+        "UPDATE prompts SET title = ?2, description = ?3, author = ?4, version = ?5,
+             votes = ?6, verified = ?7, deprecated = ?8, metadata_json = ?9, body = ?10
+         WHERE name = ?1",
+        params![
+            name,
+            meta.title,
+            meta.description,
+            meta.author,
+            meta.version,
+            meta.votes,
+            meta.verified,
+            meta.deprecated,
+            metadata_json,
+            template.content,
+        ],
+    )?;
+    for category in &meta.categories {
+        tx.execute(
+            "INSERT OR IGNORE INTO prompt_categories (prompt, category) VALUES (?1, ?2)",
+            params![name, category],
+        )?;
+    }
+    for tag in &meta.tags {
+        tx.execute(
+            "INSERT OR IGNORE INTO prompt_tags (prompt, tag) VALUES (?1, ?2)",
+            params![name, tag],
+        )?;
+    }
+    for (position, param) in meta.parameters.iter().enumerate() {
+        let param_type = serde_json::to_value(&param.param_type)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        let default_json = param
+            .default
+            .as_ref()
+            .and_then(|d| serde_json::to_string(d).ok());
+        tx.execute(
+            "INSERT OR REPLACE INTO prompt_parameters
+                 (prompt, position, name, param_type, required, description, default_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                name,
+                position as i64,
+                param.name,
+                param_type,
+                param.required,
+                param.description,
+                default_json,
+            ],
+        )?;
+    }
+    tx.execute(
+        "INSERT INTO prompts_fts (name, title, description, tags, body)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            name,
+            meta.title,
+            meta.description,
+            meta.tags.join(" "),
+            template.content,
+        ],
+    )?;
+    Ok(())
+}
+
+fn unindex_prompt(tx: &Transaction<'_>, name: &str) -> StoreResult<()> {
+    for sql in [
+        "DELETE FROM prompt_categories WHERE prompt = ?1",
+        "DELETE FROM prompt_tags WHERE prompt = ?1",
+        "DELETE FROM prompt_parameters WHERE prompt = ?1",
+        "DELETE FROM prompts_fts WHERE name = ?1",
+    ] {
+        tx.execute(sql, params![name])?;
+    }
+    Ok(())
+}
+
+fn append_revision(
+    tx: &Transaction<'_>,
+    name: &str,
+    action: &str,
+    content: Option<&str>,
+    modified_ns: i64,
+) -> StoreResult<()> {
+    tx.execute(
+        "INSERT INTO prompt_revisions (prompt, revision, action, content, modified_ns)
+         VALUES (?1, (SELECT COALESCE(MAX(revision), 0) + 1 FROM prompt_revisions WHERE prompt = ?1),
+                 ?2, ?3, ?4)",
+        params![name, action, content, modified_ns],
+    )?;
+    Ok(())
+}
+
+/// FTS5 query requiring every word, each as a quoted prefix term
+///
+/// Quoting keeps user input from being read as FTS5 query syntax.
+fn fts_query(words: &[String]) -> String {
+    words
+        .iter()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[async_trait]
+impl PromptStore for SqliteStore {
+    async fn init(&self) -> anyhow::Result<()> {
+        self.with_conn(migrate_schema)
+            .await
+            .context("Failed to initialize prompt database")
+    }
+
+    async fn list(&self) -> StoreResult<Vec<String>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare_cached("SELECT name FROM prompts")?;
+            let names = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<Vec<String>, _>>()?;
+            Ok(names)
+        })
+        .await
+    }
+
+    async fn read(&self, name: &str) -> StoreResult<String> {
+        let name = name.to_string();
+        self.with_conn(move |conn| {
+            conn.query_row(
+                "SELECT content FROM prompts WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or(StoreError::NotFound)
+        })
+        .await
+    }
+
+    async fn metadata(&self, name: &str) -> StoreResult<Option<StoredMetadata>> {
+        let name = name.to_string();
+        self.with_conn(move |conn| {
+            let row = conn
+                .query_row(
+                    "SELECT length(CAST(content AS BLOB)), modified_ns FROM prompts WHERE name = ?1",
+                    params![name],
+                    |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+                )
+                .optional()?;
+            Ok(row.map(|(size, modified_ns)| StoredMetadata {
+                size: size.max(0) as u64,
+                modified: from_nanos(modified_ns),
+            }))
+        })
+        .await
+    }
+
+    async fn create(&self, name: &str, content: &str) -> StoreResult<()> {
+        let (name, content) = (name.to_string(), content.to_string());
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            let modified_ns = next_modified_ns(None);
+            tx.execute(
+                "INSERT INTO prompts (name, content, modified_ns) VALUES (?1, ?2, ?3)",
+                params![name, content, modified_ns],
+            )?;
+            index_prompt(&tx, &name, &content)?;
+            append_revision(&tx, &name, "create", Some(&content), modified_ns)?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn update(&self, name: &str, content: &str) -> StoreResult<()> {
+        let (name, content) = (name.to_string(), content.to_string());
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            let previous: Option<i64> = tx
+                .query_row(
+                    "SELECT modified_ns FROM prompts WHERE name = ?1",
+                    params![name],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(previous) = previous else {
+                return Err(StoreError::NotFound);
+            };
+            let modified_ns = next_modified_ns(Some(previous));
+            tx.execute(
+                "UPDATE prompts SET content = ?2, modified_ns = ?3 WHERE name = ?1",
+                params![name, content, modified_ns],
+            )?;
+            index_prompt(&tx, &name, &content)?;
+            append_revision(&tx, &name, "update", Some(&content), modified_ns)?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn delete(&self, name: &str) -> StoreResult<()> {
+        let name = name.to_string();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            if tx.execute("DELETE FROM prompts WHERE name = ?1", params![name])? == 0 {
+                return Err(StoreError::NotFound);
+            }
+            unindex_prompt(&tx, &name)?;
+            append_revision(&tx, &name, "delete", None, next_modified_ns(None))?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn rename(&self, old_name: &str, new_name: &str, content: &str) -> StoreResult<()> {
+        let (old_name, new_name, content) =
+            (old_name.to_string(), new_name.to_string(), content.to_string());
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            if tx.execute("DELETE FROM prompts WHERE name = ?1", params![old_name])? == 0 {
+                return Err(StoreError::NotFound);
+            }
+            let modified_ns = next_modified_ns(None);
+            tx.execute(
+                "INSERT INTO prompts (name, content, modified_ns) VALUES (?1, ?2, ?3)",
+                params![new_name, content, modified_ns],
+            )?;
+            unindex_prompt(&tx, &old_name)?;
+            index_prompt(&tx, &new_name, &content)?;
+            append_revision(&tx, &old_name, "rename", None, modified_ns)?;
+            append_revision(&tx, &new_name, "rename", Some(&content), modified_ns)?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn query_templates(&self, query: &PromptQuery) -> StoreResult<Option<Vec<PromptTemplate>>> {
+        let query = query.clone();
+        self.with_conn(move |conn| {
+            let mut sql = String::from("SELECT p.name, p.metadata_json, p.body FROM prompts p");
+            let mut args: Vec<Value> = Vec::new();
+
+            let words = query.words();
+            if !words.is_empty() {
+                sql.push_str(
+                    " JOIN (SELECT name, bm25(prompts_fts) AS rank FROM prompts_fts
+                            WHERE prompts_fts MATCH ?) f ON f.name = p.name",
+                );
+                args.push(Value::Text(fts_query(&words)));
+            }
+            sql.push_str(" WHERE p.metadata_json IS NOT NULL");
+            if let Some(namespace) = query.namespace {
+                // Range over the primary key: '0' sorts right after '/'
+                let namespace = namespace.trim_matches(NAMESPACE_SEPARATOR);
+                if !namespace.is_empty() {
+                    sql.push_str(" AND p.name >= ? AND p.name < ?");
+                    args.push(Value::Text(format!("{namespace}{NAMESPACE_SEPARATOR}")));
+                    args.push(Value::Text(format!("{namespace}0")));
+                }
+            }
+            if let Some(category) = query.category {
+                sql.push_str(
+                    " AND EXISTS (SELECT 1 FROM prompt_categories c
+                                  WHERE c.prompt = p.name AND c.category = ?)",
+                );
+                args.push(Value::Text(category));
+            }
+            if let Some(tag) = query.tag {
+                sql.push_str(
+                    " AND EXISTS (SELECT 1 FROM prompt_tags t WHERE t.prompt = p.name AND t.tag = ?)",
+                );
+                args.push(Value::Text(tag));
+            }
+            if let Some(author) = query.author {
+                sql.push_str(" AND p.author = ? COLLATE NOCASE");
+                args.push(Value::Text(author));
+            }
+            sql.push_str(if words.is_empty() {
+                " ORDER BY p.name"
+            } else {
+                " ORDER BY f.rank, p.name"
+            });
+            if let Some(limit) = query.limit {
+                sql.push_str(" LIMIT ?");
+                args.push(Value::Integer(i64::try_from(limit).unwrap_or(i64::MAX)));
+            }
+
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt
+                .query_map(params_from_iter(args), |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut templates = Vec::with_capacity(rows.len());
+            for (name, metadata_json, body) in rows {
+                let metadata: PromptMetadata = serde_json::from_str(&metadata_json)
+                    .with_context(|| format!("Corrupt metadata index for prompt '{name}'"))?;
+                templates.push(PromptTemplate {
+                    filename: name,
+                    metadata,
+                    content: body,
+                });
+            }
+            Ok(Some(templates))
+        })
+        .await
+    }
+
+    async fn revisions(&self, name: &str) -> StoreResult<Option<Vec<PromptRevision>>> {
+        Ok(Some(SqliteStore::revisions(self, name).await?))
+    }
+}
@@ -1,66 +1,55 @@
-use super::metadata::{ParameterType, PromptMetadata, PromptTemplate};
+use super::attachments::{AttachmentBudget, AttachmentKind, AttachmentSandbox};
+use super::config::PromptLimits;
+use super::diagnostics::{diagnose_frontmatter, locate_diagnostics, Diagnostic, ValidationErrors};
+use super::env_policy::env_snapshot;
+use super::lint::referenced_variables;
+use super::manager::{is_valid_prompt_name, PROMPT_NAME_RULE};
+use super::messages::{
+    attachment_marker, expand_message_blocks, split_messages, strip_marker_chars,
+    strip_message_markers, RenderedMessage,
+};
+use super::metadata::{ParameterType, PromptMetadata, PromptTemplate, TokenOverflow};
+use super::tokens::TokenEstimate;
 use anyhow::{Context, Result};
 use gray_matter::engine::YAML;
 use gray_matter::{Matter, Pod};
 use kodegen_mcp_schema::prompt::TemplateParamValue;
 use minijinja::Environment;
-use std::collections::HashMap;
-use std::sync::{LazyLock, OnceLock};
-use tokio::time::{timeout, Duration};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock};
+use tokio::time::timeout;
 
 /// Static empty HashMap for use when no parameters are provided
 static EMPTY_PARAMS: LazyLock<HashMap<String, TemplateParamValue>> = LazyLock::new(HashMap::new);
 
-/// Get max parameter size (supports KODEGEN_MAX_PARAM_SIZE env var)
-fn get_max_param_size() -> usize {
-    static MAX_SIZE: OnceLock<usize> = OnceLock::new();
-    *MAX_SIZE.get_or_init(|| {
-        std::env::var("KODEGEN_MAX_PARAM_SIZE")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(1_000_000)
-    })
-}
-
-/// Get max parameter count (supports KODEGEN_MAX_PARAM_COUNT env var)
-fn get_max_param_count() -> usize {
-    static MAX_COUNT: OnceLock<usize> = OnceLock::new();
-    *MAX_COUNT.get_or_init(|| {
-        std::env::var("KODEGEN_MAX_PARAM_COUNT")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(100)
-    })
-}
-
-/// Get max total parameters size (supports KODEGEN_MAX_TOTAL_PARAMS_SIZE env var)
-fn get_max_total_params_size() -> usize {
-    static MAX_TOTAL: OnceLock<usize> = OnceLock::new();
-    *MAX_TOTAL.get_or_init(|| {
-        std::env::var("KODEGEN_MAX_TOTAL_PARAMS_SIZE")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(10_000_000)
-    })
-}
-
 /// Parse a .j2.md file into metadata and content
+///
+/// # Errors
+/// Frontmatter and metadata problems are returned as
+/// [`ValidationErrors`] with file-relative locations and key paths; use
+/// [`diagnostics_of`](super::diagnostics::diagnostics_of) to recover them.
 pub fn parse_template(filename: &str, file_content: &str) -> Result<PromptTemplate> {
     // Use gray_matter to split frontmatter and content
     let matter = Matter::<YAML>::new();
     let parsed: gray_matter::ParsedEntity<Pod> = matter
         .parse(file_content)
-        .map_err(|e| anyhow::anyhow!("Failed to parse frontmatter: {e}"))?;
+        .map_err(|e| frontmatter_error(file_content, format!("Failed to parse frontmatter: {e}")))?;
 
     // Extract and deserialize frontmatter
     let metadata: PromptMetadata = parsed
         .data
-        .ok_or_else(|| anyhow::anyhow!("No frontmatter found in template"))?
+        .ok_or_else(|| frontmatter_error(file_content, "No frontmatter found in template".into()))?
         .deserialize()
-        .context("Failed to parse YAML frontmatter")?;
+        .map_err(|e| {
+            frontmatter_error(file_content, format!("Failed to parse YAML frontmatter: {e}"))
+        })?;
 
     // Validate metadata
-    validate_metadata(&metadata)?;
+    let diagnostics = validate_metadata(&metadata);
+    if !diagnostics.is_empty() {
+        return Err(ValidationErrors::new(locate_diagnostics(file_content, diagnostics)).into());
+    }
 
     // Get content (after frontmatter)
     let content = parsed.content;
@@ -72,63 +61,316 @@ pub fn parse_template(filename: &str, file_content: &str) -> Result<PromptTempla
     })
 }
 
-/// Validate metadata fields
-fn validate_metadata(metadata: &PromptMetadata) -> Result<()> {
+/// Structured error for frontmatter that could not be parsed
+///
+/// Falls back to `fallback` as a single diagnostic if the problem cannot be
+/// pinpointed.
+fn frontmatter_error(file_content: &str, fallback: String) -> anyhow::Error {
+    let mut diagnostics = diagnose_frontmatter(file_content);
+    if diagnostics.is_empty() {
+        diagnostics.push(Diagnostic::new(fallback));
+    }
+    ValidationErrors::new(diagnostics).into()
+}
+
+/// Serialize a template back into .j2.md file content (frontmatter + body)
+///
+/// Unknown frontmatter keys kept in `metadata.extra` are written back, so
+/// `parse_template(serialize_template(t))` preserves the full frontmatter.
+pub fn serialize_template(template: &PromptTemplate) -> Result<String> {
+    let frontmatter =
+        serde_yaml::to_string(&template.metadata).context("Failed to serialize frontmatter")?;
+    let body = template.content.trim_matches('\n');
+    Ok(format!("---\n{frontmatter}---\n\n{body}\n"))
+}
+
+/// Set one top-level frontmatter key in place, leaving the rest of the file as is
+///
+/// The key's line, and the indented or `- ` lines continuing its value, are
+/// replaced with `key: <value>` (written as JSON, which YAML accepts). A
+/// missing key is added at the end of the frontmatter; `None` removes it.
+/// Comments, key order and the body keep their exact bytes.
+pub(crate) fn set_frontmatter_key(
+    content: &str,
+    key: &str,
+    value: Option<&serde_json::Value>,
+) -> Result<String> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let is_fence = |line: &str| line.trim_end() == "---";
+    if !lines.first().is_some_and(|line| is_fence(line)) {
+        anyhow::bail!("No frontmatter found in template");
+    }
+    let close = lines
+        .iter()
+        .skip(1)
+        .position(|line| is_fence(line))
+        .map(|index| index + 1)
+        .context("Frontmatter is not closed with '---'")?;
+
+    let is_key = |line: &str| {
+        line.strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start_matches([' ', '\t']).starts_with(':'))
+    };
+    let continues = |line: &str| line.starts_with([' ', '\t', '-']);
+    let (start, end) = match (1..close).find(|&i| is_key(lines[i])) {
+        Some(start) => {
+            let mut end = start + 1;
+            // Blank lines belong to the value only if more of it follows
+            let mut next = end;
+            while next < close {
+                if continues(lines[next]) {
+                    next += 1;
+                    end = next;
+                } else if lines[next].trim().is_empty() {
+                    next += 1;
+                } else {
+                    break;
+                }
+            }
+            (start, end)
+        }
+        None => (close, close),
+    };
+
+    let newline = if lines[start].ends_with("\r\n") { "\r\n" } else { "\n" };
+    let replacement = match value {
+        Some(value) => format!("{key}: {}{newline}", serde_json::to_string(value)?),
+        None => String::new(),
+    };
+    let mut edited = String::with_capacity(content.len() + replacement.len());
+    lines[..start].iter().for_each(|line| edited.push_str(line));
+    edited.push_str(&replacement);
+    lines[end..].iter().for_each(|line| edited.push_str(line));
+    Ok(edited)
+}
+
+/// Validate metadata fields, collecting every problem found
+fn validate_metadata(metadata: &PromptMetadata) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
     if metadata.title.is_empty() {
-        anyhow::bail!("Title cannot be empty");
+        diagnostics.push(
+            Diagnostic::new("Title cannot be empty")
+                .with_path("title")
+                .with_suggestion("Set 'title' to a short human-readable name"),
+        );
     }
     if metadata.description.is_empty() {
-        anyhow::bail!("Description cannot be empty");
+        diagnostics.push(
+            Diagnostic::new("Description cannot be empty")
+                .with_path("description")
+                .with_suggestion("Describe what the prompt does in one sentence"),
+        );
     }
     if metadata.categories.is_empty() {
-        anyhow::bail!("At least one category is required");
+        diagnostics.push(
+            Diagnostic::new("At least one category is required")
+                .with_path("categories")
+                .with_suggestion("Add a category, e.g. categories: [\"general\"]"),
+        );
     }
     if metadata.author.is_empty() {
-        anyhow::bail!("Author cannot be empty");
+        diagnostics.push(
+            Diagnostic::new("Author cannot be empty")
+                .with_path("author")
+                .with_suggestion("Set 'author' to your name or team"),
+        );
     }
     
     // Validate parameter definitions
-    for param in &metadata.parameters {
-        validate_parameter_definition(param)
-            .with_context(|| format!("Invalid parameter definition: '{}'", param.name))?;
+    for (index, param) in metadata.parameters.iter().enumerate() {
+        validate_parameter_definition(index, param, &mut diagnostics);
     }
+
+    validate_extended_metadata(metadata, &mut diagnostics);
     
-    Ok(())
+    diagnostics
+}
+
+/// Validate optional metadata (version, tags, model hints, deprecation, aliases)
+fn validate_extended_metadata(metadata: &PromptMetadata, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(version) = &metadata.version
+        && let Err(e) = semver::Version::parse(version)
+    {
+        diagnostics.push(
+            Diagnostic::new(format!("Invalid version '{version}': {e}"))
+                .with_path("version")
+                .with_types("semantic version", version.clone())
+                .with_suggestion("Use MAJOR.MINOR.PATCH, e.g. \"1.0.0\""),
+        );
+    }
+
+    for (index, tag) in metadata.tags.iter().enumerate() {
+        if tag.trim().is_empty() {
+            diagnostics.push(
+                Diagnostic::new("Tags cannot be empty")
+                    .with_path(format!("tags[{index}]"))
+                    .with_suggestion("Remove the empty tag"),
+            );
+        }
+    }
+
+    if let Some(model) = &metadata.model
+        && model.trim().is_empty()
+    {
+        diagnostics.push(
+            Diagnostic::new("Model cannot be empty")
+                .with_path("model")
+                .with_suggestion("Remove the field instead"),
+        );
+    }
+
+    if let Some(temperature) = metadata.temperature
+        && !(0.0..=2.0).contains(&temperature)
+    {
+        diagnostics.push(
+            Diagnostic::new(format!(
+                "Temperature must be between 0.0 and 2.0, got {temperature}"
+            ))
+            .with_path("temperature")
+            .with_types("number between 0.0 and 2.0", temperature.to_string())
+            .with_suggestion("Use a value such as 0.2 (focused) or 0.8 (creative)"),
+        );
+    }
+
+    if metadata.on_token_overflow != TokenOverflow::Allow && metadata.max_tokens.is_none() {
+        diagnostics.push(
+            Diagnostic::new("on_token_overflow is set but the prompt has no max_tokens budget")
+                .with_path("on_token_overflow")
+                .with_suggestion("Add max_tokens or remove on_token_overflow"),
+        );
+    }
+
+    if metadata.max_tokens == Some(0) {
+        diagnostics.push(
+            Diagnostic::new("max_tokens must be greater than 0")
+                .with_path("max_tokens")
+                .with_types("positive integer", "0")
+                .with_suggestion("Remove the field or set a positive limit"),
+        );
+    }
+
+    if let Some(limits) = &metadata.limits {
+        let zero = [
+            ("timeout_ms", limits.timeout_ms == Some(0)),
+            ("fuel", limits.fuel == Some(0)),
+            ("recursion_depth", limits.recursion_depth == Some(0)),
+            ("max_output_size", limits.max_output_size == Some(0)),
+        ];
+        for (key, _) in zero.into_iter().filter(|(_, is_zero)| *is_zero) {
+            diagnostics.push(
+                Diagnostic::new(format!("limits.{key} must be greater than 0"))
+                    .with_path(format!("limits.{key}"))
+                    .with_types("positive integer", "0")
+                    .with_suggestion("Remove the field to use the server's limit"),
+            );
+        }
+    }
+
+    if let Some(replacement) = &metadata.replaced_by {
+        if !metadata.deprecated {
+            diagnostics.push(
+                Diagnostic::new(format!(
+                    "replaced_by is set to '{replacement}' but the prompt is not deprecated"
+                ))
+                .with_path("replaced_by")
+                .with_suggestion("Add 'deprecated: true' or remove replaced_by"),
+            );
+        }
+        if !is_valid_prompt_name(replacement) {
+            diagnostics.push(
+                Diagnostic::new(format!("Invalid replaced_by '{replacement}'"))
+                    .with_path("replaced_by")
+                    .with_suggestion(PROMPT_NAME_RULE),
+            );
+        }
+    }
+
+    if let Some(source) = &metadata.forked_from
+        && !is_valid_prompt_name(source)
+    {
+        diagnostics.push(
+            Diagnostic::new(format!("Invalid forked_from '{source}'"))
+                .with_path("forked_from")
+                .with_suggestion(PROMPT_NAME_RULE),
+        );
+    }
+
+    for (index, alias) in metadata.aliases.iter().enumerate() {
+        if !is_valid_prompt_name(alias) {
+            diagnostics.push(
+                Diagnostic::new(format!("Invalid alias '{alias}'"))
+                    .with_path(format!("aliases[{index}]"))
+                    .with_suggestion(PROMPT_NAME_RULE),
+            );
+        }
+        if metadata.aliases[..index].contains(alias) {
+            diagnostics.push(
+                Diagnostic::new(format!("Duplicate alias '{alias}'"))
+                    .with_path(format!("aliases[{index}]"))
+                    .with_suggestion("Remove the duplicate entry"),
+            );
+        }
+    }
 }
 
 /// Validate a parameter definition's default value and logical consistency
-fn validate_parameter_definition(param: &super::metadata::ParameterDefinition) -> Result<()> {
+fn validate_parameter_definition(
+    index: usize,
+    param: &super::metadata::ParameterDefinition,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
     // Check 1: If default exists, validate it matches declared type
-    if let Some(default) = &param.default {
-        // Reuse existing validation logic!
-        validate_parameter_type(param, default).with_context(|| {
-            let actual_type = match default {
-                TemplateParamValue::String(_) => "string",
-                TemplateParamValue::Number(_) => "number",
-                TemplateParamValue::Bool(_) => "boolean",
-                TemplateParamValue::StringArray(_) => "array",
-            };
-            format!(
+    if let Some(default) = &param.default
+        && validate_parameter_type(param, default).is_err()
+    {
+        let expected = param_type_name(&param.param_type);
+        let actual = param_value_type_name(default);
+        diagnostics.push(
+            Diagnostic::new(format!(
                 "Parameter '{}' has default value type mismatch. \
-                 Declared as {:?} but default value is {}. \
-                 Default: {:?}\n\
-                 \n\
-                 Fix the template's YAML frontmatter to use the correct type for the default value.",
-                param.name, param.param_type, actual_type, default
-            )
-        })?;
+                 Declared as {expected} but default value is {actual}",
+                param.name
+            ))
+            .with_path(format!("parameters[{index}].default"))
+            .with_types(expected, actual)
+            .with_suggestion(format!(
+                "Change the default to a {expected} or set param_type: {actual}"
+            )),
+        );
     }
 
     // Check 2: Validate logical consistency - required + default is contradictory
     if param.required && param.default.is_some() {
-        anyhow::bail!(
-            "Parameter '{}' is marked as required but has a default value. \
-             This is contradictory - remove 'required: true' or remove the default.",
-            param.name
+        diagnostics.push(
+            Diagnostic::new(format!(
+                "Parameter '{}' is marked as required but has a default value",
+                param.name
+            ))
+            .with_path(format!("parameters[{index}].required"))
+            .with_suggestion("Remove 'required: true' or remove the default"),
         );
     }
+}
+
+/// Frontmatter name of a declared parameter type
+fn param_type_name(param_type: &ParameterType) -> &'static str {
+    match param_type {
+        ParameterType::String => "string",
+        ParameterType::Number => "number",
+        ParameterType::Boolean => "boolean",
+        ParameterType::Array => "array",
+    }
+}
 
-    Ok(())
+/// Frontmatter type name of a parameter value
+fn param_value_type_name(value: &TemplateParamValue) -> &'static str {
+    match value {
+        TemplateParamValue::String(_) => "string",
+        TemplateParamValue::Number(_) => "number",
+        TemplateParamValue::Bool(_) => "boolean",
+        TemplateParamValue::StringArray(_) => "array",
+    }
 }
 
 /// Render a template with parameters and environment variables
@@ -137,189 +379,453 @@ fn validate_parameter_definition(param: &super::metadata::ParameterDefinition) -
 /// - Template size is validated before parsing (max 1MB)
 /// - Parameter sizes are validated before rendering (max 1MB per param, 10MB total)
 /// - Parameter count is limited (max 100 parameters)
-/// - `MiniJinja` has built-in recursion limits (default ~500 levels)
-/// - **Timeout enforcement (5 seconds) prevents infinite loops and expensive operations**
+/// - Recursion depth is limited (default 500 levels)
+/// - **Fuel (an instruction budget) stops infinite loops and expensive operations**,
+///   including the render thread itself
+/// - Output size is limited (default 10MB) while rendering
+/// - The caller waits at most the render timeout (default 5 seconds)
 /// - Rendering runs in `spawn_blocking` to prevent blocking async executor
+/// - Limits are the process-wide [`PromptLimits`](super::config::PromptLimits), tightened
+///   by the prompt's own `limits` frontmatter
 /// - These protections prevent resource exhaustion from malicious templates and parameters
+///
+/// Compiles the template for this one render; use [`CompiledTemplate`] to
+/// render the same template repeatedly or with other limits.
 pub async fn render_template(
     template: &PromptTemplate,
     parameters: Option<&HashMap<String, TemplateParamValue>>,
 ) -> Result<String> {
-    // Clone data for spawn_blocking (MiniJinja Environment is not Send)
-    let template_content = template.content.clone();
-    let template_filename = template.filename.clone();
-    let ctx = build_context(template, parameters)?;
-    
-    // Run rendering in blocking task pool with timeout
-    let render_task = tokio::task::spawn_blocking(move || {
-        let mut env = Environment::new();
-        env.set_auto_escape_callback(|_| minijinja::AutoEscape::None);
-        env.add_template(&template_filename, &template_content)?;
-        let tmpl = env.get_template(&template_filename)?;
-        tmpl.render(ctx)
-    });
-    
-    match timeout(Duration::from_secs(5), render_task).await {
-        Ok(Ok(Ok(rendered))) => Ok(rendered),
-        Ok(Ok(Err(e))) => Err(e.into()),
-        Ok(Err(e)) => Err(anyhow::anyhow!("Render task panicked: {e}")),
-        Err(_) => Err(anyhow::anyhow!(
-            "Template rendering timed out after 5 seconds. \
-             Template may contain infinite loops, deeply nested constructs, \
-             or expensive operations. Simplify the template and try again."
-        )),
-    }
+    CompiledTemplate::compile(template, super::config::limits())
+        .await?
+        .render(parameters)
+        .await
 }
 
-/// Build template context from parameters
-fn build_context(
+/// Rendered text with its token estimate
+#[derive(Debug, Clone)]
+pub struct RenderedPrompt {
+    /// Rendered text, cut to the prompt's budget when it truncates
+    pub content: String,
+    /// Estimated tokens and the prompt's budget
+    pub tokens: TokenEstimate,
+}
+
+/// Rendered messages with the token estimate of their text
+#[derive(Debug, Clone)]
+pub struct RenderedMessages {
+    /// Messages in template order, cut to the prompt's budget when it truncates
+    pub messages: Vec<RenderedMessage>,
+    /// Estimated tokens and the prompt's budget
+    pub tokens: TokenEstimate,
+}
+
+/// Rendered text plus where every context value came from
+#[derive(Debug, Clone)]
+pub struct RenderPreview {
+    /// Rendered text (as returned by [`render_template`])
+    pub rendered: String,
+    /// Parameters supplied by the caller
+    pub provided: Vec<String>,
+    /// Parameters filled in from their declared defaults
+    pub defaulted: Vec<String>,
+    /// Environment variables the template reads that are exposed to it
+    pub env_vars: Vec<String>,
+    /// Variables the template reads that are not defined (render as empty)
+    pub undefined: Vec<String>,
+    /// Time spent rendering in milliseconds
+    pub render_ms: f64,
+    /// Estimated tokens and the prompt's budget (set by
+    /// [`PromptManager::preview_prompt`](super::manager::PromptManager::preview_prompt))
+    pub tokens: Option<TokenEstimate>,
+}
+
+/// Render a template and report parameter provenance
+///
+/// Same rendering and security limits as [`render_template`]. Environment
+/// variables and undefined variables are found by inspecting the template, so
+/// names read only in branches that did not run are still listed.
+pub async fn render_template_preview(
     template: &PromptTemplate,
     parameters: Option<&HashMap<String, TemplateParamValue>>,
-) -> Result<minijinja::Value> {
-    let params = parameters.unwrap_or(&EMPTY_PARAMS);
+) -> Result<RenderPreview> {
+    CompiledTemplate::compile(template, super::config::limits())
+        .await?
+        .render_preview(parameters)
+        .await
+}
 
-    // 🔒 SECURITY: Validate parameter sizes FIRST (before any processing)
-    validate_parameter_sizes(params)?;
+/// Render a template into role-tagged messages
+///
+/// Content inside `{% message "system" %}...{% endmessage %}` blocks (roles:
+/// system, user, assistant) becomes one message per block. Text outside any
+/// block becomes a user message. Files referenced with `{{ attach("path") }}`
+/// or `{{ image("path") }}` are loaded through `sandbox` and attached to the
+/// enclosing message. Same security limits as [`render_template`].
+pub async fn render_template_messages(
+    template: &PromptTemplate,
+    parameters: Option<&HashMap<String, TemplateParamValue>>,
+    sandbox: Option<&AttachmentSandbox>,
+) -> Result<Vec<RenderedMessage>> {
+    CompiledTemplate::compile(template, super::config::limits())
+        .await?
+        .render_messages(parameters, sandbox)
+        .await
+}
 
-    // Validate parameters against definitions
-    validate_parameters(template, params)?;
+/// A prompt parsed once into a ready-to-render MiniJinja environment
+///
+/// Compiling expands message blocks, parses the template and applies the
+/// environment settings and render limits. Each render after that only
+/// evaluates it. [`PromptManager`](super::manager::PromptManager) keeps one
+/// per cached prompt version, so hot prompts are not reparsed on every render.
+pub struct CompiledTemplate {
+    template: PromptTemplate,
+    env: Environment<'static>,
+    limits: PromptLimits,
+}
 
-    // Apply defaults for missing optional parameters
-    let mut params_with_defaults = apply_defaults(template, params);
+impl std::fmt::Debug for CompiledTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompiledTemplate")
+            .field("name", &self.template.filename)
+            .field("limits", &self.limits)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CompiledTemplate {
+    /// Compile `template` to render under `limits` (parsing runs on the blocking pool)
+    ///
+    /// The prompt's own `limits` frontmatter can tighten `limits` but not
+    /// raise them.
+    ///
+    /// # Errors
+    /// Fails on template syntax errors and malformed message blocks.
+    pub async fn compile(template: &PromptTemplate, limits: &PromptLimits) -> Result<Arc<Self>> {
+        let template = template.clone();
+        let limits = limits.for_prompt(template.metadata.limits.as_ref());
+        tokio::task::spawn_blocking(move || Self::compile_blocking(template, limits).map(Arc::new))
+            .await
+            .map_err(|e| anyhow::anyhow!("Template compile task panicked: {e}"))?
+    }
+
+    fn compile_blocking(template: PromptTemplate, limits: PromptLimits) -> Result<Self> {
+        super::metrics::metrics().record_compile();
+        let source = expand_message_blocks(&template.content)?;
+
+        let mut env = Environment::new();
+        env.set_auto_escape_callback(|_| minijinja::AutoEscape::None);
+        // MiniJinja counts fuel in an isize; larger budgets would start out negative
+        env.set_fuel(Some(limits.render_fuel.min(isize::MAX as u64)));
+        env.set_recursion_limit(limits.max_recursion_depth);
+        let max_attachments = limits.max_attachments;
+        env.add_function("attach", move |state: &minijinja::State, path: String| {
+            count_attachment(state, max_attachments)?;
+            attachment_marker(AttachmentKind::Resource, &path)
+        });
+        env.add_function("image", move |state: &minijinja::State, path: String| {
+            count_attachment(state, max_attachments)?;
+            attachment_marker(AttachmentKind::Image, &path)
+        });
+        env.add_template_owned(template.filename.clone(), source)?;
+        Ok(Self { template, env, limits })
+    }
+
+    /// Template this was compiled from
+    pub fn template(&self) -> &PromptTemplate {
+        &self.template
+    }
+
+    /// Limits its renders run under (after the prompt's own limits)
+    pub fn limits(&self) -> &PromptLimits {
+        &self.limits
+    }
+
+    /// Render with parameters (see [`render_template`])
+    pub async fn render(
+        self: &Arc<Self>,
+        parameters: Option<&HashMap<String, TemplateParamValue>>,
+    ) -> Result<String> {
+        let ctx = build_context(&self.template, parameters, &self.limits)?;
+        self.render_with_markers(ctx.to_value(), |rendered, _| {
+            Ok(strip_message_markers(&rendered))
+        })
+        .await
+    }
 
-    // Add environment variables
-    add_env_vars(&mut params_with_defaults);
+    /// Render and report parameter provenance (see [`render_template_preview`])
+    pub async fn render_preview(
+        self: &Arc<Self>,
+        parameters: Option<&HashMap<String, TemplateParamValue>>,
+    ) -> Result<RenderPreview> {
+        let template = &self.template;
+        let ctx = build_context(template, parameters, &self.limits)?;
+
+        let start = std::time::Instant::now();
+        let rendered = self
+            .render_with_markers(ctx.to_value(), |rendered, _| Ok(rendered))
+            .await?;
+        let render_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+        let mut provided: Vec<String> = parameters
+            .map(|params| params.keys().cloned().collect())
+            .unwrap_or_default();
+        provided.sort();
+        let mut defaulted: Vec<String> = ctx
+            .params
+            .keys()
+            .filter(|name| !parameters.is_some_and(|params| params.contains_key(*name)))
+            .cloned()
+            .collect();
+        defaulted.sort();
+
+        let mut env_vars = Vec::new();
+        let mut undefined = Vec::new();
+        for name in referenced_variables(&template.content, true).unwrap_or_default() {
+            match name.split_once('.') {
+                Some(("env", var)) => {
+                    let var = var.split('.').next().unwrap_or(var);
+                    if ctx.env.contains_key(var) {
+                        env_vars.push(var.to_string());
+                    } else {
+                        undefined.push(format!("env.{var}"));
+                    }
+                }
+                _ => {
+                    let root = name.split('.').next().unwrap_or(&name);
+                    if root != "env" && !ctx.params.contains_key(root) {
+                        undefined.push(root.to_string());
+                    }
+                }
+            }
+        }
+        env_vars.sort();
+        env_vars.dedup();
+        undefined.sort();
+        undefined.dedup();
+
+        Ok(RenderPreview {
+            rendered: strip_message_markers(&rendered),
+            provided,
+            defaulted,
+            env_vars,
+            undefined,
+            render_ms,
+            tokens: None,
+        })
+    }
+
+    /// Render into role-tagged messages (see [`render_template_messages`])
+    pub async fn render_messages(
+        self: &Arc<Self>,
+        parameters: Option<&HashMap<String, TemplateParamValue>>,
+        sandbox: Option<&AttachmentSandbox>,
+    ) -> Result<Vec<RenderedMessage>> {
+        let ctx = build_context(&self.template, parameters, &self.limits)?;
+
+        // Attachments are loaded by the render thread, within the render timeout
+        let sandbox = sandbox.cloned();
+        let budget = AttachmentBudget::new(&self.limits);
+        self.render_with_markers(ctx.to_value(), move |rendered, cancelled| {
+            let mut budget = budget.with_cancel_flag(cancelled);
+            split_messages(&rendered, sandbox.as_ref(), &mut budget)
+        })
+        .await
+    }
 
-    Ok(minijinja::Value::from_serialize(&params_with_defaults))
+    /// Render, leaving message block markers in the output, then `finish` it
+    ///
+    /// `finish` runs on the render thread, inside the render timeout, with the
+    /// flag that is set when the caller stops waiting.
+    async fn render_with_markers<T, F>(
+        self: &Arc<Self>,
+        ctx: minijinja::Value,
+        finish: F,
+    ) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(String, Arc<AtomicBool>) -> Result<T> + Send + 'static,
+    {
+        let compiled = Arc::clone(self);
+        let limits = self.limits;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let mut sink = RenderSink::new(limits.max_output_size, cancelled.clone());
+        let finish_cancelled = cancelled.clone();
+        let running = super::metrics::metrics().render_thread();
+
+        // Run rendering in blocking task pool with timeout
+        let render_task = tokio::task::spawn_blocking(move || {
+            let _running = running;
+            let tmpl = compiled.env.get_template(&compiled.template.filename)?;
+            let rendered = match tmpl.render_captured_to(ctx, &mut sink) {
+                Ok(_) => sink.into_string(),
+                Err(_) if sink.overflowed => Err(anyhow::anyhow!(
+                    "Rendered output exceeds {} bytes. \
+                     Template may loop over large parameters; reduce the input or raise \
+                     KODEGEN_MAX_OUTPUT_SIZE (or the prompt's limits.max_output_size).",
+                    limits.max_output_size
+                )),
+                Err(e) if e.kind() == minijinja::ErrorKind::OutOfFuel => Err(anyhow::anyhow!(
+                    "Template rendering ran out of fuel after {} instructions. \
+                     Template may contain infinite loops or expensive operations. \
+                     Simplify the template or raise KODEGEN_RENDER_FUEL \
+                     (or the prompt's limits.fuel).",
+                    limits.render_fuel
+                )),
+                Err(e) => Err(e.into()),
+            }?;
+            finish(rendered, finish_cancelled)
+        });
+
+        let start = std::time::Instant::now();
+        let result = timeout(limits.render_timeout, render_task).await;
+        match &result {
+            Ok(_) => super::metrics::metrics().record_render(start.elapsed()),
+            Err(_) => super::metrics::metrics().record_render_timeout(),
+        }
+        match result {
+            Ok(Ok(rendered)) => rendered,
+            Ok(Err(e)) => Err(anyhow::anyhow!("Render task panicked: {e}")),
+            Err(_) => {
+                // Stops the render thread at its next write; fuel stops it otherwise
+                cancelled.store(true, Ordering::Relaxed);
+                Err(anyhow::anyhow!(
+                    "Template rendering timed out after {} ms. \
+                     Template may contain infinite loops, deeply nested constructs, \
+                     or expensive operations. Simplify the template and try again.",
+                    limits.render_timeout.as_millis()
+                ))
+            }
+        }
+    }
+}
+
+/// Name of the render temp counting `attach()` / `image()` calls
+const ATTACHMENT_COUNT: &str = "kodegen.attachments";
+
+/// Count one `attach()` / `image()` call against the render's attachment limit
+///
+/// Counted as the marker is emitted, so a template looping over `attach()`
+/// fails at the first call over the limit instead of after rendering.
+fn count_attachment(state: &minijinja::State, max: usize) -> Result<(), minijinja::Error> {
+    let count = state
+        .get_temp(ATTACHMENT_COUNT)
+        .and_then(|count| count.as_usize())
+        .unwrap_or(0)
+        + 1;
+    if count > max {
+        return Err(minijinja::Error::new(
+            minijinja::ErrorKind::InvalidOperation,
+            format!(
+                "template attaches more than {max} files; attach fewer files or raise \
+                 KODEGEN_MAX_ATTACHMENTS"
+            ),
+        ));
+    }
+    state.set_temp(ATTACHMENT_COUNT, minijinja::Value::from(count));
+    Ok(())
 }
 
-/// Match environment variable name against a glob-style pattern
+/// Render output buffer that enforces the output limit
 ///
-/// Patterns:
-/// - "*" matches all
-/// - "PREFIX*" matches names starting with PREFIX
-/// - "*SUFFIX" matches names ending with SUFFIX  
-/// - "*MIDDLE*" matches names containing MIDDLE
-/// - "EXACT" matches exact name
-fn matches_env_pattern(var_name: &str, pattern: &str) -> bool {
-    if pattern == "*" {
-        return true;
-    }
-
-    if pattern.starts_with('*') && pattern.ends_with('*') {
-        // *MIDDLE* - contains
-        if let Some(stripped) = pattern.strip_prefix('*').and_then(|s| s.strip_suffix('*')) {
-            var_name.contains(stripped)
-        } else {
-            false
+/// Writes fail once the output would exceed `max_size` or the caller has
+/// stopped waiting, which aborts the render.
+struct RenderSink {
+    output: Vec<u8>,
+    max_size: usize,
+    cancelled: Arc<AtomicBool>,
+    /// Set when a write was refused for exceeding `max_size`
+    overflowed: bool,
+}
+
+impl RenderSink {
+    fn new(max_size: usize, cancelled: Arc<AtomicBool>) -> Self {
+        Self {
+            output: Vec::new(),
+            max_size,
+            cancelled,
+            overflowed: false,
         }
-    } else if let Some(suffix) = pattern.strip_prefix('*') {
-        // *SUFFIX - ends with
-        var_name.ends_with(suffix)
-    } else if let Some(prefix) = pattern.strip_suffix('*') {
-        // PREFIX* - starts with
-        var_name.starts_with(prefix)
-    } else {
-        // EXACT - exact match
-        var_name == pattern
+    }
+
+    fn into_string(self) -> Result<String> {
+        String::from_utf8(self.output).context("Rendered output is not valid UTF-8")
     }
 }
 
-/// Load allowed environment variables from `KODEGEN_ALLOWED_ENV_VARS`
-/// Format: Colon-separated on Unix/macOS, semicolon-separated on Windows
-/// Default: Common safe variables (USER, HOME, SHELL, PWD, EDITOR, TERM, USERNAME, USERPROFILE)
-fn load_allowed_env_vars_from_env() -> Vec<String> {
-    let separator = if cfg!(windows) { ';' } else { ':' };
-
-    match std::env::var("KODEGEN_ALLOWED_ENV_VARS") {
-        Ok(custom) if !custom.is_empty() => {
-            custom.split(separator)
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect()
+impl std::io::Write for RenderSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(std::io::Error::other("render cancelled"));
         }
-        _ => {
-            // Default safe variables (Unix + Windows equivalents)
-            vec![
-                "USER", "HOME", "SHELL", "PWD", "EDITOR", "TERM",
-                "USERNAME", "USERPROFILE", "HOMEDRIVE", "HOMEPATH"
-            ]
-            .into_iter()
-            .map(String::from)
-            .collect()
+        if self.output.len() + buf.len() > self.max_size {
+            self.overflowed = true;
+            return Err(std::io::Error::other("render output limit exceeded"));
         }
+        self.output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
 }
 
-/// Load blocked environment variables from `KODEGEN_BLOCKED_ENV_VARS`
-/// Format: Colon-separated on Unix/macOS, semicolon-separated on Windows
-/// Default: Common sensitive patterns (*_SECRET, *_PASSWORD, *_TOKEN, *_KEY, etc.)
-fn load_blocked_env_vars_from_env() -> Vec<String> {
-    let separator = if cfg!(windows) { ';' } else { ':' };
-
-    match std::env::var("KODEGEN_BLOCKED_ENV_VARS") {
-        Ok(custom) => {
-            // If explicitly set (even to empty), use it
-            if custom.is_empty() {
-                vec![] // User disabled blocking
-            } else {
-                custom.split(separator)
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty())
-                    .collect()
-            }
-        }
-        _ => {
-            // Default: Block common sensitive patterns
-            vec![
-                "*_SECRET", "*SECRET*",
-                "*_PASSWORD", "*PASSWORD*",
-                "*_TOKEN", "*TOKEN*",
-                "*_KEY", "*KEY*",
-                "*_CREDENTIAL", "*CREDENTIAL*",
-                "*_AUTH", "*AUTH*",
-                "AWS_SECRET_ACCESS_KEY",
-                "GITHUB_TOKEN",
-                "DATABASE_PASSWORD",
-            ]
-            .into_iter()
-            .map(String::from)
-            .collect()
+/// Values available to a template while rendering
+struct RenderContext {
+    /// Caller parameters with defaults applied
+    params: HashMap<String, TemplateParamValue>,
+    /// Exposed environment variables (the `env` object)
+    env: Arc<BTreeMap<String, String>>,
+}
+
+impl RenderContext {
+    fn to_value(&self) -> minijinja::Value {
+        minijinja::context! {
+            env => &*self.env,
+            ..minijinja::Value::from_serialize(&self.params)
         }
     }
 }
 
-/// Add environment variables to template context
-///
-/// Security model:
-/// 1. Blocklist is checked FIRST (takes precedence)
-/// 2. Then allowlist is checked
-/// 3. Supports glob patterns (*, PREFIX*, *SUFFIX, *MIDDLE*)
-fn add_env_vars(params: &mut HashMap<String, TemplateParamValue>) {
-    let allowed_patterns = load_allowed_env_vars_from_env();
-    let blocked_patterns = load_blocked_env_vars_from_env();
-
-    let safe_env_vars: Vec<String> = std::env::vars()
-        .filter(|(key, _)| {
-            // STEP 1: Check blocklist first (takes precedence)
-            let is_blocked = blocked_patterns
-                .iter()
-                .any(|pattern| matches_env_pattern(key, pattern));
-
-            if is_blocked {
-                return false;
-            }
+/// Build template context from parameters
+fn build_context(
+    template: &PromptTemplate,
+    parameters: Option<&HashMap<String, TemplateParamValue>>,
+    limits: &PromptLimits,
+) -> Result<RenderContext> {
+    let params = parameters.unwrap_or(&EMPTY_PARAMS);
 
-            // STEP 2: Check allowlist
-            allowed_patterns
-                .iter()
-                .any(|pattern| matches_env_pattern(key, pattern))
-        })
-        .map(|(k, v)| format!("{k}={v}"))
-        .collect();
+    // 🔒 SECURITY: Validate parameter sizes FIRST (before any processing)
+    validate_parameter_sizes(params, limits)?;
+
+    // Validate parameters against definitions
+    validate_parameters(template, params)?;
 
-    // Add as env array in context (key=value format)
-    params.insert("env".to_string(), TemplateParamValue::StringArray(safe_env_vars));
+    // Apply defaults for missing optional parameters
+    let mut params = apply_defaults(template, params);
+
+    // 🔒 SECURITY: Caller values must not open messages or forge attachments
+    strip_param_markers(&mut params);
+
+    Ok(RenderContext {
+        params,
+        // Environment variables allowed by the env policy (markers already stripped)
+        env: env_snapshot(),
+    })
+}
+
+/// Strip message marker characters from every string parameter value
+fn strip_param_markers(params: &mut HashMap<String, TemplateParamValue>) {
+    for value in params.values_mut() {
+        match value {
+            TemplateParamValue::String(s) => strip_marker_chars(s),
+            TemplateParamValue::StringArray(items) => {
+                items.iter_mut().for_each(strip_marker_chars);
+            }
+            TemplateParamValue::Number(_) | TemplateParamValue::Bool(_) => {}
+        }
+    }
 }
 
 /// Get the byte size of a TemplateParamValue
@@ -333,10 +839,13 @@ fn param_value_size(value: &TemplateParamValue) -> usize {
 }
 
 /// Validate parameter sizes to prevent resource exhaustion
-fn validate_parameter_sizes(params: &HashMap<String, TemplateParamValue>) -> Result<()> {
-    let max_param_size = get_max_param_size();
-    let max_param_count = get_max_param_count();
-    let max_total_size = get_max_total_params_size();
+fn validate_parameter_sizes(
+    params: &HashMap<String, TemplateParamValue>,
+    limits: &PromptLimits,
+) -> Result<()> {
+    let max_param_size = limits.max_param_size;
+    let max_param_count = limits.max_param_count;
+    let max_total_size = limits.max_total_params_size;
 
     // Check parameter count
     if params.len() > max_param_count {
@@ -423,12 +932,7 @@ fn validate_parameter_type(
     );
 
     if !valid {
-        let actual_type = match value {
-            TemplateParamValue::String(_) => "string",
-            TemplateParamValue::Number(_) => "number",
-            TemplateParamValue::Bool(_) => "boolean",
-            TemplateParamValue::StringArray(_) => "array",
-        };
+        let actual_type = param_value_type_name(value);
         anyhow::bail!(
             "Parameter '{}' has wrong type. Expected {:?}, got {}",
             param_def.name,
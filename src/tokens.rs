@@ -0,0 +1,176 @@
+//! Token estimates and `max_tokens` budgets for rendered prompts
+//!
+//! Every render reports roughly how many tokens its output takes, so callers
+//! can see how much of a model's context a prompt uses before sending it. The
+//! count comes from a [`TokenEstimator`]. The default [`HeuristicEstimator`]
+//! needs no model vocabulary. Embedders with a real tokenizer can plug it in
+//! with [`PromptManager::with_token_estimator`](crate::PromptManager::with_token_estimator).
+//!
+//! A prompt declares its budget with `max_tokens` in frontmatter.
+//! `on_token_overflow` decides what a render over budget does: report it
+//! (`allow`, the default), fail, or cut the output to fit (`truncate`).
+
+use super::messages::RenderedMessage;
+use super::metadata::{PromptMetadata, TokenOverflow};
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Counts tokens in rendered text
+pub trait TokenEstimator: Send + Sync {
+    /// Short name reported with each estimate (e.g. `heuristic`, `cl100k`)
+    fn name(&self) -> &str;
+
+    /// Estimated number of tokens in `text`
+    fn estimate(&self, text: &str) -> usize;
+
+    /// Longest prefix of `text` estimated at no more than `max_tokens`
+    ///
+    /// The default binary-searches character boundaries with
+    /// [`estimate`](Self::estimate). Override it when the tokenizer can cut at
+    /// a token boundary directly.
+    fn truncate<'a>(&self, text: &'a str, max_tokens: usize) -> &'a str {
+        if self.estimate(text) <= max_tokens {
+            return text;
+        }
+        let boundaries: Vec<usize> = text.char_indices().map(|(index, _)| index).collect();
+        // Invariant: the prefix ending at boundaries[fits] fits, boundaries[.. too_long] may not
+        let (mut fits, mut too_long) = (0, boundaries.len());
+        while fits + 1 < too_long {
+            let mid = (fits + too_long) / 2;
+            if self.estimate(&text[..boundaries[mid]]) <= max_tokens {
+                fits = mid;
+            } else {
+                too_long = mid;
+            }
+        }
+        &text[..boundaries[fits]]
+    }
+}
+
+/// About four characters per token, the usual rule of thumb for English
+/// text and code with BPE tokenizers
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicEstimator;
+
+/// Characters per token assumed by [`HeuristicEstimator`]
+const CHARS_PER_TOKEN: usize = 4;
+
+impl TokenEstimator for HeuristicEstimator {
+    fn name(&self) -> &str {
+        "heuristic"
+    }
+
+    fn estimate(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(CHARS_PER_TOKEN)
+    }
+
+    fn truncate<'a>(&self, text: &'a str, max_tokens: usize) -> &'a str {
+        text.char_indices()
+            .nth(max_tokens.saturating_mul(CHARS_PER_TOKEN))
+            .map_or(text, |(index, _)| &text[..index])
+    }
+}
+
+/// Estimated size of a render, checked against the prompt's budget
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TokenEstimate {
+    /// Estimated tokens in the returned output (after any truncation)
+    pub tokens: usize,
+    /// Estimator that produced the count
+    pub estimator: String,
+    /// The prompt's `max_tokens` budget, if it declares one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<u32>,
+    /// Whether the output exceeds the budget (`on_token_overflow: allow`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub over_budget: bool,
+    /// Whether the output was cut to fit the budget, and its size before
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncated_from: Option<usize>,
+}
+
+/// Count `text` and apply the prompt's budget
+pub(crate) fn fit_text(
+    estimator: &dyn TokenEstimator,
+    metadata: &PromptMetadata,
+    text: String,
+) -> Result<(String, TokenEstimate)> {
+    let tokens = estimator.estimate(&text);
+    let mut estimate = check_budget(estimator, metadata, tokens)?;
+    let Some(budget) = estimate.truncated_from.and(estimate.budget) else {
+        return Ok((text, estimate));
+    };
+
+    let cut = estimator.truncate(&text, budget as usize).to_string();
+    estimate.tokens = estimator.estimate(&cut);
+    Ok((cut, estimate))
+}
+
+/// Count the text of `messages` and apply the prompt's budget
+///
+/// Truncation keeps messages in order until the budget runs out, cuts the
+/// message that crosses it, and drops the rest (with their attachments).
+pub(crate) fn fit_messages(
+    estimator: &dyn TokenEstimator,
+    metadata: &PromptMetadata,
+    messages: Vec<RenderedMessage>,
+) -> Result<(Vec<RenderedMessage>, TokenEstimate)> {
+    let tokens = messages.iter().map(|m| estimator.estimate(&m.content)).sum();
+    let mut estimate = check_budget(estimator, metadata, tokens)?;
+    let Some(budget) = estimate.truncated_from.and(estimate.budget) else {
+        return Ok((messages, estimate));
+    };
+
+    let mut remaining = budget as usize;
+    let mut kept = Vec::new();
+    for mut message in messages {
+        let tokens = estimator.estimate(&message.content);
+        if tokens > remaining {
+            message.content = estimator.truncate(&message.content, remaining).to_string();
+            if !message.content.trim().is_empty() {
+                kept.push(message);
+            }
+            break;
+        }
+        remaining -= tokens;
+        kept.push(message);
+    }
+    estimate.tokens = kept.iter().map(|m| estimator.estimate(&m.content)).sum();
+    Ok((kept, estimate))
+}
+
+/// Estimate for `tokens`, failing when the prompt says to on overflow
+///
+/// `truncated_from` is set when the caller has to truncate.
+fn check_budget(
+    estimator: &dyn TokenEstimator,
+    metadata: &PromptMetadata,
+    tokens: usize,
+) -> Result<TokenEstimate> {
+    let mut estimate = TokenEstimate {
+        tokens,
+        estimator: estimator.name().to_string(),
+        budget: metadata.max_tokens,
+        over_budget: false,
+        truncated_from: None,
+    };
+    let Some(budget) = metadata.max_tokens else {
+        return Ok(estimate);
+    };
+    if tokens <= budget as usize {
+        return Ok(estimate);
+    }
+
+    match metadata.on_token_overflow {
+        TokenOverflow::Allow => estimate.over_budget = true,
+        TokenOverflow::Truncate => estimate.truncated_from = Some(tokens),
+        TokenOverflow::Fail => anyhow::bail!(
+            "Rendered prompt is about {tokens} tokens ({} estimate), over its max_tokens \
+             budget of {budget}. Pass smaller parameters, raise max_tokens, or set \
+             on_token_overflow: truncate.",
+            estimator.name()
+        ),
+    }
+    Ok(estimate)
+}
@@ -0,0 +1,310 @@
+//! Per-prompt usage analytics kept beside the library
+//!
+//! Render counts, failures, last use and votes live in a small JSON sidecar
+//! file (`.usage.json` in the prompts directory by default) rather than in
+//! the prompt files, so recording them never rewrites a prompt, bumps its
+//! modification time or creates a git commit. Git storage only tracks
+//! `*.j2.md` files, and the store listing skips the sidecar.
+
+use anyhow::{Context, Result};
+use log::warn;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Sidecar file name inside a prompts directory
+pub const USAGE_FILE: &str = ".usage.json";
+
+/// Path of the usage sidecar, overriding the default location
+pub const USAGE_ENV: &str = "KODEGEN_PROMPTS_USAGE";
+
+/// How a prompt has been used
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PromptUsage {
+    /// Successful renders
+    #[serde(default)]
+    pub renders: u64,
+    /// Renders that failed (missing parameters, template errors, timeouts)
+    #[serde(default)]
+    pub failures: u64,
+    /// RFC 3339 time of the last render attempt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<String>,
+    /// Net votes cast with `prompt_vote`, added to the frontmatter `votes`
+    #[serde(default)]
+    pub votes: i64,
+}
+
+impl PromptUsage {
+    /// Frontmatter `votes` plus the votes cast since (never below zero)
+    pub fn total_votes(&self, frontmatter_votes: u32) -> u32 {
+        (i64::from(frontmatter_votes) + self.votes).clamp(0, i64::from(u32::MAX)) as u32
+    }
+
+    /// Add the render counts of `other`, keeping the later last use
+    fn add_renders(&mut self, other: &PromptUsage) {
+        self.renders += other.renders;
+        self.failures += other.failures;
+        // RFC 3339 UTC times in one format order lexicographically
+        if other.last_used > self.last_used {
+            self.last_used.clone_from(&other.last_used);
+        }
+    }
+}
+
+/// How long render counts wait in memory before they are written
+pub const FLUSH_DELAY: Duration = Duration::from_secs(1);
+
+/// Usage of every prompt, loaded on first use
+///
+/// Clones share state. Votes, renames and removals are saved with a
+/// temporary file and rename before they take effect in memory, so a vote
+/// that fails to save is not counted. Render counts are batched: the first
+/// render after a save schedules a write [`FLUSH_DELAY`] later, which saves
+/// every render counted in between. Call [`flush`](Self::flush) before the
+/// process exits to save the last batch.
+///
+/// Several processes (a server and the CLI) may share one file. Each save
+/// takes an exclusive lock on `<file>.lock`, re-reads the file and applies
+/// this store's changes on top, so no writer overwrites another's counts.
+#[derive(Debug, Clone)]
+pub struct UsageStore {
+    /// `None` keeps usage in memory only
+    path: Option<PathBuf>,
+    /// Write changes back to `path` (off for read-only libraries)
+    save: bool,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// `None` until first loaded from `path`
+    usage: Option<BTreeMap<String, PromptUsage>>,
+    /// Render counts not yet saved, added to the file's counts on the next save
+    unsaved_renders: BTreeMap<String, PromptUsage>,
+    /// A delayed flush is pending
+    flush_scheduled: bool,
+}
+
+impl UsageStore {
+    /// Usage persisted to the JSON file at `path`
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: Some(path.into()),
+            save: true,
+            state: Arc::default(),
+        }
+    }
+
+    /// Usage kept for the life of the process
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            save: false,
+            state: Arc::default(),
+        }
+    }
+
+    /// Store that reads the same file but keeps later changes in memory
+    ///
+    /// Used for read-only libraries, whose directory must not be written.
+    pub fn unsaved(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            save: false,
+            state: Arc::default(),
+        }
+    }
+
+    /// Usage of `name` (default when never used)
+    pub async fn get(&self, name: &str) -> Result<PromptUsage> {
+        self.read(|usage| usage.get(name).cloned().unwrap_or_default())
+            .await
+    }
+
+    /// Usage of every prompt that has any
+    pub async fn all(&self) -> Result<BTreeMap<String, PromptUsage>> {
+        self.read(Clone::clone).await
+    }
+
+    /// Count a render attempt of `name` (saved with the next batch)
+    pub async fn record_render(&self, name: &str, success: bool) -> Result<()> {
+        let mut render = PromptUsage {
+            last_used: Some(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+            ..PromptUsage::default()
+        };
+        if success {
+            render.renders = 1;
+        } else {
+            render.failures = 1;
+        }
+
+        let mut state = self.state.lock().await;
+        let usage = self.loaded(&mut state.usage).await?;
+        usage.entry(name.to_string()).or_default().add_renders(&render);
+
+        if self.persists() {
+            let state = &mut *state;
+            state.unsaved_renders.entry(name.to_string()).or_default().add_renders(&render);
+            if !state.flush_scheduled {
+                state.flush_scheduled = true;
+                let store = self.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(FLUSH_DELAY).await;
+                    if let Err(e) = store.flush().await {
+                        warn!("Failed to save prompt usage: {e:#}");
+                    }
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Save render counts that are still waiting for their batch
+    pub async fn flush(&self) -> Result<()> {
+        let mut state = self.state.lock().await;
+        state.flush_scheduled = false;
+        if state.unsaved_renders.is_empty() {
+            return Ok(());
+        }
+        self.merge_and_save(&mut state, |_| {}).await
+    }
+
+    /// Add `delta` to the net votes of `name`, returning its new usage
+    pub async fn vote(&self, name: &str, delta: i64) -> Result<PromptUsage> {
+        let mut updated = PromptUsage::default();
+        self.update(|usage| {
+            let entry = usage.entry(name.to_string()).or_default();
+            entry.votes += delta;
+            updated = entry.clone();
+        })
+        .await?;
+        Ok(updated)
+    }
+
+    /// Carry usage over to a prompt's new name
+    pub async fn rename(&self, old_name: &str, new_name: &str) -> Result<()> {
+        self.update(|usage| {
+            if let Some(entry) = usage.remove(old_name) {
+                usage.insert(new_name.to_string(), entry);
+            }
+        })
+        .await
+    }
+
+    /// Forget a deleted prompt
+    pub async fn remove(&self, name: &str) -> Result<()> {
+        self.update(|usage| {
+            usage.remove(name);
+        })
+        .await
+    }
+
+    fn persists(&self) -> bool {
+        self.save && self.path.is_some()
+    }
+
+    async fn read<R>(&self, f: impl FnOnce(&BTreeMap<String, PromptUsage>) -> R) -> Result<R> {
+        let mut state = self.state.lock().await;
+        let usage = self.loaded(&mut state.usage).await?;
+        Ok(f(usage))
+    }
+
+    /// Apply `f` to the saved usage, save it, and only then replace the usage in memory
+    ///
+    /// The save also covers any render counts waiting for their batch.
+    async fn update(&self, f: impl FnOnce(&mut BTreeMap<String, PromptUsage>)) -> Result<()> {
+        let mut state = self.state.lock().await;
+        if !self.persists() {
+            f(self.loaded(&mut state.usage).await?);
+            return Ok(());
+        }
+        self.merge_and_save(&mut state, f).await
+    }
+
+    /// Under the file lock: re-read the file, add the unsaved render counts,
+    /// apply `f`, save, and make the result the usage in memory
+    async fn merge_and_save(
+        &self,
+        state: &mut State,
+        f: impl FnOnce(&mut BTreeMap<String, PromptUsage>),
+    ) -> Result<()> {
+        let _lock = self.lock().await?;
+        let mut usage = self.load().await?;
+        for (name, renders) in &state.unsaved_renders {
+            usage.entry(name.clone()).or_default().add_renders(renders);
+        }
+        f(&mut usage);
+        self.save(&usage).await?;
+        state.usage = Some(usage);
+        state.unsaved_renders.clear();
+        Ok(())
+    }
+
+    /// Exclusive lock on `<file>.lock`, held until the returned file is dropped
+    async fn lock(&self) -> Result<std::fs::File> {
+        let Some(path) = &self.path else {
+            anyhow::bail!("Usage kept in memory has no file to lock");
+        };
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let lock_path = path.with_extension("json.lock");
+        tokio::task::spawn_blocking(move || {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&lock_path)
+                .with_context(|| format!("Failed to open usage lock: {}", lock_path.display()))?;
+            file.lock()
+                .with_context(|| format!("Failed to lock usage file: {}", lock_path.display()))?;
+            Ok(file)
+        })
+        .await
+        .context("Usage lock task failed")?
+    }
+
+    async fn loaded<'a>(
+        &self,
+        usage: &'a mut Option<BTreeMap<String, PromptUsage>>,
+    ) -> Result<&'a mut BTreeMap<String, PromptUsage>> {
+        if usage.is_none() {
+            *usage = Some(self.load().await?);
+        }
+        Ok(usage.get_or_insert_with(BTreeMap::new))
+    }
+
+    async fn load(&self) -> Result<BTreeMap<String, PromptUsage>> {
+        let Some(path) = &self.path else {
+            return Ok(BTreeMap::new());
+        };
+        match tokio::fs::read_to_string(path).await {
+            Ok(json) => serde_json::from_str(&json)
+                .with_context(|| format!("Invalid usage file: {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read usage file: {}", path.display())),
+        }
+    }
+
+    async fn save(&self, usage: &BTreeMap<String, PromptUsage>) -> Result<()> {
+        let Some(path) = self.path.as_ref().filter(|_| self.save) else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(usage).context("Failed to serialize usage")?;
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, json)
+            .await
+            .with_context(|| format!("Failed to write usage file: {}", tmp.display()))?;
+        tokio::fs::rename(&tmp, path)
+            .await
+            .with_context(|| format!("Failed to write usage file: {}", path.display()))?;
+        Ok(())
+    }
+}
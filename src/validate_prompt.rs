@@ -0,0 +1,140 @@
+use super::diagnostics::{format_diagnostic_lines, Diagnostic};
+use super::manager::{is_valid_prompt_name, PromptManager, PROMPT_NAME_RULE};
+use super::schema::{PromptValidateOutput, PromptValidatePrompts, ValidatePromptArgs, PROMPT_VALIDATE};
+use super::template::{parse_template, CompiledTemplate};
+use super::validation::check_prompt_content;
+use kodegen_mcp_schema::{McpError, Tool, ToolExecutionContext, ToolResponse};
+
+#[derive(Clone)]
+pub struct ValidatePromptTool {
+    manager: PromptManager,
+}
+
+impl ValidatePromptTool {
+    /// Create with a pre-initialized PromptManager (for HTTP server)
+    pub fn with_manager(manager: PromptManager) -> Self {
+        Self { manager }
+    }
+
+    /// Create with default manager (for standalone use)
+    pub async fn new() -> Result<Self, McpError> {
+        let manager = PromptManager::new();
+        manager.init().await?;
+        Ok(Self { manager })
+    }
+}
+
+impl Tool for ValidatePromptTool {
+    type Args = ValidatePromptArgs;
+    type Prompts = PromptValidatePrompts;
+
+    fn name() -> &'static str {
+        PROMPT_VALIDATE
+    }
+
+    fn description() -> &'static str {
+        "Validate prompt template content without saving it. Runs the same checks as \
+         prompt_add/prompt_edit plus lint checks (undeclared variables, unused parameters, \
+         deprecated prompts without a replacement) and returns every finding as a diagnostic \
+         with line, column, key path and a suggested fix. Errors make valid=false; warnings \
+         do not. Pass sample `parameters` (or render=true) to trial-render the template and \
+         get the output back in `rendered`. Nothing is written."
+    }
+
+    fn read_only() -> bool {
+        true
+    }
+
+    fn destructive() -> bool {
+        false
+    }
+
+    fn idempotent() -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Self::Args, _ctx: ToolExecutionContext) -> Result<ToolResponse<<Self::Args as kodegen_mcp_schema::ToolArgs>::Output>, McpError> {
+        let mut diagnostics = Vec::new();
+
+        // Target name: must be valid, and prompt_add fails if it is taken
+        if let Some(name) = &args.name {
+            if !is_valid_prompt_name(name) {
+                diagnostics.push(
+                    Diagnostic::new(format!("Invalid prompt name: '{name}'"))
+                        .with_suggestion(PROMPT_NAME_RULE),
+                );
+            } else if self.manager.prompt_exists(name).await {
+                diagnostics.push(
+                    Diagnostic::new(format!(
+                        "Prompt '{name}' already exists; prompt_add would fail"
+                    ))
+                    .with_suggestion("Use prompt_edit to update it, or choose another name")
+                    .as_warning(),
+                );
+            }
+        }
+
+        // Same checks as prompt_add / prompt_edit, plus lint
+        let filename = args.name.as_deref().unwrap_or("_validation");
+        let checks = check_prompt_content(filename, &args.content);
+        let file_valid = !checks.iter().any(Diagnostic::is_error);
+        diagnostics.extend(checks);
+
+        // Trial render needs a parsed template
+        let mut rendered = None;
+        if file_valid
+            && (args.render || args.parameters.is_some())
+            && let Ok(template) = parse_template(filename, &args.content)
+        {
+            let render = async {
+                CompiledTemplate::compile(&template, self.manager.limits())
+                    .await?
+                    .render(args.parameters.as_ref())
+                    .await
+            };
+            match render.await {
+                Ok(output) => rendered = Some(output),
+                Err(e) => diagnostics.push(
+                    Diagnostic::new(format!("Trial render failed: {e:#}"))
+                        .with_suggestion("Check the sample parameters against the declared parameters"),
+                ),
+            }
+        }
+
+        let error_count = diagnostics.iter().filter(|d| d.is_error()).count();
+        let warning_count = diagnostics.len() - error_count;
+        let valid = error_count == 0;
+        let label = args.name.as_deref().unwrap_or("(unnamed)");
+
+        // Terminal summary
+        let mut summary = if valid {
+            format!("\x1b[32m Prompt Valid: {label}\x1b[0m")
+        } else {
+            format!("\x1b[31m Prompt Invalid: {label}\x1b[0m")
+        };
+        summary.push_str(&format!("\n Errors: {error_count} · Warnings: {warning_count}"));
+        if !diagnostics.is_empty() {
+            summary.push('\n');
+            summary.push_str(&format_diagnostic_lines(&diagnostics));
+        }
+
+        let message = if valid {
+            format!("Content is valid ({warning_count} warning(s))")
+        } else {
+            format!("Content is invalid: {error_count} error(s), {warning_count} warning(s)")
+        };
+
+        // Typed output
+        let output = PromptValidateOutput {
+            valid,
+            name: args.name,
+            message,
+            error_count,
+            warning_count,
+            diagnostics,
+            rendered,
+        };
+
+        Ok(ToolResponse::new(summary, output))
+    }
+}
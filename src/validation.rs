@@ -1,3 +1,5 @@
+use super::diagnostics::{body_line_offset, diagnostics_of, Diagnostic, ValidationErrors};
+use super::metrics::metrics;
 use anyhow::Result;
 use minijinja::Environment;
 use lazy_static::lazy_static;
@@ -8,36 +10,105 @@ const MAX_TEMPLATE_SIZE: usize = 1_000_000;
 
 /// Validate `MiniJinja` template syntax
 pub fn validate_template_syntax(content: &str) -> Result<()> {
+    match template_syntax_diagnostic(content) {
+        Some(diagnostic) => anyhow::bail!("{}", diagnostic.message),
+        None => Ok(()),
+    }
+}
+
+/// Diagnostic for a template syntax error (line relative to the template body)
+fn template_syntax_diagnostic(content: &str) -> Option<Diagnostic> {
     let mut env = Environment::new();
 
-    // Try to add template - will fail if syntax invalid
-    env.add_template("_validation", content)
-        .map_err(|e| anyhow::anyhow!("Template syntax error: {e}"))?;
+    // Message blocks are not native MiniJinja tags - expand them first
+    let source = match super::messages::expand_message_blocks(content) {
+        Ok(source) => source,
+        Err(e) => {
+            return Some(
+                Diagnostic::new(e.to_string())
+                    .with_suggestion("Close every {% message %} block with {% endmessage %}"),
+            );
+        }
+    };
 
-    Ok(())
+    // Try to add template - will fail if syntax invalid
+    let e = env.add_template("_validation", &source).err()?;
+    let detail = e.detail().map_or_else(|| e.kind().to_string(), str::to_string);
+    let mut diagnostic = Diagnostic::new(format!("Template syntax error: {detail}"));
+    if let Some(line) = e.line() {
+        diagnostic = diagnostic.at_line(line);
+    }
+    Some(diagnostic.with_suggestion("Check that every {% %} and {{ }} tag is closed and balanced"))
 }
 
 /// Validate complete prompt file (metadata + content)
-pub fn validate_prompt_file(content: &str) -> Result<()> {
+///
+/// # Errors
+/// Returns every problem found as a [`ValidationErrors`] list with
+/// file-relative line numbers.
+pub fn validate_prompt_file(content: &str) -> Result<(), ValidationErrors> {
     // Validate size first (security: prevent resource exhaustion)
     if content.len() > MAX_TEMPLATE_SIZE {
-        anyhow::bail!(
-            "Template too large ({} bytes). Maximum size is {} bytes (1MB).",
-            content.len(),
-            MAX_TEMPLATE_SIZE
-        );
+        metrics().record_validation_failure("size");
+        return Err(ValidationErrors::single(
+            Diagnostic::new(format!(
+                "Template too large ({} bytes). Maximum size is {} bytes (1MB).",
+                content.len(),
+                MAX_TEMPLATE_SIZE
+            ))
+            .with_types(format!("at most {MAX_TEMPLATE_SIZE} bytes"), format!("{} bytes", content.len()))
+            .with_suggestion("Split the prompt into smaller templates"),
+        ));
     }
 
     // Parse to ensure valid structure
-    let template = super::template::parse_template("_validation", content)?;
+    let template = super::template::parse_template("_validation", content).map_err(|e| {
+        metrics().record_validation_failure("frontmatter");
+        ValidationErrors::new(diagnostics_of(&e))
+    })?;
+
+    let line_offset = body_line_offset(content, &template.content);
+    let mut diagnostics = Vec::new();
 
     // Validate template syntax
-    validate_template_syntax(&template.content)?;
+    if let Some(diagnostic) = template_syntax_diagnostic(&template.content) {
+        metrics().record_validation_failure("syntax");
+        diagnostics.push(diagnostic);
+    }
 
     // Additional checks
-    validate_no_dangerous_operations(&template.content)?;
+    for (rule, diagnostic) in dangerous_operation_diagnostics(&template.content) {
+        metrics().record_validation_failure(rule);
+        diagnostics.push(diagnostic);
+    }
+
+    if diagnostics.is_empty() {
+        return Ok(());
+    }
 
-    Ok(())
+    // Body lines are relative to the template content; make them file-relative
+    for diagnostic in &mut diagnostics {
+        if let Some(line) = diagnostic.line.as_mut() {
+            *line += line_offset;
+        }
+    }
+    Err(ValidationErrors::new(diagnostics))
+}
+
+/// Validation errors plus lint warnings for prompt file content
+///
+/// The checks `prompt_validate` and the `validate` / `lint` CLI commands
+/// share: everything `prompt_add` would reject, followed by lint findings
+/// for content that parses. Nothing is rendered.
+pub fn check_prompt_content(name: &str, content: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = match validate_prompt_file(content) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.diagnostics,
+    };
+    if let Ok(template) = super::template::parse_template(name, content) {
+        diagnostics.extend(super::lint::lint_template(&template, content));
+    }
+    diagnostics
 }
 
 lazy_static! {
@@ -66,32 +137,49 @@ lazy_static! {
             .expect("Failed to compile from import pattern");
 }
 
-/// Check for dangerous template operations
+/// Check for dangerous template operations, tagged with the rule broken
 /// Based on security policy and runtime constraints (no loader configured)
-fn validate_no_dangerous_operations(content: &str) -> Result<()> {
-    // Block include directives (file access)
-    if INCLUDE_PATTERN.is_match(content) {
-        anyhow::bail!(
+fn dangerous_operation_diagnostics(content: &str) -> Vec<(&'static str, Diagnostic)> {
+    let checks: [(&'static str, &[&Regex], &str); 3] = [
+        // Block include directives (file access)
+        (
+            "include",
+            &[&INCLUDE_PATTERN],
             "Template contains forbidden 'include' directive. \
-             File inclusion is not allowed for security reasons."
-        );
-    }
-
-    // Block extends directives (template inheritance)
-    if EXTENDS_PATTERN.is_match(content) {
-        anyhow::bail!(
+             File inclusion is not allowed for security reasons.",
+        ),
+        // Block extends directives (template inheritance)
+        (
+            "extends",
+            &[&EXTENDS_PATTERN],
             "Template contains forbidden 'extends' directive. \
-             Template inheritance is not supported."
-        );
-    }
-
-    // Block import directives (module loading)
-    if IMPORT_PATTERN.is_match(content) || FROM_IMPORT_PATTERN.is_match(content) {
-        anyhow::bail!(
+             Template inheritance is not supported.",
+        ),
+        // Block import directives (module loading)
+        (
+            "import",
+            &[&IMPORT_PATTERN, &FROM_IMPORT_PATTERN],
             "Template contains forbidden 'import' directive. \
-             Module imports are not allowed."
-        );
-    }
+             Module imports are not allowed.",
+        ),
+    ];
 
-    Ok(())
+    let mut diagnostics = Vec::new();
+    for (rule, patterns, message) in checks {
+        let first_match = patterns
+            .iter()
+            .filter_map(|pattern| pattern.find(content))
+            .min_by_key(|m| m.start());
+        if let Some(m) = first_match {
+            let line = content[..m.start()].matches('\n').count() + 1;
+            let column = m.start() - content[..m.start()].rfind('\n').map_or(0, |i| i + 1) + 1;
+            diagnostics.push((
+                rule,
+                Diagnostic::new(message)
+                    .at(line, column)
+                    .with_suggestion("Inline the content directly in this template"),
+            ));
+        }
+    }
+    diagnostics
 }
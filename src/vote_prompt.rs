@@ -0,0 +1,78 @@
+use super::manager::PromptManager;
+use super::schema::{PromptVoteOutput, PromptVotePrompts, VoteDirection, VotePromptArgs, PROMPT_VOTE};
+use kodegen_mcp_schema::{McpError, Tool, ToolExecutionContext, ToolResponse};
+
+#[derive(Clone)]
+pub struct VotePromptTool {
+    manager: PromptManager,
+}
+
+impl VotePromptTool {
+    /// Create with a pre-initialized PromptManager (for HTTP server)
+    pub fn with_manager(manager: PromptManager) -> Self {
+        Self { manager }
+    }
+
+    /// Create with default manager (for standalone use)
+    pub async fn new() -> Result<Self, McpError> {
+        let manager = PromptManager::new();
+        manager.init().await?;
+        Ok(Self { manager })
+    }
+}
+
+impl Tool for VotePromptTool {
+    type Args = VotePromptArgs;
+    type Prompts = PromptVotePrompts;
+
+    fn name() -> &'static str {
+        PROMPT_VOTE
+    }
+
+    fn description() -> &'static str {
+        "Vote a prompt up or down after using it. Votes are stored with the usage analytics \
+         (not in the prompt file) and added to the frontmatter votes; each vote is applied \
+         and saved atomically. prompt_get list_prompts with sort=votes or sort=popular \
+         puts the most useful prompts first."
+    }
+
+    fn read_only() -> bool {
+        false // Changes the vote count
+    }
+
+    fn destructive() -> bool {
+        false
+    }
+
+    fn idempotent() -> bool {
+        false // Every call is another vote
+    }
+
+    async fn execute(&self, args: Self::Args, _ctx: ToolExecutionContext) -> Result<ToolResponse<<Self::Args as kodegen_mcp_schema::ToolArgs>::Output>, McpError> {
+        let (template, usage) = self
+            .manager
+            .vote_prompt(&args.name, args.direction == VoteDirection::Up)
+            .await
+            .map_err(McpError::Other)?;
+        let votes = usage.total_votes(template.metadata.votes);
+
+        let (arrow, verb) = match args.direction {
+            VoteDirection::Up => ("▲", "up"),
+            VoteDirection::Down => ("▼", "down"),
+        };
+        let summary = format!(
+            "\x1b[32m{arrow} Prompt Voted: {}\x1b[0m\n  Votes: {votes} · Renders: {}",
+            template.filename, usage.renders
+        );
+
+        let output = PromptVoteOutput {
+            success: true,
+            name: template.filename.clone(),
+            direction: args.direction,
+            votes,
+            message: format!("Voted '{}' {verb} ({votes} votes)", template.filename),
+            usage,
+        };
+        Ok(ToolResponse::new(summary, output))
+    }
+}
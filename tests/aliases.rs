@@ -0,0 +1,66 @@
+//! Alias resolution and alias collisions
+
+use kodegen_tools_prompt::{MemoryStore, PromptManager, ValidationErrors};
+
+fn prompt(title: &str, aliases: &[&str]) -> String {
+    format!(
+        "---
+title: \"{title}\"
+description: \"Alias test\"
+categories: [\"test\"]
+author: \"test\"
+aliases: {aliases:?}
+---
+Hello from {title}
+"
+    )
+}
+
+fn rejection(result: anyhow::Result<()>) -> String {
+    let error = result.expect_err("write must be rejected");
+    assert!(error.downcast_ref::<ValidationErrors>().is_some(), "{error:#}");
+    format!("{error:#}")
+}
+
+#[tokio::test]
+async fn aliases_resolve_and_follow_writes() -> anyhow::Result<()> {
+    let manager = PromptManager::with_store(MemoryStore::new());
+    manager.add_prompt("intro", &prompt("intro", &["onboarding"])).await?;
+    assert_eq!(manager.load_prompt("onboarding").await?.filename, "intro");
+
+    // Renaming keeps the old name and the aliases pointing at the prompt
+    manager.rename_prompt("intro", "welcome").await?;
+    assert_eq!(manager.load_prompt("intro").await?.filename, "welcome");
+    assert_eq!(manager.load_prompt("onboarding").await?.filename, "welcome");
+
+    // Dropping an alias frees it
+    manager.edit_prompt("welcome", &prompt("welcome", &["intro"])).await?;
+    assert!(manager.load_prompt("onboarding").await.is_err());
+    manager.add_prompt("onboarding", &prompt("onboarding", &[])).await?;
+
+    manager.delete_prompt("welcome").await?;
+    assert!(manager.load_prompt("intro").await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn colliding_names_and_aliases_are_rejected() -> anyhow::Result<()> {
+    let manager = PromptManager::with_store(MemoryStore::new());
+    manager.add_prompt("intro", &prompt("intro", &["onboarding"])).await?;
+
+    let message = rejection(manager.add_prompt("onboarding", &prompt("x", &[])).await);
+    assert!(message.contains("already an alias of prompt 'intro'"), "{message}");
+
+    let message = rejection(manager.add_prompt("other", &prompt("x", &["intro"])).await);
+    assert!(message.contains("name of another prompt"), "{message}");
+
+    let message = rejection(manager.add_prompt("other", &prompt("x", &["onboarding"])).await);
+    assert!(message.contains("already used by prompt 'intro'"), "{message}");
+
+    let message = rejection(manager.add_prompt("other", &prompt("x", &["other"])).await);
+    assert!(message.contains("own name"), "{message}");
+
+    // A prompt may keep its own aliases when edited
+    manager.edit_prompt("intro", &prompt("intro v2", &["onboarding"])).await?;
+    Ok(())
+}
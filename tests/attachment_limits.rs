@@ -0,0 +1,98 @@
+//! Attachment count and total size limits, checked before files are read
+
+use kodegen_tools_prompt::template::{parse_template, CompiledTemplate};
+use kodegen_tools_prompt::{AttachmentSandbox, PromptLimits};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Workspace with `count` files of `size` bytes named `file{n}.txt`
+struct Workspace(PathBuf);
+
+impl Workspace {
+    fn new(name: &str, count: usize, size: usize) -> Self {
+        let dir = std::env::temp_dir().join(format!("kodegen-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create workspace");
+        for n in 0..count {
+            std::fs::write(dir.join(format!("file{n}.txt")), "x".repeat(size)).expect("write file");
+        }
+        Self(dir)
+    }
+
+    fn sandbox(&self) -> AttachmentSandbox {
+        AttachmentSandbox::new(&self.0, &self.0)
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn limits() -> PromptLimits {
+    PromptLimits {
+        max_attachments: 3,
+        max_attachment_size: 1_000,
+        max_total_attachment_size: 2_000,
+        ..PromptLimits::default()
+    }
+}
+
+async fn render_messages(
+    body: &str,
+    workspace: &Workspace,
+    limits: &PromptLimits,
+) -> anyhow::Result<usize> {
+    let source = format!(
+        "---\ntitle: \"Attachments\"\ndescription: \"Attaches files\"\n\
+         categories: [\"test\"]\nauthor: \"test\"\n---\n{body}\n"
+    );
+    let template = parse_template("attachments", &source)?;
+    let messages = CompiledTemplate::compile(&template, limits)
+        .await?
+        .render_messages(None, Some(&workspace.sandbox()))
+        .await?;
+    Ok(messages.iter().map(|m| m.attachments.len()).sum())
+}
+
+#[tokio::test]
+async fn attachments_within_limits_are_loaded() -> anyhow::Result<()> {
+    let workspace = Workspace::new("attach-ok", 3, 500);
+    let body = "{% for n in range(3) %}{{ attach('file' ~ n ~ '.txt') }}{% endfor %}";
+
+    assert_eq!(render_messages(body, &workspace, &limits()).await?, 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn attachment_loop_stops_at_the_count_limit() {
+    let workspace = Workspace::new("attach-loop", 1, 1_000);
+    let body = "{% for i in range(100000) %}{{ attach('file0.txt') }}{% endfor %}";
+
+    let start = Instant::now();
+    let error = render_messages(body, &workspace, &limits()).await.unwrap_err();
+
+    assert!(format!("{error:#}").contains("more than 3 files"), "{error:#}");
+    assert!(start.elapsed() < Duration::from_secs(2), "took {:?}", start.elapsed());
+}
+
+#[tokio::test]
+async fn attachments_over_the_total_size_are_rejected() {
+    let workspace = Workspace::new("attach-total", 3, 800);
+    let body = "{% for n in range(3) %}{{ attach('file' ~ n ~ '.txt') }}{% endfor %}";
+
+    let error = render_messages(body, &workspace, &limits()).await.unwrap_err();
+
+    assert!(format!("{error:#}").contains("max 2000 bytes in total"), "{error:#}");
+}
+
+#[tokio::test]
+async fn attachments_over_the_file_size_are_rejected() {
+    let workspace = Workspace::new("attach-size", 1, 1_500);
+
+    let error = render_messages("{{ attach('file0.txt') }}", &workspace, &limits())
+        .await
+        .unwrap_err();
+
+    assert!(format!("{error:#}").contains("is too large: 1500 bytes"), "{error:#}");
+}
@@ -0,0 +1,145 @@
+//! Audit log queries and the hashes recorded for renders and failed changes
+
+use anyhow::Context;
+use kodegen_tools_prompt::policy::with_client;
+use kodegen_tools_prompt::{
+    AuditEntry, AuditLog, AuditOutcome, AuditQuery, ClientIdentity, MemoryStore, Policy,
+    PolicyDenied, PromptManager,
+};
+use std::sync::Arc;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+fn temp_log(test: &str) -> anyhow::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("kodegen-audit-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join("audit.jsonl"))
+}
+
+fn sha256_hex(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn prompt(title: &str) -> String {
+    format!(
+        "---
+title: \"{title}\"
+description: \"Audit test\"
+categories: [\"test\"]
+author: \"test\"
+---
+Hello from {title}
+"
+    )
+}
+
+#[tokio::test]
+async fn query_reads_newest_entries_across_chunks() -> anyhow::Result<()> {
+    let path = temp_log("query")?;
+    let log = AuditLog::new(&path);
+    // Long names spread the entries over several read chunks
+    let padding = "x".repeat(200);
+    for i in 0..2000 {
+        log.append(&AuditEntry::new("prompt_get", &format!("p{i}-{padding}"), None))
+            .await?;
+    }
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, b"not json\n\n"))?;
+
+    let newest = log.query(&AuditQuery { limit: 3, ..AuditQuery::default() }).await?;
+    let all = log.query(&AuditQuery { limit: usize::MAX, ..AuditQuery::default() }).await?;
+    let oldest = log
+        .query(&AuditQuery {
+            prompt: Some(format!("p0-{padding}")),
+            limit: 10,
+            ..AuditQuery::default()
+        })
+        .await?;
+    std::fs::remove_dir_all(path.parent().unwrap())?;
+
+    let names: Vec<&str> = newest.iter().map(|e| e.prompt.split('-').next().unwrap()).collect();
+    assert_eq!(names, ["p1999", "p1998", "p1997"]);
+    assert_eq!(all.len(), 2000, "every line is read once, malformed ones skipped");
+    assert!(all.iter().enumerate().all(|(i, e)| e.prompt.starts_with(&format!("p{}-", 1999 - i))));
+    assert_eq!(oldest.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn render_records_the_hash_of_the_version_rendered() -> anyhow::Result<()> {
+    let path = temp_log("render")?;
+    let manager = PromptManager::with_store(MemoryStore::new()).with_audit_log(AuditLog::new(&path));
+    let content = prompt("greeting");
+    manager.add_prompt("greeting", &content).await?;
+
+    manager
+        .auditing_render(AuditEntry::new("prompt_get", "greeting", None).with_action("render"))
+        .render_prompt("greeting", None)
+        .await?;
+    let failed = manager
+        .auditing_render(AuditEntry::new("prompt_get", "missing", None).with_action("render"))
+        .render_prompt("missing", None)
+        .await;
+    let entries = manager
+        .audit_log()
+        .unwrap()
+        .query(&AuditQuery { limit: 10, ..AuditQuery::default() })
+        .await?;
+    std::fs::remove_dir_all(path.parent().unwrap())?;
+
+    assert!(failed.is_err());
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].outcome, AuditOutcome::Failure);
+    assert_eq!(entries[0].hash_after, None);
+    assert_eq!(entries[1].outcome, AuditOutcome::Success);
+    assert_eq!(entries[1].hash_after.as_deref(), Some(sha256_hex(&content).as_str()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn only_policy_denials_are_recorded_as_denied() -> anyhow::Result<()> {
+    let policy = Policy::from_yaml("default:\n  - actions: [read]\n    prompts: [\"*\"]\n")?;
+    let manager = PromptManager::with_store(MemoryStore::new());
+    manager.add_prompt("greeting", &prompt("greeting")).await?;
+    let guarded = manager.clone().with_policy(Arc::new(policy));
+
+    let denied = with_client(ClientIdentity::default(), guarded.render_prompt("greeting", None))
+        .await
+        .map(|_| ())
+        .context("Render failed");
+    let error = denied.as_ref().expect_err("rendering is not allowed");
+    assert!(error.downcast_ref::<PolicyDenied>().is_some(), "{error:#}");
+    let outcome = |result: &anyhow::Result<()>| {
+        AuditEntry::new("prompt_get", "greeting", None).with_result(result).outcome
+    };
+    assert_eq!(outcome(&denied), AuditOutcome::Denied);
+
+    // Same wording, but from the OS rather than the policy
+    let io = Err(anyhow::anyhow!("Permission denied: os error 13"));
+    assert_eq!(outcome(&io), AuditOutcome::Failure);
+    Ok(())
+}
+
+#[tokio::test]
+async fn hashes_belong_to_the_exact_prompt_named() -> anyhow::Result<()> {
+    let path = temp_log("alias")?;
+    let manager = PromptManager::with_store(MemoryStore::new()).with_audit_log(AuditLog::new(&path));
+    let content = prompt("intro").replace("---\nHello", "aliases: [\"onboarding\"]\n---\nHello");
+    manager.add_prompt("intro", &content).await?;
+
+    let by_alias = manager.audit_hash("onboarding").await;
+    let delete_by_alias = manager.remove_prompt("onboarding").await;
+    let removed = manager.remove_prompt("intro").await?;
+    std::fs::remove_dir_all(path.parent().unwrap())?;
+
+    assert_eq!(by_alias, None, "aliases are not resolved for hashes");
+    assert!(delete_by_alias.is_err(), "deletes need the stored name");
+    assert_eq!(removed, content, "the removed content is what gets hashed");
+    Ok(())
+}
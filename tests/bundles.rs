@@ -0,0 +1,240 @@
+//! Prompt bundles: export/import round trips, conflicts and rollback
+
+use async_trait::async_trait;
+use kodegen_tools_prompt::bundle::ImportAction;
+use kodegen_tools_prompt::policy::with_client;
+use kodegen_tools_prompt::store::StoreResult;
+use kodegen_tools_prompt::{
+    BundleFormat, ClientIdentity, ConflictPolicy, ExportFilter, MemoryStore, Policy, PromptBundle,
+    PromptManager, PromptStore, StoreError, StoredMetadata,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+fn prompt(title: &str) -> String {
+    format!(
+        "---
+title: \"{title}\"
+description: \"Bundle test\"
+categories: [\"test\"]
+author: \"test\"
+parameters:
+  - name: \"who\"
+    description: \"Who to greet\"
+    default: \"world\"
+---
+{title}: hello {{{{ who }}}}
+"
+    )
+}
+
+/// Memory store that counts writes and can fail creating one prompt
+#[derive(Default)]
+struct FlakyStore {
+    inner: MemoryStore,
+    fail_create: Option<&'static str>,
+    writes: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl PromptStore for FlakyStore {
+    async fn init(&self) -> anyhow::Result<()> {
+        self.inner.init().await
+    }
+
+    async fn list(&self) -> StoreResult<Vec<String>> {
+        self.inner.list().await
+    }
+
+    async fn read(&self, name: &str) -> StoreResult<String> {
+        self.inner.read(name).await
+    }
+
+    async fn metadata(&self, name: &str) -> StoreResult<Option<StoredMetadata>> {
+        self.inner.metadata(name).await
+    }
+
+    async fn create(&self, name: &str, content: &str) -> StoreResult<()> {
+        if self.fail_create == Some(name) {
+            return Err(StoreError::Other(anyhow::anyhow!("disk full")));
+        }
+        self.writes.fetch_add(1, Ordering::SeqCst);
+        self.inner.create(name, content).await
+    }
+
+    async fn update(&self, name: &str, content: &str) -> StoreResult<()> {
+        self.writes.fetch_add(1, Ordering::SeqCst);
+        self.inner.update(name, content).await
+    }
+
+    async fn delete(&self, name: &str) -> StoreResult<()> {
+        self.writes.fetch_add(1, Ordering::SeqCst);
+        self.inner.delete(name).await
+    }
+}
+
+async fn library(names: &[&str]) -> anyhow::Result<PromptManager> {
+    let manager = PromptManager::with_store(MemoryStore::new());
+    for name in names {
+        manager.add_prompt(name, &prompt(name)).await?;
+    }
+    Ok(manager)
+}
+
+fn bundle(names: &[&str]) -> anyhow::Result<PromptBundle> {
+    let mut templates = Vec::new();
+    for name in names {
+        let content = prompt(name);
+        templates.push((kodegen_tools_prompt::template::parse_template(name, &content)?, content));
+    }
+    Ok(PromptBundle::from_templates(templates))
+}
+
+fn outcomes(report: &kodegen_tools_prompt::ImportReport) -> Vec<(&str, &str, ImportAction)> {
+    report
+        .outcomes
+        .iter()
+        .map(|o| (o.source.as_str(), o.name.as_str(), o.action))
+        .collect()
+}
+
+#[tokio::test]
+async fn export_then_import_round_trips_in_every_format() -> anyhow::Result<()> {
+    let names = ["greeting", "review/security", "review/web/xss"];
+    let source = library(&names).await?;
+    let exported = source.export_prompts(&ExportFilter::default()).await?;
+
+    for format in [BundleFormat::TarGz, BundleFormat::Json, BundleFormat::Yaml] {
+        let bytes = exported.encode(format)?;
+        assert_eq!(BundleFormat::detect(&bytes), format);
+        let decoded = PromptBundle::decode(&bytes, None)?;
+        decoded.validate()?;
+
+        let target = PromptManager::with_store(MemoryStore::new());
+        let report = target.import_prompts(&decoded, ConflictPolicy::Skip, false).await?;
+        assert_eq!(report.count(ImportAction::Created), names.len(), "{format:?}");
+        assert_eq!(target.prompt_names().await?, names, "{format:?}");
+        for name in names {
+            assert_eq!(
+                target.read_prompt_source(name).await?,
+                source.read_prompt_source(name).await?,
+                "{format:?} {name}"
+            );
+        }
+        let rendered = target.render_prompt("review/security", None).await?;
+        assert_eq!(rendered.content.trim(), "review/security: hello world");
+    }
+
+    let selected = source
+        .export_prompts(&ExportFilter { names: vec!["review/*".into()], ..ExportFilter::default() })
+        .await?;
+    let selected: Vec<&str> = selected.prompts.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(selected, ["review/security", "review/web/xss"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn conflicts_skip_overwrite_or_rename() -> anyhow::Result<()> {
+    let incoming = bundle(&["greeting", "fresh"])?;
+    let kept = prompt("greeting").replace("hello", "hi");
+
+    // Skip keeps the existing prompt
+    let manager = library(&[]).await?;
+    manager.add_prompt("greeting", &kept).await?;
+    let report = manager.import_prompts(&incoming, ConflictPolicy::Skip, false).await?;
+    assert_eq!(
+        outcomes(&report),
+        [("greeting", "greeting", ImportAction::Skipped), ("fresh", "fresh", ImportAction::Created)]
+    );
+    assert_eq!(manager.read_prompt_source("greeting").await?, kept);
+
+    // Overwrite replaces it
+    let manager = library(&[]).await?;
+    manager.add_prompt("greeting", &kept).await?;
+    let report = manager.import_prompts(&incoming, ConflictPolicy::Overwrite, false).await?;
+    assert_eq!(report.count(ImportAction::Overwritten), 1);
+    assert_eq!(manager.read_prompt_source("greeting").await?, prompt("greeting"));
+
+    // Rename picks the first free name, skipping names in the bundle
+    let manager = library(&["greeting", "greeting_2"]).await?;
+    let crowded = bundle(&["greeting", "greeting_3"])?;
+    let report = manager.import_prompts(&crowded, ConflictPolicy::Rename, false).await?;
+    assert_eq!(
+        outcomes(&report),
+        [
+            ("greeting", "greeting_4", ImportAction::Renamed),
+            ("greeting_3", "greeting_3", ImportAction::Created),
+        ]
+    );
+    assert_eq!(manager.read_prompt_source("greeting").await?, prompt("greeting"));
+
+    // A dry run plans the same outcomes and writes nothing
+    let manager = library(&[]).await?;
+    manager.add_prompt("greeting", &kept).await?;
+    let report = manager.import_prompts(&incoming, ConflictPolicy::Overwrite, true).await?;
+    assert!(report.dry_run);
+    assert_eq!(report.count(ImportAction::Overwritten), 1);
+    assert_eq!(report.count(ImportAction::Created), 1);
+    assert_eq!(manager.read_prompt_source("greeting").await?, kept);
+    assert!(!manager.prompt_exists("fresh").await);
+    Ok(())
+}
+
+#[tokio::test]
+async fn failed_import_rolls_back_earlier_writes() -> anyhow::Result<()> {
+    let store = FlakyStore { fail_create: Some("broken"), ..FlakyStore::default() };
+    let manager = PromptManager::with_store(store);
+    let original = prompt("existing").replace("hello", "hi");
+    manager.add_prompt("existing", &original).await?;
+
+    let incoming = bundle(&["existing", "created", "broken", "never"])?;
+    let error = manager
+        .import_prompts(&incoming, ConflictPolicy::Overwrite, false)
+        .await
+        .expect_err("creating 'broken' fails");
+    let message = format!("{error:#}");
+    assert!(message.contains("Import failed at 'broken'"), "{message}");
+    assert!(message.contains("2 prompt(s) already imported were rolled back"), "{message}");
+
+    assert_eq!(manager.read_prompt_source("existing").await?, original);
+    assert!(!manager.prompt_exists("created").await);
+    assert!(!manager.prompt_exists("never").await);
+    assert_eq!(manager.prompt_names().await?, ["existing"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn every_entry_is_authorized_before_any_write() -> anyhow::Result<()> {
+    // Creating is allowed everywhere, overwriting nothing
+    let policy = Policy::from_yaml("default:\n  - actions: [read, create]\n    prompts: [\"*\"]\n")?;
+    let writes = Arc::new(AtomicUsize::new(0));
+    let store = FlakyStore { writes: writes.clone(), ..FlakyStore::default() };
+    let manager = PromptManager::with_store(store);
+    manager.add_prompt("zz-last", &prompt("zz-last")).await?;
+    let before = writes.load(Ordering::SeqCst);
+    let guarded = manager.clone().with_policy(Arc::new(policy));
+
+    // The denied overwrite is the last entry, after two allowed creates
+    let incoming = bundle(&["aa-first", "bb-second", "zz-last"])?;
+    let error = with_client(
+        ClientIdentity::default(),
+        guarded.import_prompts(&incoming, ConflictPolicy::Overwrite, false),
+    )
+    .await
+    .expect_err("overwriting zz-last is denied");
+    let message = format!("{error:#}");
+    assert!(message.contains("nothing was written"), "{message}");
+    assert!(message.contains("zz-last") && !message.contains("aa-first"), "{message}");
+    assert_eq!(writes.load(Ordering::SeqCst), before, "no store writes");
+    assert_eq!(manager.prompt_names().await?, ["zz-last"]);
+
+    // Skipping the conflict needs no update permission
+    let report = with_client(
+        ClientIdentity::default(),
+        guarded.import_prompts(&incoming, ConflictPolicy::Skip, false),
+    )
+    .await?;
+    assert_eq!(report.count(ImportAction::Created), 2);
+    assert_eq!(report.count(ImportAction::Skipped), 1);
+    Ok(())
+}
@@ -0,0 +1,202 @@
+//! Command-line subcommands: exit codes and subcommand detection
+
+use assert_cmd::Command;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+fn temp_dir(test: &str) -> anyhow::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("kodegen-cli-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+const GREETING: &str = "---
+title: \"Greeting\"
+description: \"Greets someone\"
+categories: [\"test\"]
+author: \"test\"
+parameters:
+  - name: \"who\"
+    description: \"Who to greet\"
+    default: \"world\"
+---
+Hello {{ who }}
+";
+
+/// Valid, but `unused` is never read by the template
+const UNUSED_PARAMETER: &str = "---
+title: \"Unused\"
+description: \"Declares a parameter it never reads\"
+categories: [\"test\"]
+author: \"test\"
+parameters:
+  - name: \"unused\"
+    description: \"Never read\"
+---
+Nothing to fill in
+";
+
+const BAD_YAML: &str = "---
+title: [unclosed
+---
+Hello
+";
+
+/// The binary run in `dir`, with its prompts kept under `dir/config`
+fn kodegen(dir: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_kodegen-prompt"));
+    command
+        .current_dir(dir)
+        .env("HOME", dir)
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .env_remove("KODEGEN_PROMPTS_DB")
+        .env_remove("KODEGEN_PROMPTS_READ_ONLY")
+        .env_remove("KODEGEN_PROMPTS_GIT")
+        // A subcommand that fell through to the server would never exit
+        .timeout(Duration::from_secs(60));
+    command
+}
+
+fn write(dir: &Path, file: &str, content: &str) -> anyhow::Result<PathBuf> {
+    let path = dir.join(file);
+    std::fs::write(&path, content)?;
+    Ok(path)
+}
+
+fn stdout(output: &std::process::Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn validate_exits_invalid_only_for_errors() -> anyhow::Result<()> {
+    let dir = temp_dir("validate")?;
+    let valid = write(&dir, "greeting.j2.md", GREETING)?;
+    let warned = write(&dir, "unused.j2.md", UNUSED_PARAMETER)?;
+    let invalid = write(&dir, "broken.j2.md", BAD_YAML)?;
+
+    let ok = kodegen(&dir).arg("validate").arg(&valid).output()?;
+    // Warnings alone do not fail validation
+    let warnings = kodegen(&dir).arg("validate").arg(&warned).output()?;
+    let errors = kodegen(&dir).arg("validate").arg(&valid).arg(&invalid).output()?;
+    let stdin = kodegen(&dir).args(["validate", "-"]).write_stdin(BAD_YAML).output()?;
+    let missing = kodegen(&dir).args(["validate", "no-such-file.j2.md"]).output()?;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(ok.status.code(), Some(0));
+    assert_eq!(warnings.status.code(), Some(0));
+    assert!(stdout(&warnings).contains("warning: "), "{}", stdout(&warnings));
+    assert_eq!(errors.status.code(), Some(2));
+    assert!(stdout(&errors).contains("broken.j2.md:"), "{}", stdout(&errors));
+    assert_eq!(stdin.status.code(), Some(2));
+    assert!(stdout(&stdin).contains("<stdin>:"), "{}", stdout(&stdin));
+    assert_eq!(missing.status.code(), Some(1), "unreadable files are failures, not invalid");
+    Ok(())
+}
+
+#[test]
+fn add_exits_invalid_for_rejected_content() -> anyhow::Result<()> {
+    let dir = temp_dir("add")?;
+    let file = write(&dir, "greeting.j2.md", GREETING)?;
+    let broken = write(&dir, "broken.j2.md", BAD_YAML)?;
+    let stored = dir.join("config").join("kodegen").join("prompts");
+
+    let added = kodegen(&dir).arg("add").arg(&file).output()?;
+    let exists = stored.join("greeting.j2.md").exists();
+    let taken = kodegen(&dir).arg("add").arg(&file).output()?;
+    let rejected = kodegen(&dir).arg("--json").arg("add").arg(&broken).output()?;
+    let named = kodegen(&dir).args(["add", "-", "--name", "team/hello"]).write_stdin(GREETING).output()?;
+    let team = stored.join("team").join("hello.j2.md").exists();
+    let broken_saved = stored.join("broken.j2.md").exists();
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(added.status.code(), Some(0));
+    assert!(exists, "the prompt was saved under XDG_CONFIG_HOME");
+    assert_eq!(taken.status.code(), Some(1), "the name is already taken");
+    assert_eq!(rejected.status.code(), Some(2));
+    let report: serde_json::Value = serde_json::from_slice(&rejected.stdout)?;
+    assert_eq!(report["success"], false, "{report}");
+    assert!(report["diagnostics"].as_array().is_some_and(|d| !d.is_empty()), "{report}");
+    assert!(!broken_saved);
+    assert_eq!(named.status.code(), Some(0));
+    assert!(team);
+    Ok(())
+}
+
+#[test]
+fn lint_exits_with_warnings_code() -> anyhow::Result<()> {
+    let dir = temp_dir("lint")?;
+    let greeting = write(&dir, "greeting.j2.md", GREETING)?;
+    let unused = write(&dir, "unused.j2.md", UNUSED_PARAMETER)?;
+    kodegen(&dir).arg("add").arg(&greeting).assert().code(0);
+    kodegen(&dir).arg("add").arg(&unused).assert().code(0);
+
+    let clean = kodegen(&dir).args(["lint", "greeting"]).output()?;
+    let warned = kodegen(&dir).args(["lint", "greeting", "unused"]).output()?;
+    let report = kodegen(&dir).args(["lint", "--json", "unused"]).output()?;
+
+    // A stored prompt edited by hand into invalid content
+    let stored = dir.join("config").join("kodegen").join("prompts");
+    std::fs::write(stored.join("unused.j2.md"), BAD_YAML)?;
+    let invalid = kodegen(&dir).args(["lint", "greeting", "unused"]).output()?;
+    let missing = kodegen(&dir).args(["lint", "no-such-prompt"]).output()?;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(clean.status.code(), Some(0), "{}", stdout(&clean));
+    assert_eq!(warned.status.code(), Some(3));
+    assert!(stdout(&warned).contains("unused: warning: "), "{}", stdout(&warned));
+    assert_eq!(report.status.code(), Some(3));
+    let report: serde_json::Value = serde_json::from_slice(&report.stdout)?;
+    assert_eq!((report["error_count"].as_u64(), report["warning_count"].as_u64()), (Some(0), Some(1)));
+    assert_eq!(invalid.status.code(), Some(2), "errors win over warnings");
+    assert_eq!(missing.status.code(), Some(1));
+    Ok(())
+}
+
+#[test]
+fn render_prints_the_prompt_or_fails() -> anyhow::Result<()> {
+    let dir = temp_dir("render")?;
+    let greeting = write(&dir, "greeting.j2.md", GREETING)?;
+    kodegen(&dir).arg("add").arg(&greeting).assert().code(0);
+
+    let defaults = kodegen(&dir).args(["render", "greeting"]).output()?;
+    let param = kodegen(&dir).args(["render", "greeting", "-p", "who=cli"]).output()?;
+    let json = kodegen(&dir).args(["--json", "render", "greeting"]).output()?;
+    let missing = kodegen(&dir).args(["render", "no-such-prompt"]).output()?;
+    let bad_param = kodegen(&dir).args(["render", "greeting", "-p", "who"]).output()?;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(defaults.status.code(), Some(0));
+    assert_eq!(stdout(&defaults).trim(), "Hello world");
+    assert_eq!(stdout(&param).trim(), "Hello cli");
+    let rendered: serde_json::Value = serde_json::from_slice(&json.stdout)?;
+    assert_eq!(rendered["content"].as_str().map(str::trim), Some("Hello world"), "{rendered}");
+    assert_eq!(missing.status.code(), Some(1));
+    assert!(stdout(&missing).is_empty());
+    assert_eq!(bad_param.status.code(), Some(2), "clap rejects malformed parameters");
+    Ok(())
+}
+
+#[test]
+fn subcommands_run_instead_of_the_server() -> anyhow::Result<()> {
+    let dir = temp_dir("dispatch")?;
+    let greeting = write(&dir, "greeting.j2.md", GREETING)?;
+    kodegen(&dir).arg("add").arg(&greeting).assert().code(0);
+
+    // `--json` before the subcommand still selects the CLI
+    let listed = kodegen(&dir).args(["--json", "list"]).output()?;
+    let help = kodegen(&dir).arg("help").output()?;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(listed.status.code(), Some(0));
+    let listed: serde_json::Value = serde_json::from_slice(&listed.stdout)?;
+    assert!(
+        listed["prompts"].as_array().is_some_and(|p| p.iter().any(|p| p["name"] == "greeting")),
+        "{listed}"
+    );
+    assert_eq!(help.status.code(), Some(0));
+    for subcommand in ["validate", "lint", "render", "add"] {
+        assert!(stdout(&help).contains(subcommand), "{}", stdout(&help));
+    }
+    Ok(())
+}
@@ -0,0 +1,123 @@
+//! Diagnostic positions and key paths for rejected prompt content
+
+use kodegen_tools_prompt::validation::check_prompt_content;
+use kodegen_tools_prompt::{diagnostics_of, Diagnostic, MemoryStore, PromptManager};
+
+/// The unclosed flow sequence runs into `author:` on line 5
+const BAD_YAML: &str = "---
+title: \"Broken\"
+description: \"Diagnostics test\"
+categories: [unclosed
+author: \"test\"
+---
+Hello
+";
+
+const BAD_TYPES: &str = "---
+title: \"Typed\"
+description: \"Diagnostics test\"
+categories: [\"test\"]
+author: \"test\"
+votes: many
+parameters:
+  - name: \"who\"
+    description: \"Who to greet\"
+  - name: \"count\"
+    description: \"How many\"
+    required: \"yes\"
+---
+Hello {{ who }} x{{ count }}
+";
+
+/// The unfinished `for` on line 15 follows two message blocks
+const BAD_SYNTAX: &str = "---
+title: \"Syntax\"
+description: \"Diagnostics test\"
+categories: [\"test\"]
+author: \"test\"
+parameters:
+  - name: \"who\"
+    description: \"Who to greet\"
+---
+{% message \"system\" %}
+Be brief.
+{% endmessage %}
+{% message \"user\" %}
+Hi {{ who }}
+{% for x in %}
+{% endmessage %}
+";
+
+const WARNINGS: &str = "---
+title: \"Lint\"
+description: \"Diagnostics test\"
+categories: [\"test\"]
+author: \"test\"
+parameters:
+  - name: \"unused\"
+    description: \"Never read\"
+---
+Hi
+  {{ who }}
+";
+
+/// Line, column and key path of each diagnostic
+fn positions(diagnostics: &[Diagnostic]) -> Vec<(Option<usize>, Option<usize>, Option<&str>)> {
+    diagnostics
+        .iter()
+        .map(|d| (d.line, d.column, d.path.as_deref()))
+        .collect()
+}
+
+/// Diagnostics from `add_prompt`, after checking `check_prompt_content` (the
+/// `prompt_validate` checks) reports the same ones
+async fn rejected(content: &str) -> Vec<Diagnostic> {
+    let manager = PromptManager::with_store(MemoryStore::new());
+    let error = manager.add_prompt("checked", content).await.expect_err("content is invalid");
+    let added = diagnostics_of(&error);
+    let checked = check_prompt_content("checked", content);
+    assert_eq!(positions(&added), positions(&checked));
+    assert!(!manager.prompt_exists("checked").await);
+    added
+}
+
+#[tokio::test]
+async fn yaml_errors_point_into_the_file() {
+    let diagnostics = rejected(BAD_YAML).await;
+    assert_eq!(positions(&diagnostics), [(Some(5), Some(7), None)]);
+    assert!(diagnostics[0].message.starts_with("Invalid YAML: "), "{:?}", diagnostics[0]);
+    // The frontmatter-relative location is replaced, not repeated
+    assert!(!diagnostics[0].message.contains(" at line "), "{:?}", diagnostics[0]);
+}
+
+#[tokio::test]
+async fn type_errors_name_their_key_path() {
+    let diagnostics = rejected(BAD_TYPES).await;
+    assert_eq!(
+        positions(&diagnostics),
+        [
+            (Some(6), Some(8), Some("votes")),
+            (Some(12), Some(15), Some("parameters[1].required")),
+        ]
+    );
+    let required = &diagnostics[1];
+    assert_eq!(required.expected.as_deref(), Some("a boolean"));
+    assert_eq!(required.actual.as_deref(), Some("string \"yes\""));
+}
+
+#[tokio::test]
+async fn template_syntax_errors_report_their_file_line() {
+    let diagnostics = rejected(BAD_SYNTAX).await;
+    assert_eq!(positions(&diagnostics), [(Some(15), None, None)]);
+    assert!(diagnostics[0].message.starts_with("Template syntax error: "), "{:?}", diagnostics[0]);
+}
+
+#[test]
+fn lint_warnings_are_located() {
+    let diagnostics = check_prompt_content("lint", WARNINGS);
+    assert!(diagnostics.iter().all(|d| !d.is_error()));
+    assert_eq!(
+        positions(&diagnostics),
+        [(Some(11), Some(6), None), (Some(7), Some(11), Some("parameters[0].name"))]
+    );
+}
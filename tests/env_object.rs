@@ -0,0 +1,34 @@
+//! Shape of the `env` template object: a name -> value map
+
+use kodegen_tools_prompt::template::{parse_template, render_template};
+use kodegen_tools_prompt::EnvPolicy;
+
+const PROMPT: &str = "---
+title: \"Env\"
+description: \"Reads the environment\"
+categories: [\"test\"]
+author: \"test\"
+---
+mapping={{ env is mapping }}
+one={{ env.KODEGEN_SHAPE_ONE }}
+{% for name, value in env | items %}{{ name }}:{{ value }};{% endfor %}
+";
+
+#[tokio::test]
+async fn env_is_a_map_of_exposed_variables() -> anyhow::Result<()> {
+    // SAFETY: set before the policy snapshots the environment; nothing else
+    // in this test binary reads it
+    unsafe {
+        std::env::set_var("KODEGEN_SHAPE_ONE", "a=b");
+        std::env::set_var("KODEGEN_SHAPE_TWO", "2");
+    }
+    EnvPolicy::new(["KODEGEN_SHAPE_*"], ["*TWO"])?.install();
+
+    let rendered = render_template(&parse_template("env", PROMPT)?, None).await?;
+    assert_eq!(
+        rendered.trim(),
+        "mapping=True\none=a=b\nKODEGEN_SHAPE_ONE:a=b;",
+        "blocked variables are left out of the map"
+    );
+    Ok(())
+}
@@ -0,0 +1,47 @@
+//! Env policy patterns and how `KODEGEN_ALLOWED_ENV_VARS` is read
+
+use kodegen_tools_prompt::env_policy::DEFAULT_ALLOWED_ENV_VARS;
+use kodegen_tools_prompt::EnvPolicy;
+
+#[test]
+fn only_leading_and_trailing_stars_are_wildcards() -> anyhow::Result<()> {
+    let policy = EnvPolicy::new(["APP_*", "*_NAME", "*MID*", "EXACT", "A*B"], Vec::<&str>::new())?;
+    for name in ["APP_", "APP_PORT", "USER_NAME", "A_MID_Z", "MID", "EXACT", "A*B"] {
+        assert!(policy.allows(name), "{name}");
+    }
+    for name in ["MY_APP_PORT", "USER_NAME_2", "EXACTLY", "AB", "AXB", "A_B"] {
+        assert!(!policy.allows(name), "{name}");
+    }
+
+    // A `*` inside a pattern stays literal at either end, too
+    let policy = EnvPolicy::new(["*A*B", "X*Y*"], Vec::<&str>::new())?;
+    assert!(policy.allows("ZA*B") && policy.allows("X*YZ"));
+    assert!(!policy.allows("ZAQB") && !policy.allows("XQYZ"));
+
+    let everything = EnvPolicy::new(["*"], ["*SECRET*"])?;
+    assert!(everything.allows("ANYTHING"));
+    assert!(!everything.allows("MY_SECRET_VALUE"), "the blocklist wins");
+    Ok(())
+}
+
+#[test]
+fn blank_allowlists_expose_nothing_and_empty_ones_the_defaults() {
+    let separator = if cfg!(windows) { ';' } else { ':' };
+    let allowed_from = |value: Option<&str>| {
+        // SAFETY: this is the only test in this binary that touches the
+        // environment
+        unsafe {
+            match value {
+                Some(value) => std::env::set_var("KODEGEN_ALLOWED_ENV_VARS", value),
+                None => std::env::remove_var("KODEGEN_ALLOWED_ENV_VARS"),
+            }
+        }
+        EnvPolicy::from_env().allowed().to_vec()
+    };
+
+    assert_eq!(allowed_from(None), DEFAULT_ALLOWED_ENV_VARS);
+    assert_eq!(allowed_from(Some("")), DEFAULT_ALLOWED_ENV_VARS);
+    assert!(allowed_from(Some("   ")).is_empty());
+    assert!(allowed_from(Some(&format!(" {separator} {separator}"))).is_empty());
+    assert_eq!(allowed_from(Some(&format!(" HOME {separator} USER "))), ["HOME", "USER"]);
+}
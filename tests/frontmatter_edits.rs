@@ -0,0 +1,55 @@
+//! Rename and copy edit only the frontmatter keys they change
+
+use kodegen_tools_prompt::{MemoryStore, PromptManager};
+
+const SOURCE: &str = "---
+# Owned by the platform team
+title: \"Review\"
+author: \"alice\"   # original author
+description: \"Reviews a change\"
+categories: [\"review\"]
+verified: true
+votes: 7
+aliases:
+  - old_review
+  - checker
+owner_team: platform
+---
+
+{# keep this comment #}
+Review {{ target | default('the change') }}
+";
+
+#[tokio::test]
+async fn rename_rewrites_only_the_aliases() -> anyhow::Result<()> {
+    let manager = PromptManager::with_store(MemoryStore::new());
+    manager.add_prompt("review", SOURCE).await?;
+    manager.rename_prompt("review", "code_review").await?;
+
+    let expected = SOURCE.replace(
+        "aliases:\n  - old_review\n  - checker\n",
+        "aliases: [\"old_review\",\"checker\",\"review\"]\n",
+    );
+    assert_eq!(manager.read_prompt_source("code_review").await?, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn copy_keeps_comments_order_and_body() -> anyhow::Result<()> {
+    let manager = PromptManager::with_store(MemoryStore::new());
+    manager.add_prompt("review", SOURCE).await?;
+    manager.copy_prompt("review", "my_review", Some("bob")).await?;
+
+    let expected = SOURCE
+        .replace("author: \"alice\"   # original author\n", "author: \"bob\"\n")
+        .replace("verified: true\n", "verified: false\n")
+        .replace("votes: 7\n", "votes: 0\n")
+        .replace("aliases:\n  - old_review\n  - checker\n", "")
+        .replace("owner_team: platform\n", "owner_team: platform\nforked_from: \"review\"\n");
+    assert_eq!(manager.read_prompt_source("my_review").await?, expected);
+
+    let copy = manager.load_prompt("my_review").await?;
+    assert_eq!(copy.metadata.forked_from.as_deref(), Some("review"));
+    assert!(copy.metadata.aliases.is_empty());
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! Git storage: commits, history across renames, diffs and restores
+//!
+//! Every test is skipped when the `git` CLI is not installed.
+
+use kodegen_tools_prompt::git_store::GitStore;
+use kodegen_tools_prompt::{ChangeAuthor, FsStore, PromptManager};
+use std::path::{Path, PathBuf};
+
+fn git_installed() -> bool {
+    let installed = std::process::Command::new("git")
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success());
+    if !installed {
+        eprintln!("git is not installed; skipping");
+    }
+    installed
+}
+
+fn temp_dir(test: &str) -> anyhow::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("kodegen-git-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn prompt(title: &str) -> String {
+    format!(
+        "---
+title: \"{title}\"
+description: \"Git test\"
+categories: [\"test\"]
+author: \"test\"
+---
+Hello from {title}
+"
+    )
+}
+
+/// Git-backed manager over `dir`, holding one prompt committed on init
+async fn repository(dir: &Path) -> anyhow::Result<PromptManager> {
+    std::fs::write(dir.join("existing.j2.md"), prompt("existing"))?;
+    let manager = PromptManager::with_store(FsStore::new(dir)).with_git_storage();
+    manager.init().await?;
+    Ok(manager)
+}
+
+fn messages(entries: &[kodegen_tools_prompt::HistoryEntry]) -> Vec<&str> {
+    entries.iter().map(|entry| entry.message.as_str()).collect()
+}
+
+#[tokio::test]
+async fn init_and_writes_commit_with_their_author() -> anyhow::Result<()> {
+    if !git_installed() {
+        return Ok(());
+    }
+    let dir = temp_dir("commit")?;
+    let manager = repository(&dir).await?;
+    let ada = manager.as_author(ChangeAuthor::new("Ada", "ada@example.com"));
+    ada.add_prompt("greeting", &prompt("first")).await?;
+    ada.edit_prompt("greeting", &prompt("second")).await?;
+    // Unchanged content makes no commit
+    ada.edit_prompt("greeting", &prompt("second")).await?;
+
+    let existing = manager.prompt_history("existing", 10).await?;
+    let greeting = manager.prompt_history("greeting", 10).await?;
+    let limited = manager.prompt_history("greeting", 1).await?;
+    let initialized = dir.join(".git").is_dir();
+    std::fs::remove_dir_all(&dir)?;
+
+    assert!(initialized);
+    assert_eq!(messages(&existing), ["Import existing prompts"]);
+    assert_eq!(existing[0].author, "kodegen-prompt");
+    assert_eq!(messages(&greeting), ["Edit prompt 'greeting'", "Add prompt 'greeting'"]);
+    assert!(greeting.iter().all(|e| e.author == "Ada" && e.email == "ada@example.com"));
+    assert_eq!(greeting[0].commit.len(), 40, "full commit hashes");
+    assert_eq!(limited.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn history_follows_renames_but_not_copies() -> anyhow::Result<()> {
+    if !git_installed() {
+        return Ok(());
+    }
+    let dir = temp_dir("rename")?;
+    let manager = repository(&dir).await?;
+    manager.add_prompt("greeting", &prompt("greeting")).await?;
+    manager.edit_prompt("greeting", &prompt("greeting v2")).await?;
+    manager.rename_prompt("greeting", "welcome").await?;
+    manager.copy_prompt("welcome", "team/welcome", None).await?;
+
+    let renamed = manager.prompt_history("welcome", 10).await?;
+    // The old name is an alias of the new one
+    let by_alias = manager.prompt_history("greeting", 10).await?;
+    let limited = manager.prompt_history("welcome", 2).await?;
+    let copied = manager.prompt_history("team/welcome", 10).await?;
+    std::fs::remove_dir_all(&dir)?;
+
+    let expected = [
+        "Rename prompt 'greeting' to 'welcome'",
+        "Edit prompt 'greeting'",
+        "Add prompt 'greeting'",
+    ];
+    assert_eq!(messages(&renamed), expected);
+    assert_eq!(messages(&by_alias), expected);
+    assert_eq!(messages(&limited), expected[..2]);
+    // A copy starts its own history
+    assert_eq!(messages(&copied), ["Copy prompt 'welcome' to 'team/welcome'"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn diffs_and_restores_between_revisions() -> anyhow::Result<()> {
+    if !git_installed() {
+        return Ok(());
+    }
+    let dir = temp_dir("restore")?;
+    let manager = repository(&dir).await?;
+    manager.add_prompt("greeting", &prompt("first")).await?;
+    manager.edit_prompt("greeting", &prompt("second")).await?;
+    let first = manager.prompt_history("greeting", 10).await?[1].commit.clone();
+
+    let to_current = manager.prompt_diff("greeting", "HEAD~1", None).await?;
+    let between = manager.prompt_diff("greeting", &first, Some("HEAD")).await?;
+    let unchanged = manager.prompt_diff("greeting", "HEAD", None).await?;
+
+    let restored = manager.restore_prompt("greeting", &first).await?;
+    let content = std::fs::read_to_string(dir.join("greeting.j2.md"))?;
+    let after_restore = manager.prompt_history("greeting", 10).await?;
+
+    // Deleted prompts come back from history
+    manager.delete_prompt("greeting").await?;
+    let recreated = manager.restore_prompt("greeting", "HEAD~1").await?;
+    let after_delete = manager.prompt_history("greeting", 10).await?;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert!(to_current.contains("-title: \"first\"") && to_current.contains("+title: \"second\""));
+    assert_eq!(between, to_current);
+    assert!(unchanged.is_empty());
+    assert_eq!(restored.metadata.title, "first");
+    assert_eq!(content, prompt("first"));
+    assert_eq!(after_restore[0].message, format!("Restore prompt 'greeting' to {}", &first[..12]));
+    assert_eq!(recreated.metadata.title, "first");
+    assert_eq!(after_delete[1].message, "Delete prompt 'greeting'");
+    assert!(after_delete[0].message.starts_with("Restore prompt 'greeting' to "));
+    Ok(())
+}
+
+#[tokio::test]
+async fn revisions_that_look_like_options_are_rejected() -> anyhow::Result<()> {
+    if !git_installed() {
+        return Ok(());
+    }
+    let dir = temp_dir("options")?;
+    let manager = repository(&dir).await?;
+    let git = GitStore::new(&dir);
+    let planted = dir.join("planted");
+    let option = format!("--output={}", planted.display());
+
+    let head = git.resolve(" HEAD ").await?;
+    let mut errors = Vec::new();
+    for revision in [option.as_str(), "-n", "--all", "", "  "] {
+        errors.push(git.resolve(revision).await.expect_err(revision).to_string());
+    }
+    let diff = manager.prompt_diff("existing", &option, None).await;
+    let diff_to = manager.prompt_diff("existing", "HEAD", Some(&option)).await;
+    let restore = manager.restore_prompt("existing", "-p").await;
+    let unknown = git.resolve("no-such-branch").await;
+    let written = planted.exists();
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(head.len(), 40);
+    assert!(errors.iter().all(|e| e.starts_with("Invalid revision")), "{errors:?}");
+    assert!(diff.is_err() && diff_to.is_err() && restore.is_err());
+    assert!(unknown.expect_err("unknown revision").to_string().contains("Unknown revision"));
+    assert!(!written, "git never saw the option");
+    Ok(())
+}
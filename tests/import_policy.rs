@@ -0,0 +1,64 @@
+//! Imports are authorized as a whole before anything is written
+
+use kodegen_tools_prompt::policy::with_client;
+use kodegen_tools_prompt::template::parse_template;
+use kodegen_tools_prompt::{
+    ClientIdentity, ConflictPolicy, MemoryStore, Policy, PromptBundle, PromptManager,
+};
+use std::sync::Arc;
+
+const POLICY: &str = "
+default:
+  - actions: [all]
+    prompts: [\"team-*\"]
+";
+
+fn prompt(name: &str) -> String {
+    format!(
+        "---
+title: \"{name}\"
+description: \"Import test\"
+categories: [\"test\"]
+author: \"test\"
+---
+Hello from {name}
+"
+    )
+}
+
+fn bundle(names: &[&str]) -> anyhow::Result<PromptBundle> {
+    let mut templates = Vec::new();
+    for name in names {
+        let content = prompt(name);
+        templates.push((parse_template(name, &content)?, content));
+    }
+    Ok(PromptBundle::from_templates(templates))
+}
+
+#[tokio::test]
+async fn denied_entry_rejects_the_whole_import() -> anyhow::Result<()> {
+    let manager = PromptManager::with_store(MemoryStore::new())
+        .with_policy(Arc::new(Policy::from_yaml(POLICY)?));
+    let mixed = bundle(&["team-notes", "private-notes"])?;
+
+    with_client(ClientIdentity::default(), async {
+        for dry_run in [true, false] {
+            let err = manager
+                .import_prompts(&mixed, ConflictPolicy::Skip, dry_run)
+                .await
+                .expect_err("import with a denied entry must fail");
+            let message = format!("{err:#}");
+            assert!(message.contains("private-notes"), "{message}");
+            assert!(!message.contains("team-notes"), "{message}");
+            assert!(!manager.prompt_exists("team-notes").await, "dry_run={dry_run}");
+        }
+
+        let report = manager
+            .import_prompts(&bundle(&["team-notes"])?, ConflictPolicy::Skip, false)
+            .await?;
+        assert_eq!(report.outcomes.len(), 1);
+        assert!(manager.prompt_exists("team-notes").await);
+        Ok(())
+    })
+    .await
+}
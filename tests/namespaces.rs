@@ -0,0 +1,109 @@
+//! Namespaced names: traversal, symlink escapes and namespace matching
+
+use kodegen_tools_prompt::{in_namespace, FsStore, PromptManager, PromptStore};
+use std::path::PathBuf;
+
+fn temp_dir(test: &str) -> anyhow::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("kodegen-ns-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn prompt(title: &str) -> String {
+    format!(
+        "---
+title: \"{title}\"
+description: \"Namespace test\"
+categories: [\"test\"]
+author: \"test\"
+---
+Hello from {title}
+"
+    )
+}
+
+#[tokio::test]
+async fn malformed_names_are_rejected() -> anyhow::Result<()> {
+    let dir = temp_dir("names")?;
+    let prompts = dir.join("prompts");
+    std::fs::create_dir_all(&prompts)?;
+    let manager = PromptManager::with_store(FsStore::new(&prompts));
+
+    let too_deep = ["n"; 9].join("/");
+    let cases = [
+        ("review/../../escape", "'..' not allowed"),
+        ("..", "'..' not allowed"),
+        ("/etc/passwd", "cannot start or end with '/'"),
+        ("review/", "cannot start or end with '/'"),
+        ("review//security", "Use alphanumeric"),
+        (too_deep.as_str(), "At most 8 namespace levels"),
+    ];
+    for (name, message) in cases {
+        let error = manager.add_prompt(name, &prompt("bad")).await.expect_err(name);
+        assert!(format!("{error:#}").contains(message), "{name}: {error:#}");
+        assert!(manager.load_prompt(name).await.is_err(), "{name}");
+    }
+
+    // Eight levels is still fine
+    let deepest = ["n"; 8].join("/");
+    manager.add_prompt(&deepest, &prompt("deep")).await?;
+    let names = manager.prompt_names().await?;
+    let outside: Vec<_> = std::fs::read_dir(&dir)?.collect();
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(names, [deepest]);
+    assert_eq!(outside.len(), 1, "nothing was written next to the prompts directory");
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn symlinked_namespace_cannot_escape_the_root() -> anyhow::Result<()> {
+    let dir = temp_dir("symlink")?;
+    let prompts = dir.join("prompts");
+    let outside = dir.join("outside");
+    std::fs::create_dir_all(&prompts)?;
+    std::fs::create_dir_all(&outside)?;
+    std::fs::write(outside.join("secret.j2.md"), prompt("secret"))?;
+    std::os::unix::fs::symlink(&outside, prompts.join("linked"))?;
+
+    let store = FsStore::new(&prompts);
+    store.create("local", &prompt("local")).await?;
+
+    let read = store.read("linked/secret").await;
+    let metadata = store.metadata("linked/secret").await;
+    let created = store.create("linked/planted", &prompt("planted")).await;
+    let listed = store.list().await?;
+
+    let manager = PromptManager::with_store(FsStore::new(&prompts));
+    let loaded = manager.load_prompt("linked/secret").await;
+    let added = manager.add_prompt("linked/other", &prompt("other")).await;
+    let planted = outside.join("planted.j2.md").exists() || outside.join("other.j2.md").exists();
+    std::fs::remove_dir_all(&dir)?;
+
+    let error = read.expect_err("read through the symlink");
+    assert!(error.to_string().contains("outside the prompts directory"), "{error}");
+    assert!(metadata.is_err());
+    assert!(created.is_err());
+    assert!(loaded.is_err());
+    assert!(added.is_err());
+    assert!(!planted, "nothing was written outside the root");
+    assert_eq!(listed, ["local"], "symlinked namespaces are skipped when listing");
+    Ok(())
+}
+
+#[test]
+fn namespaces_match_whole_segments() {
+    assert!(in_namespace("review/security", "review"));
+    assert!(in_namespace("review/web/xss", "review"));
+    assert!(in_namespace("review/web/xss", "review/web"));
+    assert!(in_namespace("review/security", "review/"));
+    assert!(in_namespace("anything", ""));
+
+    assert!(!in_namespace("reviewer/x", "review"));
+    assert!(!in_namespace("review", "review"), "a prompt is not inside its own name");
+    assert!(!in_namespace("reviews", "review"));
+    assert!(!in_namespace("review/web", "review/web/xss"));
+    assert!(!in_namespace("code/review/x", "review"));
+}
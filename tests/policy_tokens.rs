@@ -0,0 +1,45 @@
+//! Bearer tokens identify policy clients by exact match only
+
+use kodegen_tools_prompt::{ClientIdentity, Policy, PolicyAction};
+
+const POLICY: &str = "
+clients:
+  - name: ci
+    tokens: [\"ci-token\", \"ci-spare\"]
+    allow:
+      - actions: [all]
+        prompts: [\"*\"]
+  - name: shared
+    tokens: [\"ci-spare\"]
+    connection_ids: [\"agent-*\"]
+    allow:
+      - actions: [read]
+        prompts: [\"*\"]
+";
+
+fn token(token: &str) -> ClientIdentity {
+    ClientIdentity { token: Some(token.to_string()), ..ClientIdentity::default() }
+}
+
+#[test]
+fn tokens_match_exactly() -> anyhow::Result<()> {
+    let policy = Policy::from_yaml(POLICY)?;
+    let updates = |client: &ClientIdentity| policy.allows(client, PolicyAction::Update, "greeting");
+
+    assert!(updates(&token("ci-token")));
+    assert!(updates(&token("ci-spare")), "any of a client's tokens, first client wins");
+    for near_miss in ["ci-toke", "ci-token2", "CI-TOKEN", "", "ci-token\0"] {
+        let client = token(near_miss);
+        assert!(!updates(&client), "{near_miss:?}");
+        assert!(!policy.allows(&client, PolicyAction::Read, "greeting"), "{near_miss:?}");
+    }
+
+    // An unknown token falls back to the connection ID
+    let agent = ClientIdentity {
+        connection_id: Some("agent-1".to_string()),
+        token: Some("wrong".to_string()),
+    };
+    assert!(policy.allows(&agent, PolicyAction::Read, "greeting"));
+    assert!(!policy.allows(&agent, PolicyAction::Update, "greeting"));
+    Ok(())
+}
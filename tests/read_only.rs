@@ -0,0 +1,59 @@
+//! Read-only namespaces and read-only libraries
+
+use kodegen_tools_prompt::usage::USAGE_FILE;
+use kodegen_tools_prompt::{FsStore, MemoryStore, PromptManager};
+
+fn prompt(title: &str) -> String {
+    format!(
+        "---
+title: \"{title}\"
+description: \"Read-only test\"
+categories: [\"test\"]
+author: \"test\"
+---
+Hello from {title}
+"
+    )
+}
+
+#[tokio::test]
+async fn read_only_namespace_rejects_changes_to_its_prompts() -> anyhow::Result<()> {
+    let writable = PromptManager::with_store(MemoryStore::new());
+    writable.add_prompt("system/base", &prompt("base")).await?;
+    let manager = writable.with_read_only_namespace("system");
+
+    for result in [
+        manager.add_prompt("system/new", &prompt("new")).await,
+        manager.add_prompt("system/deep/new", &prompt("new")).await,
+        manager.edit_prompt("system/base", &prompt("edited")).await,
+        manager.delete_prompt("system/base").await,
+        manager.rename_prompt("system/base", "user/base").await.map(drop),
+        manager.copy_prompt("system/base", "system/copy", None).await.map(drop),
+    ] {
+        let message = format!("{:#}", result.expect_err("system/ is read-only"));
+        assert!(message.contains("namespace 'system' is read-only"), "{message}");
+    }
+
+    // Only the namespace is frozen
+    manager.add_prompt("systems/notes", &prompt("notes")).await?;
+    manager.copy_prompt("system/base", "user/base", None).await?;
+    manager.render_prompt("system/base", None).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_only_library_does_not_write_usage() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("kodegen-read-only-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join("greeting.j2.md"), prompt("greeting"))?;
+
+    let manager = PromptManager::with_store(FsStore::new(&dir)).with_read_only(true);
+    manager.render_prompt("greeting", None).await?;
+    let (_, usage) = manager.prompt_usage("greeting").await?;
+    let written = dir.join(USAGE_FILE).exists();
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(usage.renders, 1, "renders are still counted in memory");
+    assert!(!written, "read-only library must not get a usage file");
+    Ok(())
+}
@@ -0,0 +1,140 @@
+//! Pathological templates stop within the render limits and free their thread
+
+use kodegen_tools_prompt::template::{parse_template, CompiledTemplate};
+use kodegen_tools_prompt::metrics::{metrics, LibraryStats};
+use kodegen_tools_prompt::{MemoryStore, PromptLimits, PromptManager};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// The running-thread gauge is process-wide; run these one at a time
+static SERIAL: Mutex<()> = Mutex::const_new(());
+
+/// Small limits so every case fails fast, even in debug builds
+fn limits() -> PromptLimits {
+    PromptLimits {
+        render_timeout: Duration::from_secs(10),
+        render_fuel: 200_000,
+        max_recursion_depth: 50,
+        max_output_size: 64 * 1024,
+        ..PromptLimits::default()
+    }
+}
+
+fn prompt(body: &str) -> String {
+    format!(
+        "---\ntitle: \"Pathological\"\ndescription: \"Stress test\"\ncategories: [\"test\"]\n\
+         author: \"test\"\n---\n{body}\n"
+    )
+}
+
+/// Render `body` under `limits`, returning the error and how long it took
+async fn render_error(body: &str, limits: &PromptLimits) -> (String, Duration) {
+    let template = parse_template("pathological", &prompt(body)).expect("template parses");
+    let compiled = CompiledTemplate::compile(&template, limits)
+        .await
+        .expect("template compiles");
+    let start = Instant::now();
+    let result = compiled.render(None).await;
+    let elapsed = start.elapsed();
+    let error = result.expect_err("render should be stopped");
+    (format!("{error:#}"), elapsed)
+}
+
+/// Wait until no render thread is running (the CPU is free again)
+async fn wait_for_render_threads() -> Duration {
+    let start = Instant::now();
+    while metrics().snapshot(LibraryStats::default()).render.running > 0 {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "render thread still running after {:?}",
+            start.elapsed()
+        );
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    start.elapsed()
+}
+
+#[tokio::test]
+async fn infinite_loop_runs_out_of_fuel() {
+    let _serial = SERIAL.lock().await;
+    let body = "{% for a in range(100000) %}{% for b in range(100000) %}{% endfor %}{% endfor %}";
+
+    let (error, elapsed) = render_error(body, &limits()).await;
+
+    assert!(error.contains("ran out of fuel"), "{error}");
+    assert!(elapsed < Duration::from_secs(5), "took {elapsed:?}");
+    wait_for_render_threads().await;
+}
+
+#[tokio::test]
+async fn deep_recursion_hits_the_recursion_limit() {
+    let _serial = SERIAL.lock().await;
+    let body = "{% macro down(n) %}{{ down(n + 1) }}{% endmacro %}{{ down(0) }}";
+
+    let (error, elapsed) = render_error(body, &limits()).await;
+
+    assert!(error.contains("recursion"), "{error}");
+    assert!(elapsed < Duration::from_secs(5), "took {elapsed:?}");
+    wait_for_render_threads().await;
+}
+
+#[tokio::test]
+async fn huge_output_hits_the_output_limit() {
+    let _serial = SERIAL.lock().await;
+    let body = "{% for a in range(100000) %}{{ 'x' * 1000 }}{% endfor %}";
+    let limits = PromptLimits { render_fuel: u64::MAX, ..limits() };
+
+    let (error, elapsed) = render_error(body, &limits).await;
+
+    assert!(error.contains("exceeds 65536 bytes"), "{error}");
+    assert!(elapsed < Duration::from_secs(5), "took {elapsed:?}");
+    wait_for_render_threads().await;
+}
+
+#[tokio::test]
+async fn timed_out_render_stops_its_thread() {
+    let _serial = SERIAL.lock().await;
+    // Fuel and output for seconds of work: only the timeout stops it sooner
+    let body = "{% for a in range(100000) %}{% for b in range(100000) %}x{% endfor %}{% endfor %}";
+    let limits = PromptLimits {
+        render_timeout: Duration::from_millis(100),
+        render_fuel: 50_000_000,
+        max_output_size: 100 * 1024 * 1024,
+        ..limits()
+    };
+
+    let (error, elapsed) = render_error(body, &limits).await;
+
+    assert!(error.contains("timed out after 100 ms"), "{error}");
+    assert!(elapsed < Duration::from_secs(5), "took {elapsed:?}");
+    let stopped = wait_for_render_threads().await;
+    assert!(stopped < Duration::from_secs(1), "thread ran {stopped:?} past the timeout");
+}
+
+#[tokio::test]
+async fn managers_render_with_their_own_limits() -> anyhow::Result<()> {
+    let _serial = SERIAL.lock().await;
+    let body = prompt("{% for a in range(1000) %}{{ a }}{% endfor %}");
+    let tight = PromptLimits { max_output_size: 100, ..limits() };
+
+    let roomy = PromptManager::with_store(MemoryStore::new()).with_limits(limits());
+    let cramped = PromptManager::with_store(MemoryStore::new()).with_limits(tight);
+    roomy.add_prompt("counting", &body).await?;
+    cramped.add_prompt("counting", &body).await?;
+
+    assert!(roomy.render_prompt("counting", None).await.is_ok());
+    let error = cramped.render_prompt("counting", None).await.unwrap_err();
+    assert!(format!("{error:#}").contains("exceeds 100 bytes"), "{error:#}");
+    Ok(())
+}
+
+#[test]
+fn installing_different_default_limits_fails() {
+    // New managers copy the process-wide defaults, fixing them if unset
+    let defaults = *PromptManager::with_store(MemoryStore::new()).limits();
+    let other = PromptLimits { max_param_count: defaults.max_param_count + 1, ..defaults };
+
+    assert!(defaults.install().is_ok());
+    let error = other.install().unwrap_err();
+    assert!(error.to_string().contains("with_limits"), "{error}");
+}
@@ -0,0 +1,57 @@
+//! Search modes: index, scan, or whichever the store supports
+
+use kodegen_tools_prompt::{
+    MemoryStore, PromptManager, PromptQuery, PromptServerConfig, SearchMode,
+};
+
+fn prompt(title: &str) -> String {
+    format!(
+        "---
+title: \"{title}\"
+description: \"Search test\"
+categories: [\"test\"]
+author: \"test\"
+---
+Hello from {title}
+"
+    )
+}
+
+fn text(text: &str) -> PromptQuery {
+    PromptQuery { text: Some(text.to_string()), ..PromptQuery::default() }
+}
+
+#[tokio::test]
+async fn unindexed_stores_scan_unless_index_is_required() -> anyhow::Result<()> {
+    let manager = PromptManager::with_store(MemoryStore::new());
+    manager.add_prompt("beta", &prompt("beta")).await?;
+    manager.add_prompt("alpha", &prompt("alpha")).await?;
+
+    for mode in [SearchMode::Auto, SearchMode::Scan] {
+        let found = manager.clone().with_search_mode(mode).search_prompts(&text("hello")).await?;
+        let names: Vec<&str> = found.iter().map(|t| t.filename.as_str()).collect();
+        assert_eq!(names, ["alpha", "beta"], "{mode:?}");
+    }
+
+    let error = manager
+        .with_search_mode(SearchMode::Index)
+        .search_prompts(&text("hello"))
+        .await
+        .expect_err("memory store has no index");
+    assert!(error.to_string().contains("no search index"), "{error:#}");
+    Ok(())
+}
+
+#[test]
+fn index_mode_needs_a_database() {
+    let dir = std::env::temp_dir().join(format!("kodegen-search-{}", std::process::id()));
+    let error = PromptServerConfig::new()
+        .prompts_dir(&dir)
+        .search(SearchMode::Index)
+        .create_manager()
+        .err()
+        .expect("the prompts directory has no index");
+    assert!(error.to_string().contains("needs a prompt database"), "{error:#}");
+    assert_eq!("fts".parse::<SearchMode>().unwrap(), SearchMode::Index);
+    assert!("grep".parse::<SearchMode>().is_err());
+}
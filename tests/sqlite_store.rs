@@ -0,0 +1,274 @@
+//! SQLite store: schema migration, ranked search, imports and revision history
+#![cfg(feature = "sqlite")]
+
+use kodegen_tools_prompt::policy::with_client;
+use kodegen_tools_prompt::store::migrate_prompts;
+use kodegen_tools_prompt::{
+    ClientIdentity, FsStore, MemoryStore, Policy, PromptManager, PromptQuery, PromptStore,
+    SqliteStore,
+};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn temp_dir(test: &str) -> anyhow::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("kodegen-sqlite-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn prompt(title: &str) -> String {
+    format!(
+        "---
+title: \"{title}\"
+description: \"SQLite test\"
+categories: [\"test\"]
+author: \"test\"
+---
+Hello from {title}
+"
+    )
+}
+
+async fn database() -> anyhow::Result<PromptManager> {
+    let store = SqliteStore::open_in_memory()?;
+    store.init().await?;
+    Ok(PromptManager::with_store(store))
+}
+
+fn names(templates: &[kodegen_tools_prompt::PromptTemplate]) -> Vec<&str> {
+    templates.iter().map(|t| t.filename.as_str()).collect()
+}
+
+fn revisions(log: &[kodegen_tools_prompt::PromptRevision]) -> Vec<(u64, &str, Option<&str>)> {
+    log.iter()
+        .map(|r| (r.revision, r.action.as_str(), r.content.as_deref()))
+        .collect()
+}
+
+#[tokio::test]
+async fn version_1_databases_are_indexed_on_open() -> anyhow::Result<()> {
+    let dir = temp_dir("v1")?;
+    let path = dir.join("prompts.db");
+    let tagged = prompt("tagged").replace("author: \"test\"", "author: \"Ada\"\ntags: [\"legacy\"]");
+    {
+        // The version 1 schema: raw content only
+        let conn = rusqlite::Connection::open(&path)?;
+        conn.execute_batch(
+            "CREATE TABLE prompts (
+                 name        TEXT PRIMARY KEY NOT NULL,
+                 content     TEXT NOT NULL,
+                 modified_ns INTEGER NOT NULL
+             );
+             PRAGMA user_version = 1;",
+        )?;
+        for (name, content) in [
+            ("review/tagged", tagged.as_str()),
+            ("plain", &prompt("plain")),
+            ("broken", "---\ntitle: [unclosed\n---\nSearchable body\n"),
+        ] {
+            conn.execute(
+                "INSERT INTO prompts (name, content, modified_ns) VALUES (?1, ?2, 1)",
+                rusqlite::params![name, content],
+            )?;
+        }
+    }
+
+    let store = SqliteStore::open(&path)?;
+    store.init().await?;
+    // Opening again must not index or record anything twice
+    store.init().await?;
+    let by_tag = store
+        .query_templates(&PromptQuery { tag: Some("legacy".into()), ..PromptQuery::default() })
+        .await?
+        .unwrap_or_default();
+    let by_author = store
+        .query_templates(&PromptQuery { author: Some("ada".into()), ..PromptQuery::default() })
+        .await?
+        .unwrap_or_default();
+    let listed = store.query_templates(&PromptQuery::default()).await?.unwrap_or_default();
+    let tagged_log = store.revisions("review/tagged").await?;
+    let broken_log = store.revisions("broken").await?;
+    let broken = store.read("broken").await?;
+    let version: i64 = rusqlite::Connection::open(&path)?
+        .pragma_query_value(None, "user_version", |row| row.get(0))?;
+    drop(store);
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(version, 2);
+    assert_eq!(names(&by_tag), ["review/tagged"]);
+    assert_eq!(names(&by_author), ["review/tagged"]);
+    assert_eq!(by_tag[0].metadata.tags, ["legacy"]);
+    // Prompts that don't parse are kept but not listed
+    assert_eq!(names(&listed), ["plain", "review/tagged"]);
+    assert!(broken.contains("Searchable body"));
+    assert_eq!(revisions(&tagged_log), [(1, "create", Some(tagged.as_str()))]);
+    assert_eq!(broken_log.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn text_search_ranks_by_relevance() -> anyhow::Result<()> {
+    let store = SqliteStore::open_in_memory()?;
+    store.init().await?;
+    // Sorted by name the weak match would come first
+    let weak = prompt("alpha").replace(
+        "Hello from alpha",
+        "A long body about many things: tests, builds, releases, docs and one rust mention",
+    );
+    let strong = prompt("rust rust").replace("Hello from rust rust", "Rust, rust and more rust");
+    store.create("alpha", &weak).await?;
+    store.create("zeta", &strong).await?;
+    store.create("other", &prompt("other")).await?;
+
+    let search = |text: &str| PromptQuery { text: Some(text.to_string()), ..PromptQuery::default() };
+    let ranked = store.query_templates(&search("rust")).await?.unwrap_or_default();
+    // Words match as prefixes and must all appear
+    let prefix = store.query_templates(&search("RUS")).await?.unwrap_or_default();
+    let both = store.query_templates(&search("rust releases")).await?.unwrap_or_default();
+    // Quotes and operators are searched for, not parsed as FTS5 syntax
+    let syntax = store.query_templates(&search("rust\" OR other")).await?;
+    let limited = store
+        .query_templates(&PromptQuery { limit: Some(1), ..search("rust") })
+        .await?
+        .unwrap_or_default();
+
+    assert_eq!(names(&ranked), ["zeta", "alpha"]);
+    assert_eq!(names(&prefix), ["zeta", "alpha"]);
+    assert_eq!(names(&both), ["alpha"]);
+    assert_eq!(syntax.map(|found| found.len()), Some(0));
+    assert_eq!(names(&limited), ["zeta"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn rerunning_a_migration_skips_copied_prompts() -> anyhow::Result<()> {
+    let dir = temp_dir("migrate")?;
+    let source = FsStore::new(dir.join("prompts"));
+    source.init().await?;
+    source.create("greeting", &prompt("greeting")).await?;
+    source.create("review/security", &prompt("security")).await?;
+    let target = SqliteStore::open(dir.join("prompts.db"))?;
+    target.init().await?;
+
+    let first = migrate_prompts(&source, &target).await?;
+    // Changed in the database since, and a new prompt in the directory
+    target.update("greeting", &prompt("edited")).await?;
+    source.create("added", &prompt("added")).await?;
+    let second = migrate_prompts(&source, &target).await?;
+    let greeting = target.read("greeting").await?;
+    let added = target.read("added").await?;
+    let greeting_log = target.revisions("greeting").await?;
+    drop(target);
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(first.copied, ["greeting", "review/security"]);
+    assert!(first.skipped.is_empty());
+    assert_eq!(second.copied, ["added"]);
+    assert_eq!(second.skipped, ["greeting", "review/security"]);
+    assert_eq!(greeting, prompt("edited"), "skipped prompts are not overwritten");
+    assert_eq!(added, prompt("added"), "content is copied byte-for-byte");
+    assert_eq!(greeting_log.len(), 2, "a skip records no revision");
+    Ok(())
+}
+
+#[tokio::test]
+async fn every_write_appends_a_revision() -> anyhow::Result<()> {
+    let store = SqliteStore::open_in_memory()?;
+    store.init().await?;
+    store.create("greeting", &prompt("first")).await?;
+    store.update("greeting", &prompt("second")).await?;
+    store.rename("greeting", "welcome", &prompt("moved")).await?;
+    store.delete("welcome").await?;
+    // A failed write records nothing
+    assert!(store.update("greeting", &prompt("ghost")).await.is_err());
+    store.create("greeting", &prompt("again")).await?;
+
+    let old_name = store.revisions("greeting").await?;
+    let new_name = store.revisions("welcome").await?;
+    let (first, second, again) = (prompt("first"), prompt("second"), prompt("again"));
+    assert_eq!(
+        revisions(&old_name),
+        [
+            (4, "create", Some(again.as_str())),
+            (3, "rename", None),
+            (2, "update", Some(second.as_str())),
+            (1, "create", Some(first.as_str())),
+        ]
+    );
+    let moved = prompt("moved");
+    assert_eq!(revisions(&new_name), [(2, "delete", None), (1, "rename", Some(moved.as_str()))]);
+    assert!(new_name[0].modified >= new_name[1].modified);
+    assert!(store.revisions("never").await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_limits_apply_to_readable_prompts() -> anyhow::Result<()> {
+    let manager = database().await?;
+    for name in ["private/a", "private/b", "private/c", "public/x", "public/y"] {
+        manager.add_prompt(name, &prompt(name)).await?;
+    }
+    let policy = Policy::from_yaml("default:\n  - actions: [read]\n    prompts: [\"public/*\"]\n")?;
+    let guarded = manager.with_policy(Arc::new(policy));
+
+    let query = PromptQuery {
+        text: Some("hello".to_string()),
+        limit: Some(2),
+        ..PromptQuery::default()
+    };
+    let found = with_client(ClientIdentity::default(), guarded.search_prompts(&query)).await?;
+    let mut names: Vec<&str> = found.iter().map(|t| t.filename.as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(names, ["public/x", "public/y"]);
+
+    let one = PromptQuery { limit: Some(1), ..query };
+    let found = with_client(ClientIdentity::default(), guarded.search_prompts(&one)).await?;
+    assert_eq!(found.len(), 1);
+    assert!(found[0].filename.starts_with("public/"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn history_lists_diffs_and_restores_revisions() -> anyhow::Result<()> {
+    let manager = database().await?;
+    manager.add_prompt("greeting", &prompt("first")).await?;
+    manager.edit_prompt("greeting", &prompt("second")).await?;
+    manager.delete_prompt("greeting").await?;
+
+    let log = manager.prompt_history("greeting", 10).await?;
+    let steps: Vec<(&str, &str)> = log.iter().map(|e| (e.commit.as_str(), e.message.as_str())).collect();
+    assert_eq!(steps, [("3", "delete"), ("2", "update"), ("1", "create")]);
+    assert_eq!(manager.prompt_history("greeting", 1).await?.len(), 1);
+
+    let diff = manager.prompt_diff("greeting", "1", Some("2")).await?;
+    assert!(diff.contains("-title: \"first\""), "{diff}");
+    assert!(diff.contains("+title: \"second\""), "{diff}");
+    assert!(manager.prompt_diff("greeting", "2", Some("2")).await?.is_empty());
+    // Against the current (deleted) prompt, every line goes
+    let removed = manager.prompt_diff("greeting", "2", None).await?;
+    assert!(removed.contains("-Hello from second"), "{removed}");
+
+    let error = manager.restore_prompt("greeting", "3").await.expect_err("revision 3 is a delete");
+    assert!(error.to_string().contains("deleted the prompt"), "{error:#}");
+    let error = manager.restore_prompt("greeting", "HEAD~1").await.expect_err("not a number");
+    assert!(error.to_string().contains("expected a revision number"), "{error:#}");
+    let error = manager.restore_prompt("greeting", "9").await.expect_err("no such revision");
+    assert!(error.to_string().contains("no revision 9"), "{error:#}");
+
+    let restored = manager.restore_prompt("greeting", "1").await?;
+    assert_eq!(restored.metadata.title, "first");
+    assert_eq!(manager.load_prompt("greeting").await?.metadata.title, "first");
+    let log = manager.prompt_history("greeting", 10).await?;
+    assert_eq!((log[0].commit.as_str(), log[0].message.as_str()), ("4", "create"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn stores_without_revisions_have_no_history() -> anyhow::Result<()> {
+    let manager = PromptManager::with_store(MemoryStore::new());
+    manager.add_prompt("greeting", &prompt("greeting")).await?;
+    let error = manager.prompt_history("greeting", 10).await.expect_err("memory store keeps no history");
+    assert!(error.to_string().contains("requires git storage"), "{error:#}");
+    Ok(())
+}
@@ -0,0 +1,259 @@
+//! `serve --stdio`: MCP over piped stdin/stdout
+
+use kodegen_tools_prompt::{tool_names, MemoryStore, PromptManager};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+fn temp_dir(test: &str) -> anyhow::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("kodegen-stdio-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+const GREETING: &str = "---
+title: \"Greeting\"
+description: \"Greets someone\"
+categories: [\"test\"]
+author: \"test\"
+parameters:
+  - name: \"who\"
+    description: \"Who to greet\"
+    required: true
+---
+{% message \"user\" %}Hello {{ who }}{% endmessage %}
+";
+
+/// The server binary speaking MCP over its stdin/stdout
+struct StdioServer {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    stderr: Arc<Mutex<String>>,
+    next_id: u64,
+    /// The `initialize` response
+    info: Value,
+    /// Every line the server wrote to stdout
+    lines: Vec<String>,
+    /// Notifications received while waiting for responses
+    notifications: Vec<String>,
+}
+
+impl StdioServer {
+    /// Start in `dir` with the prompts kept under it, and initialize
+    fn start(dir: &Path) -> anyhow::Result<Self> {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_kodegen-prompt"))
+            .args(["serve", "--stdio"])
+            .current_dir(dir)
+            .env("HOME", dir)
+            .env("XDG_CONFIG_HOME", dir.join("config"))
+            .env("RUST_LOG", "debug")
+            .env_remove("KODEGEN_PROMPTS_DB")
+            .env_remove("KODEGEN_PROMPTS_READ_ONLY")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("piped stdin");
+        let stdout = BufReader::new(child.stdout.take().expect("piped stdout"));
+
+        // Drained on a thread so debug logging can't fill the pipe
+        let stderr = Arc::new(Mutex::new(String::new()));
+        let mut pipe = child.stderr.take().expect("piped stderr");
+        let sink = stderr.clone();
+        std::thread::spawn(move || {
+            let mut buffer = String::new();
+            let _ = pipe.read_to_string(&mut buffer);
+            sink.lock().unwrap().push_str(&buffer);
+        });
+
+        let mut server = Self {
+            child,
+            stdin,
+            stdout,
+            stderr,
+            next_id: 1,
+            info: Value::Null,
+            lines: Vec::new(),
+            notifications: Vec::new(),
+        };
+        let init = server.request(
+            "initialize",
+            json!({
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": { "name": "stdio-test", "version": "0" }
+            }),
+        )?;
+        anyhow::ensure!(init["result"]["serverInfo"]["name"].is_string(), "{init}");
+        server.info = init;
+        server.send(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))?;
+        Ok(server)
+    }
+
+    fn send(&mut self, message: &Value) -> anyhow::Result<()> {
+        writeln!(self.stdin, "{message}")?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+
+    /// Send a request and wait for its response
+    fn request(&mut self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+        loop {
+            let mut line = String::new();
+            anyhow::ensure!(self.stdout.read_line(&mut line)? > 0, "server closed stdout");
+            self.lines.push(line.clone());
+            let message: Value = serde_json::from_str(&line)?;
+            if message["id"] == id {
+                return Ok(message);
+            }
+            if let Some(method) = message["method"].as_str() {
+                self.notifications.push(method.to_string());
+            }
+        }
+    }
+
+    fn call_tool(&mut self, name: &str, arguments: Value) -> anyhow::Result<Value> {
+        let response = self.request("tools/call", json!({ "name": name, "arguments": arguments }))?;
+        anyhow::ensure!(response["result"]["isError"] != true, "{response}");
+        Ok(response)
+    }
+
+    fn prompt_names(&mut self) -> anyhow::Result<Vec<String>> {
+        let response = self.request("prompts/list", json!({}))?;
+        Ok(response["result"]["prompts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|prompt| prompt["name"].as_str().map(str::to_string))
+            .collect())
+    }
+
+    /// Close stdin and wait for the server to exit; returns its stderr
+    fn stop(self) -> anyhow::Result<String> {
+        Ok(self.shutdown()?.1)
+    }
+
+    /// Close stdin and wait for the server to exit; returns every stdout
+    /// line (including any written after the last response) and stderr
+    fn shutdown(mut self) -> anyhow::Result<(Vec<String>, String)> {
+        drop(self.stdin);
+        let mut rest = String::new();
+        self.stdout.read_to_string(&mut rest)?;
+        self.lines.extend(rest.lines().map(str::to_string));
+        self.child.wait()?;
+        // The reader thread finishes once the pipe closes
+        for _ in 0..50 {
+            if Arc::strong_count(&self.stderr) == 1 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        let stderr = self.stderr.lock().unwrap().clone();
+        Ok((self.lines, stderr))
+    }
+}
+
+#[test]
+fn initialize_and_list_tools_with_logs_on_stderr() -> anyhow::Result<()> {
+    let dir = temp_dir("handshake")?;
+    let mut server = StdioServer::start(&dir)?;
+    let info = server.info["result"].clone();
+    let tools = server.request("tools/list", json!({}))?;
+    // A tool call logs at debug level while it runs
+    server.call_tool("prompt_add", json!({ "name": "greeting", "content": GREETING }))?;
+    let (lines, stderr) = server.shutdown()?;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(info["protocolVersion"], "2024-11-05", "{info}");
+    assert_eq!(info["serverInfo"]["name"], "kodegen_tools_prompt", "{info}");
+    assert!(info["capabilities"]["tools"].is_object(), "{info}");
+    assert_eq!(info["capabilities"]["prompts"]["listChanged"], true, "{info}");
+
+    let mut listed: Vec<&str> = tools["result"]["tools"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|tool| tool["name"].as_str())
+        .collect();
+    listed.sort_unstable();
+    let mut expected = tool_names().to_vec();
+    expected.sort_unstable();
+    assert_eq!(listed, expected, "{tools}");
+    assert!(tools["result"]["tools"][0]["inputSchema"].is_object(), "{tools}");
+
+    // stdout is nothing but JSON-RPC messages; the logging went to stderr
+    assert!(!lines.is_empty());
+    for line in &lines {
+        let message: Value = serde_json::from_str(line)
+            .map_err(|e| anyhow::anyhow!("non-JSON line on stdout ({e}): {line:?}"))?;
+        assert_eq!(message["jsonrpc"], "2.0", "{line}");
+    }
+    assert!(stderr.contains("DEBUG"), "debug logs reach stderr: {stderr:?}");
+    Ok(())
+}
+
+#[test]
+fn prompts_added_and_deleted_after_startup_are_listed() -> anyhow::Result<()> {
+    let dir = temp_dir("templates")?;
+    let mut server = StdioServer::start(&dir)?;
+    assert!(!server.prompt_names()?.contains(&"greeting".to_string()));
+
+    server.call_tool("prompt_add", json!({ "name": "greeting", "content": GREETING }))?;
+    let changed = server.notifications.contains(&"notifications/prompts/list_changed".to_string());
+    let listed = server.prompt_names()?;
+    let got = server.request(
+        "prompts/get",
+        json!({ "name": "greeting", "arguments": { "who": "stdio" } }),
+    )?;
+
+    server.call_tool("prompt_delete", json!({ "name": "greeting", "confirm": true }))?;
+    let after_delete = server.prompt_names()?;
+    let missing = server.request("prompts/get", json!({ "name": "greeting" }))?;
+    server.stop()?;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert!(changed, "prompt_add sends prompts/list_changed");
+    assert!(listed.contains(&"greeting".to_string()), "{listed:?}");
+    assert_eq!(got["result"]["messages"][0]["content"]["text"], "Hello stdio", "{got}");
+    assert!(!after_delete.contains(&"greeting".to_string()), "{after_delete:?}");
+    assert!(missing["error"].is_object(), "{missing}");
+    Ok(())
+}
+
+/// The `path` field of a tool's JSON output
+fn reported_path(response: &Value) -> anyhow::Result<Value> {
+    let text = response["result"]["content"][1]["text"].as_str().unwrap_or_default();
+    let output: Value = serde_json::from_str(text)?;
+    Ok(output["path"].clone())
+}
+
+#[test]
+fn written_prompts_report_their_file() -> anyhow::Result<()> {
+    let dir = temp_dir("paths")?;
+    let prompts = dir.join("config").join("kodegen").join("prompts");
+    let mut server = StdioServer::start(&dir)?;
+    let added = server.call_tool("prompt_add", json!({ "name": "greeting", "content": GREETING }))?;
+    let copied = server.call_tool("prompt_copy", json!({ "name": "greeting", "new_name": "team/hello" }))?;
+    let renamed = server.call_tool("prompt_rename", json!({ "name": "greeting", "new_name": "welcome" }))?;
+    server.stop()?;
+    let exists = prompts.join("team").join("hello.j2.md").exists() && prompts.join("welcome.j2.md").exists();
+    std::fs::remove_dir_all(&dir)?;
+
+    let file = |relative: &str| json!(prompts.join(relative).display().to_string());
+    assert_eq!(reported_path(&added)?, file("greeting.j2.md"));
+    assert_eq!(reported_path(&copied)?, file("team/hello.j2.md"));
+    assert_eq!(reported_path(&renamed)?, file("welcome.j2.md"));
+    assert!(exists, "the reported files are the ones written");
+
+    // Stores without a directory report none
+    let memory = PromptManager::with_store(MemoryStore::new());
+    assert_eq!(memory.prompt_path("greeting"), None);
+    Ok(())
+}
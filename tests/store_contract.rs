@@ -0,0 +1,134 @@
+//! The `PromptStore` contract, run against every backend
+
+use kodegen_tools_prompt::{FsStore, MemoryStore, PromptStore, StoreError, StoredMetadata};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+fn temp_dir(test: &str) -> anyhow::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("kodegen-store-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn prompt(title: &str) -> String {
+    format!(
+        "---
+title: \"{title}\"
+description: \"Store test\"
+categories: [\"test\"]
+author: \"test\"
+---
+Hello from {title}
+"
+    )
+}
+
+fn is_not_found<T>(result: &Result<T, StoreError>) -> bool {
+    matches!(result, Err(StoreError::NotFound))
+}
+
+fn is_already_exists<T>(result: &Result<T, StoreError>) -> bool {
+    matches!(result, Err(StoreError::AlreadyExists))
+}
+
+async fn stamp(store: &dyn PromptStore, name: &str) -> anyhow::Result<StoredMetadata> {
+    store
+        .metadata(name)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("'{name}' has no metadata"))
+}
+
+/// Every backend must behave the same way
+async fn check_contract(store: &dyn PromptStore) -> anyhow::Result<()> {
+    store.init().await?;
+    assert!(store.metadata("greeting").await?.is_none());
+    assert!(is_not_found(&store.read("greeting").await));
+
+    // Create never overwrites
+    store.create("greeting", &prompt("first")).await?;
+    let created = stamp(store, "greeting").await?;
+    assert_eq!(created.size, prompt("first").len() as u64);
+    assert!(is_already_exists(&store.create("greeting", &prompt("other")).await));
+    assert_eq!(store.read("greeting").await?, prompt("first"));
+
+    // Update never creates
+    assert!(is_not_found(&store.update("missing", &prompt("missing")).await));
+    assert!(store.metadata("missing").await?.is_none());
+
+    // Every write moves the stamp, even back-to-back writes of the same size
+    // (the template cache compares stamps to notice changes)
+    store.update("greeting", &prompt("secnd")).await?;
+    let updated = stamp(store, "greeting").await?;
+    store.update("greeting", &prompt("third")).await?;
+    let again = stamp(store, "greeting").await?;
+    assert_eq!(store.read("greeting").await?, prompt("third"));
+    assert_ne!(updated, created);
+    assert_ne!(again, updated, "same-size writes must still change the metadata");
+
+    // Rename refuses a taken name and leaves both prompts alone
+    store.create("taken", &prompt("taken")).await?;
+    let taken = stamp(store, "taken").await?;
+    assert!(is_already_exists(&store.rename("greeting", "taken", &prompt("moved")).await));
+    assert_eq!(store.read("greeting").await?, prompt("third"));
+    assert_eq!(store.read("taken").await?, prompt("taken"));
+    assert_eq!(stamp(store, "taken").await?, taken);
+
+    // A rename moves the prompt with its new content
+    assert!(is_not_found(&store.rename("missing", "moved", &prompt("moved")).await));
+    store.rename("greeting", "team/welcome", &prompt("moved")).await?;
+    assert!(store.metadata("greeting").await?.is_none());
+    assert_eq!(store.read("team/welcome").await?, prompt("moved"));
+
+    let mut names = store.list().await?;
+    names.sort();
+    assert_eq!(names, ["taken", "team/welcome"]);
+
+    // Delete removes, once
+    store.delete("taken").await?;
+    assert!(is_not_found(&store.delete("taken").await));
+    assert!(store.metadata("taken").await?.is_none());
+    assert_eq!(store.list().await?, ["team/welcome"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn memory_store_keeps_the_contract() -> anyhow::Result<()> {
+    check_contract(&MemoryStore::new()).await
+}
+
+#[tokio::test]
+async fn fs_store_keeps_the_contract() -> anyhow::Result<()> {
+    let dir = temp_dir("fs")?;
+    let result = check_contract(&FsStore::new(dir.join("prompts"))).await;
+    std::fs::remove_dir_all(&dir)?;
+    result
+}
+
+#[tokio::test]
+async fn fs_store_moves_stamps_the_clock_would_not() -> anyhow::Result<()> {
+    let dir = temp_dir("clock")?;
+    let store = FsStore::new(&dir);
+    store.create("greeting", &prompt("first")).await?;
+
+    // A modification time ahead of the clock, as after a coarse-resolution write
+    let ahead = SystemTime::now() + Duration::from_secs(3600);
+    std::fs::File::options()
+        .write(true)
+        .open(dir.join("greeting.j2.md"))?
+        .set_modified(ahead)?;
+    let before = stamp(&store, "greeting").await?;
+    store.update("greeting", &prompt("secnd")).await?;
+    let after = stamp(&store, "greeting").await?;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(before.modified, ahead);
+    assert!(after.modified > before.modified, "{after:?} vs {before:?}");
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sqlite_store_keeps_the_contract() -> anyhow::Result<()> {
+    check_contract(&kodegen_tools_prompt::SqliteStore::open_in_memory()?).await
+}
@@ -0,0 +1,111 @@
+//! Compiled-template cache: writes recompile, concurrent renders compile once
+
+use kodegen_tools_prompt::metrics::{metrics, LibraryStats};
+use kodegen_tools_prompt::{FsStore, MemoryStore, PromptManager};
+use std::path::PathBuf;
+use tokio::sync::Mutex;
+
+/// The compile counter is process-wide; run these one at a time
+static SERIAL: Mutex<()> = Mutex::const_new(());
+
+fn temp_dir(test: &str) -> anyhow::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("kodegen-cache-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn prompt(greeting: &str) -> String {
+    format!(
+        "---
+title: \"Greeting\"
+description: \"Cache test\"
+categories: [\"test\"]
+author: \"test\"
+parameters:
+  - name: \"who\"
+    description: \"Who to greet\"
+    default: \"world\"
+---
+{greeting} {{{{ who }}}}
+"
+    )
+}
+
+fn compiles() -> u64 {
+    metrics().snapshot(LibraryStats::default()).render.compiles
+}
+
+async fn render(manager: &PromptManager) -> anyhow::Result<String> {
+    Ok(manager.render_prompt("greeting", None).await?.content.trim().to_string())
+}
+
+#[tokio::test]
+async fn writes_recompile_on_the_next_render() -> anyhow::Result<()> {
+    let _serial = SERIAL.lock().await;
+    let manager = PromptManager::with_store(MemoryStore::new());
+    manager.add_prompt("greeting", &prompt("Hello")).await?;
+
+    let start = compiles();
+    let first = render(&manager).await?;
+    let cached = render(&manager).await?;
+    let after_cached = compiles();
+    // Same length, so only the modification stamp tells the versions apart
+    manager.edit_prompt("greeting", &prompt("Howdy")).await?;
+    let edited = render(&manager).await?;
+    let after_edit = compiles();
+
+    assert_eq!((first.as_str(), cached.as_str()), ("Hello world", "Hello world"));
+    assert_eq!(after_cached - start, 1, "an unchanged prompt is compiled once");
+    assert_eq!(edited, "Howdy world");
+    assert_eq!(after_edit - after_cached, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn files_changed_on_disk_recompile() -> anyhow::Result<()> {
+    let _serial = SERIAL.lock().await;
+    let dir = temp_dir("disk")?;
+    let manager = PromptManager::with_store(FsStore::new(&dir));
+    manager.add_prompt("greeting", &prompt("Hello")).await?;
+    let first = render(&manager).await?;
+
+    // Another process (here: another manager) rewrites the file
+    let other = PromptManager::with_store(FsStore::new(&dir));
+    other.edit_prompt("greeting", &prompt("Howdy")).await?;
+    let edited = render(&manager).await?;
+    other.delete_prompt("greeting").await?;
+    let deleted = manager.render_prompt("greeting", None).await;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(first, "Hello world");
+    assert_eq!(edited, "Howdy world");
+    assert!(deleted.is_err(), "a deleted prompt is not served from the cache");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_renders_compile_once() -> anyhow::Result<()> {
+    let _serial = SERIAL.lock().await;
+    let manager = PromptManager::with_store(MemoryStore::new());
+    manager.add_prompt("greeting", &prompt("Hello")).await?;
+    // Parse and cache the prompt first, so every render shares one slot
+    manager.load_prompt("greeting").await?;
+
+    let start = compiles();
+    let renders: Vec<_> = (0..32)
+        .map(|_| {
+            let manager = manager.clone();
+            tokio::spawn(async move { render(&manager).await })
+        })
+        .collect();
+    for handle in renders {
+        assert_eq!(handle.await??, "Hello world");
+    }
+    let compiled = compiles() - start;
+    let stats = manager.library_stats().await?;
+
+    assert_eq!(compiled, 1, "32 concurrent renders share one compile");
+    assert_eq!((stats.cached, stats.compiled), (1, 1));
+    Ok(())
+}
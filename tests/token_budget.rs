@@ -0,0 +1,163 @@
+//! Token budgets: allow, fail and truncate, for text and messages
+
+use kodegen_tools_prompt::{HeuristicEstimator, MemoryStore, PromptManager, TokenEstimator};
+use std::sync::Arc;
+
+fn prompt(budget: &str, body: &str) -> String {
+    format!(
+        "---
+title: \"Budget\"
+description: \"Token budget test\"
+categories: [\"test\"]
+author: \"test\"
+{budget}
+---
+{body}"
+    )
+}
+
+/// 40 characters: 10 tokens at four characters per token
+const FORTY: &str = "0123456789abcdefghijklmnopqrstuvwxyzABCD";
+
+async fn library(prompts: &[(&str, String)]) -> anyhow::Result<PromptManager> {
+    let manager = PromptManager::with_store(MemoryStore::new());
+    for (name, content) in prompts {
+        manager.add_prompt(name, content).await?;
+    }
+    Ok(manager)
+}
+
+#[tokio::test]
+async fn text_over_budget_is_reported_failed_or_cut() -> anyhow::Result<()> {
+    let manager = library(&[
+        ("unbudgeted", prompt("", FORTY)),
+        ("within", prompt("max_tokens: 10\non_token_overflow: fail", FORTY)),
+        ("allow", prompt("max_tokens: 4", FORTY)),
+        ("fail", prompt("max_tokens: 4\non_token_overflow: fail", FORTY)),
+        ("truncate", prompt("max_tokens: 4\non_token_overflow: truncate", FORTY)),
+    ])
+    .await?;
+
+    let unbudgeted = manager.render_prompt("unbudgeted", None).await?;
+    assert_eq!((unbudgeted.tokens.tokens, unbudgeted.tokens.budget), (10, None));
+    assert_eq!(unbudgeted.tokens.estimator, "heuristic");
+
+    // Exactly at the budget is fine, even when overflow fails
+    let within = manager.render_prompt("within", None).await?;
+    assert_eq!(within.content, FORTY);
+    assert!(!within.tokens.over_budget);
+
+    // Allow (the default) returns everything and flags it
+    let allowed = manager.render_prompt("allow", None).await?;
+    assert_eq!(allowed.content, FORTY);
+    assert_eq!((allowed.tokens.tokens, allowed.tokens.budget), (10, Some(4)));
+    assert!(allowed.tokens.over_budget);
+    assert_eq!(allowed.tokens.truncated_from, None);
+
+    let error = manager.render_prompt("fail", None).await.expect_err("over budget");
+    let message = format!("{error:#}");
+    assert!(message.contains("about 10 tokens"), "{message}");
+    assert!(message.contains("max_tokens budget of 4"), "{message}");
+
+    let truncated = manager.render_prompt("truncate", None).await?;
+    assert_eq!(truncated.content, &FORTY[..16]);
+    assert_eq!(truncated.tokens.tokens, 4);
+    assert_eq!(truncated.tokens.truncated_from, Some(10));
+    assert!(!truncated.tokens.over_budget);
+    Ok(())
+}
+
+#[tokio::test]
+async fn truncated_messages_keep_order_and_drop_the_rest() -> anyhow::Result<()> {
+    let body = format!(
+        "{{% message \"system\" %}}{first}{{% endmessage %}}\
+         {{% message \"user\" %}}{FORTY}{{% endmessage %}}\
+         {{% message \"assistant\" %}}{FORTY}{{% endmessage %}}",
+        first = &FORTY[..20],
+    );
+    let manager = library(&[
+        // 5 + 10 + 10 tokens: the user message crosses the budget
+        ("crossing", prompt("max_tokens: 8\non_token_overflow: truncate", &body)),
+        // The budget runs out exactly after the first message
+        ("exhausted", prompt("max_tokens: 5\non_token_overflow: truncate", &body)),
+        ("fail", prompt("max_tokens: 8\non_token_overflow: fail", &body)),
+        ("allow", prompt("max_tokens: 8", &body)),
+    ])
+    .await?;
+    let render = |name: &'static str| manager.render_prompt_messages(name, None, None);
+
+    let crossing = render("crossing").await?;
+    let kept: Vec<(&str, &str)> =
+        crossing.messages.iter().map(|m| (m.role.as_str(), m.content.as_str())).collect();
+    assert_eq!(kept, [("system", &FORTY[..20]), ("user", &FORTY[..12])]);
+    assert_eq!(crossing.tokens.tokens, 8);
+    assert_eq!(crossing.tokens.truncated_from, Some(25));
+
+    // A message cut to nothing is dropped too
+    let exhausted = render("exhausted").await?;
+    assert_eq!(exhausted.messages.len(), 1);
+    assert_eq!(exhausted.tokens.tokens, 5);
+
+    assert!(render("fail").await.is_err());
+    let allowed = render("allow").await?;
+    assert_eq!(allowed.messages.len(), 3);
+    assert!(allowed.tokens.over_budget);
+    assert_eq!(allowed.tokens.tokens, 25);
+    Ok(())
+}
+
+/// Counts bytes, so it relies on the default `truncate`
+struct ByteEstimator;
+
+impl TokenEstimator for ByteEstimator {
+    fn name(&self) -> &str {
+        "bytes"
+    }
+
+    fn estimate(&self, text: &str) -> usize {
+        text.len()
+    }
+}
+
+#[test]
+fn truncation_cuts_on_char_boundaries() {
+    let text = "héllo wörld 😀 ünïcode";
+
+    // Budgets that land inside multi-byte characters back off to a boundary
+    for budget in 0..=text.len() + 1 {
+        let cut = ByteEstimator.truncate(text, budget);
+        assert!(text.starts_with(cut));
+        assert!(cut.len() <= budget);
+        // The next character would not have fit
+        if let Some(next) = text[cut.len()..].chars().next() {
+            assert!(cut.len() + next.len_utf8() > budget, "{budget}: {cut:?}");
+        }
+    }
+    assert_eq!(ByteEstimator.truncate(text, 2), "h");
+    assert_eq!(ByteEstimator.truncate(text, 3), "hé");
+    assert_eq!(ByteEstimator.truncate("😀😀", 7), "😀");
+
+    // The heuristic counts characters, not bytes
+    let emoji = "😀".repeat(12);
+    assert_eq!(HeuristicEstimator.estimate(&emoji), 3);
+    assert_eq!(HeuristicEstimator.truncate(&emoji, 2), "😀".repeat(8));
+    assert_eq!(HeuristicEstimator.truncate(text, 100), text);
+    assert_eq!(HeuristicEstimator.truncate(text, 0), "");
+}
+
+#[tokio::test]
+async fn custom_estimators_count_and_cut() -> anyhow::Result<()> {
+    let manager = library(&[(
+        "greeting",
+        prompt("max_tokens: 7\non_token_overflow: truncate", "Grüße aus Köln"),
+    )])
+    .await?
+    .with_token_estimator(Arc::new(ByteEstimator));
+
+    let rendered = manager.render_prompt("greeting", None).await?;
+    assert_eq!(rendered.content, "Grüße");
+    assert_eq!(rendered.tokens.estimator, "bytes");
+    assert_eq!(rendered.tokens.tokens, 7);
+    assert_eq!(rendered.tokens.truncated_from, Some("Grüße aus Köln".len()));
+    Ok(())
+}
@@ -0,0 +1,182 @@
+//! Vote counting, batched render counts and popularity ordering
+
+use kodegen_tools_prompt::schema::prompt_get::{PromptSort, PromptSummary};
+use kodegen_tools_prompt::sort_summaries;
+use kodegen_tools_prompt::usage::FLUSH_DELAY;
+use kodegen_tools_prompt::{
+    MemoryStore, PromptManager, PromptServerConfig, PromptUsage, UsageStore,
+};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const GREETING: &str = "---
+title: \"Greeting\"
+description: \"Says hi\"
+categories: [\"test\"]
+author: \"test\"
+---
+Hi
+";
+
+fn temp_dir(test: &str) -> anyhow::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("kodegen-usage-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+#[tokio::test]
+async fn votes_are_counted_and_saved() -> anyhow::Result<()> {
+    let dir = temp_dir("votes")?;
+    let path = dir.join("usage.json");
+    let store = UsageStore::at(&path);
+
+    store.vote("review", 1).await?;
+    store.vote("review", 1).await?;
+    let usage = store.vote("review", -1).await?;
+    store.vote("other", -1).await?;
+    let reloaded = UsageStore::at(&path).get("review").await?;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(usage.votes, 1);
+    assert_eq!(reloaded.votes, 1, "votes are saved as they are cast");
+    assert_eq!(usage.total_votes(3), 4);
+    let against = PromptUsage { votes: -5, ..PromptUsage::default() };
+    assert_eq!(against.total_votes(3), 0, "never below zero");
+    Ok(())
+}
+
+#[tokio::test]
+async fn vote_that_fails_to_save_is_not_counted() -> anyhow::Result<()> {
+    let dir = temp_dir("failed-vote")?;
+    let path = dir.join("usage.json");
+    let store = UsageStore::at(&path);
+    store.vote("review", 1).await?;
+
+    // A non-empty directory in the file's place makes the rename fail
+    std::fs::remove_file(&path)?;
+    std::fs::create_dir_all(path.join("blocker"))?;
+    let failed = store.vote("review", 1).await;
+    let usage = store.get("review").await?;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert!(failed.is_err());
+    assert_eq!(usage.votes, 1, "the unsaved vote must not be applied");
+    Ok(())
+}
+
+#[tokio::test]
+async fn render_counts_are_saved_in_batches() -> anyhow::Result<()> {
+    let dir = temp_dir("renders")?;
+    let path = dir.join("usage.json");
+    let store = UsageStore::at(&path);
+
+    for success in [true, true, false] {
+        store.record_render("review", success).await?;
+    }
+    let written_at_once = path.exists();
+    tokio::time::sleep(FLUSH_DELAY + Duration::from_millis(500)).await;
+    let saved = UsageStore::at(&path).get("review").await?;
+
+    store.record_render("review", true).await?;
+    store.flush().await?;
+    let flushed = UsageStore::at(&path).get("review").await?;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert!(!written_at_once, "renders wait for their batch");
+    assert_eq!((saved.renders, saved.failures), (2, 1));
+    assert_eq!(flushed.renders, 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn writers_sharing_a_file_keep_each_others_counts() -> anyhow::Result<()> {
+    let dir = temp_dir("shared")?;
+    let path = dir.join("usage.json");
+    // A server and a CLI invocation, each with its own view of the file
+    let server = UsageStore::at(&path);
+    let cli = UsageStore::at(&path);
+    server.get("review").await?;
+
+    cli.record_render("review", true).await?;
+    cli.vote("review", 1).await?;
+    server.record_render("review", true).await?;
+    server.record_render("other", false).await?;
+    server.flush().await?;
+    let vote = server.vote("review", 1).await?;
+    cli.record_render("review", true).await?;
+    cli.flush().await?;
+    let saved = UsageStore::at(&path).all().await?;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!((vote.renders, vote.votes), (2, 2), "the vote saw the CLI's changes");
+    assert_eq!(saved["review"].renders, 3);
+    assert_eq!(saved["review"].votes, 2);
+    assert_eq!(saved["other"].failures, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn http_shutdown_saves_the_last_batch() -> anyhow::Result<()> {
+    let dir = temp_dir("shutdown")?;
+    let path = dir.join("usage.json");
+    let manager =
+        PromptManager::with_store(MemoryStore::new()).with_usage_store(UsageStore::at(&path));
+    manager.add_prompt("greeting", GREETING).await?;
+    manager.render_prompt("greeting", None).await?;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let handle = PromptServerConfig::new()
+        .manager(manager)
+        .http_listener(listener)
+        .start()
+        .await?;
+    handle.cancel();
+    handle
+        .wait_for_completion(Duration::from_secs(5))
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+    // Read before the batch's own delayed write could have run
+    let saved = UsageStore::at(&path).get("greeting").await?;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(saved.renders, 1);
+    Ok(())
+}
+
+fn summary(name: &str, votes: u32, renders: u64) -> anyhow::Result<PromptSummary> {
+    let mut summary: PromptSummary = serde_json::from_value(serde_json::json!({
+        "name": name,
+        "title": name,
+        "description": "",
+        "categories": [],
+        "author": "test",
+        "verified": false,
+        "parameters": [],
+        "votes": votes,
+    }))?;
+    summary.usage = (renders > 0).then(|| PromptUsage { renders, ..PromptUsage::default() });
+    Ok(summary)
+}
+
+#[test]
+fn popular_and_votes_sort_orders() -> anyhow::Result<()> {
+    let prompts = vec![
+        summary("alpha", 5, 0)?,
+        summary("beta", 1, 10)?,
+        summary("delta", 2, 10)?,
+        summary("gamma", 2, 10)?,
+        summary("zeta", 9, 3)?,
+    ];
+    let order = |sort| {
+        let mut prompts = prompts.clone();
+        sort_summaries(&mut prompts, sort);
+        prompts.into_iter().map(|p| p.name).collect::<Vec<_>>()
+    };
+
+    // Most renders, then most votes, then name
+    assert_eq!(order(PromptSort::Popular), ["delta", "gamma", "beta", "zeta", "alpha"]);
+    // Most votes, then most renders, then name
+    assert_eq!(order(PromptSort::Votes), ["zeta", "alpha", "delta", "gamma", "beta"]);
+    Ok(())
+}